@@ -1,6 +1,9 @@
 use aws_config::meta::region::RegionProviderChain;
-use aws_sdk_sesv2::types::{Body, Content, Destination, EmailContent, Message};
+use aws_sdk_sesv2::primitives::Blob;
+use aws_sdk_sesv2::types::{Body, Content, Destination, EmailContent, Message, RawMessage};
 use aws_sdk_sesv2::{Client, Error, config::Region};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
 
 pub const DEFAULT_NOREPLY_EMAIL_ADDRESS: &str = "noreply@granite-manager.com";
 pub const DEFAULT_SEND_EMAIL_ADDRESS: &str = "sales@granite-manager.com";
@@ -88,6 +91,174 @@ pub async fn send_message_from(
     Ok(output.message_id().unwrap_or("").to_string())
 }
 
+/// RFC 2047 encoded-word for non-ASCII header values; ASCII passes through untouched.
+/// Subjects and message ids can come from inbound mail; a CR or LF left in one would start
+/// a header of the sender's choosing, so every control character becomes a space.
+fn header_text(value: &str) -> String {
+    value
+        .chars()
+        .map(|character| {
+            if character.is_control() {
+                ' '
+            } else {
+                character
+            }
+        })
+        .collect()
+}
+
+fn encode_header_value(value: &str) -> String {
+    let value = header_text(value);
+    if value.is_ascii() {
+        return value;
+    }
+    format!("=?UTF-8?B?{}?=", STANDARD.encode(value))
+}
+
+fn address_list(to: &[&str]) -> String {
+    to.iter()
+        .map(|address| header_text(address))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Only the display name of `"Name" <address>` may be encoded, never the address itself.
+fn encode_mailbox(mailbox: &str) -> String {
+    let mailbox = header_text(mailbox);
+    match (mailbox.rfind('<'), mailbox.ends_with('>')) {
+        (Some(open), true) => {
+            let name = mailbox[..open].trim().trim_matches('"');
+            if name.is_ascii() {
+                return mailbox;
+            }
+            format!("{} {}", encode_header_value(name), &mailbox[open..])
+        }
+        _ => mailbox,
+    }
+}
+
+fn wrap_base64(data: &[u8]) -> String {
+    let encoded = STANDARD.encode(data);
+    encoded
+        .as_bytes()
+        .chunks(76)
+        .map(|line| std::str::from_utf8(line).expect("base64 is ASCII"))
+        .collect::<Vec<_>>()
+        .join("\r\n")
+}
+
+/// Single-part HTML message with RFC 8058 one-click unsubscribe headers.
+pub fn build_raw_message(
+    to: &[&str],
+    subject: &str,
+    message: &str,
+    from: &str,
+    unsubscribe_url: &str,
+) -> String {
+    [
+        format!("From: {}", encode_mailbox(from)),
+        format!("To: {}", address_list(to)),
+        format!("Subject: {}", encode_header_value(subject)),
+        "MIME-Version: 1.0".to_string(),
+        format!("List-Unsubscribe: <{}>", header_text(unsubscribe_url)),
+        "List-Unsubscribe-Post: List-Unsubscribe=One-Click".to_string(),
+        "Content-Type: text/html; charset=UTF-8".to_string(),
+        "Content-Transfer-Encoding: base64".to_string(),
+        String::new(),
+        wrap_base64(message.as_bytes()),
+    ]
+    .join("\r\n")
+}
+
+/// Single-part HTML reply that threads under `in_reply_to` in the customer's mail client.
+pub fn build_raw_reply_message(
+    to: &[&str],
+    subject: &str,
+    message: &str,
+    from: &str,
+    in_reply_to: &str,
+    references: &[String],
+) -> String {
+    let mut references: Vec<String> = references.iter().map(|id| angle_id(id)).collect();
+    let in_reply_to = angle_id(in_reply_to);
+    if !references.contains(&in_reply_to) {
+        references.push(in_reply_to.clone());
+    }
+    [
+        format!("From: {}", encode_mailbox(from)),
+        format!("To: {}", address_list(to)),
+        format!("Subject: {}", encode_header_value(subject)),
+        "MIME-Version: 1.0".to_string(),
+        format!("In-Reply-To: {in_reply_to}"),
+        format!("References: {}", references.join(" ")),
+        "Content-Type: text/html; charset=UTF-8".to_string(),
+        "Content-Transfer-Encoding: base64".to_string(),
+        String::new(),
+        wrap_base64(message.as_bytes()),
+    ]
+    .join("\r\n")
+}
+
+/// Inbound message ids are stored without their angle brackets. Ids never contain
+/// whitespace, so any that arrives with one, a CR or LF included, is dropped.
+fn angle_id(message_id: &str) -> String {
+    let id: String = message_id
+        .chars()
+        .filter(|character| !character.is_whitespace() && !character.is_control())
+        .collect();
+    format!("<{}>", id.trim_start_matches('<').trim_end_matches('>'))
+}
+
+async fn send_raw_message(to: &[&str], from: &str, raw_message: String) -> Result<String, Error> {
+    let region_provider = RegionProviderChain::first_try(Region::new("us-east-2"));
+    let shared_config = aws_config::from_env().region(region_provider).load().await;
+    let client = Client::new(&shared_config);
+
+    let mut dest: Destination = Destination::builder().build();
+    dest.to_addresses = Some(to.iter().map(|s| (*s).to_string()).collect());
+    let raw = RawMessage::builder()
+        .data(Blob::new(raw_message))
+        .build()
+        .expect("building RawMessage");
+
+    let email_content = EmailContent::builder().raw(raw).build();
+
+    let output = client
+        .send_email()
+        .from_email_address(from)
+        .destination(dest)
+        .content(email_content)
+        .send()
+        .await?;
+
+    Ok(output.message_id().unwrap_or("").to_string())
+}
+
+/// Same as `send_message_from`, but as raw MIME so the unsubscribe headers reach the inbox.
+pub async fn send_message_with_unsubscribe(
+    to: &[&str],
+    subject: &str,
+    message: &str,
+    from: &str,
+    unsubscribe_url: &str,
+) -> Result<String, Error> {
+    let raw = build_raw_message(to, subject, message, from, unsubscribe_url);
+    send_raw_message(to, from, raw).await
+}
+
+/// Sends `message` as a reply within an existing conversation.
+pub async fn send_reply_message(
+    to: &[&str],
+    subject: &str,
+    message: &str,
+    from: &str,
+    in_reply_to: &str,
+    references: &[String],
+) -> Result<String, Error> {
+    let raw = build_raw_reply_message(to, subject, message, from, in_reply_to, references);
+    send_raw_message(to, from, raw).await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -164,4 +335,91 @@ mod tests {
             "\"Alex Rep\" <sales@granite-manager.com>"
         );
     }
+
+    #[test]
+    fn raw_message_carries_one_click_unsubscribe_headers() {
+        let raw = build_raw_message(
+            &["lead@example.com"],
+            "Your quote",
+            "<p>Hi</p>",
+            "\"Alex Rep\" <rep@acme.com>",
+            "https://hooks.example.com/v1/unsubscribe/1/2/sig",
+        );
+        let (headers, body) = raw.split_once("\r\n\r\n").expect("header/body split");
+        assert!(headers.contains("From: \"Alex Rep\" <rep@acme.com>"));
+        assert!(headers.contains("To: lead@example.com"));
+        assert!(headers.contains("Subject: Your quote"));
+        assert!(
+            headers
+                .contains("List-Unsubscribe: <https://hooks.example.com/v1/unsubscribe/1/2/sig>")
+        );
+        assert!(headers.contains("List-Unsubscribe-Post: List-Unsubscribe=One-Click"));
+        assert_eq!(STANDARD.decode(body).unwrap(), b"<p>Hi</p>");
+    }
+
+    #[test]
+    fn raw_message_encodes_non_ascii_headers_and_wraps_body() {
+        let html = "é".repeat(100);
+        let raw = build_raw_message(
+            &["lead@example.com"],
+            "Devis café",
+            &html,
+            "\"José\" <rep@acme.com>",
+            "https://hooks.example.com/u",
+        );
+        assert!(raw.contains("Subject: =?UTF-8?B?"));
+        assert!(raw.contains(" <rep@acme.com>"));
+        assert!(!raw.contains("José"));
+        let (_, body) = raw.split_once("\r\n\r\n").unwrap();
+        assert!(body.split("\r\n").all(|line| line.len() <= 76));
+        assert_eq!(
+            STANDARD.decode(body.replace("\r\n", "")).unwrap(),
+            html.as_bytes()
+        );
+    }
+
+    #[test]
+    fn raw_reply_carries_threading_headers() {
+        let raw = build_raw_reply_message(
+            &["customer@example.com"],
+            "Re: Quote",
+            "<p>Thanks</p>",
+            "sales@granite-manager.com",
+            "second@example.com",
+            &[
+                "first@example.com".to_string(),
+                "<second@example.com>".to_string(),
+            ],
+        );
+        assert!(raw.contains("In-Reply-To: <second@example.com>\r\n"));
+        assert!(raw.contains("References: <first@example.com> <second@example.com>\r\n"));
+        assert!(!raw.contains("List-Unsubscribe"));
+    }
+
+    #[test]
+    fn header_values_cannot_start_new_headers() {
+        let raw = build_raw_reply_message(
+            &["customer@example.com"],
+            "Re: Quote\r\nBcc: victim@example.com",
+            "<p>Thanks</p>",
+            "sales@granite-manager.com",
+            "second@example.com>\r\nBcc: victim@example.com",
+            &["first@example.com\r\nBcc: victim@example.com".to_string()],
+        );
+        let (headers, _) = raw.split_once("\r\n\r\n").expect("header/body split");
+        assert!(!headers.lines().any(|line| line.starts_with("Bcc:")));
+        assert!(headers.contains("Subject: Re: Quote  Bcc: victim@example.com\r\n"));
+
+        let raw = build_raw_message(
+            &["lead@example.com\nBcc: victim@example.com"],
+            "Quote\rBcc: victim@example.com",
+            "<p>Hi</p>",
+            "\"Alex\nRep\" <rep@acme.com>",
+            "https://hooks.example.com/u\r\nBcc: victim@example.com",
+        );
+        let (headers, _) = raw.split_once("\r\n\r\n").expect("header/body split");
+        assert!(!headers.lines().any(|line| line.starts_with("Bcc:")));
+        assert!(!headers.replace("\r\n", "").contains(['\r', '\n']));
+        assert!(headers.contains("From: \"Alex Rep\" <rep@acme.com>\r\n"));
+    }
 }
@@ -0,0 +1,106 @@
+use crate::utils::assignment::{AssignmentMode, Territory};
+use sqlx::mysql::MySqlQueryResult;
+use sqlx::{MySqlConnection, MySqlExecutor, MySqlPool};
+
+pub struct AssignmentPolicy {
+    pub mode: AssignmentMode,
+    pub last_assigned_user_id: Option<i32>,
+}
+
+pub struct RepAssignmentSettings {
+    pub user_id: i32,
+    pub weight: i32,
+    pub monthly_cap: Option<i32>,
+    pub is_active: bool,
+}
+
+pub async fn get_assignment_policy(
+    pool: &MySqlPool,
+    company_id: i32,
+) -> Result<Option<AssignmentPolicy>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT mode, last_assigned_user_id FROM lead_assignment_policies WHERE company_id = ?"#,
+        company_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|row| AssignmentPolicy {
+        mode: AssignmentMode::from_db(&row.mode),
+        last_assigned_user_id: row.last_assigned_user_id,
+    }))
+}
+
+/// `get_assignment_policy` that locks the row until the caller's transaction ends, so leads
+/// assigned at the same time each see the previous one's `last_assigned_user_id`.
+pub async fn lock_assignment_policy(
+    tx: &mut MySqlConnection,
+    company_id: i32,
+) -> Result<Option<AssignmentPolicy>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT mode, last_assigned_user_id FROM lead_assignment_policies WHERE company_id = ? FOR UPDATE"#,
+        company_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    Ok(row.map(|row| AssignmentPolicy {
+        mode: AssignmentMode::from_db(&row.mode),
+        last_assigned_user_id: row.last_assigned_user_id,
+    }))
+}
+
+pub async fn set_last_assigned_user<'e>(
+    executor: impl MySqlExecutor<'e>,
+    company_id: i32,
+    user_id: i32,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"UPDATE lead_assignment_policies SET last_assigned_user_id = ? WHERE company_id = ?"#,
+        user_id,
+        company_id
+    )
+    .execute(executor)
+    .await
+}
+
+pub async fn list_rep_assignment_settings(
+    pool: &MySqlPool,
+    company_id: i32,
+) -> Result<Vec<RepAssignmentSettings>, sqlx::Error> {
+    sqlx::query_as!(
+        RepAssignmentSettings,
+        r#"
+        SELECT user_id, weight, monthly_cap, is_active as "is_active: bool"
+        FROM lead_assignment_reps
+        WHERE company_id = ?
+        "#,
+        company_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn list_assignment_territories(
+    pool: &MySqlPool,
+    company_id: i32,
+) -> Result<Vec<Territory>, sqlx::Error> {
+    sqlx::query_as!(
+        Territory,
+        r#"SELECT user_id, postal_prefix FROM lead_assignment_territories WHERE company_id = ?"#,
+        company_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_customer_postal_code(
+    pool: &MySqlPool,
+    customer_id: i32,
+) -> Result<Option<String>, sqlx::Error> {
+    let postal_code = sqlx::query_scalar!(
+        r#"SELECT postal_code FROM customers WHERE id = ?"#,
+        customer_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(postal_code.flatten())
+}
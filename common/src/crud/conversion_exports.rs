@@ -0,0 +1,430 @@
+use chrono::{DateTime, Utc};
+use sqlx::MySqlPool;
+use sqlx::mysql::MySqlQueryResult;
+
+/// Give up on an event after this many failed sends.
+pub const MAX_CONVERSION_EXPORT_ATTEMPTS: i32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionPlatform {
+    Meta,
+    GoogleAds,
+}
+
+impl ConversionPlatform {
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "meta" => Some(Self::Meta),
+            "google_ads" => Some(Self::GoogleAds),
+            _ => None,
+        }
+    }
+
+    /// How old an event the platform still accepts: Meta takes events from the last seven
+    /// days, Google Ads clicks from the last 90.
+    pub const fn upload_window_days(self) -> i32 {
+        match self {
+            Self::Meta => 7,
+            Self::GoogleAds => 90,
+        }
+    }
+}
+
+/// A queued event with everything needed to build the platform request.
+#[derive(Debug, Clone)]
+pub struct PendingConversionEvent {
+    pub id: u64,
+    pub company_id: i32,
+    pub deal_id: u64,
+    pub customer_id: i32,
+    pub platform: String,
+    pub attempts: i32,
+    pub event_time: DateTime<Utc>,
+    pub meta_event_name: Option<String>,
+    pub google_conversion_action_id: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub amount: Option<f64>,
+    pub currency: String,
+    pub gclid: Option<String>,
+    pub wbraid: Option<String>,
+    pub fbclid: Option<String>,
+    /// When the `fbclid` was recorded, which Meta expects in `fbc`.
+    pub fbclid_at: Option<DateTime<Utc>>,
+    pub meta_pixel_id: Option<String>,
+    pub meta_access_token: Option<String>,
+    pub google_ads_customer_id: Option<String>,
+    pub google_ads_login_customer_id: Option<String>,
+    pub google_ads_refresh_token: Option<String>,
+}
+
+/// Queues an event for every recent deal that is won or sits in a mapped stage, once per
+/// deal, rule and platform. Only platforms the company has credentials for are queued.
+/// The event is dated when the deal was won or last entered the stage, and skipped when that
+/// is already outside the platform's upload window.
+pub async fn enqueue_conversion_events(pool: &MySqlPool) -> Result<u64, sqlx::Error> {
+    let meta = sqlx::query!(
+        r#"
+        INSERT IGNORE INTO conversion_export_queue
+            (company_id, deal_id, rule_id, platform, event_time)
+        SELECT
+            r.company_id,
+            d.id,
+            r.id,
+            'meta',
+            IF(
+                r.list_id IS NULL,
+                d.won_at,
+                COALESCE(
+                    (SELECT MAX(h.entered_at) FROM deal_stage_history h
+                     WHERE h.deal_id = d.id AND h.list_id = d.list_id),
+                    d.created_at
+                )
+            ) AS event_time
+        FROM conversion_export_rules r
+        INNER JOIN conversion_export_settings s ON s.company_id = r.company_id
+        INNER JOIN customers c ON c.company_id = r.company_id
+        INNER JOIN deals d ON d.customer_id = c.id
+        WHERE r.meta_event_name IS NOT NULL
+          AND s.meta_pixel_id IS NOT NULL
+          AND s.meta_access_token IS NOT NULL
+          AND d.deleted_at IS NULL
+          AND d.created_at >= NOW() - INTERVAL 90 DAY
+          AND ((r.list_id IS NULL AND d.is_won = 1) OR r.list_id = d.list_id)
+        HAVING event_time >= NOW() - INTERVAL ? DAY
+        "#,
+        ConversionPlatform::Meta.upload_window_days()
+    )
+    .execute(pool)
+    .await?;
+    let google = sqlx::query!(
+        r#"
+        INSERT IGNORE INTO conversion_export_queue
+            (company_id, deal_id, rule_id, platform, event_time)
+        SELECT
+            r.company_id,
+            d.id,
+            r.id,
+            'google_ads',
+            IF(
+                r.list_id IS NULL,
+                d.won_at,
+                COALESCE(
+                    (SELECT MAX(h.entered_at) FROM deal_stage_history h
+                     WHERE h.deal_id = d.id AND h.list_id = d.list_id),
+                    d.created_at
+                )
+            ) AS event_time
+        FROM conversion_export_rules r
+        INNER JOIN conversion_export_settings s ON s.company_id = r.company_id
+        INNER JOIN customers c ON c.company_id = r.company_id
+        INNER JOIN deals d ON d.customer_id = c.id
+        WHERE r.google_conversion_action_id IS NOT NULL
+          AND s.google_ads_customer_id IS NOT NULL
+          AND s.google_ads_refresh_token IS NOT NULL
+          AND d.deleted_at IS NULL
+          AND d.created_at >= NOW() - INTERVAL 90 DAY
+          AND ((r.list_id IS NULL AND d.is_won = 1) OR r.list_id = d.list_id)
+        HAVING event_time >= NOW() - INTERVAL ? DAY
+        "#,
+        ConversionPlatform::GoogleAds.upload_window_days()
+    )
+    .execute(pool)
+    .await?;
+    Ok(meta.rows_affected() + google.rows_affected())
+}
+
+/// Due events with the customer's contact details and click ids; last-touch click ids win
+/// over first-touch ones.
+pub async fn get_due_conversion_events(
+    pool: &MySqlPool,
+    limit: u32,
+) -> Result<Vec<PendingConversionEvent>, sqlx::Error> {
+    sqlx::query_as!(
+        PendingConversionEvent,
+        r#"
+        SELECT
+            q.id,
+            q.company_id,
+            q.deal_id,
+            d.customer_id,
+            q.platform,
+            q.attempts,
+            q.event_time AS "event_time: DateTime<Utc>",
+            r.meta_event_name,
+            r.google_conversion_action_id,
+            ce.email AS "email?",
+            c.phone,
+            CAST(d.amount AS DOUBLE) AS "amount?: f64",
+            s.currency,
+            COALESCE(lt.gclid, ft.gclid) AS "gclid?",
+            COALESCE(lt.wbraid, ft.wbraid) AS "wbraid?",
+            COALESCE(lt.fbclid, ft.fbclid) AS "fbclid?",
+            CASE
+                WHEN lt.fbclid IS NOT NULL THEN lt.updated_at
+                WHEN ft.fbclid IS NOT NULL THEN ft.created_at
+            END AS "fbclid_at?: DateTime<Utc>",
+            s.meta_pixel_id,
+            s.meta_access_token,
+            s.google_ads_customer_id,
+            s.google_ads_login_customer_id,
+            s.google_ads_refresh_token
+        FROM conversion_export_queue q
+        INNER JOIN conversion_export_rules r ON r.id = q.rule_id
+        INNER JOIN conversion_export_settings s ON s.company_id = q.company_id
+        INNER JOIN deals d ON d.id = q.deal_id
+        INNER JOIN customers c ON c.id = d.customer_id
+        LEFT JOIN customers_emails ce ON ce.id = c.email_id
+        LEFT JOIN lead_attributions lt ON lt.customer_id = c.id AND lt.touch = 'last'
+        LEFT JOIN lead_attributions ft ON ft.customer_id = c.id AND ft.touch = 'first'
+        WHERE q.status = 'pending'
+          AND q.next_attempt_at <= NOW()
+        ORDER BY q.id
+        LIMIT ?
+        "#,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn mark_conversion_event_sent(
+    pool: &MySqlPool,
+    id: u64,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE conversion_export_queue
+        SET status = 'sent', sent_at = NOW(), attempts = attempts + 1, last_error = NULL
+        WHERE id = ?
+        "#,
+        id
+    )
+    .execute(pool)
+    .await
+}
+
+/// Fails pending events the platform would no longer accept instead of retrying them.
+pub async fn expire_conversion_events(pool: &MySqlPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE conversion_export_queue
+        SET status = 'failed', last_error = 'Event is older than the platform accepts'
+        WHERE status = 'pending'
+          AND ((platform = 'meta' AND event_time < NOW() - INTERVAL ? DAY)
+            OR (platform = 'google_ads' AND event_time < NOW() - INTERVAL ? DAY))
+        "#,
+        ConversionPlatform::Meta.upload_window_days(),
+        ConversionPlatform::GoogleAds.upload_window_days()
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Retries back off 10, 20, 40, ... minutes; after the last attempt the event is `failed`.
+pub async fn mark_conversion_event_failed(
+    pool: &MySqlPool,
+    id: u64,
+    error: &str,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE conversion_export_queue
+        SET attempts = attempts + 1,
+            last_error = ?,
+            status = IF(attempts >= ?, 'failed', 'pending'),
+            next_attempt_at = NOW() + INTERVAL (10 * POW(2, attempts - 1)) MINUTE
+        WHERE id = ?
+        "#,
+        error,
+        MAX_CONVERSION_EXPORT_ATTEMPTS,
+        id
+    )
+    .execute(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn won_deal(pool: &MySqlPool) -> u64 {
+        let customer_id = sqlx::query!(
+            "INSERT INTO customers (name, company_id, source, phone) VALUES ('Won Lead', 1, 'leads', '317-555-0199')"
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        sqlx::query!(
+            "INSERT INTO deals (customer_id, list_id, position, amount, is_won) VALUES (?, 4, 0, 12500.50, 1)",
+            customer_id
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_id()
+    }
+
+    async fn configure_meta(pool: &MySqlPool) {
+        sqlx::query!(
+            "INSERT INTO conversion_export_settings (company_id, meta_pixel_id, meta_access_token) VALUES (1, '123', 'token')"
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO conversion_export_rules (company_id, meta_event_name, google_conversion_action_id) VALUES (1, 'Purchase', '555')"
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn won_deal_is_queued_once_per_configured_platform(pool: MySqlPool) {
+        configure_meta(&pool).await;
+        let deal_id = won_deal(&pool).await;
+
+        assert_eq!(enqueue_conversion_events(&pool).await.unwrap(), 1);
+        assert_eq!(enqueue_conversion_events(&pool).await.unwrap(), 0);
+
+        let due = get_due_conversion_events(&pool, 10).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].deal_id, deal_id);
+        assert_eq!(
+            ConversionPlatform::from_db(&due[0].platform),
+            Some(ConversionPlatform::Meta)
+        );
+        assert_eq!(due[0].meta_event_name.as_deref(), Some("Purchase"));
+        assert_eq!(due[0].phone.as_deref(), Some("317-555-0199"));
+        assert_eq!(due[0].amount, Some(12500.5));
+        assert_eq!(due[0].currency, "USD");
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn failed_event_backs_off_then_gives_up(pool: MySqlPool) {
+        configure_meta(&pool).await;
+        won_deal(&pool).await;
+        enqueue_conversion_events(&pool).await.unwrap();
+        let id = get_due_conversion_events(&pool, 10).await.unwrap()[0].id;
+
+        mark_conversion_event_failed(&pool, id, "timeout")
+            .await
+            .unwrap();
+        assert!(
+            get_due_conversion_events(&pool, 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        for _ in 1..MAX_CONVERSION_EXPORT_ATTEMPTS {
+            mark_conversion_event_failed(&pool, id, "timeout")
+                .await
+                .unwrap();
+        }
+        let row = sqlx::query!(
+            "SELECT status, attempts, last_error FROM conversion_export_queue WHERE id = ?",
+            id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(row.status, "failed");
+        assert_eq!(row.attempts, MAX_CONVERSION_EXPORT_ATTEMPTS);
+        assert_eq!(row.last_error.as_deref(), Some("timeout"));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn events_are_dated_when_won_or_staged_within_each_window(pool: MySqlPool) {
+        sqlx::query!(
+            "INSERT INTO conversion_export_settings (company_id, meta_pixel_id, meta_access_token, google_ads_customer_id, google_ads_refresh_token) VALUES (1, '123', 'token', '123-456-7890', 'refresh')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let won_rule = sqlx::query!(
+            "INSERT INTO conversion_export_rules (company_id, meta_event_name, google_conversion_action_id) VALUES (1, 'Purchase', '555')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        let stage_rule = sqlx::query!(
+            "INSERT INTO conversion_export_rules (company_id, list_id, meta_event_name, google_conversion_action_id) VALUES (1, 4, 'Lead', '556')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        let deal_id = won_deal(&pool).await;
+        sqlx::query!(
+            "UPDATE deals SET won_at = NOW() - INTERVAL 10 DAY WHERE id = ?",
+            deal_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO deal_stage_history (deal_id, list_id, entered_at) VALUES (?, 4, NOW() - INTERVAL 2 DAY)",
+            deal_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // Won ten days ago is too old for Meta; the stage was entered within both windows.
+        assert_eq!(enqueue_conversion_events(&pool).await.unwrap(), 3);
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT q.rule_id, q.platform,
+                q.event_time = d.won_at AS "at_won!: i64",
+                q.event_time = h.entered_at AS "at_stage!: i64"
+            FROM conversion_export_queue q
+            INNER JOIN deals d ON d.id = q.deal_id
+            INNER JOIN deal_stage_history h ON h.deal_id = d.id
+            ORDER BY q.rule_id, q.platform
+            "#
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(u64::try_from(rows[0].rule_id).unwrap(), won_rule);
+        assert_eq!(rows[0].platform, "google_ads");
+        assert_eq!(rows[0].at_won, 1);
+        for row in &rows[1..] {
+            assert_eq!(u64::try_from(row.rule_id).unwrap(), stage_rule);
+            assert_eq!(row.at_stage, 1);
+        }
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn events_past_the_upload_window_are_dropped(pool: MySqlPool) {
+        configure_meta(&pool).await;
+        won_deal(&pool).await;
+        enqueue_conversion_events(&pool).await.unwrap();
+        assert_eq!(expire_conversion_events(&pool).await.unwrap(), 0);
+
+        sqlx::query!("UPDATE conversion_export_queue SET event_time = NOW() - INTERVAL 8 DAY")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(expire_conversion_events(&pool).await.unwrap(), 1);
+        assert!(
+            get_due_conversion_events(&pool, 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let row = sqlx::query!("SELECT status, attempts FROM conversion_export_queue")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(row.status, "failed");
+        assert_eq!(row.attempts, 0);
+    }
+}
@@ -0,0 +1,199 @@
+use crate::utils::phone::PhoneNumber;
+use sqlx::MySqlPool;
+
+struct CustomerPhones {
+    id: i32,
+    phone: Option<String>,
+    phone_2: Option<String>,
+}
+
+/// Rewrites `phone` and `phone_2` in display format and fills their E.164 copies.
+/// Numbers that do not parse are left as they are; the row is marked as tried either way.
+async fn normalize_customer_phones(
+    pool: &MySqlPool,
+    row: &CustomerPhones,
+) -> Result<bool, sqlx::Error> {
+    let phone = row.phone.as_deref().and_then(PhoneNumber::parse);
+    let phone_2 = row.phone_2.as_deref().and_then(PhoneNumber::parse);
+    sqlx::query!(
+        r#"
+        UPDATE customers
+        SET phone = COALESCE(?, phone),
+            phone_2 = COALESCE(?, phone_2),
+            phone_e164 = COALESCE(?, phone_e164),
+            phone_2_e164 = COALESCE(?, phone_2_e164),
+            phones_normalized_at = UTC_TIMESTAMP()
+        WHERE id = ?
+        "#,
+        phone.as_ref().map(ToString::to_string),
+        phone_2.as_ref().map(ToString::to_string),
+        phone.as_ref().map(PhoneNumber::e164),
+        phone_2.as_ref().map(PhoneNumber::e164),
+        row.id
+    )
+    .execute(pool)
+    .await?;
+    Ok(phone.is_some() || phone_2.is_some())
+}
+
+/// Normalizes up to `batch_size` customers written before `phone_e164` existed, or by code
+/// that does not set it, and not tried since their numbers last changed. Runs on every scheduled tick, so each call
+/// handles one batch. Returns how many customers got at least one normalized number.
+pub async fn backfill_customer_phones(
+    pool: &MySqlPool,
+    batch_size: u32,
+) -> Result<u64, sqlx::Error> {
+    let rows = sqlx::query_as!(
+        CustomerPhones,
+        r#"
+        SELECT id, phone, phone_2
+        FROM customers
+        WHERE phones_normalized_at IS NULL
+          AND ((phone IS NOT NULL AND phone_e164 IS NULL)
+            OR (phone_2 IS NOT NULL AND phone_2_e164 IS NULL))
+        ORDER BY id
+        LIMIT ?
+        "#,
+        batch_size
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut updated = 0;
+    for row in &rows {
+        if normalize_customer_phones(pool, row).await? {
+            updated += 1;
+        }
+    }
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn backfill_normalizes_parseable_phones(pool: MySqlPool) {
+        let us = sqlx::query!(
+            "INSERT INTO customers (name, company_id, phone, phone_2) VALUES ('US', 1, '(317) 555-0199 x12', '+44 20 7946 0958')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        let junk = sqlx::query!(
+            "INSERT INTO customers (name, company_id, phone) VALUES ('Junk', 1, '555-0199')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        let mixed = sqlx::query!(
+            "INSERT INTO customers (name, company_id, phone, phone_2) VALUES ('Mixed', 1, '3175550100', 'call me')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+
+        // One batch per call, and tried rows are not picked up again.
+        assert_eq!(backfill_customer_phones(&pool, 2).await.unwrap(), 1);
+        assert_eq!(backfill_customer_phones(&pool, 2).await.unwrap(), 1);
+        assert_eq!(backfill_customer_phones(&pool, 2).await.unwrap(), 0);
+        let untried = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM customers WHERE phones_normalized_at IS NULL AND id IN (?, ?, ?)",
+            us,
+            junk,
+            mixed
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(untried, 0);
+
+        let row = sqlx::query!(
+            "SELECT phone, phone_2, phone_e164, phone_2_e164 FROM customers WHERE id = ?",
+            us
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(row.phone.as_deref(), Some("317-555-0199 ext. 12"));
+        assert_eq!(row.phone_e164.as_deref(), Some("+13175550199"));
+        assert_eq!(row.phone_2.as_deref(), Some("+44 2079460958"));
+        assert_eq!(row.phone_2_e164.as_deref(), Some("+442079460958"));
+
+        let row = sqlx::query!("SELECT phone, phone_e164 FROM customers WHERE id = ?", junk)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(row.phone.as_deref(), Some("555-0199"));
+        assert_eq!(row.phone_e164, None);
+
+        let row = sqlx::query!(
+            "SELECT phone_e164, phone_2, phone_2_e164 FROM customers WHERE id = ?",
+            mixed
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(row.phone_e164.as_deref(), Some("+13175550100"));
+        assert_eq!(row.phone_2.as_deref(), Some("call me"));
+        assert_eq!(row.phone_2_e164, None);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn phone_edits_without_e164_are_normalized_again(pool: MySqlPool) {
+        let id = sqlx::query!(
+            "INSERT INTO customers (name, company_id, phone) VALUES ('US', 1, '3175550199')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        assert_eq!(backfill_customer_phones(&pool, 10).await.unwrap(), 1);
+
+        // Writing only the copy keeps it.
+        sqlx::query!(
+            "UPDATE customers SET phone_e164 = '+13175550198' WHERE id = ?",
+            id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let row = sqlx::query!(
+            "SELECT phone_e164, phones_normalized_at FROM customers WHERE id = ?",
+            id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(row.phone_e164.as_deref(), Some("+13175550198"));
+        assert!(row.phones_normalized_at.is_some());
+
+        // A CRM edit changes only the number, so the stale copy is dropped and retried.
+        sqlx::query!(
+            "UPDATE customers SET phone = '(317) 555-0142' WHERE id = ?",
+            id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let row = sqlx::query!(
+            "SELECT phone_e164, phones_normalized_at FROM customers WHERE id = ?",
+            id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(row.phone_e164, None);
+        assert!(row.phones_normalized_at.is_none());
+
+        assert_eq!(backfill_customer_phones(&pool, 10).await.unwrap(), 1);
+        let row = sqlx::query!("SELECT phone, phone_e164 FROM customers WHERE id = ?", id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(row.phone.as_deref(), Some("317-555-0142"));
+        assert_eq!(row.phone_e164.as_deref(), Some("+13175550142"));
+    }
+}
@@ -0,0 +1,337 @@
+use crate::crud::user::SALES_MANAGER;
+use sqlx::mysql::MySqlQueryResult;
+use sqlx::{MySqlExecutor, MySqlPool};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscalationStep {
+    Reping,
+    AutoAssign,
+}
+
+impl EscalationStep {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Reping => "reping",
+            Self::AutoAssign => "auto_assign",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UnassignedLead {
+    pub customer_id: i32,
+    pub company_id: i32,
+    pub customer_name: Option<String>,
+    pub threshold_minutes: i32,
+    pub fallback_mode: String,
+}
+
+#[derive(Debug)]
+pub struct ManagerLeadMessage {
+    pub chat_id: i64,
+    pub message_id: i32,
+    pub message_text: Option<String>,
+}
+
+/// Leads whose first manager message is older than the company's re-ping threshold and
+/// still have no sales rep. Only the last week is scanned so enabling escalation does not
+/// ping managers about an old backlog.
+pub async fn get_leads_due_for_reping(
+    pool: &MySqlPool,
+) -> Result<Vec<UnassignedLead>, sqlx::Error> {
+    sqlx::query_as!(
+        UnassignedLead,
+        r#"
+        SELECT
+            c.id AS customer_id,
+            p.company_id,
+            c.name AS customer_name,
+            p.escalate_after_minutes AS "threshold_minutes!: i32",
+            p.fallback_mode
+        FROM customers c
+        INNER JOIN lead_assignment_policies p ON p.company_id = c.company_id
+        INNER JOIN (
+            SELECT customer_id, MIN(created_at) AS first_sent_at
+            FROM telegram_lead_messages
+            WHERE deleted_at IS NULL
+            GROUP BY customer_id
+        ) tlm ON tlm.customer_id = c.id
+        LEFT JOIN lead_escalations e ON e.customer_id = c.id AND e.step = 'reping'
+        WHERE c.sales_rep IS NULL
+          AND c.deleted_at IS NULL
+          AND p.escalate_after_minutes IS NOT NULL
+          AND tlm.first_sent_at <= NOW() - INTERVAL p.escalate_after_minutes MINUTE
+          AND tlm.first_sent_at >= NOW() - INTERVAL 7 DAY
+          AND e.id IS NULL
+        "#
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Same as [`get_leads_due_for_reping`] for the auto-assign threshold.
+pub async fn get_leads_due_for_auto_assign(
+    pool: &MySqlPool,
+) -> Result<Vec<UnassignedLead>, sqlx::Error> {
+    sqlx::query_as!(
+        UnassignedLead,
+        r#"
+        SELECT
+            c.id AS customer_id,
+            p.company_id,
+            c.name AS customer_name,
+            p.auto_assign_after_minutes AS "threshold_minutes!: i32",
+            p.fallback_mode
+        FROM customers c
+        INNER JOIN lead_assignment_policies p ON p.company_id = c.company_id
+        INNER JOIN (
+            SELECT customer_id, MIN(created_at) AS first_sent_at
+            FROM telegram_lead_messages
+            WHERE deleted_at IS NULL
+            GROUP BY customer_id
+        ) tlm ON tlm.customer_id = c.id
+        LEFT JOIN lead_escalations e ON e.customer_id = c.id AND e.step = 'auto_assign'
+        WHERE c.sales_rep IS NULL
+          AND c.deleted_at IS NULL
+          AND p.auto_assign_after_minutes IS NOT NULL
+          AND tlm.first_sent_at <= NOW() - INTERVAL p.auto_assign_after_minutes MINUTE
+          AND tlm.first_sent_at >= NOW() - INTERVAL 7 DAY
+          AND e.id IS NULL
+        "#
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Records the step before it runs; `false` means an earlier run already claimed it.
+pub async fn claim_lead_escalation<'e>(
+    executor: impl MySqlExecutor<'e>,
+    customer_id: i32,
+    company_id: i32,
+    step: EscalationStep,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        INSERT IGNORE INTO lead_escalations (customer_id, company_id, step)
+        VALUES (?, ?, ?)
+        "#,
+        customer_id,
+        company_id,
+        step.as_str()
+    )
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn set_lead_escalation_assigned_user<'e>(
+    executor: impl MySqlExecutor<'e>,
+    customer_id: i32,
+    step: EscalationStep,
+    user_id: i32,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE lead_escalations
+        SET assigned_user_id = ?
+        WHERE customer_id = ? AND step = ?
+        "#,
+        user_id,
+        customer_id,
+        step.as_str()
+    )
+    .execute(executor)
+    .await
+}
+
+/// Lead messages still visible in sales managers' chats and the company's lead groups, the
+/// same set the assign callback edits in the webhooks bot.
+pub async fn list_manager_lead_messages(
+    pool: &MySqlPool,
+    company_id: i32,
+    customer_id: i32,
+) -> Result<Vec<ManagerLeadMessage>, sqlx::Error> {
+    sqlx::query_as!(
+        ManagerLeadMessage,
+        r#"
+        SELECT tlm.chat_id, tlm.message_id, tlm.message_text
+        FROM telegram_lead_messages tlm
+        WHERE tlm.company_id = ?
+          AND tlm.customer_id = ?
+          AND tlm.deleted_at IS NULL
+          AND (
+            EXISTS (
+                SELECT 1
+                FROM users u
+                INNER JOIN users_positions up
+                    ON up.user_id = u.id
+                    AND up.position_id = ?
+                WHERE u.telegram_id = tlm.chat_id
+                  AND u.company_id = tlm.company_id
+            )
+            OR EXISTS (
+                SELECT 1
+                FROM telegram_lead_groups g
+                WHERE g.chat_id = tlm.chat_id
+                  AND g.company_id = tlm.company_id
+            )
+            OR EXISTS (
+                SELECT 1
+                FROM lead_notification_rules r
+                WHERE r.chat_id = tlm.chat_id
+                  AND r.company_id = tlm.company_id
+            )
+          )
+        "#,
+        company_id,
+        customer_id,
+        SALES_MANAGER
+    )
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crud::leads::assign_unassigned_lead;
+
+    async fn insert_manager(pool: &MySqlPool, telegram_id: i64) {
+        let user_id = sqlx::query!(
+            "INSERT INTO users (email, company_id, telegram_id) VALUES (?, 1, ?)",
+            format!("manager-{telegram_id}@example.com"),
+            telegram_id
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        sqlx::query!(
+            "INSERT INTO users_positions (user_id, position_id) VALUES (?, 2)",
+            user_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn insert_pinged_lead(pool: &MySqlPool, minutes_ago: i32) -> i32 {
+        let customer_id = sqlx::query!(
+            "INSERT INTO customers (name, company_id, source) VALUES ('Waiting Lead', 1, 'leads')"
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        sqlx::query!(
+            "INSERT INTO telegram_lead_messages (customer_id, company_id, chat_id, message_id, message_text, created_at) \
+             VALUES (?, 1, 456, ?, 'Name: Waiting Lead', NOW() - INTERVAL ? MINUTE)",
+            customer_id,
+            customer_id,
+            minutes_ago
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        i32::try_from(customer_id).unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn reping_is_due_once_after_threshold(pool: MySqlPool) {
+        sqlx::query!(
+            "INSERT INTO lead_assignment_policies (company_id, escalate_after_minutes) VALUES (1, 30)"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let waiting = insert_pinged_lead(&pool, 45).await;
+        insert_pinged_lead(&pool, 10).await;
+
+        let due = get_leads_due_for_reping(&pool).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].customer_id, waiting);
+        assert_eq!(due[0].threshold_minutes, 30);
+        assert!(
+            get_leads_due_for_auto_assign(&pool)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        assert!(
+            claim_lead_escalation(&pool, waiting, 1, EscalationStep::Reping)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !claim_lead_escalation(&pool, waiting, 1, EscalationStep::Reping)
+                .await
+                .unwrap()
+        );
+        assert!(get_leads_due_for_reping(&pool).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn assigned_leads_are_not_escalated(pool: MySqlPool) {
+        sqlx::query!(
+            "INSERT INTO lead_assignment_policies (company_id, escalate_after_minutes, auto_assign_after_minutes) VALUES (1, 30, 60)"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let customer_id = insert_pinged_lead(&pool, 90).await;
+        let rep_id = sqlx::query!(
+            "INSERT INTO users (email, company_id) VALUES ('rep-escalation@example.com', 1)"
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        sqlx::query!(
+            "UPDATE customers SET sales_rep = ? WHERE id = ?",
+            rep_id,
+            customer_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert!(get_leads_due_for_reping(&pool).await.unwrap().is_empty());
+        assert!(
+            get_leads_due_for_auto_assign(&pool)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // An escalation that read the lead before the manager assigned it leaves it alone.
+        let other_rep = i32::try_from(rep_id).unwrap() + 1;
+        assert!(
+            !assign_unassigned_lead(&pool, customer_id, other_rep)
+                .await
+                .unwrap()
+        );
+        let sales_rep =
+            sqlx::query_scalar!("SELECT sales_rep FROM customers WHERE id = ?", customer_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(sales_rep, Some(i32::try_from(rep_id).unwrap()));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn manager_messages_keep_their_text(pool: MySqlPool) {
+        insert_manager(&pool, 456).await;
+        let customer_id = insert_pinged_lead(&pool, 5).await;
+
+        let messages = list_manager_lead_messages(&pool, 1, customer_id)
+            .await
+            .unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].chat_id, 456);
+        assert_eq!(
+            messages[0].message_text.as_deref(),
+            Some("Name: Waiting Lead")
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use chrono::NaiveDateTime;
+use sqlx::MySqlPool;
+use sqlx::mysql::MySqlQueryResult;
+
+#[derive(Debug)]
+pub struct DelayedLeadNotification {
+    pub id: i64,
+    pub company_id: i32,
+    pub customer_id: i32,
+    pub chat_id: i64,
+    pub message_text: String,
+    pub include_assignment_prompt: bool,
+    pub sales_rep: Option<i32>,
+}
+
+/// Holds a lead notification for one chat until `send_after` (UTC). Notifications without
+/// the assignment prompt are sent even if the lead is assigned in the meantime.
+pub async fn queue_delayed_lead_notification(
+    pool: &MySqlPool,
+    company_id: i32,
+    customer_id: i32,
+    chat_id: i64,
+    message_text: &str,
+    include_assignment_prompt: bool,
+    send_after: NaiveDateTime,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO delayed_lead_notifications
+            (company_id, customer_id, chat_id, message_text, include_assignment_prompt, send_after)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        company_id,
+        customer_id,
+        chat_id,
+        message_text,
+        include_assignment_prompt,
+        send_after
+    )
+    .execute(pool)
+    .await
+}
+
+/// Held notifications whose quiet hours are over, with the customer's current rep so leads
+/// assigned in the meantime can be skipped.
+pub async fn get_due_delayed_lead_notifications(
+    pool: &MySqlPool,
+) -> Result<Vec<DelayedLeadNotification>, sqlx::Error> {
+    sqlx::query_as!(
+        DelayedLeadNotification,
+        r#"
+        SELECT d.id, d.company_id, d.customer_id, d.chat_id, d.message_text,
+               d.include_assignment_prompt as "include_assignment_prompt: bool", c.sales_rep
+        FROM delayed_lead_notifications d
+        INNER JOIN customers c ON c.id = d.customer_id
+        WHERE d.sent_at IS NULL
+          AND d.send_after <= UTC_TIMESTAMP()
+          AND c.deleted_at IS NULL
+        ORDER BY d.id
+        "#
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn mark_delayed_lead_notification_sent(
+    pool: &MySqlPool,
+    id: i64,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        "UPDATE delayed_lead_notifications SET sent_at = UTC_TIMESTAMP() WHERE id = ?",
+        id
+    )
+    .execute(pool)
+    .await
+}
+
+/// Same row the webhooks bot writes per lead message, so escalation and cleanup find
+/// notifications that went out late.
+pub async fn insert_lead_message(
+    pool: &MySqlPool,
+    customer_id: i32,
+    company_id: i32,
+    chat_id: i64,
+    message_id: i32,
+    message_text: &str,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO telegram_lead_messages (customer_id, company_id, chat_id, message_id, message_text)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+        customer_id,
+        company_id,
+        chat_id,
+        message_id,
+        message_text
+    )
+    .execute(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeDelta, Utc};
+
+    async fn insert_customer(pool: &MySqlPool) -> i32 {
+        let id = sqlx::query!(
+            "INSERT INTO customers (name, company_id, source) VALUES ('Night Lead', 1, 'leads')"
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        i32::try_from(id).unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_only_due_unsent_notifications_are_returned(pool: MySqlPool) {
+        let customer_id = insert_customer(&pool).await;
+        let now = Utc::now().naive_utc();
+        queue_delayed_lead_notification(
+            &pool,
+            1,
+            customer_id,
+            456,
+            "Lead",
+            true,
+            now - TimeDelta::minutes(1),
+        )
+        .await
+        .unwrap();
+        queue_delayed_lead_notification(
+            &pool,
+            1,
+            customer_id,
+            789,
+            "Lead",
+            true,
+            now + TimeDelta::hours(1),
+        )
+        .await
+        .unwrap();
+
+        let due = get_due_delayed_lead_notifications(&pool).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].chat_id, 456);
+        assert_eq!(due[0].sales_rep, None);
+
+        mark_delayed_lead_notification_sent(&pool, due[0].id)
+            .await
+            .unwrap();
+        assert!(
+            get_due_delayed_lead_notifications(&pool)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use sqlx::mysql::MySqlQueryResult;
+use sqlx::{MySqlConnection, MySqlExecutor, MySqlPool, query};
+
+pub async fn assign_lead<'e>(
+    executor: impl MySqlExecutor<'e>,
+    lead_id: i32,
+    user_id: i32,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    return query!(
+        r#"UPDATE customers SET sales_rep = ?, assigned_date = NOW() WHERE id = ?"#,
+        user_id,
+        lead_id,
+    )
+    .execute(executor)
+    .await;
+}
+
+/// Like `assign_lead`, but only while nobody has the lead; `false` means someone assigned
+/// it first.
+pub async fn assign_unassigned_lead<'e>(
+    executor: impl MySqlExecutor<'e>,
+    lead_id: i32,
+    user_id: i32,
+) -> Result<bool, sqlx::Error> {
+    let result = query!(
+        r#"UPDATE customers SET sales_rep = ?, assigned_date = NOW() WHERE id = ? AND sales_rep IS NULL"#,
+        user_id,
+        lead_id,
+    )
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+pub struct CreatedDeal {
+    pub id: u64,
+    pub created: bool,
+}
+
+impl CreatedDeal {
+    pub fn last_insert_id(&self) -> u64 {
+        self.id
+    }
+}
+
+pub async fn create_deal(
+    pool: &MySqlPool,
+    customer_id: i32,
+    list_id: i32,
+    next_pos: i32,
+    sales_rep: i32,
+) -> Result<CreatedDeal, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let deal = create_deal_in(&mut tx, customer_id, list_id, next_pos, sales_rep).await?;
+    tx.commit().await?;
+    Ok(deal)
+}
+
+/// `create_deal` inside the caller's transaction, so it commits together with other writes.
+pub async fn create_deal_in(
+    tx: &mut MySqlConnection,
+    customer_id: i32,
+    list_id: i32,
+    next_pos: i32,
+    sales_rep: i32,
+) -> Result<CreatedDeal, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT id FROM customers WHERE id = ? FOR UPDATE"#,
+        customer_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let existing_id = sqlx::query_scalar!(
+        r#"SELECT id FROM deals
+           WHERE customer_id = ?
+             AND deleted_at IS NULL
+             AND is_won IS NULL
+           ORDER BY id ASC
+           LIMIT 1"#,
+        customer_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if let Some(existing_id) = existing_id {
+        query!(
+            r#"UPDATE deals
+               SET user_id = ?
+               WHERE id = ?
+                 AND deleted_at IS NULL
+                 AND (user_id IS NULL OR user_id <> ?)"#,
+            sales_rep,
+            existing_id,
+            sales_rep,
+        )
+        .execute(&mut *tx)
+        .await?;
+        return Ok(CreatedDeal {
+            id: existing_id,
+            created: false,
+        });
+    }
+
+    let result = query!(
+        r#"INSERT INTO deals (customer_id, status, list_id, position, user_id) VALUES (?,?,?,?,?)"#,
+        customer_id,
+        "New Customer",
+        list_id,
+        next_pos,
+        sales_rep,
+    )
+    .execute(&mut *tx)
+    .await?;
+    Ok(CreatedDeal {
+        id: result.last_insert_id(),
+        created: true,
+    })
+}
+
+pub async fn get_default_list_id_from_company_id(
+    pool: &MySqlPool,
+    company_id: i32,
+) -> Result<i32, sqlx::Error> {
+    let id = sqlx::query_scalar!(
+        r#"
+        SELECT dl.id
+        FROM deals_list dl
+        INNER JOIN groups_list gl ON dl.group_id = gl.id
+        WHERE gl.company_id = ?
+          AND gl.is_default = 1
+          AND dl.deleted_at IS NULL
+          AND gl.deleted_at IS NULL
+        ORDER BY dl.position ASC, dl.id ASC
+        LIMIT 1
+        "#,
+        company_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(id.unwrap_or(1))
+}
@@ -1,7 +1,14 @@
+pub mod assignment;
+pub mod conversion_exports;
+pub mod customer_phones;
 pub mod email_template;
+pub mod lead_escalations;
+pub mod lead_notifications;
+pub mod leads;
 pub mod notifications;
 pub mod outbound_email;
 pub mod scheduled_emails;
 pub mod setup;
+pub mod suppressions;
 pub mod template;
 pub mod user;
@@ -66,3 +66,29 @@ pub async fn mark_deadline_reminder_telegram_sent(
     .execute(pool)
     .await
 }
+
+/// Moves an open reminder of `user_id` `minutes` ahead so the reminder job sends it again.
+pub async fn snooze_activity_deadline_reminder(
+    pool: &MySqlPool,
+    notification_id: u64,
+    user_id: i32,
+    minutes: u32,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE notifications
+        SET due_at = UTC_TIMESTAMP() + INTERVAL ? MINUTE,
+            actor_name = NULL
+        WHERE id = ?
+          AND user_id = ?
+          AND notification_type = 'activity_deadline_reminder'
+          AND is_done = 0
+        "#,
+        minutes,
+        notification_id,
+        user_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
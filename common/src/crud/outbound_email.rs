@@ -31,14 +31,76 @@ pub fn normalize_outbound_message_id(raw: &str) -> String {
     }
 }
 
+/// An email sent outside the drip sequence, such as a lead auto-reply. Without a
+/// `thread_id` the email starts a new conversation.
+pub struct OutboundEmail<'a> {
+    pub user_id: i32,
+    pub customer_id: Option<i32>,
+    pub company_id: i32,
+    pub deal_id: Option<i32>,
+    pub subject: &'a str,
+    pub html_body: &'a str,
+    pub sender_from: &'a str,
+    pub recipient_email: &'a str,
+    pub message_id: &'a str,
+    pub thread_id: Option<&'a str>,
+}
+
 pub async fn record_outbound_scheduled_email(
     pool: &MySqlPool,
     email: &OutboundScheduledEmail,
 ) -> Result<u64, sqlx::Error> {
-    let thread_id = Uuid::new_v4().to_string();
-    let sender_email = extract_email_address(&email.sender_from);
-    let receiver_email = extract_email_address(&email.recipient_email);
     let message_id = normalize_outbound_message_id(&email.message_id);
+    let email_id = insert_outbound_email(
+        pool,
+        &OutboundEmail {
+            user_id: email.user_id,
+            customer_id: Some(email.customer_id),
+            company_id: email.company_id,
+            deal_id: Some(email.deal_id),
+            subject: &email.subject,
+            html_body: &email.html_body,
+            sender_from: &email.sender_from,
+            recipient_email: &email.recipient_email,
+            message_id: &message_id,
+            thread_id: None,
+        },
+    )
+    .await?;
+    sqlx::query("UPDATE scheduled_emails SET message_id = ? WHERE id = ?")
+        .bind(&message_id)
+        .bind(email.scheduled_email_id)
+        .execute(pool)
+        .await?;
+
+    Ok(email_id)
+}
+
+/// Records a sent email in the customer's conversation history.
+pub async fn record_outbound_email(
+    pool: &MySqlPool,
+    email: &OutboundEmail<'_>,
+) -> Result<u64, sqlx::Error> {
+    let message_id = normalize_outbound_message_id(email.message_id);
+    insert_outbound_email(
+        pool,
+        &OutboundEmail {
+            message_id: &message_id,
+            ..*email
+        },
+    )
+    .await
+}
+
+async fn insert_outbound_email(
+    pool: &MySqlPool,
+    email: &OutboundEmail<'_>,
+) -> Result<u64, sqlx::Error> {
+    let thread_id = email
+        .thread_id
+        .map_or_else(|| Uuid::new_v4().to_string(), str::to_string);
+    let sender_email = extract_email_address(email.sender_from);
+    let receiver_email = extract_email_address(email.recipient_email);
 
     let mut result = sqlx::query(
         r#"
@@ -50,10 +112,10 @@ pub async fn record_outbound_scheduled_email(
         "#,
     )
     .bind(email.user_id)
-    .bind(&email.subject)
-    .bind(&email.html_body)
-    .bind(&email.html_body)
-    .bind(&message_id)
+    .bind(email.subject)
+    .bind(email.html_body)
+    .bind(email.html_body)
+    .bind(email.message_id)
     .bind(&sender_email)
     .bind(&receiver_email)
     .bind(&thread_id)
@@ -74,9 +136,9 @@ pub async fn record_outbound_scheduled_email(
                 "#,
             )
             .bind(email.user_id)
-            .bind(&email.subject)
-            .bind(&email.html_body)
-            .bind(&message_id)
+            .bind(email.subject)
+            .bind(email.html_body)
+            .bind(email.message_id)
             .bind(&sender_email)
             .bind(&receiver_email)
             .bind(&thread_id)
@@ -99,11 +161,6 @@ pub async fn record_outbound_scheduled_email(
         email.customer_id,
     )
     .await?;
-    sqlx::query("UPDATE scheduled_emails SET message_id = ? WHERE id = ?")
-        .bind(&message_id)
-        .bind(email.scheduled_email_id)
-        .execute(pool)
-        .await?;
 
     Ok(email_id)
 }
@@ -125,7 +182,7 @@ async fn insert_outbound_participants(
     sender_email: &str,
     receiver_email: &str,
     user_id: i32,
-    customer_id: i32,
+    customer_id: Option<i32>,
 ) -> Result<MySqlQueryResult, sqlx::Error> {
     sqlx::query(
         r#"
@@ -113,6 +113,56 @@ pub async fn cancel_pending_emails_for_non_leads(
     .await
 }
 
+/// Cancels the company's pending drip emails to `email` (case-insensitive), e.g. after a
+/// permanent bounce or a spam complaint.
+pub async fn cancel_pending_scheduled_emails_for_address(
+    pool: &MySqlPool,
+    company_id: i32,
+    email: &str,
+    reason: &str,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE scheduled_emails se
+        INNER JOIN customers c ON c.id = se.customer_id
+        INNER JOIN customers_emails ce ON ce.id = c.email_id
+        SET se.status = 'cancelled',
+            se.error_message = ?
+        WHERE se.company_id = ?
+          AND se.status = 'pending'
+          AND LOWER(TRIM(ce.email)) = LOWER(TRIM(?))
+        "#,
+        reason,
+        company_id,
+        email
+    )
+    .execute(pool)
+    .await
+}
+
+pub async fn cancel_pending_scheduled_emails_for_customer(
+    pool: &MySqlPool,
+    company_id: i32,
+    customer_id: i32,
+    reason: &str,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE scheduled_emails
+        SET status = 'cancelled',
+            error_message = ?
+        WHERE company_id = ?
+          AND customer_id = ?
+          AND status = 'pending'
+        "#,
+        reason,
+        company_id,
+        customer_id
+    )
+    .execute(pool)
+    .await
+}
+
 async fn is_lead_customer(pool: &MySqlPool, customer_id: i32) -> Result<bool, sqlx::Error> {
     let source = sqlx::query_scalar!(
         r#"SELECT source FROM customers WHERE id = ? AND deleted_at IS NULL"#,
@@ -191,6 +241,16 @@ pub async fn get_ready_scheduled_emails(
                 AND d.list_id = scheduled_emails.list_id
             )
           )
+          AND NOT EXISTS (
+            SELECT 1
+            FROM email_events ev
+            WHERE ev.company_id = scheduled_emails.company_id
+              AND ev.recipient_email = LOWER(TRIM(customers_emails.email))
+              AND (
+                ev.event_type = 'complaint'
+                OR (ev.event_type = 'bounce' AND ev.bounce_type = 'Permanent')
+              )
+          )
         "#
     )
     .fetch_all(pool)
@@ -755,6 +815,95 @@ mod tests {
         assert!(ready_after.is_empty());
     }
 
+    /// A permanent bounce or complaint recorded for the company hides the address from ready.
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_bounced_address_is_not_ready(pool: MySqlPool) {
+        let user_id = insert_test_user(&pool, "test_bounce@example.com", "Test Bounce").await;
+        let bounced = insert_test_customer(&pool, "Bounced@Test.com", "Bounced", 1).await;
+        let transient = insert_test_customer(&pool, "slow@test.com", "Slow", 1).await;
+        let template_id = insert_test_template(&pool, "test_bounce_tpl", "Hi", Some(0)).await;
+
+        for (customer_id, deal_id) in [(bounced, 90080), (transient, 90081)] {
+            insert_scheduled_email(
+                &pool,
+                make_template(template_id, Some(0)),
+                deal_id,
+                customer_id,
+                user_id,
+                1,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        let bounces = [
+            ("bounced@test.com", "Permanent"),
+            ("slow@test.com", "Transient"),
+        ];
+        for (recipient, bounce_type) in bounces {
+            sqlx::query!(
+                "INSERT INTO email_events (event_id, ses_message_id, company_id, event_type, recipient_email, bounce_type) \
+                 VALUES (?, 'ses-1', 1, 'bounce', ?, ?)",
+                recipient,
+                recipient,
+                bounce_type
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let ready = get_ready_scheduled_emails(&pool).await.unwrap();
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].customer_id, transient);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_cancel_pending_for_address_is_company_scoped(pool: MySqlPool) {
+        let user_id = insert_test_user(&pool, "test_cancel@example.com", "Test Cancel").await;
+        let customer_id = insert_test_customer(&pool, "complainer@test.com", "Complainer", 1).await;
+        let template_id = insert_test_template(&pool, "test_cancel_tpl", "Hi", Some(0)).await;
+        insert_scheduled_email(
+            &pool,
+            make_template(template_id, Some(0)),
+            90090,
+            customer_id,
+            user_id,
+            1,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let other_company =
+            cancel_pending_scheduled_emails_for_address(&pool, 2, "complainer@test.com", "x")
+                .await
+                .unwrap();
+        assert_eq!(other_company.rows_affected(), 0);
+
+        let cancelled = cancel_pending_scheduled_emails_for_address(
+            &pool,
+            1,
+            " COMPLAINER@test.com",
+            "Recipient complained",
+        )
+        .await
+        .unwrap();
+        assert_eq!(cancelled.rows_affected(), 1);
+
+        let row = sqlx::query!(
+            "SELECT status, error_message FROM scheduled_emails WHERE customer_id = ?",
+            customer_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(row.status, "cancelled");
+        assert_eq!(row.error_message.as_deref(), Some("Recipient complained"));
+    }
+
     /// Test that marking a non-existent email as failed does not crash.
     #[sqlx::test(migrations = "../migrations")]
     async fn test_mark_failed_nonexistent_id(pool: MySqlPool) {
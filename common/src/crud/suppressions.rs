@@ -0,0 +1,176 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use sqlx::mysql::MySqlQueryResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SuppressionReason {
+    Unsubscribe,
+    Bounce,
+    Complaint,
+    Manual,
+}
+
+impl SuppressionReason {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Unsubscribe => "unsubscribe",
+            Self::Bounce => "bounce",
+            Self::Complaint => "complaint",
+            Self::Manual => "manual",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmailSuppression {
+    pub id: i32,
+    pub email: String,
+    pub reason: String,
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Addresses are stored trimmed and lowercased so lookups ignore how the CRM typed them.
+pub fn normalize_suppressed_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+/// Keeps the first entry for an address: re-adding it leaves the original reason and source.
+pub async fn add_email_suppression(
+    pool: &MySqlPool,
+    company_id: i32,
+    email: &str,
+    reason: SuppressionReason,
+    source: &str,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT IGNORE INTO email_suppressions (company_id, email, reason, source)
+        VALUES (?, ?, ?, ?)
+        "#,
+        company_id,
+        normalize_suppressed_email(email),
+        reason.as_str(),
+        source
+    )
+    .execute(pool)
+    .await
+}
+
+pub async fn remove_email_suppression(
+    pool: &MySqlPool,
+    company_id: i32,
+    email: &str,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"DELETE FROM email_suppressions WHERE company_id = ? AND email = ?"#,
+        company_id,
+        normalize_suppressed_email(email)
+    )
+    .execute(pool)
+    .await
+}
+
+/// Returns the stored reason when the company must not email this address.
+pub async fn get_email_suppression_reason(
+    pool: &MySqlPool,
+    company_id: i32,
+    email: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT reason AS "reason!: String"
+        FROM email_suppressions
+        WHERE company_id = ? AND email = ?
+        "#,
+        company_id,
+        normalize_suppressed_email(email)
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn list_email_suppressions(
+    pool: &MySqlPool,
+    company_id: i32,
+) -> Result<Vec<EmailSuppression>, sqlx::Error> {
+    sqlx::query_as!(
+        EmailSuppression,
+        r#"
+        SELECT id, email, reason AS "reason!: String", source, created_at
+        FROM email_suppressions
+        WHERE company_id = ?
+        ORDER BY id ASC
+        "#,
+        company_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::MySqlPool;
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn suppression_lookup_ignores_case_and_whitespace(pool: MySqlPool) {
+        add_email_suppression(
+            &pool,
+            1,
+            " Customer@Example.com ",
+            SuppressionReason::Manual,
+            "crm",
+        )
+        .await
+        .unwrap();
+
+        let reason = get_email_suppression_reason(&pool, 1, "customer@example.com")
+            .await
+            .unwrap();
+        assert_eq!(reason.as_deref(), Some("manual"));
+
+        let listed = list_email_suppressions(&pool, 1).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].email, "customer@example.com");
+        assert_eq!(listed[0].source, "crm");
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn re_adding_keeps_the_original_reason(pool: MySqlPool) {
+        let first = add_email_suppression(&pool, 1, "a@b.com", SuppressionReason::Complaint, "ses")
+            .await
+            .unwrap();
+        assert_eq!(first.rows_affected(), 1);
+        let second = add_email_suppression(&pool, 1, "A@B.com", SuppressionReason::Manual, "crm")
+            .await
+            .unwrap();
+        assert_eq!(second.rows_affected(), 0);
+
+        let reason = get_email_suppression_reason(&pool, 1, "a@b.com")
+            .await
+            .unwrap();
+        assert_eq!(reason.as_deref(), Some("complaint"));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn removal_is_company_scoped(pool: MySqlPool) {
+        add_email_suppression(&pool, 1, "a@b.com", SuppressionReason::Bounce, "ses")
+            .await
+            .unwrap();
+
+        let other_company = remove_email_suppression(&pool, 2, "a@b.com").await.unwrap();
+        assert_eq!(other_company.rows_affected(), 0);
+
+        let removed = remove_email_suppression(&pool, 1, "A@B.com").await.unwrap();
+        assert_eq!(removed.rows_affected(), 1);
+        assert!(
+            get_email_suppression_reason(&pool, 1, "a@b.com")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -71,7 +71,7 @@ async fn fetch_customer_data(
     if let Some(d_id) = deal_id {
         let row = sqlx::query!(
             r#"
-            SELECT c.name, c.address
+            SELECT c.id, c.name, c.address
             FROM deals d
             JOIN customers c ON d.customer_id = c.id
             WHERE d.id = ? AND d.deleted_at IS NULL AND c.company_id = ?
@@ -85,6 +85,7 @@ async fn fetch_customer_data(
 
         if let Some(r) = row {
             return Ok(Some(InfoVariableData {
+                id: Some(r.id),
                 name: r.name,
                 address: r.address,
                 ..Default::default()
@@ -95,7 +96,7 @@ async fn fetch_customer_data(
     if let Some(c_id) = customer_id {
         let row = sqlx::query!(
             r#"
-            SELECT name, address
+            SELECT id, name, address
             FROM customers
             WHERE id = ? AND deleted_at IS NULL AND company_id = ?
             LIMIT 1
@@ -108,6 +109,7 @@ async fn fetch_customer_data(
 
         if let Some(r) = row {
             return Ok(Some(InfoVariableData {
+                id: Some(r.id),
                 name: r.name,
                 address: r.address,
                 ..Default::default()
@@ -1,5 +1,9 @@
 use sqlx::MySqlPool;
 
+/// `users_positions.position_id` of sales reps and sales managers.
+pub const SALES_WORKER: i32 = 1;
+pub const SALES_MANAGER: i32 = 2;
+
 pub struct UserData {
     pub name: Option<String>,
     pub email: Option<String>,
@@ -8,6 +12,16 @@ pub struct UserData {
     pub company_id: Option<i32>,
 }
 
+#[derive(Debug)]
+pub struct SalesUser {
+    pub id: i32,
+    pub telegram_id: Option<i64>,
+    pub name: Option<String>,
+    pub position_id: i32,
+    pub mtd_lead_count: i64,
+    pub user_position_id: i32,
+}
+
 pub async fn get_user_template(pool: &MySqlPool, user_id: i32) -> Result<UserData, sqlx::Error> {
     sqlx::query_as!(
         UserData,
@@ -22,3 +36,34 @@ pub async fn get_user_template(pool: &MySqlPool, user_id: i32) -> Result<UserDat
     .fetch_one(pool)
     .await
 }
+
+pub async fn get_sales_users(
+    pool: &MySqlPool,
+    company_id: i32,
+) -> Result<Vec<SalesUser>, sqlx::Error> {
+    sqlx::query_as!(
+        SalesUser,
+        r#"
+        SELECT
+            u.id,
+            u.telegram_id,
+            u.name,
+            up.position_id,
+            up.id as user_position_id,
+            COUNT(c.id) as mtd_lead_count
+        FROM users u
+        INNER JOIN users_positions up ON u.id = up.user_id
+        LEFT JOIN customers c ON u.id = c.sales_rep
+            AND c.source = 'leads'
+            AND c.assigned_date >= DATE_FORMAT(NOW(), '%Y-%m-01')
+            AND c.company_id = u.company_id
+            AND c.deleted_at IS NULL
+        WHERE u.company_id = ?
+        AND (up.position_id = 1 OR up.position_id = 2)
+        GROUP BY u.id, u.telegram_id, u.name, up.position_id, user_position_id
+        "#,
+        company_id
+    )
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,191 @@
+use crate::utils::signing::{sign_payload_truncated, verify_truncated_payload};
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+
+/// Keeps `crm:<action>:<id>:<signature>` within Telegram's 64-byte callback data.
+const SIGNATURE_BYTES: usize = 12;
+
+/// Buttons under CRM notifications. Each button is signed for the Telegram user it was
+/// sent to, so a forwarded notification does nothing for anyone else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrmAction {
+    MarkContacted(u64),
+    NextStage(u64),
+    SnoozeHour(u64),
+    SnoozeDay(u64),
+}
+
+impl CrmAction {
+    const fn code(self) -> &'static str {
+        match self {
+            Self::MarkContacted(_) => "contacted",
+            Self::NextStage(_) => "stage",
+            Self::SnoozeHour(_) => "snooze1h",
+            Self::SnoozeDay(_) => "snooze1d",
+        }
+    }
+
+    /// Deal id for deal actions, `notifications.id` for snoozes.
+    const fn target_id(self) -> u64 {
+        match self {
+            Self::MarkContacted(id)
+            | Self::NextStage(id)
+            | Self::SnoozeHour(id)
+            | Self::SnoozeDay(id) => id,
+        }
+    }
+
+    fn from_parts(code: &str, id: u64) -> Option<Self> {
+        match code {
+            "contacted" => Some(Self::MarkContacted(id)),
+            "stage" => Some(Self::NextStage(id)),
+            "snooze1h" => Some(Self::SnoozeHour(id)),
+            "snooze1d" => Some(Self::SnoozeDay(id)),
+            _ => None,
+        }
+    }
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::MarkContacted(_) => "✅ Mark contacted",
+            Self::NextStage(_) => "➡️ Next stage",
+            Self::SnoozeHour(_) => "⏰ Snooze 1h",
+            Self::SnoozeDay(_) => "⏰ Snooze 1d",
+        }
+    }
+}
+
+/// Secret for signing action buttons; notifications go out without buttons while unset.
+pub fn crm_actions_secret_from_env() -> Option<String> {
+    std::env::var("TELEGRAM_ACTIONS_SECRET")
+        .ok()
+        .filter(|secret| !secret.is_empty())
+}
+
+fn action_payload(action: CrmAction, telegram_id: i64) -> String {
+    format!("crm:{}:{}:{telegram_id}", action.code(), action.target_id())
+}
+
+pub fn crm_action_callback_data(secret: &str, action: CrmAction, telegram_id: i64) -> String {
+    format!(
+        "crm:{}:{}:{}",
+        action.code(),
+        action.target_id(),
+        sign_payload_truncated(
+            secret,
+            &action_payload(action, telegram_id),
+            SIGNATURE_BYTES
+        )
+    )
+}
+
+/// Inline keyboard with `rows`, each button signed for `telegram_id`.
+pub fn crm_action_markup(
+    secret: &str,
+    telegram_id: i64,
+    rows: Vec<Vec<CrmAction>>,
+) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(rows.into_iter().map(|row| {
+        row.into_iter()
+            .map(|action| {
+                InlineKeyboardButton::callback(
+                    action.label(),
+                    crm_action_callback_data(secret, action, telegram_id),
+                )
+            })
+            .collect::<Vec<_>>()
+    }))
+}
+
+/// The action behind `data`, only when it was signed for `telegram_id`.
+pub fn parse_crm_action(secret: &str, data: &str, telegram_id: i64) -> Option<CrmAction> {
+    let mut parts = data.split(':');
+    if parts.next()? != "crm" {
+        return None;
+    }
+    let action = CrmAction::from_parts(parts.next()?, parts.next()?.parse().ok()?)?;
+    let signature = parts.next()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    verify_truncated_payload(
+        secret,
+        &action_payload(action, telegram_id),
+        signature,
+        SIGNATURE_BYTES,
+    )
+    .then_some(action)
+}
+
+/// Button rows under a notification about a deal.
+pub fn deal_action_rows(deal_id: u64) -> Vec<Vec<CrmAction>> {
+    vec![vec![
+        CrmAction::MarkContacted(deal_id),
+        CrmAction::NextStage(deal_id),
+    ]]
+}
+
+/// Button rows under an activity deadline reminder.
+pub fn reminder_action_rows(notification_id: u64, deal_id: u64) -> Vec<Vec<CrmAction>> {
+    let mut rows = vec![vec![
+        CrmAction::SnoozeHour(notification_id),
+        CrmAction::SnoozeDay(notification_id),
+    ]];
+    rows.extend(deal_action_rows(deal_id));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use teloxide::types::InlineKeyboardButtonKind;
+
+    #[test]
+    fn callback_data_only_parses_for_its_recipient() {
+        let action = CrmAction::NextStage(u64::MAX);
+        let data = crm_action_callback_data("secret", action, 456);
+        assert!(data.len() <= 64);
+        assert_eq!(parse_crm_action("secret", &data, 456), Some(action));
+        assert_eq!(parse_crm_action("secret", &data, 789), None);
+        assert_eq!(parse_crm_action("other", &data, 456), None);
+    }
+
+    #[test]
+    fn tampered_callback_data_is_rejected() {
+        let data = crm_action_callback_data("secret", CrmAction::SnoozeHour(7), 456);
+        let tampered = data.replacen("snooze1h:7", "snooze1d:7", 1);
+        assert_eq!(parse_crm_action("secret", &tampered, 456), None);
+        assert_eq!(parse_crm_action("secret", "assign:1:2", 456), None);
+    }
+
+    #[test]
+    fn markup_keeps_rows_and_signs_every_button() {
+        let markup = crm_action_markup("secret", 456, reminder_action_rows(7, 9));
+        let rows: Vec<Vec<Option<CrmAction>>> = markup
+            .inline_keyboard
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|button| match &button.kind {
+                        InlineKeyboardButtonKind::CallbackData(data) => {
+                            parse_crm_action("secret", data, 456)
+                        }
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    Some(CrmAction::SnoozeHour(7)),
+                    Some(CrmAction::SnoozeDay(7))
+                ],
+                vec![
+                    Some(CrmAction::MarkContacted(9)),
+                    Some(CrmAction::NextStage(9))
+                ],
+            ]
+        );
+    }
+}
@@ -1,8 +1,11 @@
+use crate::telegram::html::HtmlMessage;
+
 pub const TELEGRAM_SENT_MARKER: &str = "__telegram_sent__";
 
 const EMAIL_ICON: &str = "✉️";
 const ACTIVITY_ICON: &str = "📋";
 const SMS_ICON: &str = "💬";
+const CLICK_ICON: &str = "🔗";
 
 pub fn notification_type_title(notification_type: &str) -> &'static str {
     match notification_type {
@@ -41,16 +44,18 @@ pub fn format_activity_notification(
     message: &str,
     deal_id: i32,
 ) -> String {
-    let title = notification_type_title(notification_type);
-    let customer = customer_name.unwrap_or("Deal");
     let actor_line = match actor_name {
         Some(name) if !name.is_empty() => format!("{name}: {message}"),
         _ => message.to_string(),
     };
-    format!(
-        "{ACTIVITY_ICON} {title}\n\nCustomer: {customer}\n{actor_line}\n\n{}",
-        deal_project_url(deal_id)
-    )
+    HtmlMessage::new()
+        .heading(ACTIVITY_ICON, notification_type_title(notification_type))
+        .blank_line()
+        .field("Customer", customer_name.unwrap_or("Deal"))
+        .text(&actor_line)
+        .blank_line()
+        .link("Open deal", &deal_project_url(deal_id))
+        .build()
 }
 
 pub fn format_email_notification(
@@ -59,19 +64,49 @@ pub fn format_email_notification(
     deal_id: Option<u64>,
     thread_id: &str,
 ) -> String {
-    let customer = customer_name.unwrap_or("Customer");
-    let subject_line = subject.unwrap_or("New email");
     let url = match deal_id.and_then(|value| i32::try_from(value).ok()) {
         Some(deal_id) => deal_email_chat_url(deal_id, thread_id),
         None => emails_chat_url(thread_id),
     };
-    format!("{EMAIL_ICON} New email\n\nCustomer: {customer}\nSubject: {subject_line}\n\n{url}")
+    HtmlMessage::new()
+        .heading(EMAIL_ICON, "New email")
+        .blank_line()
+        .field("Customer", customer_name.unwrap_or("Customer"))
+        .field("Subject", subject.unwrap_or("New email"))
+        .blank_line()
+        .link("Open email", &url)
+        .build()
+}
+
+pub fn format_email_click_notification(customer_name: Option<&str>, url: &str) -> String {
+    HtmlMessage::new()
+        .heading(CLICK_ICON, "Email link clicked")
+        .blank_line()
+        .field("Customer", customer_name.unwrap_or("Customer"))
+        .field("Link", url)
+        .build()
 }
 
-pub fn format_sms_notification(sender_phone: &str, message: &str, phone_digits: &str) -> String {
-    format!(
-        "{SMS_ICON} New CloudTalk SMS from {sender_phone}\n\n{message}\n\nOpen thread: /employee/cloudtalk/thread/{phone_digits}"
-    )
+pub fn format_sms_notification(
+    sender_phone: &str,
+    message: &str,
+    image_urls: &[String],
+    phone_digits: &str,
+) -> String {
+    let mut text = HtmlMessage::new()
+        .heading(SMS_ICON, &format!("New CloudTalk SMS from {sender_phone}"))
+        .blank_line();
+    if !message.is_empty() {
+        text = text.text(message);
+    }
+    for (index, url) in image_urls.iter().enumerate() {
+        text = text.link(&format!("Photo {}", index + 1), url);
+    }
+    text.blank_line()
+        .text(&format!(
+            "Open thread: /employee/cloudtalk/thread/{phone_digits}"
+        ))
+        .build()
 }
 
 #[cfg(test)]
@@ -87,23 +122,64 @@ mod tests {
             "Call back",
             12,
         );
-        assert!(text.starts_with("📋 Activity Reminder"));
-        assert!(text.contains("Customer: Jane"));
+        assert!(text.starts_with("📋 <b>Activity Reminder</b>"));
+        assert!(text.contains("<b>Customer:</b> Jane"));
         assert!(text.contains("Call back"));
+        assert!(text.ends_with(
+            "<a href=\"https://granite-manager.com/employee/deals/edit/12/project\">Open deal</a>"
+        ));
+    }
+
+    #[test]
+    fn activity_notification_escapes_crm_content() {
+        let text = format_activity_notification(
+            "note_added",
+            Some("Tom & Jerry"),
+            Some("Alex"),
+            "<b>quote</b> sent",
+            12,
+        );
+        assert!(text.contains("<b>Customer:</b> Tom &amp; Jerry"));
+        assert!(text.contains("Alex: &lt;b&gt;quote&lt;/b&gt; sent"));
     }
 
     #[test]
     fn email_notification_uses_envelope_icon() {
         let text = format_email_notification(Some("Jane"), Some("Quote"), Some(12), "thread-1");
-        assert!(text.starts_with("✉️ New email"));
-        assert!(text.contains("Subject: Quote"));
+        assert!(text.starts_with("✉️ <b>New email</b>"));
+        assert!(text.contains("<b>Subject:</b> Quote"));
+    }
+
+    #[test]
+    fn email_click_notification_names_customer_and_link() {
+        let text = format_email_click_notification(Some("Jane"), "https://acme.com/stones");
+        assert!(text.starts_with("🔗 <b>Email link clicked</b>"));
+        assert!(text.contains("<b>Customer:</b> Jane"));
+        assert!(text.contains("<b>Link:</b> https://acme.com/stones"));
     }
 
     #[test]
     fn sms_notification_uses_message_icon() {
-        let text = format_sms_notification("+15551234567", "Hello", "15551234567");
-        assert!(text.starts_with("💬 New CloudTalk SMS from +15551234567"));
+        let text = format_sms_notification("+15551234567", "Hello", &[], "15551234567");
+        assert!(text.starts_with("💬 <b>New CloudTalk SMS from +15551234567</b>"));
         assert!(text.contains("Hello"));
         assert!(text.contains("/employee/cloudtalk/thread/15551234567"));
     }
+
+    #[test]
+    fn sms_notification_lists_photos() {
+        let urls = vec![
+            "https://bucket.s3.us-east-2.amazonaws.com/a.jpg".to_string(),
+            "https://bucket.s3.us-east-2.amazonaws.com/b.png".to_string(),
+        ];
+        let text = format_sms_notification("+15551234567", "Kitchen", &urls, "15551234567");
+        assert!(text.contains(
+            "Kitchen\n<a href=\"https://bucket.s3.us-east-2.amazonaws.com/a.jpg\">Photo 1</a>\n<a href=\"https://bucket.s3.us-east-2.amazonaws.com/b.png\">Photo 2</a>"
+        ));
+
+        let text = format_sms_notification("+15551234567", "", &urls[..1], "15551234567");
+        assert!(text.contains(
+            "\n\n<a href=\"https://bucket.s3.us-east-2.amazonaws.com/a.jpg\">Photo 1</a>\n\n"
+        ));
+    }
 }
@@ -0,0 +1,202 @@
+/// Longest text Telegram accepts in one message.
+pub const MESSAGE_LIMIT: usize = 4096;
+
+/// Escapes text for Telegram's HTML parse mode; anything from customers or the CRM goes
+/// through here.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// Line-by-line builder of HTML parse-mode messages. Tags never span lines, so
+/// `split_html_message` can cut between any two of them.
+#[derive(Debug, Default)]
+pub struct HtmlMessage {
+    text: String,
+}
+
+impl HtmlMessage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push_line(&mut self, line: &str) {
+        if !self.text.is_empty() {
+            self.text.push('\n');
+        }
+        self.text.push_str(line);
+    }
+
+    /// `📋 <b>Title</b>`
+    #[must_use]
+    pub fn heading(mut self, icon: &str, title: &str) -> Self {
+        self.push_line(&format!("{icon} <b>{}</b>", escape_html(title)));
+        self
+    }
+
+    /// `<b>Label:</b> value`
+    #[must_use]
+    pub fn field(mut self, label: &str, value: &str) -> Self {
+        self.push_line(&format!(
+            "<b>{}:</b> {}",
+            escape_html(label),
+            escape_html(value)
+        ));
+        self
+    }
+
+    /// Like `field`, but leaves out missing and blank values.
+    #[must_use]
+    pub fn optional_field(self, label: &str, value: Option<&str>) -> Self {
+        match value.map(str::trim).filter(|value| !value.is_empty()) {
+            Some(value) => self.field(label, value),
+            None => self,
+        }
+    }
+
+    #[must_use]
+    pub fn text(mut self, text: &str) -> Self {
+        self.push_line(&escape_html(text));
+        self
+    }
+
+    #[must_use]
+    pub fn link(mut self, label: &str, url: &str) -> Self {
+        self.push_line(&format!(
+            "<a href=\"{}\">{}</a>",
+            escape_html(url),
+            escape_html(label)
+        ));
+        self
+    }
+
+    #[must_use]
+    pub fn blank_line(mut self) -> Self {
+        self.push_line("");
+        self
+    }
+
+    pub fn build(self) -> String {
+        self.text
+    }
+}
+
+/// Cuts a line longer than `limit` characters, never inside a tag, an element or an entity.
+fn split_long_line(line: &str, limit: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut count = 0;
+    let mut safe_end = 0;
+    let (mut in_tag, mut closing_tag, mut in_entity) = (false, false, false);
+    let mut depth = 0usize;
+    for (index, character) in line.char_indices() {
+        if !in_tag && !in_entity && depth == 0 {
+            safe_end = index;
+        }
+        if count >= limit && safe_end > start {
+            pieces.push(&line[start..safe_end]);
+            count = line[safe_end..index].chars().count();
+            start = safe_end;
+        }
+        match character {
+            '<' => {
+                in_tag = true;
+                closing_tag = line[index + 1..].starts_with('/');
+            }
+            '>' if in_tag => {
+                in_tag = false;
+                depth = if closing_tag {
+                    depth.saturating_sub(1)
+                } else {
+                    depth + 1
+                };
+            }
+            '&' => in_entity = true,
+            ';' => in_entity = false,
+            _ => {}
+        }
+        count += 1;
+    }
+    pieces.push(&line[start..]);
+    pieces
+}
+
+/// Splits an HTML message into parts of at most `limit` characters, between lines where
+/// possible.
+pub fn split_html_message(text: &str, limit: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut current_len = 0;
+    for line in text.split('\n') {
+        for (index, piece) in split_long_line(line, limit).into_iter().enumerate() {
+            let piece_len = piece.chars().count();
+            // Pieces of one long line each start a part instead of gaining a line break.
+            if current_len > 0 && (index > 0 || current_len + 1 + piece_len > limit) {
+                parts.push(std::mem::take(&mut current));
+                current_len = 0;
+            }
+            if current_len > 0 {
+                current.push('\n');
+                current_len += 1;
+            }
+            current.push_str(piece);
+            current_len += piece_len;
+        }
+    }
+    if !current.trim().is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_escapes_values_and_links() {
+        let text = HtmlMessage::new()
+            .heading("📋", "Activity <Reminder>")
+            .blank_line()
+            .field("Customer", "Tom & \"Jerry\"")
+            .optional_field("Email", Some("  "))
+            .text("<script>")
+            .link("Open deal", "https://example.com/?a=1&b=2")
+            .build();
+        assert_eq!(
+            text,
+            "📋 <b>Activity &lt;Reminder&gt;</b>\n\n<b>Customer:</b> Tom &amp; &quot;Jerry&quot;\n&lt;script&gt;\n<a href=\"https://example.com/?a=1&amp;b=2\">Open deal</a>"
+        );
+    }
+
+    #[test]
+    fn short_messages_are_not_split() {
+        assert_eq!(split_html_message("a\nb", 10), vec!["a\nb".to_string()]);
+    }
+
+    #[test]
+    fn long_messages_split_between_lines() {
+        let text = format!("{}\n{}", "a".repeat(6), "b".repeat(6));
+        assert_eq!(
+            split_html_message(&text, 10),
+            vec!["a".repeat(6), "b".repeat(6)]
+        );
+    }
+
+    #[test]
+    fn long_lines_are_not_cut_inside_entities_or_tags() {
+        assert_eq!(
+            split_html_message("aaaaaaaa&amp;<b>b</b>", 10),
+            vec!["aaaaaaaa", "&amp;", "<b>b</b>"]
+        );
+    }
+}
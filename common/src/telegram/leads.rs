@@ -0,0 +1,60 @@
+use crate::telegram::html::{MESSAGE_LIMIT, escape_html};
+
+const ESCALATION_ICON: &str = "⏰";
+
+/// Lead messages are split below Telegram's limit, leaving room for the status lines that
+/// assignment and escalation edits append.
+pub const LEAD_PART_LIMIT: usize = MESSAGE_LIMIT - 256;
+
+/// Callback data of a manager's assign button; the webhooks bot parses it with `parse_assign`.
+pub fn assign_callback_data(lead_id: u64, user_position_id: i32) -> String {
+    format!("assign:{lead_id}:{user_position_id}")
+}
+
+pub fn assign_button_label(name: &str, mtd_lead_count: i64) -> String {
+    format!("{name}: {mtd_lead_count}")
+}
+
+/// `original` is the HTML lead message as it was sent.
+pub fn format_escalated_lead_message(original: &str, minutes: i32) -> String {
+    format!(
+        "{ESCALATION_ICON} Still unassigned after {minutes} minutes\n\n{}",
+        original.trim_end()
+    )
+}
+
+/// `original` is the HTML lead message as it was sent; the rep name is escaped to match.
+pub fn format_auto_assigned_lead_message(original: &str, rep_name: &str, minutes: i32) -> String {
+    format!(
+        "{}\n\nLead auto-assigned to {} after {minutes} minutes without assignment",
+        original.trim_end(),
+        escape_html(rep_name)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assign_button_matches_webhooks_callback_format() {
+        assert_eq!(assign_callback_data(42, 7), "assign:42:7");
+        assert_eq!(assign_button_label("Alex", 3), "Alex: 3");
+    }
+
+    #[test]
+    fn escalation_keeps_the_original_lead_details() {
+        let text = format_escalated_lead_message("Name: Jordan\nChoose a salesperson.\n", 30);
+        assert_eq!(
+            text,
+            "⏰ Still unassigned after 30 minutes\n\nName: Jordan\nChoose a salesperson."
+        );
+        let text = format_auto_assigned_lead_message("Name: Jordan", "Alex", 90);
+        assert_eq!(
+            text,
+            "Name: Jordan\n\nLead auto-assigned to Alex after 90 minutes without assignment"
+        );
+        let text = format_auto_assigned_lead_message("Name: Jordan", "Sam & Co", 90);
+        assert!(text.contains("auto-assigned to Sam &amp; Co after"));
+    }
+}
@@ -1 +1,4 @@
+pub mod actions;
 pub mod crm;
+pub mod html;
+pub mod leads;
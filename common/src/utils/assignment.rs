@@ -0,0 +1,278 @@
+use crate::crud::assignment::RepAssignmentSettings;
+use crate::crud::user::{SALES_WORKER, SalesUser};
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignmentMode {
+    Manual,
+    RoundRobin,
+    LeastMtd,
+    Weighted,
+    Territory,
+}
+
+impl AssignmentMode {
+    /// Unknown values fall back to manual so a bad row never assigns leads by accident.
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "round_robin" => Self::RoundRobin,
+            "least_mtd" => Self::LeastMtd,
+            "weighted" => Self::Weighted,
+            "territory" => Self::Territory,
+            _ => Self::Manual,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepCandidate {
+    pub user_id: i32,
+    pub mtd_lead_count: i64,
+    pub weight: i32,
+    pub monthly_cap: Option<i32>,
+    pub is_active: bool,
+}
+
+impl RepCandidate {
+    fn qualifies(&self) -> bool {
+        self.is_active
+            && self.weight > 0
+            && self
+                .monthly_cap
+                .is_none_or(|cap| self.mtd_lead_count < i64::from(cap))
+    }
+}
+
+/// Sales workers merged with their assignment overrides; reps without a row take part with
+/// weight 1 and no monthly cap.
+pub fn rep_candidates(
+    users: &[SalesUser],
+    settings: &[RepAssignmentSettings],
+) -> Vec<RepCandidate> {
+    let mut candidates: Vec<RepCandidate> = Vec::new();
+    for user in users.iter().filter(|u| u.position_id == SALES_WORKER) {
+        if candidates.iter().any(|c| c.user_id == user.id) {
+            continue;
+        }
+        let setting = settings.iter().find(|s| s.user_id == user.id);
+        candidates.push(RepCandidate {
+            user_id: user.id,
+            mtd_lead_count: user.mtd_lead_count,
+            weight: setting.map_or(1, |s| s.weight),
+            monthly_cap: setting.and_then(|s| s.monthly_cap),
+            is_active: setting.is_none_or(|s| s.is_active),
+        });
+    }
+    candidates
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Territory {
+    pub user_id: i32,
+    pub postal_prefix: String,
+}
+
+/// US ZIP+4 and stray spaces reduce to the leading digits, e.g. ` 46220-1234` -> `46220`.
+pub fn normalize_postal_code(postal_code: &str) -> Option<String> {
+    let digits: String = postal_code
+        .trim()
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .take(5)
+        .collect();
+    (!digits.is_empty()).then_some(digits)
+}
+
+fn least_mtd<'a>(candidates: impl Iterator<Item = &'a RepCandidate>) -> Option<i32> {
+    candidates
+        .min_by_key(|c| (c.mtd_lead_count, c.user_id))
+        .map(|c| c.user_id)
+}
+
+/// Lowest `mtd / weight` wins, compared by cross-multiplying to stay in integers.
+fn weighted_cmp(a: &RepCandidate, b: &RepCandidate) -> Ordering {
+    (i128::from(a.mtd_lead_count) * i128::from(b.weight))
+        .cmp(&(i128::from(b.mtd_lead_count) * i128::from(a.weight)))
+        .then(a.user_id.cmp(&b.user_id))
+}
+
+/// Next qualifying rep after the last one assigned, in user id order, wrapping around.
+fn round_robin(qualified: &[&RepCandidate], last_assigned: Option<i32>) -> Option<i32> {
+    let mut ids: Vec<i32> = qualified.iter().map(|c| c.user_id).collect();
+    ids.sort_unstable();
+    ids.dedup();
+    let last = last_assigned.unwrap_or(i32::MIN);
+    ids.iter()
+        .find(|id| **id > last)
+        .or_else(|| ids.first())
+        .copied()
+}
+
+/// Owner of the longest prefix matching the lead's ZIP among qualifying reps; ties between
+/// reps on the same prefix length go to the one with fewer leads this month.
+fn territory(
+    qualified: &[&RepCandidate],
+    territories: &[Territory],
+    postal_code: Option<&str>,
+) -> Option<i32> {
+    let postal_code = normalize_postal_code(postal_code?)?;
+    let longest = territories
+        .iter()
+        .filter(|t| !t.postal_prefix.is_empty() && postal_code.starts_with(&t.postal_prefix))
+        .filter(|t| qualified.iter().any(|c| c.user_id == t.user_id))
+        .map(|t| t.postal_prefix.len())
+        .max()?;
+    least_mtd(qualified.iter().copied().filter(|c| {
+        territories.iter().any(|t| {
+            t.user_id == c.user_id
+                && t.postal_prefix.len() == longest
+                && postal_code.starts_with(&t.postal_prefix)
+        })
+    }))
+}
+
+/// Picks the rep a new lead goes to, or `None` when the company assigns manually or no
+/// rep qualifies (inactive, zero weight, monthly cap reached or no matching territory).
+pub fn pick_rep(
+    mode: AssignmentMode,
+    candidates: &[RepCandidate],
+    last_assigned: Option<i32>,
+    territories: &[Territory],
+    postal_code: Option<&str>,
+) -> Option<i32> {
+    let qualified: Vec<&RepCandidate> = candidates.iter().filter(|c| c.qualifies()).collect();
+    match mode {
+        AssignmentMode::Manual => None,
+        AssignmentMode::RoundRobin => round_robin(&qualified, last_assigned),
+        AssignmentMode::LeastMtd => least_mtd(qualified.iter().copied()),
+        AssignmentMode::Weighted => qualified
+            .iter()
+            .copied()
+            .min_by(|a, b| weighted_cmp(a, b))
+            .map(|c| c.user_id),
+        AssignmentMode::Territory => territory(&qualified, territories, postal_code),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rep(user_id: i32, mtd_lead_count: i64) -> RepCandidate {
+        RepCandidate {
+            user_id,
+            mtd_lead_count,
+            weight: 1,
+            monthly_cap: None,
+            is_active: true,
+        }
+    }
+
+    fn zone(user_id: i32, postal_prefix: &str) -> Territory {
+        Territory {
+            user_id,
+            postal_prefix: postal_prefix.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_mode_from_db() {
+        assert_eq!(
+            AssignmentMode::from_db("round_robin"),
+            AssignmentMode::RoundRobin
+        );
+        assert_eq!(
+            AssignmentMode::from_db("territory"),
+            AssignmentMode::Territory
+        );
+        assert_eq!(AssignmentMode::from_db("bogus"), AssignmentMode::Manual);
+    }
+
+    #[test]
+    fn test_normalize_postal_code() {
+        assert_eq!(
+            normalize_postal_code(" 46220-1234"),
+            Some("46220".to_string())
+        );
+        assert_eq!(normalize_postal_code("462"), Some("462".to_string()));
+        assert_eq!(normalize_postal_code("N/A"), None);
+    }
+
+    #[test]
+    fn test_manual_never_picks() {
+        assert_eq!(
+            pick_rep(AssignmentMode::Manual, &[rep(1, 0)], None, &[], None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_round_robin_wraps_and_skips_unqualified() {
+        let mut capped = rep(3, 5);
+        capped.monthly_cap = Some(5);
+        let reps = [rep(7, 0), rep(2, 9), capped];
+        let pick = |last| pick_rep(AssignmentMode::RoundRobin, &reps, last, &[], None);
+        assert_eq!(pick(None), Some(2));
+        assert_eq!(pick(Some(2)), Some(7));
+        assert_eq!(pick(Some(7)), Some(2));
+        assert_eq!(pick(Some(4)), Some(7));
+    }
+
+    #[test]
+    fn test_least_mtd_prefers_fewest_leads() {
+        let reps = [rep(1, 4), rep(2, 1), rep(3, 1)];
+        assert_eq!(
+            pick_rep(AssignmentMode::LeastMtd, &reps, None, &[], None),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_weighted_balances_by_capacity() {
+        let mut senior = rep(1, 5);
+        senior.weight = 3;
+        let junior = rep(2, 2);
+        assert_eq!(
+            pick_rep(
+                AssignmentMode::Weighted,
+                &[senior.clone(), junior.clone()],
+                None,
+                &[],
+                None
+            ),
+            Some(1)
+        );
+        senior.mtd_lead_count = 7;
+        assert_eq!(
+            pick_rep(AssignmentMode::Weighted, &[senior, junior], None, &[], None),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_territory_uses_longest_prefix() {
+        let reps = [rep(1, 0), rep(2, 5)];
+        let zones = [zone(1, "46"), zone(2, "462")];
+        let pick = |zip| pick_rep(AssignmentMode::Territory, &reps, None, &zones, zip);
+        assert_eq!(pick(Some("46220")), Some(2));
+        assert_eq!(pick(Some("46101")), Some(1));
+        assert_eq!(pick(Some("90210")), None);
+        assert_eq!(pick(None), None);
+    }
+
+    #[test]
+    fn test_no_qualified_rep_falls_back_to_manual() {
+        let mut inactive = rep(1, 0);
+        inactive.is_active = false;
+        let mut idle = rep(2, 0);
+        idle.weight = 0;
+        let reps = [inactive, idle];
+        for mode in [
+            AssignmentMode::RoundRobin,
+            AssignmentMode::LeastMtd,
+            AssignmentMode::Weighted,
+        ] {
+            assert_eq!(pick_rep(mode, &reps, None, &[], None), None);
+        }
+    }
+}
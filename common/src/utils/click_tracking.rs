@@ -0,0 +1,208 @@
+use crate::utils::signing::{sign_payload, verify_payload};
+
+pub const CLICK_ROUTE: &str = "/v1/email-click";
+
+/// Who a tracked link was sent to; every field is covered by the link signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClickTarget {
+    pub company_id: i32,
+    pub customer_id: i32,
+    pub scheduled_email_id: Option<i32>,
+}
+
+fn click_payload(target: &ClickTarget, url: &str) -> String {
+    let scheduled = target
+        .scheduled_email_id
+        .map(|id| id.to_string())
+        .unwrap_or_default();
+    format!(
+        "click:{}:{}:{scheduled}:{url}",
+        target.company_id, target.customer_id
+    )
+}
+
+pub fn sign_click(secret: &str, target: &ClickTarget, url: &str) -> String {
+    sign_payload(secret, &click_payload(target, url))
+}
+
+pub fn verify_click(secret: &str, target: &ClickTarget, url: &str, signature: &str) -> bool {
+    verify_payload(secret, &click_payload(target, url), signature)
+}
+
+pub fn tracked_link(base_url: &str, secret: &str, target: &ClickTarget, url: &str) -> String {
+    let scheduled = target
+        .scheduled_email_id
+        .map(|id| format!("&scheduled_email_id={id}"))
+        .unwrap_or_default();
+    format!(
+        "{}{CLICK_ROUTE}?company_id={}&customer_id={}{scheduled}&url={}&sig={}",
+        base_url.trim_end_matches('/'),
+        target.company_id,
+        target.customer_id,
+        urlencoding::encode(url),
+        sign_click(secret, target, url)
+    )
+}
+
+/// Only web links are wrapped; unsubscribe and already-tracked links are left alone.
+fn is_trackable(url: &str) -> bool {
+    let lower = url.trim().to_ascii_lowercase();
+    (lower.starts_with("https://") || lower.starts_with("http://"))
+        && !lower.contains("/v1/unsubscribe/")
+        && !lower.contains(CLICK_ROUTE)
+}
+
+/// Calls `rewrite` for every quoted `href` value (with `&amp;` decoded) and swaps in
+/// the returned URL. Everything outside `href` attributes is copied verbatim.
+pub fn rewrite_links<F>(html: &str, mut rewrite: F) -> String
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut result = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.to_ascii_lowercase().find("href=") {
+        let value_at = start + "href=".len();
+        result.push_str(&rest[..value_at]);
+        let after = &rest[value_at..];
+        let Some(quote) = after.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            rest = after;
+            continue;
+        };
+        let Some(len) = after[1..].find(quote) else {
+            rest = after;
+            break;
+        };
+        let raw = &after[1..=len];
+        result.push(quote);
+        match rewrite(&raw.replace("&amp;", "&")) {
+            Some(url) => result.push_str(&url.replace('&', "&amp;")),
+            None => result.push_str(raw),
+        }
+        result.push(quote);
+        rest = &after[len + 2..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+pub fn track_links(html: &str, base_url: &str, secret: &str, target: &ClickTarget) -> String {
+    rewrite_links(html, |url| {
+        is_trackable(url).then(|| tracked_link(base_url, secret, target, url.trim()))
+    })
+}
+
+/// Needs `WEBHOOKS_PUBLIC_URL` and `CLICK_TRACKING_SECRET`; without either the HTML is
+/// returned unchanged.
+pub fn track_links_from_env(html: &str, target: &ClickTarget) -> String {
+    let (Ok(base_url), Ok(secret)) = (
+        std::env::var("WEBHOOKS_PUBLIC_URL"),
+        std::env::var("CLICK_TRACKING_SECRET"),
+    ) else {
+        return html.to_string();
+    };
+    if base_url.trim().is_empty() || secret.is_empty() {
+        return html.to_string();
+    }
+    track_links(html, base_url.trim(), &secret, target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET: ClickTarget = ClickTarget {
+        company_id: 1,
+        customer_id: 42,
+        scheduled_email_id: Some(7),
+    };
+
+    #[test]
+    fn tracked_link_carries_target_and_encoded_url() {
+        let link = tracked_link(
+            "https://hooks.example.com/",
+            "secret",
+            &TARGET,
+            "https://acme.com/a?b=1",
+        );
+        assert!(link.starts_with(
+            "https://hooks.example.com/v1/email-click?company_id=1&customer_id=42&scheduled_email_id=7&url=https%3A%2F%2Facme.com%2Fa%3Fb%3D1&sig="
+        ));
+        let signature = link.rsplit("&sig=").next().unwrap();
+        assert!(verify_click(
+            "secret",
+            &TARGET,
+            "https://acme.com/a?b=1",
+            signature
+        ));
+        assert!(!verify_click(
+            "secret",
+            &TARGET,
+            "https://evil.com",
+            signature
+        ));
+        let other_customer = ClickTarget {
+            customer_id: 43,
+            ..TARGET
+        };
+        assert!(!verify_click(
+            "secret",
+            &other_customer,
+            "https://acme.com/a?b=1",
+            signature
+        ));
+    }
+
+    #[test]
+    fn rewrites_web_links_and_keeps_everything_else() {
+        let html = concat!(
+            r#"<p><a href="https://example.granite-manager.com/customer/1/stones">Stones</a> "#,
+            r#"<a href='http://acme.com/?a=1&amp;b=2'>Acme</a> "#,
+            r#"<a href="mailto:rep@acme.com">Mail</a> "#,
+            r#"<a href="https://hooks.example.com/v1/unsubscribe/1/42/sig">Unsubscribe</a></p>"#
+        );
+        let mut seen = Vec::new();
+        let result = rewrite_links(html, |url| {
+            seen.push(url.to_string());
+            is_trackable(url).then(|| format!("https://t.example.com/?u={}&x=1", url.len()))
+        });
+
+        assert_eq!(
+            seen,
+            [
+                "https://example.granite-manager.com/customer/1/stones",
+                "http://acme.com/?a=1&b=2",
+                "mailto:rep@acme.com",
+                "https://hooks.example.com/v1/unsubscribe/1/42/sig",
+            ]
+        );
+        assert_eq!(
+            result,
+            concat!(
+                r#"<p><a href="https://t.example.com/?u=53&amp;x=1">Stones</a> "#,
+                r#"<a href='https://t.example.com/?u=24&amp;x=1'>Acme</a> "#,
+                r#"<a href="mailto:rep@acme.com">Mail</a> "#,
+                r#"<a href="https://hooks.example.com/v1/unsubscribe/1/42/sig">Unsubscribe</a></p>"#
+            )
+        );
+    }
+
+    #[test]
+    fn tracking_is_not_applied_twice() {
+        let once = track_links(
+            r#"<a href="https://acme.com">Acme</a>"#,
+            "https://hooks.example.com",
+            "secret",
+            &TARGET,
+        );
+        let twice = track_links(&once, "https://hooks.example.com", "secret", &TARGET);
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn unterminated_href_is_copied_verbatim() {
+        let html = r#"<a href="https://acme.com>Acme</a>"#;
+        assert_eq!(rewrite_links(html, |_| Some("x".to_string())), html);
+    }
+}
@@ -0,0 +1,287 @@
+use crate::crud::conversion_exports::PendingConversionEvent;
+use crate::utils::phone::normalize_to_e164;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+
+fn sha256_hex(value: &str) -> String {
+    Sha256::digest(value.as_bytes())
+        .iter()
+        .fold(String::new(), |mut output, b| {
+            let _ = write!(output, "{b:02x}");
+            output
+        })
+}
+
+/// Both platforms match on the SHA-256 of the trimmed, lowercased address.
+pub fn hash_email(email: &str) -> Option<String> {
+    let normalized = email.trim().to_lowercase();
+    (!normalized.is_empty()).then(|| sha256_hex(&normalized))
+}
+
+/// Meta expects the E.164 digits without `+`, Google Ads the full E.164 form.
+pub fn hash_phone_for_meta(phone: &str) -> Option<String> {
+    normalize_to_e164(phone).map(|e164| sha256_hex(&e164[1..]))
+}
+
+pub fn hash_phone_for_google_ads(phone: &str) -> Option<String> {
+    normalize_to_e164(phone).map(|e164| sha256_hex(&e164))
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct MetaEventsRequest {
+    pub data: Vec<MetaEvent>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct MetaEvent {
+    pub event_name: String,
+    pub event_time: i64,
+    /// Lets Meta drop the event if a retry delivers it twice.
+    pub event_id: String,
+    pub action_source: &'static str,
+    pub user_data: MetaUserData,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_data: Option<MetaCustomData>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct MetaUserData {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub em: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ph: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fbc: Option<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct MetaCustomData {
+    pub value: f64,
+    pub currency: String,
+}
+
+/// `None` when the event's rule has no Meta event name.
+pub fn meta_events_request(event: &PendingConversionEvent) -> Option<MetaEventsRequest> {
+    let event_name = event.meta_event_name.clone()?;
+    let user_data = MetaUserData {
+        em: event
+            .email
+            .as_deref()
+            .and_then(hash_email)
+            .into_iter()
+            .collect(),
+        ph: event
+            .phone
+            .as_deref()
+            .and_then(hash_phone_for_meta)
+            .into_iter()
+            .collect(),
+        fbc: event
+            .fbclid
+            .as_ref()
+            .zip(event.fbclid_at)
+            .map(|(fbclid, clicked_at)| format!("fb.1.{}.{fbclid}", clicked_at.timestamp_millis())),
+    };
+    Some(MetaEventsRequest {
+        data: vec![MetaEvent {
+            event_id: format!("deal-{}-{event_name}", event.deal_id),
+            event_name,
+            event_time: event.event_time.timestamp(),
+            action_source: "system_generated",
+            user_data,
+            custom_data: event.amount.map(|value| MetaCustomData {
+                value,
+                currency: event.currency.clone(),
+            }),
+        }],
+    })
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleAdsUploadRequest {
+    pub conversions: Vec<GoogleAdsClickConversion>,
+    pub partial_failure: bool,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleAdsClickConversion {
+    pub conversion_action: String,
+    pub conversion_date_time: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gclid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wbraid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversion_value: Option<f64>,
+    pub currency_code: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub user_identifiers: Vec<GoogleAdsUserIdentifier>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleAdsUserIdentifier {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hashed_email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hashed_phone_number: Option<String>,
+}
+
+/// `None` when the rule has no Google Ads action or there is nothing to match the lead on.
+/// `gclid` and `wbraid` are mutually exclusive, so `wbraid` is only sent without a `gclid`.
+pub fn google_ads_upload_request(event: &PendingConversionEvent) -> Option<GoogleAdsUploadRequest> {
+    let action_id = event.google_conversion_action_id.as_deref()?;
+    let customer_id = event.google_ads_customer_id.as_deref()?;
+    let mut user_identifiers = Vec::new();
+    if let Some(hashed_email) = event.email.as_deref().and_then(hash_email) {
+        user_identifiers.push(GoogleAdsUserIdentifier {
+            hashed_email: Some(hashed_email),
+            hashed_phone_number: None,
+        });
+    }
+    if let Some(hashed_phone) = event.phone.as_deref().and_then(hash_phone_for_google_ads) {
+        user_identifiers.push(GoogleAdsUserIdentifier {
+            hashed_email: None,
+            hashed_phone_number: Some(hashed_phone),
+        });
+    }
+    let gclid = event.gclid.clone();
+    let wbraid = if gclid.is_none() {
+        event.wbraid.clone()
+    } else {
+        None
+    };
+    if gclid.is_none() && wbraid.is_none() && user_identifiers.is_empty() {
+        return None;
+    }
+    Some(GoogleAdsUploadRequest {
+        conversions: vec![GoogleAdsClickConversion {
+            conversion_action: format!(
+                "customers/{}/conversionActions/{action_id}",
+                customer_id.replace('-', "")
+            ),
+            conversion_date_time: event
+                .event_time
+                .format("%Y-%m-%d %H:%M:%S+00:00")
+                .to_string(),
+            gclid,
+            wbraid,
+            conversion_value: event.amount,
+            currency_code: event.currency.clone(),
+            user_identifiers,
+        }],
+        partial_failure: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn event() -> PendingConversionEvent {
+        PendingConversionEvent {
+            id: 1,
+            company_id: 1,
+            deal_id: 42,
+            customer_id: 7,
+            platform: "meta".to_string(),
+            attempts: 0,
+            event_time: Utc.with_ymd_and_hms(2026, 10, 18, 15, 30, 0).unwrap(),
+            meta_event_name: Some("Purchase".to_string()),
+            google_conversion_action_id: Some("987".to_string()),
+            email: Some(" Jane@Example.com ".to_string()),
+            phone: Some("317-555-0199".to_string()),
+            amount: Some(12500.5),
+            currency: "USD".to_string(),
+            gclid: Some("gclid-1".to_string()),
+            wbraid: Some("wbraid-1".to_string()),
+            fbclid: Some("fbclid-1".to_string()),
+            fbclid_at: Some(Utc.with_ymd_and_hms(2026, 10, 11, 9, 0, 0).unwrap()),
+            meta_pixel_id: Some("123".to_string()),
+            meta_access_token: Some("token".to_string()),
+            google_ads_customer_id: Some("123-456-7890".to_string()),
+            google_ads_login_customer_id: None,
+            google_ads_refresh_token: Some("refresh".to_string()),
+        }
+    }
+
+    #[test]
+    fn hashes_normalized_contact_details() {
+        assert_eq!(
+            hash_email(" Jane@Example.com "),
+            hash_email("jane@example.com")
+        );
+        assert_eq!(hash_email("jane@example.com").unwrap().len(), 64);
+        assert_eq!(hash_email("  "), None);
+        assert_eq!(
+            hash_phone_for_meta("317-555-0199"),
+            hash_phone_for_meta("+1 (317) 555-0199")
+        );
+        assert_ne!(
+            hash_phone_for_meta("317-555-0199"),
+            hash_phone_for_google_ads("317-555-0199")
+        );
+        assert_eq!(hash_phone_for_meta("555-0199"), None);
+    }
+
+    #[test]
+    fn meta_request_uses_hashes_and_fbclid_click_time() {
+        let request = meta_events_request(&event()).unwrap();
+        let meta_event = &request.data[0];
+        assert_eq!(meta_event.event_name, "Purchase");
+        assert_eq!(meta_event.event_id, "deal-42-Purchase");
+        assert_eq!(meta_event.event_time, 1_792_337_400);
+        assert_eq!(
+            meta_event.user_data.em,
+            vec![hash_email("jane@example.com").unwrap()]
+        );
+        assert_eq!(
+            meta_event.user_data.fbc.as_deref(),
+            Some("fb.1.1791709200000.fbclid-1")
+        );
+        assert_eq!(
+            meta_event.custom_data,
+            Some(MetaCustomData {
+                value: 12500.5,
+                currency: "USD".to_string()
+            })
+        );
+
+        let mut without_name = event();
+        without_name.meta_event_name = None;
+        assert!(meta_events_request(&without_name).is_none());
+    }
+
+    #[test]
+    fn google_request_prefers_gclid_over_wbraid() {
+        let request = google_ads_upload_request(&event()).unwrap();
+        let conversion = &request.conversions[0];
+        assert_eq!(
+            conversion.conversion_action,
+            "customers/1234567890/conversionActions/987"
+        );
+        assert_eq!(conversion.conversion_date_time, "2026-10-18 15:30:00+00:00");
+        assert_eq!(conversion.gclid.as_deref(), Some("gclid-1"));
+        assert_eq!(conversion.wbraid, None);
+        assert_eq!(conversion.user_identifiers.len(), 2);
+
+        let mut app_click = event();
+        app_click.gclid = None;
+        let request = google_ads_upload_request(&app_click).unwrap();
+        assert_eq!(request.conversions[0].wbraid.as_deref(), Some("wbraid-1"));
+    }
+
+    #[test]
+    fn google_request_needs_something_to_match_on() {
+        let mut anonymous = event();
+        anonymous.gclid = None;
+        anonymous.wbraid = None;
+        anonymous.email = None;
+        anonymous.phone = None;
+        assert!(google_ads_upload_request(&anonymous).is_none());
+    }
+}
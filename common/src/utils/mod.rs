@@ -1 +1,7 @@
+pub mod assignment;
+pub mod click_tracking;
+pub mod conversions;
+pub mod phone;
+pub mod signing;
 pub mod template;
+pub mod unsubscribe;
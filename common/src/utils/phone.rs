@@ -0,0 +1,222 @@
+use std::fmt;
+
+/// ITU country codes are prefix-free: 1 and 7 are the only one-digit codes and these are
+/// the two-digit ones, everything else is three digits.
+const TWO_DIGIT_COUNTRY_CODES: [&str; 44] = [
+    "20", "27", "30", "31", "32", "33", "34", "36", "39", "40", "41", "43", "44", "45", "46", "47",
+    "48", "49", "51", "52", "53", "54", "55", "56", "57", "58", "60", "61", "62", "63", "64", "65",
+    "66", "81", "82", "84", "86", "90", "91", "92", "93", "94", "95", "98",
+];
+
+const NANP_COUNTRY_CODE: &str = "1";
+
+/// A validated phone number. Numbers without a `+`, `00` or `011` prefix are read as
+/// North American, which is where every company using the CRM is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhoneNumber {
+    country_code: String,
+    national: String,
+    extension: Option<String>,
+}
+
+fn country_code_len(digits: &str) -> usize {
+    if digits.starts_with('1') || digits.starts_with('7') {
+        1
+    } else if TWO_DIGIT_COUNTRY_CODES
+        .iter()
+        .any(|code| digits.starts_with(code))
+    {
+        2
+    } else {
+        3
+    }
+}
+
+/// Splits `317-555-0199 ext. 12`, `x12`, `#12` and `;ext=12` into number and extension.
+fn split_extension(raw: &str) -> (&str, Option<String>) {
+    let lower = raw.to_ascii_lowercase();
+    let cut = ["ext", "x", "#", ";"]
+        .iter()
+        .filter_map(|marker| lower.find(marker))
+        .min();
+    match cut {
+        Some(index) => {
+            let extension: String = raw[index..].chars().filter(char::is_ascii_digit).collect();
+            (&raw[..index], (!extension.is_empty()).then_some(extension))
+        }
+        None => (raw, None),
+    }
+}
+
+impl PhoneNumber {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let (number, extension) = split_extension(raw.trim());
+        let digits: String = number.chars().filter(char::is_ascii_digit).collect();
+        let international = if number.trim_start().starts_with('+') {
+            Some(digits.as_str())
+        } else {
+            digits
+                .strip_prefix("011")
+                .or_else(|| digits.strip_prefix("00"))
+                .filter(|rest| rest.len() > 10)
+        };
+
+        let (country_code, national) = match international {
+            Some(full) if full.starts_with(NANP_COUNTRY_CODE) => (NANP_COUNTRY_CODE, &full[1..]),
+            Some(full) => full.split_at(country_code_len(full).min(full.len())),
+            None if digits.len() == 11 && digits.starts_with(NANP_COUNTRY_CODE) => {
+                (NANP_COUNTRY_CODE, &digits[1..])
+            }
+            None => (NANP_COUNTRY_CODE, digits.as_str()),
+        };
+
+        let valid = if country_code == NANP_COUNTRY_CODE {
+            national.len() == 10 && !national.starts_with(['0', '1'])
+        } else {
+            national.len() >= 4 && country_code.len() + national.len() <= 15
+        };
+        valid.then(|| Self {
+            country_code: country_code.to_string(),
+            national: national.to_string(),
+            extension,
+        })
+    }
+
+    /// Rebuilds a number from [`Self::match_key`], as stored in the SMS tables.
+    pub fn from_match_key(key: u64) -> Option<Self> {
+        let digits = key.to_string();
+        if digits.len() == 10 {
+            Self::parse(&digits)
+        } else {
+            Self::parse(&format!("+{digits}"))
+        }
+    }
+
+    pub fn is_north_american(&self) -> bool {
+        self.country_code == NANP_COUNTRY_CODE
+    }
+
+    pub fn extension(&self) -> Option<&str> {
+        self.extension.as_deref()
+    }
+
+    /// `+13175550199`; the extension is not part of E.164.
+    pub fn e164(&self) -> String {
+        format!("+{}{}", self.country_code, self.national)
+    }
+
+    /// Numeric key used by the SMS tables: the 10 national digits for North American
+    /// numbers, so existing rows keep matching, and all E.164 digits otherwise.
+    pub fn match_key(&self) -> u64 {
+        let digits = if self.is_north_american() {
+            self.national.clone()
+        } else {
+            format!("{}{}", self.country_code, self.national)
+        };
+        digits.parse().unwrap_or_default()
+    }
+}
+
+/// `317-555-0199` for North American numbers, `+44 2079460958` otherwise, with
+/// ` ext. 12` when there is an extension.
+impl fmt::Display for PhoneNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_north_american() {
+            let national = &self.national;
+            write!(
+                f,
+                "{}-{}-{}",
+                &national[0..3],
+                &national[3..6],
+                &national[6..10]
+            )?;
+        } else {
+            write!(f, "+{} {}", self.country_code, self.national)?;
+        }
+        if let Some(extension) = &self.extension {
+            write!(f, " ext. {extension}")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn normalize_to_e164(raw: &str) -> Option<String> {
+    PhoneNumber::parse(raw).map(|phone| phone.e164())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_north_american_formats() {
+        for raw in [
+            "(317) 555-0199",
+            "317-555-0199",
+            "3175550199",
+            "+1 317 555 0199",
+            "13175550199",
+            "011 1 317 555 0199",
+        ] {
+            let phone = PhoneNumber::parse(raw).unwrap();
+            assert_eq!(phone.e164(), "+13175550199", "{raw}");
+            assert_eq!(phone.to_string(), "317-555-0199", "{raw}");
+            assert_eq!(phone.match_key(), 3_175_550_199, "{raw}");
+        }
+    }
+
+    #[test]
+    fn parses_international_numbers() {
+        let london = PhoneNumber::parse("+44 20 7946 0958").unwrap();
+        assert_eq!(london.e164(), "+442079460958");
+        assert_eq!(london.to_string(), "+44 2079460958");
+        assert_eq!(london.match_key(), 442_079_460_958);
+        assert!(!london.is_north_american());
+
+        assert_eq!(
+            normalize_to_e164("0049 30 1234567").as_deref(),
+            Some("+49301234567")
+        );
+        assert_eq!(
+            normalize_to_e164("+380 44 123 4567").as_deref(),
+            Some("+380441234567")
+        );
+    }
+
+    #[test]
+    fn keeps_extensions_out_of_e164() {
+        for raw in [
+            "317-555-0199 ext. 12",
+            "317-555-0199 x12",
+            "(317) 555-0199 #12",
+            "+13175550199;ext=12",
+        ] {
+            let phone = PhoneNumber::parse(raw).unwrap();
+            assert_eq!(phone.e164(), "+13175550199", "{raw}");
+            assert_eq!(phone.extension(), Some("12"), "{raw}");
+            assert_eq!(phone.to_string(), "317-555-0199 ext. 12", "{raw}");
+        }
+    }
+
+    #[test]
+    fn rejects_incomplete_numbers() {
+        for raw in [
+            "",
+            "555-0199",
+            "031-555-0199",
+            "23175550199",
+            "+44 12",
+            "+1234567890123456",
+        ] {
+            assert_eq!(PhoneNumber::parse(raw), None, "{raw}");
+        }
+    }
+
+    #[test]
+    fn match_key_round_trips() {
+        for raw in ["317-555-0199", "+44 20 7946 0958"] {
+            let phone = PhoneNumber::parse(raw).unwrap();
+            assert_eq!(PhoneNumber::from_match_key(phone.match_key()), Some(phone));
+        }
+    }
+}
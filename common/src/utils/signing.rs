@@ -0,0 +1,122 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn payload_mac(secret: &str, payload: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key");
+    mac.update(payload);
+    mac
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// URL-safe HMAC-SHA256 of `payload`, for links that must not be forged or altered.
+pub fn sign_payload(secret: &str, payload: &str) -> String {
+    URL_SAFE_NO_PAD.encode(
+        payload_mac(secret, payload.as_bytes())
+            .finalize()
+            .into_bytes(),
+    )
+}
+
+/// Constant-time check of a `sign_payload` signature.
+pub fn verify_payload(secret: &str, payload: &str, signature: &str) -> bool {
+    let Ok(bytes) = URL_SAFE_NO_PAD.decode(signature.trim()) else {
+        return false;
+    };
+    payload_mac(secret, payload.as_bytes())
+        .verify_slice(&bytes)
+        .is_ok()
+}
+
+/// `sign_payload` cut to its first `bytes` bytes, for size-limited places such as Telegram
+/// callback data.
+pub fn sign_payload_truncated(secret: &str, payload: &str, bytes: usize) -> String {
+    let tag = payload_mac(secret, payload.as_bytes())
+        .finalize()
+        .into_bytes();
+    URL_SAFE_NO_PAD.encode(&tag[..bytes.min(tag.len())])
+}
+
+/// Constant-time check of a `sign_payload_truncated` signature of exactly `bytes` bytes.
+pub fn verify_truncated_payload(
+    secret: &str,
+    payload: &str,
+    signature: &str,
+    bytes: usize,
+) -> bool {
+    let Ok(tag) = URL_SAFE_NO_PAD.decode(signature.trim()) else {
+        return false;
+    };
+    tag.len() == bytes
+        && payload_mac(secret, payload.as_bytes())
+            .verify_truncated_left(&tag)
+            .is_ok()
+}
+
+/// Constant-time check of a hex HMAC-SHA256 of a raw body, as sent by Meta in
+/// `X-Hub-Signature-256` after the `sha256=` prefix.
+pub fn verify_hex_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Some(bytes) = decode_hex(signature.trim()) else {
+        return false;
+    };
+    payload_mac(secret, body).verify_slice(&bytes).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_only_verifies_for_the_same_secret_and_payload() {
+        let signature = sign_payload("secret", "payload");
+        assert!(verify_payload("secret", "payload", &signature));
+        assert!(!verify_payload("secret", "payload2", &signature));
+        assert!(!verify_payload("other", "payload", &signature));
+        assert!(!verify_payload("secret", "payload", "not base64!"));
+    }
+
+    #[test]
+    fn truncated_signature_requires_its_full_length() {
+        let signature = sign_payload_truncated("secret", "payload", 12);
+        assert_eq!(signature.len(), 16);
+        assert!(verify_truncated_payload(
+            "secret", "payload", &signature, 12
+        ));
+        assert!(!verify_truncated_payload(
+            "secret", "payload2", &signature, 12
+        ));
+        assert!(!verify_truncated_payload(
+            "secret",
+            "payload",
+            &signature[..8],
+            12
+        ));
+    }
+
+    #[test]
+    fn hex_signature_verifies_the_raw_body() {
+        let body = br#"{"object":"page"}"#;
+        let signature: String = payload_mac("app-secret", body)
+            .finalize()
+            .into_bytes()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        assert!(verify_hex_signature("app-secret", body, &signature));
+        assert!(!verify_hex_signature("app-secret", b"{}", &signature));
+        assert!(!verify_hex_signature("other", body, &signature));
+        assert!(!verify_hex_signature("app-secret", body, "zz"));
+    }
+}
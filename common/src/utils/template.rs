@@ -1,4 +1,5 @@
 use crate::crud::template::{InfoVariableData, TemplateVariableData};
+use crate::utils::unsubscribe::unsubscribe_link_from_env;
 use chrono::Local;
 use std::collections::HashMap;
 
@@ -55,6 +56,16 @@ fn customer_inventory_link(company: Option<&InfoVariableData>) -> Option<String>
     ))
 }
 
+/// Signed per customer and company, so a link only ever opts out the recipient it was sent to.
+fn customer_unsubscribe_link(
+    customer: Option<&InfoVariableData>,
+    company: Option<&InfoVariableData>,
+) -> Option<String> {
+    let customer_id = customer?.id?;
+    let company_id = company?.id?;
+    unsubscribe_link_from_env(company_id, customer_id)
+}
+
 fn build_variable_map(data: &TemplateVariableData) -> HashMap<&'static str, String> {
     let customer = data.customer.as_ref();
     let company = data.company.as_ref();
@@ -83,6 +94,10 @@ fn build_variable_map(data: &TemplateVariableData) -> HashMap<&'static str, Stri
         ),
         ("company.domain", company.and_then(|c| c.domain.clone())),
         ("current_date", Some(format_current_date())),
+        (
+            "unsubscribe_link",
+            customer_unsubscribe_link(customer, company),
+        ),
     ]
     .into_iter()
     .filter_map(|(k, v)| v.filter(|s| !s.is_empty()).map(|val| (k, val)))
@@ -303,4 +318,43 @@ mod tests {
         );
         assert_eq!(result, "{{unknown.var}} and Jordan");
     }
+
+    /// Same fixed values on every call: safe under parallel test execution since no other
+    /// test in this crate reads/writes these unsubscribe env vars.
+    fn set_unsubscribe_env() {
+        unsafe {
+            std::env::set_var("WEBHOOKS_PUBLIC_URL", "https://hooks.example.com");
+            std::env::set_var("UNSUBSCRIBE_SECRET", "test-unsubscribe-secret");
+        }
+    }
+
+    #[test]
+    fn replaces_unsubscribe_link_signed_for_customer_and_company() {
+        set_unsubscribe_env();
+        let mut data = make_full_data();
+        if let Some(customer) = data.customer.as_mut() {
+            customer.id = Some(42);
+        }
+        let result =
+            replace_template_variables("<a href=\"{{unsubscribe_link}}\">Unsubscribe</a>", &data);
+        assert_eq!(
+            result,
+            format!(
+                "<a href=\"{}\">Unsubscribe</a>",
+                crate::utils::unsubscribe::unsubscribe_link(
+                    "https://hooks.example.com",
+                    "test-unsubscribe-secret",
+                    1,
+                    42
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn leaves_unsubscribe_link_without_customer_id() {
+        set_unsubscribe_env();
+        let result = replace_template_variables("{{unsubscribe_link}}", &make_full_data());
+        assert_eq!(result, "{{unsubscribe_link}}");
+    }
 }
@@ -0,0 +1,73 @@
+use crate::utils::signing::{sign_payload, verify_payload};
+
+fn unsubscribe_payload(company_id: i32, customer_id: i32) -> String {
+    format!("unsubscribe:{company_id}:{customer_id}")
+}
+
+/// URL-safe signature that ties an unsubscribe link to one customer of one company.
+pub fn sign_unsubscribe(secret: &str, company_id: i32, customer_id: i32) -> String {
+    sign_payload(secret, &unsubscribe_payload(company_id, customer_id))
+}
+
+pub fn verify_unsubscribe(
+    secret: &str,
+    company_id: i32,
+    customer_id: i32,
+    signature: &str,
+) -> bool {
+    verify_payload(
+        secret,
+        &unsubscribe_payload(company_id, customer_id),
+        signature,
+    )
+}
+
+pub fn unsubscribe_link(base_url: &str, secret: &str, company_id: i32, customer_id: i32) -> String {
+    format!(
+        "{}/v1/unsubscribe/{company_id}/{customer_id}/{}",
+        base_url.trim_end_matches('/'),
+        sign_unsubscribe(secret, company_id, customer_id)
+    )
+}
+
+/// Needs `WEBHOOKS_PUBLIC_URL` and `UNSUBSCRIBE_SECRET`; without either there is no link.
+pub fn unsubscribe_link_from_env(company_id: i32, customer_id: i32) -> Option<String> {
+    let base_url = std::env::var("WEBHOOKS_PUBLIC_URL").ok()?;
+    let secret = std::env::var("UNSUBSCRIBE_SECRET").ok()?;
+    if base_url.trim().is_empty() || secret.is_empty() {
+        return None;
+    }
+    Some(unsubscribe_link(
+        base_url.trim(),
+        &secret,
+        company_id,
+        customer_id,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_round_trips_for_the_same_customer_only() {
+        let signature = sign_unsubscribe("secret", 1, 42);
+        assert!(verify_unsubscribe("secret", 1, 42, &signature));
+        assert!(!verify_unsubscribe("secret", 1, 43, &signature));
+        assert!(!verify_unsubscribe("secret", 2, 42, &signature));
+        assert!(!verify_unsubscribe("other", 1, 42, &signature));
+        assert!(!verify_unsubscribe("secret", 1, 42, "not base64!"));
+    }
+
+    #[test]
+    fn link_embeds_ids_and_signature() {
+        let link = unsubscribe_link("https://hooks.example.com/", "secret", 1, 42);
+        assert_eq!(
+            link,
+            format!(
+                "https://hooks.example.com/v1/unsubscribe/1/42/{}",
+                sign_unsubscribe("secret", 1, 42)
+            )
+        );
+    }
+}
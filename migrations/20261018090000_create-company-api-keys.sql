@@ -0,0 +1,20 @@
+-- Per-company API keys for lead intake (replaces the shared bearer UUID)
+CREATE TABLE company_api_keys (
+  id INT AUTO_INCREMENT PRIMARY KEY,
+  company_id INT NOT NULL,
+  label VARCHAR(100) NOT NULL,
+  key_prefix VARCHAR(16) NOT NULL,
+  key_hash CHAR(64) NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  last_used_at DATETIME NULL,
+  revoked_at DATETIME NULL,
+  UNIQUE KEY uniq_company_api_keys_hash (key_hash),
+  INDEX idx_company_api_keys_company (company_id),
+  CONSTRAINT fk_company_api_keys_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE
+);
+
+-- Temporary opt-in: accept lead intake without a valid key while a company migrates
+ALTER TABLE company
+    ADD COLUMN lead_auth_lenient TINYINT(1) NOT NULL DEFAULT 0;
@@ -0,0 +1,19 @@
+-- SES event publishing history per outbound message (opens stay in email_reads)
+CREATE TABLE email_events (
+  id INT AUTO_INCREMENT PRIMARY KEY,
+  event_id VARCHAR(64) NOT NULL,
+  ses_message_id VARCHAR(255) NOT NULL,
+  message_id VARCHAR(500) NULL,
+  company_id INT NULL,
+  event_type ENUM(
+    'send', 'delivery', 'bounce', 'complaint', 'reject', 'click', 'delivery_delay'
+  ) NOT NULL,
+  recipient_email VARCHAR(255) NOT NULL DEFAULT '',
+  bounce_type VARCHAR(32) NULL,
+  bounce_sub_type VARCHAR(64) NULL,
+  detail VARCHAR(1000) NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  UNIQUE KEY uniq_email_events_event_recipient (event_id, recipient_email),
+  INDEX idx_email_events_message (message_id),
+  INDEX idx_email_events_recipient (company_id, recipient_email, event_type)
+);
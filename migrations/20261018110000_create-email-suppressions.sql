@@ -0,0 +1,13 @@
+-- Per-company addresses that must never receive outbound email
+CREATE TABLE email_suppressions (
+  id INT AUTO_INCREMENT PRIMARY KEY,
+  company_id INT NOT NULL,
+  email VARCHAR(255) NOT NULL,
+  reason ENUM('unsubscribe', 'bounce', 'complaint', 'manual') NOT NULL,
+  source VARCHAR(64) NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  UNIQUE KEY uniq_email_suppressions_company_email (company_id, email),
+  CONSTRAINT fk_email_suppressions_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE
+);
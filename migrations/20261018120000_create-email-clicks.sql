@@ -0,0 +1,14 @@
+-- Clicks on tracked links in outbound emails (redirected through /v1/email-click)
+CREATE TABLE email_clicks (
+  id INT AUTO_INCREMENT PRIMARY KEY,
+  company_id INT NOT NULL,
+  customer_id INT NOT NULL,
+  scheduled_email_id INT NULL,
+  message_id VARCHAR(500) NULL,
+  url VARCHAR(2048) NOT NULL,
+  user_agent VARCHAR(500) NULL,
+  ip_address VARCHAR(100) NULL,
+  clicked_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  INDEX idx_email_clicks_customer (company_id, customer_id),
+  INDEX idx_email_clicks_message (message_id)
+);
@@ -0,0 +1,42 @@
+-- Optional per-company automatic assignment of new leads; no row means manual Telegram assignment
+CREATE TABLE lead_assignment_policies (
+  company_id INT PRIMARY KEY,
+  mode ENUM('manual', 'round_robin', 'least_mtd', 'weighted', 'territory') NOT NULL DEFAULT 'manual',
+  last_assigned_user_id INT NULL,
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  CONSTRAINT fk_lead_assignment_policies_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE
+);
+
+-- Per-rep overrides; sales reps without a row take part with weight 1 and no cap
+CREATE TABLE lead_assignment_reps (
+  id INT AUTO_INCREMENT PRIMARY KEY,
+  company_id INT NOT NULL,
+  user_id INT NOT NULL,
+  weight INT NOT NULL DEFAULT 1,
+  monthly_cap INT NULL,
+  is_active BOOLEAN NOT NULL DEFAULT TRUE,
+  UNIQUE KEY uniq_lead_assignment_reps_company_user (company_id, user_id),
+  CONSTRAINT fk_lead_assignment_reps_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_lead_assignment_reps_user
+    FOREIGN KEY (user_id) REFERENCES users (id)
+    ON DELETE CASCADE
+);
+
+-- ZIP code prefixes owned by a rep in territory mode; the longest matching prefix wins
+CREATE TABLE lead_assignment_territories (
+  id INT AUTO_INCREMENT PRIMARY KEY,
+  company_id INT NOT NULL,
+  user_id INT NOT NULL,
+  postal_prefix VARCHAR(10) NOT NULL,
+  UNIQUE KEY uniq_lead_assignment_territories_company_prefix (company_id, postal_prefix),
+  CONSTRAINT fk_lead_assignment_territories_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_lead_assignment_territories_user
+    FOREIGN KEY (user_id) REFERENCES users (id)
+    ON DELETE CASCADE
+);
@@ -0,0 +1,26 @@
+-- Escalation of leads no manager assigned in Telegram; NULL thresholds disable the step
+ALTER TABLE lead_assignment_policies
+  ADD COLUMN escalate_after_minutes INT NULL,
+  ADD COLUMN auto_assign_after_minutes INT NULL,
+  ADD COLUMN fallback_mode ENUM('least_mtd', 'round_robin', 'weighted') NOT NULL DEFAULT 'least_mtd';
+
+-- Text of the lead message as sent, so escalations can edit it without losing the details
+ALTER TABLE telegram_lead_messages
+  ADD COLUMN message_text TEXT NULL AFTER message_id;
+
+-- One row per lead and step, claimed before acting so each step fires only once
+CREATE TABLE lead_escalations (
+  id INT AUTO_INCREMENT PRIMARY KEY,
+  customer_id INT NOT NULL,
+  company_id INT NOT NULL,
+  step ENUM('reping', 'auto_assign') NOT NULL,
+  assigned_user_id INT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  UNIQUE KEY uniq_lead_escalations_customer_step (customer_id, step),
+  CONSTRAINT fk_lead_escalations_customer
+    FOREIGN KEY (customer_id) REFERENCES customers (id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_lead_escalations_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE
+);
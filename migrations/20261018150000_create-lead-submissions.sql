@@ -0,0 +1,12 @@
+-- Idempotency keys of lead intake requests, so retried or double-submitted forms are processed once
+CREATE TABLE lead_submissions (
+  id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+  company_id INT NOT NULL,
+  idempotency_key VARCHAR(255) NOT NULL,
+  expires_at TIMESTAMP NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  UNIQUE KEY uniq_lead_submissions_company_key (company_id, idempotency_key),
+  CONSTRAINT fk_lead_submissions_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE
+);
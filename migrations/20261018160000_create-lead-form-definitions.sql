@@ -0,0 +1,18 @@
+-- Per-company lead forms: field_map maps incoming JSON keys to canonical customer fields
+CREATE TABLE lead_form_definitions (
+  id INT AUTO_INCREMENT PRIMARY KEY,
+  company_id INT NOT NULL,
+  form_name VARCHAR(100) NOT NULL,
+  field_map JSON NOT NULL,
+  referral_source VARCHAR(100) NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  UNIQUE KEY uniq_lead_form_definitions_company_form (company_id, form_name),
+  CONSTRAINT fk_lead_form_definitions_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE
+);
+
+-- Submitted form fields that have no customers column
+ALTER TABLE customers
+  ADD COLUMN extra_fields JSON NULL;
@@ -0,0 +1,22 @@
+-- First-touch and last-touch marketing attribution per customer
+CREATE TABLE lead_attributions (
+  id INT AUTO_INCREMENT PRIMARY KEY,
+  customer_id INT NOT NULL,
+  touch ENUM('first', 'last') NOT NULL,
+  utm_source VARCHAR(255) NULL,
+  utm_medium VARCHAR(255) NULL,
+  utm_campaign VARCHAR(255) NULL,
+  utm_term VARCHAR(255) NULL,
+  utm_content VARCHAR(255) NULL,
+  gclid VARCHAR(255) NULL,
+  fbclid VARCHAR(255) NULL,
+  wbraid VARCHAR(255) NULL,
+  landing_page TEXT NULL,
+  referrer TEXT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  UNIQUE KEY uniq_lead_attributions_customer_touch (customer_id, touch),
+  CONSTRAINT fk_lead_attributions_customer
+    FOREIGN KEY (customer_id) REFERENCES customers (id)
+    ON DELETE CASCADE
+);
@@ -0,0 +1,53 @@
+-- Per-company ad platform credentials for offline conversion export
+CREATE TABLE conversion_export_settings (
+  company_id INT PRIMARY KEY,
+  meta_pixel_id VARCHAR(64) NULL,
+  meta_access_token TEXT NULL,
+  google_ads_customer_id VARCHAR(32) NULL,
+  google_ads_login_customer_id VARCHAR(32) NULL,
+  google_ads_refresh_token TEXT NULL,
+  currency CHAR(3) NOT NULL DEFAULT 'USD',
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  CONSTRAINT fk_conversion_export_settings_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE
+);
+
+-- Which deal events are exported; list_id NULL means the deal was won
+CREATE TABLE conversion_export_rules (
+  id INT AUTO_INCREMENT PRIMARY KEY,
+  company_id INT NOT NULL,
+  list_id INT NULL,
+  meta_event_name VARCHAR(100) NULL,
+  google_conversion_action_id VARCHAR(32) NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  CONSTRAINT fk_conversion_export_rules_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_conversion_export_rules_list
+    FOREIGN KEY (list_id) REFERENCES deals_list (id)
+    ON DELETE CASCADE
+);
+
+-- One event per deal, rule and platform, drained by the scheduled lambda
+CREATE TABLE conversion_export_queue (
+  id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+  company_id INT NOT NULL,
+  deal_id BIGINT UNSIGNED NOT NULL,
+  rule_id INT NOT NULL,
+  platform ENUM('meta', 'google_ads') NOT NULL,
+  status ENUM('pending', 'sent', 'failed') NOT NULL DEFAULT 'pending',
+  attempts INT NOT NULL DEFAULT 0,
+  next_attempt_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  last_error TEXT NULL,
+  event_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  sent_at TIMESTAMP NULL,
+  UNIQUE KEY uniq_conversion_export_queue_event (deal_id, rule_id, platform),
+  INDEX idx_conversion_export_queue_due (status, next_attempt_at),
+  CONSTRAINT fk_conversion_export_queue_deal
+    FOREIGN KEY (deal_id) REFERENCES deals (id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_conversion_export_queue_rule
+    FOREIGN KEY (rule_id) REFERENCES conversion_export_rules (id)
+    ON DELETE CASCADE
+);
@@ -0,0 +1,6 @@
+-- E.164 copies of phone and phone_2 for matching; phone keeps the display format
+ALTER TABLE customers
+    ADD COLUMN phone_e164 VARCHAR(20) NULL AFTER phone_2,
+    ADD COLUMN phone_2_e164 VARCHAR(20) NULL AFTER phone_e164,
+    ADD INDEX idx_customers_company_phone_e164 (company_id, phone_e164),
+    ADD INDEX idx_customers_company_phone_2_e164 (company_id, phone_2_e164);
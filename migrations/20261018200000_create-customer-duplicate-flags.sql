@@ -0,0 +1,18 @@
+-- Leads created next to an existing customer that scored as a possible duplicate
+CREATE TABLE customer_duplicate_flags (
+  id INT AUTO_INCREMENT PRIMARY KEY,
+  customer_id INT NOT NULL,
+  duplicate_of_customer_id INT NOT NULL,
+  score INT NOT NULL,
+  reasons VARCHAR(255) NOT NULL,
+  resolved_at TIMESTAMP NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  UNIQUE KEY uniq_customer_duplicate_flags_pair (customer_id, duplicate_of_customer_id),
+  KEY idx_customer_duplicate_flags_original (duplicate_of_customer_id),
+  CONSTRAINT fk_customer_duplicate_flags_customer
+    FOREIGN KEY (customer_id) REFERENCES customers (id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_customer_duplicate_flags_original
+    FOREIGN KEY (duplicate_of_customer_id) REFERENCES customers (id)
+    ON DELETE CASCADE
+);
@@ -0,0 +1,58 @@
+-- Optional per-company spam screening of new leads; no row means every lead is accepted
+CREATE TABLE lead_spam_settings (
+  company_id INT PRIMARY KEY,
+  quarantine_score INT NOT NULL DEFAULT 50,
+  max_identical_per_hour INT NOT NULL DEFAULT 3,
+  -- Newline separated, checked on top of the built-in disposable domains
+  blocked_email_domains TEXT NULL,
+  -- Newline separated E.164 prefixes such as +1900
+  blocked_phone_prefixes TEXT NULL,
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  CONSTRAINT fk_lead_spam_settings_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE
+);
+
+-- Per-rule weight overrides; rules without a row use their default weight, 0 turns a rule off
+CREATE TABLE lead_spam_rules (
+  id INT AUTO_INCREMENT PRIMARY KEY,
+  company_id INT NOT NULL,
+  rule VARCHAR(50) NOT NULL,
+  weight INT NOT NULL,
+  UNIQUE KEY uniq_lead_spam_rules_company_rule (company_id, rule),
+  CONSTRAINT fk_lead_spam_rules_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE
+);
+
+-- Fingerprints of screened leads, used to spot the same submission arriving over and over
+CREATE TABLE lead_spam_fingerprints (
+  id INT AUTO_INCREMENT PRIMARY KEY,
+  company_id INT NOT NULL,
+  fingerprint CHAR(64) NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  KEY idx_lead_spam_fingerprints_lookup (company_id, fingerprint, created_at),
+  CONSTRAINT fk_lead_spam_fingerprints_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE
+);
+
+-- Leads held back as likely spam until a manager approves or rejects them in Telegram
+CREATE TABLE quarantined_leads (
+  id INT AUTO_INCREMENT PRIMARY KEY,
+  company_id INT NOT NULL,
+  payload TEXT NOT NULL,
+  score INT NOT NULL,
+  reasons VARCHAR(255) NOT NULL,
+  status ENUM('pending', 'approved', 'rejected') NOT NULL DEFAULT 'pending',
+  reviewed_by INT NULL,
+  reviewed_at TIMESTAMP NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  KEY idx_quarantined_leads_company_status (company_id, status),
+  CONSTRAINT fk_quarantined_leads_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_quarantined_leads_reviewer
+    FOREIGN KEY (reviewed_by) REFERENCES users (id)
+    ON DELETE SET NULL
+);
@@ -0,0 +1,34 @@
+-- Optional per-company instant reply to new leads; no row means nothing is sent until a rep acts
+CREATE TABLE lead_auto_reply_policies (
+  company_id INT PRIMARY KEY,
+  -- IANA name such as America/Indiana/Indianapolis, used to read company_business_hours
+  timezone VARCHAR(64) NOT NULL,
+  -- Sends on behalf of this user when the lead has no rep yet
+  fallback_user_id INT NULL,
+  -- Templates use the same {{variables}} as email templates; a NULL body skips that channel
+  sms_in_hours TEXT NULL,
+  sms_after_hours TEXT NULL,
+  email_subject VARCHAR(255) NOT NULL DEFAULT '',
+  email_in_hours TEXT NULL,
+  email_after_hours TEXT NULL,
+  is_active BOOLEAN NOT NULL DEFAULT TRUE,
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  CONSTRAINT fk_lead_auto_reply_policies_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_lead_auto_reply_policies_fallback_user
+    FOREIGN KEY (fallback_user_id) REFERENCES users (id)
+    ON DELETE SET NULL
+);
+
+-- Opening hours in the policy timezone; weekday 0 is Monday and a day without a row is closed
+CREATE TABLE company_business_hours (
+  company_id INT NOT NULL,
+  weekday TINYINT NOT NULL,
+  opens_at TIME NOT NULL,
+  closes_at TIME NOT NULL,
+  PRIMARY KEY (company_id, weekday),
+  CONSTRAINT fk_company_business_hours_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE
+);
@@ -0,0 +1,12 @@
+-- Facebook pages subscribed to Meta leadgen webhooks and the company their leads belong to
+CREATE TABLE meta_lead_pages (
+  page_id VARCHAR(64) PRIMARY KEY,
+  company_id INT NOT NULL,
+  -- Page access token with leads_retrieval, used to fetch each lead from the Graph API
+  access_token TEXT NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  KEY idx_meta_lead_pages_company (company_id),
+  CONSTRAINT fk_meta_lead_pages_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE
+);
@@ -0,0 +1,73 @@
+-- Per-company routing of new lead notifications; a company without rules notifies every sales manager
+CREATE TABLE lead_notification_rules (
+  id INT AUTO_INCREMENT PRIMARY KEY,
+  company_id INT NOT NULL,
+  -- Lower runs first; the first rule whose conditions all hold decides the recipients
+  priority INT NOT NULL DEFAULT 0,
+  -- NULL conditions match any lead; text is compared case-insensitively
+  form_name VARCHAR(255) NULL,
+  referral_source VARCHAR(255) NULL,
+  postal_prefix VARCHAR(10) NULL,
+  city VARCHAR(255) NULL,
+  -- Local time window in lead_notification_settings.timezone; starts_at > ends_at wraps midnight
+  starts_at TIME NULL,
+  ends_at TIME NULL,
+  -- Telegram group that gets the lead in addition to the rule's users
+  chat_id BIGINT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  INDEX idx_lead_notification_rules_company (company_id, priority),
+  CONSTRAINT fk_lead_notification_rules_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE
+);
+
+CREATE TABLE lead_notification_rule_users (
+  rule_id INT NOT NULL,
+  user_id INT NOT NULL,
+  PRIMARY KEY (rule_id, user_id),
+  CONSTRAINT fk_lead_notification_rule_users_rule
+    FOREIGN KEY (rule_id) REFERENCES lead_notification_rules (id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_lead_notification_rule_users_user
+    FOREIGN KEY (user_id) REFERENCES users (id)
+    ON DELETE CASCADE
+);
+
+-- Timezone for rule windows and optional quiet hours; no row means UTC and no quiet hours
+CREATE TABLE lead_notification_settings (
+  company_id INT PRIMARY KEY,
+  timezone VARCHAR(64) NOT NULL DEFAULT 'UTC',
+  quiet_starts_at TIME NULL,
+  quiet_ends_at TIME NULL,
+  -- 'delay' holds notifications until quiet hours end, 'on_call' sends them only to on_call_user_id
+  quiet_mode ENUM('delay', 'on_call') NOT NULL DEFAULT 'delay',
+  on_call_user_id INT NULL,
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  CONSTRAINT fk_lead_notification_settings_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_lead_notification_settings_on_call_user
+    FOREIGN KEY (on_call_user_id) REFERENCES users (id)
+    ON DELETE SET NULL
+);
+
+-- Lead notifications held during quiet hours, one row per chat, sent by the time-triggered lambda
+CREATE TABLE delayed_lead_notifications (
+  id BIGINT AUTO_INCREMENT PRIMARY KEY,
+  company_id INT NOT NULL,
+  customer_id INT NOT NULL,
+  chat_id BIGINT NOT NULL,
+  -- Sent as is, with the assign keyboard rebuilt at delivery time
+  message_text TEXT NOT NULL,
+  -- UTC
+  send_after DATETIME NOT NULL,
+  sent_at DATETIME NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  INDEX idx_delayed_lead_notifications_due (sent_at, send_after),
+  CONSTRAINT fk_delayed_lead_notifications_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_delayed_lead_notifications_customer
+    FOREIGN KEY (customer_id) REFERENCES customers (id)
+    ON DELETE CASCADE
+);
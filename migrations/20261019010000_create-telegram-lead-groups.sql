@@ -0,0 +1,16 @@
+-- Shared Telegram group per company that gets new leads once instead of every manager's private chat
+CREATE TABLE telegram_lead_groups (
+  company_id INT PRIMARY KEY,
+  -- Negative Telegram id of the group or supergroup; updated when a group is upgraded
+  chat_id BIGINT NOT NULL,
+  title VARCHAR(255) NULL,
+  linked_by_user_id INT NULL,
+  linked_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  UNIQUE KEY uniq_telegram_lead_groups_chat (chat_id),
+  CONSTRAINT fk_telegram_lead_groups_company
+    FOREIGN KEY (company_id) REFERENCES company (id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_telegram_lead_groups_user
+    FOREIGN KEY (linked_by_user_id) REFERENCES users (id)
+    ON DELETE SET NULL
+);
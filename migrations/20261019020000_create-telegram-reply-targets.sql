@@ -0,0 +1,18 @@
+-- Conversation behind each inbound email/SMS notification, so a Telegram reply can be sent back to the customer
+CREATE TABLE telegram_reply_targets (
+  chat_id BIGINT NOT NULL,
+  message_id INT NOT NULL,
+  user_id INT NOT NULL,
+  channel ENUM('email', 'sms') NOT NULL,
+  -- Set for email notifications
+  thread_id CHAR(36) NULL,
+  -- Set for SMS notifications, as CloudTalk match keys
+  company_id INT NULL,
+  customer_phone BIGINT UNSIGNED NULL,
+  business_phone BIGINT UNSIGNED NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (chat_id, message_id),
+  CONSTRAINT fk_telegram_reply_targets_user
+    FOREIGN KEY (user_id) REFERENCES users (id)
+    ON DELETE CASCADE
+);
@@ -0,0 +1,32 @@
+-- Audit trail of Telegram account linking and one-time deep-link tokens issued by the CRM
+
+-- Pending codes were issued without an expiry; make their owners request a new one
+UPDATE users
+SET telegram_conf_code = NULL
+WHERE telegram_conf_code IS NOT NULL AND telegram_conf_expires_at IS NULL;
+
+CREATE TABLE telegram_link_attempts (
+  id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+  chat_id BIGINT NOT NULL,
+  -- Account the attempt targeted, when known
+  user_id INT NULL,
+  outcome ENUM('code_sent', 'code_rejected', 'linked', 'locked_out', 'link_rejected') NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  INDEX idx_telegram_link_attempts_chat (chat_id, created_at),
+  INDEX idx_telegram_link_attempts_user (user_id, created_at),
+  CONSTRAINT fk_telegram_link_attempts_user
+    FOREIGN KEY (user_id) REFERENCES users (id)
+    ON DELETE SET NULL
+);
+
+CREATE TABLE telegram_link_tokens (
+  nonce CHAR(32) NOT NULL PRIMARY KEY,
+  user_id INT NOT NULL,
+  expires_at TIMESTAMP NOT NULL,
+  used_at TIMESTAMP NULL,
+  used_by_chat_id BIGINT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  CONSTRAINT fk_telegram_link_tokens_user
+    FOREIGN KEY (user_id) REFERENCES users (id)
+    ON DELETE CASCADE
+);
@@ -0,0 +1,13 @@
+-- First click of each tracked link per customer; the unique key makes the rep notification fire once
+CREATE TABLE email_click_firsts (
+  company_id INT NOT NULL,
+  customer_id INT NOT NULL,
+  url_hash CHAR(64) NOT NULL,
+  clicked_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (company_id, customer_id, url_hash)
+);
+
+INSERT IGNORE INTO email_click_firsts (company_id, customer_id, url_hash, clicked_at)
+SELECT company_id, customer_id, SHA2(url, 256), MIN(clicked_at)
+FROM email_clicks
+GROUP BY company_id, customer_id, SHA2(url, 256);
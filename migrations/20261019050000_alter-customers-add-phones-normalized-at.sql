@@ -0,0 +1,4 @@
+-- Set once the phone backfill has tried a customer, so numbers that do not parse are not retried
+ALTER TABLE customers
+    ADD COLUMN phones_normalized_at TIMESTAMP NULL AFTER phone_2_e164,
+    ADD INDEX idx_customers_phones_normalized_at (phones_normalized_at, id);
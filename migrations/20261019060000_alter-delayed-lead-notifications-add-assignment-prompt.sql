@@ -0,0 +1,4 @@
+-- Rows without the prompt are notices about leads that already have a rep, so they are sent
+-- even after the lead is assigned
+ALTER TABLE delayed_lead_notifications
+    ADD COLUMN include_assignment_prompt BOOLEAN NOT NULL DEFAULT TRUE AFTER message_text;
@@ -0,0 +1,19 @@
+-- When a deal was marked won. is_won is set by the CRM, so triggers keep won_at in step with it
+ALTER TABLE deals
+    ADD COLUMN won_at TIMESTAMP NULL AFTER is_won,
+    ADD INDEX idx_deals_user_won_at (user_id, won_at);
+
+-- updated_at moves on any edit, but it is the closest record of when existing deals were won
+UPDATE deals SET won_at = COALESCE(updated_at, created_at) WHERE is_won = 1;
+
+CREATE TRIGGER deals_won_at_insert BEFORE INSERT ON deals
+FOR EACH ROW
+SET NEW.won_at = IF(NEW.is_won = 1, COALESCE(NEW.won_at, CURRENT_TIMESTAMP), NULL);
+
+CREATE TRIGGER deals_won_at_update BEFORE UPDATE ON deals
+FOR EACH ROW
+SET NEW.won_at = CASE
+    WHEN NOT (NEW.is_won <=> 1) THEN NULL
+    WHEN OLD.is_won <=> 1 THEN NEW.won_at
+    ELSE CURRENT_TIMESTAMP
+END;
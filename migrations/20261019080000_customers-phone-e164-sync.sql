@@ -0,0 +1,38 @@
+-- phone and phone_2 are also edited by the CRM, which does not write the E.164 copies.
+-- A number changed without its copy loses the copy and goes back to the phone backfill;
+-- until then duplicate matching falls back to the number itself.
+CREATE TRIGGER customers_phone_e164_update BEFORE UPDATE ON customers
+FOR EACH ROW
+SET
+    NEW.phones_normalized_at = IF(
+        (NOT (NEW.phone <=> OLD.phone) AND NEW.phone_e164 <=> OLD.phone_e164)
+            OR (NOT (NEW.phone_2 <=> OLD.phone_2) AND NEW.phone_2_e164 <=> OLD.phone_2_e164),
+        NULL,
+        NEW.phones_normalized_at
+    ),
+    NEW.phone_e164 = IF(
+        NOT (NEW.phone <=> OLD.phone) AND NEW.phone_e164 <=> OLD.phone_e164,
+        NULL,
+        NEW.phone_e164
+    ),
+    NEW.phone_2_e164 = IF(
+        NOT (NEW.phone_2 <=> OLD.phone_2) AND NEW.phone_2_e164 <=> OLD.phone_2_e164,
+        NULL,
+        NEW.phone_2_e164
+    );
+
+-- Copies already left behind by CRM edits: the number no longer contains the copy's last
+-- ten digits
+UPDATE customers
+SET phone_e164 = NULL, phones_normalized_at = NULL
+WHERE phone_e164 IS NOT NULL
+  AND (phone IS NULL
+    OR LOCATE(RIGHT(REGEXP_REPLACE(phone_e164, '[^0-9]', ''), 10),
+              REGEXP_REPLACE(phone, '[^0-9]', '')) = 0);
+
+UPDATE customers
+SET phone_2_e164 = NULL, phones_normalized_at = NULL
+WHERE phone_2_e164 IS NOT NULL
+  AND (phone_2 IS NULL
+    OR LOCATE(RIGHT(REGEXP_REPLACE(phone_2_e164, '[^0-9]', ''), 10),
+              REGEXP_REPLACE(phone_2, '[^0-9]', '')) = 0);
@@ -0,0 +1,17 @@
+-- event_time is when the deal was won or entered the stage, so the queue always writes it
+ALTER TABLE conversion_export_queue MODIFY event_time TIMESTAMP NOT NULL;
+
+-- Pending events were dated when they were queued
+UPDATE conversion_export_queue q
+INNER JOIN conversion_export_rules r ON r.id = q.rule_id
+INNER JOIN deals d ON d.id = q.deal_id
+SET q.event_time = IF(
+    r.list_id IS NULL,
+    COALESCE(d.won_at, q.event_time),
+    COALESCE(
+        (SELECT MAX(h.entered_at) FROM deal_stage_history h
+         WHERE h.deal_id = d.id AND h.list_id = r.list_id),
+        q.event_time
+    )
+)
+WHERE q.status = 'pending';
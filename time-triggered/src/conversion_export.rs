@@ -0,0 +1,375 @@
+use common::crud::conversion_exports::{
+    enqueue_conversion_events, expire_conversion_events, get_due_conversion_events,
+    mark_conversion_event_failed, mark_conversion_event_sent, ConversionPlatform,
+    PendingConversionEvent,
+};
+use common::utils::conversions::{
+    google_ads_upload_request, meta_events_request, GoogleAdsUploadRequest, MetaEventsRequest,
+};
+use lambda_runtime::{tracing, Error};
+use reqwest::Client;
+use serde_json::json;
+use sqlx::MySqlPool;
+use std::future::Future;
+
+const META_GRAPH_URL: &str = "https://graph.facebook.com/v21.0";
+const GOOGLE_ADS_API_URL: &str = "https://googleads.googleapis.com/v18";
+const GOOGLE_OAUTH_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+/// Events sent per scheduled run; the rest wait for the next tick.
+const EXPORT_BATCH_SIZE: u32 = 100;
+
+pub(crate) struct GoogleAdsAccount<'a> {
+    pub customer_id: &'a str,
+    pub login_customer_id: Option<&'a str>,
+    pub refresh_token: &'a str,
+}
+
+/// Ad platform endpoints, behind a trait so the export can run against a local stand-in.
+pub(crate) trait ConversionApi: Send + Sync {
+    fn send_meta_events<'a>(
+        &'a self,
+        pixel_id: &'a str,
+        access_token: &'a str,
+        request: &'a MetaEventsRequest,
+    ) -> impl Future<Output = Result<(), String>> + Send + 'a;
+
+    fn upload_google_ads_conversions<'a>(
+        &'a self,
+        account: &'a GoogleAdsAccount<'a>,
+        request: &'a GoogleAdsUploadRequest,
+    ) -> impl Future<Output = Result<(), String>> + Send + 'a;
+}
+
+#[derive(Clone)]
+struct GoogleAdsApp {
+    developer_token: String,
+    client_id: String,
+    client_secret: String,
+}
+
+#[derive(Clone)]
+pub(crate) struct HttpConversionApi {
+    client: Client,
+    google: Option<GoogleAdsApp>,
+}
+
+impl HttpConversionApi {
+    /// Google Ads needs the app's developer token and OAuth client; Meta only uses the
+    /// per-company access token.
+    pub(crate) fn from_env() -> Self {
+        let google = match (
+            std::env::var("GOOGLE_ADS_DEVELOPER_TOKEN"),
+            std::env::var("GOOGLE_ADS_CLIENT_ID"),
+            std::env::var("GOOGLE_ADS_CLIENT_SECRET"),
+        ) {
+            (Ok(developer_token), Ok(client_id), Ok(client_secret)) => Some(GoogleAdsApp {
+                developer_token,
+                client_id,
+                client_secret,
+            }),
+            _ => None,
+        };
+        Self {
+            client: Client::new(),
+            google,
+        }
+    }
+
+    async fn google_access_token(
+        &self,
+        app: &GoogleAdsApp,
+        refresh_token: &str,
+    ) -> Result<String, String> {
+        let response = self
+            .client
+            .post(GOOGLE_OAUTH_TOKEN_URL)
+            .json(&json!({
+                "client_id": app.client_id,
+                "client_secret": app.client_secret,
+                "refresh_token": refresh_token,
+                "grant_type": "refresh_token",
+            }))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        let body = response
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !status.is_success() {
+            return Err(format!("Google OAuth returned {status}: {body}"));
+        }
+        body.get("access_token")
+            .and_then(|value| value.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| "Google OAuth response has no access_token".to_string())
+    }
+}
+
+impl ConversionApi for HttpConversionApi {
+    async fn send_meta_events(
+        &self,
+        pixel_id: &str,
+        access_token: &str,
+        request: &MetaEventsRequest,
+    ) -> Result<(), String> {
+        let response = self
+            .client
+            .post(format!("{META_GRAPH_URL}/{pixel_id}/events"))
+            .json(&json!({ "data": request.data, "access_token": access_token }))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body = response.text().await.unwrap_or_default();
+        Err(format!("Meta returned {status}: {body}"))
+    }
+
+    async fn upload_google_ads_conversions(
+        &self,
+        account: &GoogleAdsAccount<'_>,
+        request: &GoogleAdsUploadRequest,
+    ) -> Result<(), String> {
+        let Some(app) = &self.google else {
+            return Err("GOOGLE_ADS_DEVELOPER_TOKEN or OAuth client is not set".to_string());
+        };
+        let access_token = self.google_access_token(app, account.refresh_token).await?;
+        let customer_id = account.customer_id.replace('-', "");
+        let mut builder = self
+            .client
+            .post(format!(
+                "{GOOGLE_ADS_API_URL}/customers/{customer_id}:uploadClickConversions"
+            ))
+            .bearer_auth(access_token)
+            .header("developer-token", &app.developer_token)
+            .json(request);
+        if let Some(login_customer_id) = account.login_customer_id {
+            builder = builder.header("login-customer-id", login_customer_id.replace('-', ""));
+        }
+        let response = builder.send().await.map_err(|e| e.to_string())?;
+        let status = response.status();
+        let body = response
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !status.is_success() {
+            return Err(format!("Google Ads returned {status}: {body}"));
+        }
+        // With partialFailure the call succeeds even when the conversion was rejected.
+        match body.get("partialFailureError") {
+            Some(error) if !error.is_null() => Err(format!("Google Ads rejected: {error}")),
+            _ => Ok(()),
+        }
+    }
+}
+
+async fn send_conversion_event<C: ConversionApi>(
+    api: &C,
+    event: &PendingConversionEvent,
+) -> Result<(), String> {
+    match ConversionPlatform::from_db(&event.platform) {
+        Some(ConversionPlatform::Meta) => {
+            let (Some(pixel_id), Some(access_token)) = (
+                event.meta_pixel_id.as_deref(),
+                event.meta_access_token.as_deref(),
+            ) else {
+                return Err("Meta pixel or access token is missing".to_string());
+            };
+            let Some(request) = meta_events_request(event) else {
+                return Err("Rule has no Meta event name".to_string());
+            };
+            api.send_meta_events(pixel_id, access_token, &request).await
+        }
+        Some(ConversionPlatform::GoogleAds) => {
+            let (Some(customer_id), Some(refresh_token)) = (
+                event.google_ads_customer_id.as_deref(),
+                event.google_ads_refresh_token.as_deref(),
+            ) else {
+                return Err("Google Ads customer or refresh token is missing".to_string());
+            };
+            let Some(request) = google_ads_upload_request(event) else {
+                return Err("Lead has no click id, email or phone to match on".to_string());
+            };
+            let account = GoogleAdsAccount {
+                customer_id,
+                login_customer_id: event.google_ads_login_customer_id.as_deref(),
+                refresh_token,
+            };
+            api.upload_google_ads_conversions(&account, &request).await
+        }
+        None => Err(format!("Unknown platform {}", event.platform)),
+    }
+}
+
+/// Queues new won-deal and stage events, then sends what is due. Failed sends stay queued
+/// and are retried with backoff on later runs, until the platform's upload window closes.
+pub(crate) async fn export_conversions<C: ConversionApi>(
+    pool: &MySqlPool,
+    api: &C,
+) -> Result<usize, Error> {
+    let queued = enqueue_conversion_events(pool).await?;
+    if queued > 0 {
+        tracing::info!(queued, "Queued offline conversion events");
+    }
+    let expired = expire_conversion_events(pool).await?;
+    if expired > 0 {
+        tracing::warn!(
+            expired,
+            "Dropped offline conversion events past the upload window"
+        );
+    }
+    let mut sent = 0usize;
+    for event in get_due_conversion_events(pool, EXPORT_BATCH_SIZE).await? {
+        match send_conversion_event(api, &event).await {
+            Ok(()) => {
+                mark_conversion_event_sent(pool, event.id).await?;
+                sent += 1;
+            }
+            Err(error) => {
+                tracing::warn!(
+                    %error,
+                    event_id = event.id,
+                    deal_id = event.deal_id,
+                    platform = %event.platform,
+                    attempt = event.attempts + 1,
+                    "Failed to export offline conversion"
+                );
+                mark_conversion_event_failed(pool, event.id, &error).await?;
+            }
+        }
+    }
+    Ok(sent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records request bodies instead of calling Meta and Google.
+    #[derive(Default)]
+    struct LocalConversionApi {
+        fail: bool,
+        meta: Mutex<Vec<(String, serde_json::Value)>>,
+        google: Mutex<Vec<(String, serde_json::Value)>>,
+    }
+
+    impl ConversionApi for LocalConversionApi {
+        async fn send_meta_events(
+            &self,
+            pixel_id: &str,
+            _access_token: &str,
+            request: &MetaEventsRequest,
+        ) -> Result<(), String> {
+            if self.fail {
+                return Err("meta unavailable".to_string());
+            }
+            let body = serde_json::to_value(request).unwrap();
+            self.meta.lock().unwrap().push((pixel_id.to_string(), body));
+            Ok(())
+        }
+
+        async fn upload_google_ads_conversions(
+            &self,
+            account: &GoogleAdsAccount<'_>,
+            request: &GoogleAdsUploadRequest,
+        ) -> Result<(), String> {
+            if self.fail {
+                return Err("google unavailable".to_string());
+            }
+            let body = serde_json::to_value(request).unwrap();
+            self.google
+                .lock()
+                .unwrap()
+                .push((account.customer_id.to_string(), body));
+            Ok(())
+        }
+    }
+
+    async fn won_deal_with_gclid(pool: &MySqlPool) {
+        sqlx::query!(
+            "INSERT INTO conversion_export_settings (company_id, meta_pixel_id, meta_access_token, google_ads_customer_id, google_ads_refresh_token) VALUES (1, 'pixel-1', 'token', '123-456-7890', 'refresh')"
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO conversion_export_rules (company_id, meta_event_name, google_conversion_action_id) VALUES (1, 'Purchase', '987')"
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        let customer_id = sqlx::query!(
+            "INSERT INTO customers (name, company_id, source, phone) VALUES ('Won Lead', 1, 'leads', '317-555-0199')"
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        sqlx::query!(
+            "INSERT INTO lead_attributions (customer_id, touch, gclid) VALUES (?, 'first', 'gclid-1')",
+            customer_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO deals (customer_id, list_id, position, amount, is_won) VALUES (?, 4, 0, 9000, 1)",
+            customer_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn won_deal_is_exported_to_both_platforms_once(pool: MySqlPool) {
+        won_deal_with_gclid(&pool).await;
+        let api = LocalConversionApi::default();
+
+        assert_eq!(export_conversions(&pool, &api).await.unwrap(), 2);
+        assert_eq!(export_conversions(&pool, &api).await.unwrap(), 0);
+
+        let meta = api.meta.lock().unwrap();
+        assert_eq!(meta.len(), 1);
+        assert_eq!(meta[0].0, "pixel-1");
+        assert_eq!(meta[0].1["data"][0]["event_name"], "Purchase");
+        assert_eq!(meta[0].1["data"][0]["custom_data"]["value"], 9000.0);
+        let google = api.google.lock().unwrap();
+        assert_eq!(google.len(), 1);
+        assert_eq!(google[0].0, "123-456-7890");
+        assert_eq!(google[0].1["conversions"][0]["gclid"], "gclid-1");
+
+        let statuses = sqlx::query_scalar!("SELECT status FROM conversion_export_queue")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(statuses, vec!["sent", "sent"]);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn failed_export_stays_queued_for_retry(pool: MySqlPool) {
+        won_deal_with_gclid(&pool).await;
+        let api = LocalConversionApi {
+            fail: true,
+            ..LocalConversionApi::default()
+        };
+
+        assert_eq!(export_conversions(&pool, &api).await.unwrap(), 0);
+
+        let rows = sqlx::query!("SELECT status, attempts, last_error FROM conversion_export_queue")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        for row in rows {
+            assert_eq!(row.status, "pending");
+            assert_eq!(row.attempts, 1);
+            assert!(row.last_error.unwrap().ends_with("unavailable"));
+        }
+    }
+}
@@ -1,5 +1,11 @@
+use crate::conversion_export::{export_conversions, HttpConversionApi};
+use crate::lead_escalation::escalate_unassigned_leads;
+use crate::lead_notifications::send_delayed_lead_notifications;
 use crate::schemas::{EventBridgeEvent, OutgoingMessage};
-use common::amazon::email::{assigned_sender_from, send_message_from};
+use common::amazon::email::{
+    assigned_sender_from, send_message_from, send_message_with_unsubscribe,
+};
+use common::crud::customer_phones::backfill_customer_phones;
 use common::crud::notifications::{
     get_due_activity_deadline_reminders, mark_deadline_reminder_telegram_sent,
 };
@@ -11,12 +17,22 @@ use common::crud::scheduled_emails::{
     get_ready_scheduled_emails, mark_scheduled_email_as_sent,
     mark_scheduled_email_failed_with_reason, ScheduledEmail,
 };
+use common::crud::suppressions::get_email_suppression_reason;
 use common::crud::template::fetch_template_variable_data;
+use common::telegram::actions::{
+    crm_action_markup, crm_actions_secret_from_env, reminder_action_rows,
+};
+use common::utils::click_tracking::{track_links_from_env, ClickTarget};
 use common::utils::template::replace_template_variables;
+use common::utils::unsubscribe::unsubscribe_link_from_env;
 use lambda_runtime::{tracing, Error, LambdaEvent};
 use reqwest::Client;
 use sqlx::MySqlPool;
 use teloxide::prelude::*;
+use teloxide::types::ParseMode;
+
+/// Customers per scheduled run while filling `phone_e164` for rows written without it.
+const PHONE_BACKFILL_BATCH_SIZE: u32 = 500;
 
 async fn send_due_activity_deadline_reminders(pool: &MySqlPool) -> Result<usize, Error> {
     let reminders = get_due_activity_deadline_reminders(pool).await?;
@@ -27,6 +43,7 @@ async fn send_due_activity_deadline_reminders(pool: &MySqlPool) -> Result<usize,
         .or_else(|_| std::env::var("TELEGRAM_NOTIFICATIONS_BOT_TOKEN"))
         .map_err(|error| Error::from(error.to_string()))?;
     let bot = teloxide::Bot::new(token);
+    let actions_secret = crm_actions_secret_from_env();
     let mut sent_count = 0usize;
 
     for reminder in reminders {
@@ -43,7 +60,17 @@ async fn send_due_activity_deadline_reminders(pool: &MySqlPool) -> Result<usize,
             &reminder.message,
             i32::try_from(reminder.deal_id).unwrap_or(i32::MAX),
         );
-        match bot.send_message(ChatId(telegram_id), text).await {
+        let mut request = bot
+            .send_message(ChatId(telegram_id), text)
+            .parse_mode(ParseMode::Html);
+        if let Some(secret) = actions_secret.as_deref() {
+            request = request.reply_markup(crm_action_markup(
+                secret,
+                telegram_id,
+                reminder_action_rows(reminder.id, reminder.deal_id),
+            ));
+        }
+        match request.await {
             Ok(_) => {
                 mark_deadline_reminder_telegram_sent(pool, reminder.id).await?;
                 sent_count += 1;
@@ -138,6 +165,10 @@ pub fn scheduled_email_recipient(email: Option<&str>) -> Option<&str> {
     email.map(str::trim).filter(|value| !value.is_empty())
 }
 
+pub fn suppressed_recipient_message(reason: &str) -> String {
+    format!("Recipient is on the company suppression list ({reason})")
+}
+
 async fn send_and_record_scheduled_email(
     pool: &MySqlPool,
     email: &ScheduledEmail,
@@ -156,6 +187,23 @@ async fn send_and_record_scheduled_email(
         );
         return Ok(());
     };
+    if let Some(reason) =
+        get_email_suppression_reason(pool, email.company_id, cleaned_email).await?
+    {
+        mark_scheduled_email_failed_with_reason(
+            pool,
+            email.id,
+            &suppressed_recipient_message(&reason),
+        )
+        .await?;
+        tracing::warn!(
+            customer_id = email.customer_id,
+            scheduled_email_id = email.id,
+            %reason,
+            "Skipping automated email, recipient is suppressed"
+        );
+        return Ok(());
+    }
     let data = fetch_template_variable_data(
         pool,
         email.user_id,
@@ -165,7 +213,14 @@ async fn send_and_record_scheduled_email(
     )
     .await
     .map_err(|error| Error::from(error.to_string()))?;
-    let html_body = replace_template_variables(&email.template_body, &data);
+    let html_body = track_links_from_env(
+        &replace_template_variables(&email.template_body, &data),
+        &ClickTarget {
+            company_id: email.company_id,
+            customer_id: email.customer_id,
+            scheduled_email_id: Some(email.id),
+        },
+    );
     let from = assigned_sender_from(
         data.company
             .as_ref()
@@ -173,10 +228,26 @@ async fn send_and_record_scheduled_email(
         data.user.email.as_deref(),
         data.user.email_name.as_deref(),
     );
-    let message_id =
-        send_message_from(&[cleaned_email], &email.template_subject, &html_body, &from)
+    let message_id = match unsubscribe_link_from_env(email.company_id, email.customer_id) {
+        Some(unsubscribe_url) => {
+            send_message_with_unsubscribe(
+                &[cleaned_email],
+                &email.template_subject,
+                &html_body,
+                &from,
+                &unsubscribe_url,
+            )
             .await
-            .map_err(|error| Error::from(error.to_string()))?;
+        }
+        None => {
+            tracing::warn!(
+                scheduled_email_id = email.id,
+                "Unsubscribe link is not configured; sending without List-Unsubscribe"
+            );
+            send_message_from(&[cleaned_email], &email.template_subject, &html_body, &from).await
+        }
+    }
+    .map_err(|error| Error::from(error.to_string()))?;
     if let Err(error) = record_outbound_scheduled_email(
         pool,
         &OutboundScheduledEmail {
@@ -248,14 +319,22 @@ pub(crate) async fn function_handler(
     let maintenance_reminder_count = process_maintenance_due_reminders().await?;
     let sms_followup_count = process_sms_followups().await?;
     let checklist_survey_count = process_checklist_surveys().await?;
+    let delayed_lead_count = send_delayed_lead_notifications(pool).await?;
+    let lead_escalation_count = escalate_unassigned_leads(pool).await?;
+    let conversion_export_count = export_conversions(pool, &HttpConversionApi::from_env()).await?;
+    let phone_backfill_count = backfill_customer_phones(pool, PHONE_BACKFILL_BATCH_SIZE).await?;
     let message = format!(
-        "Successfully processed {} emails, {} activity deadline reminders, {} estimate appointment reminders, {} maintenance due reminders, {} sms follow-ups, and {} checklist surveys",
+        "Successfully processed {} emails, {} activity deadline reminders, {} estimate appointment reminders, {} maintenance due reminders, {} sms follow-ups, {} checklist surveys, {} delayed lead notifications, {} lead escalations, {} conversion exports, and {} phone normalizations",
         ready_emails.len(),
         reminder_count,
         estimate_reminder_count,
         maintenance_reminder_count,
         sms_followup_count,
-        checklist_survey_count
+        checklist_survey_count,
+        delayed_lead_count,
+        lead_escalation_count,
+        conversion_export_count,
+        phone_backfill_count
     );
     let resp = OutgoingMessage::new(event.context.request_id, message.clone());
     tracing::info!("{}", message);
@@ -282,6 +361,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn suppressed_recipient_message_names_the_reason() {
+        assert_eq!(
+            suppressed_recipient_message("complaint"),
+            "Recipient is on the company suppression list (complaint)"
+        );
+    }
+
+    #[test]
+    fn send_and_record_checks_suppression_before_sending() {
+        let source = include_str!("generic_handler.rs");
+        let fn_start = source
+            .find("async fn send_and_record_scheduled_email")
+            .expect("send function");
+        let body = &source[fn_start..];
+        let check_at = body
+            .find("get_email_suppression_reason")
+            .expect("suppression check");
+        let send_at = body
+            .find("send_message_with_unsubscribe(")
+            .expect("send call");
+        assert!(
+            check_at < send_at,
+            "Suppressed recipients must be skipped before SES is called"
+        );
+    }
+
     #[test]
     fn send_and_record_saves_history_before_marking_sent() {
         let source = include_str!("generic_handler.rs");
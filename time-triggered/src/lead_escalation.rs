@@ -0,0 +1,304 @@
+use common::crud::assignment::{
+    list_rep_assignment_settings, lock_assignment_policy, set_last_assigned_user,
+};
+use common::crud::lead_escalations::{
+    claim_lead_escalation, get_leads_due_for_auto_assign, get_leads_due_for_reping,
+    list_manager_lead_messages, set_lead_escalation_assigned_user, EscalationStep,
+    ManagerLeadMessage, UnassignedLead,
+};
+use common::crud::leads::{
+    assign_unassigned_lead, create_deal_in, get_default_list_id_from_company_id,
+};
+use common::crud::scheduled_emails::schedule_templates_for_deal_list;
+use common::crud::user::{get_sales_users, SalesUser, SALES_WORKER};
+use common::telegram::crm::deal_project_url;
+use common::telegram::html::escape_html;
+use common::telegram::leads::{
+    assign_button_label, assign_callback_data, format_auto_assigned_lead_message,
+    format_escalated_lead_message,
+};
+use common::utils::assignment::{pick_rep, rep_candidates, AssignmentMode};
+use lambda_runtime::{tracing, Error};
+use sqlx::MySqlPool;
+use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, MessageId, ParseMode};
+
+/// The keyboard the webhooks bot sent with the lead, rebuilt with current month-to-date counts
+/// because editing a message's text drops its buttons.
+pub(crate) fn assignment_keyboard(customer_id: i32, users: &[SalesUser]) -> InlineKeyboardMarkup {
+    let lead_id = u64::try_from(customer_id).unwrap_or(0);
+    let buttons: Vec<InlineKeyboardButton> = users
+        .iter()
+        .filter(|user| user.position_id == SALES_WORKER)
+        .map(|user| {
+            InlineKeyboardButton::callback(
+                assign_button_label(
+                    user.name.as_deref().unwrap_or("Unknown"),
+                    user.mtd_lead_count,
+                ),
+                assign_callback_data(lead_id, user.user_position_id),
+            )
+        })
+        .collect();
+    InlineKeyboardMarkup::new(buttons.chunks(2).map(<[InlineKeyboardButton]>::to_vec))
+}
+
+/// The stored HTML of the lead message; messages sent before the text was stored fall back
+/// to the customer name.
+fn original_text(message: &ManagerLeadMessage, lead: &UnassignedLead) -> String {
+    message.message_text.clone().unwrap_or_else(|| {
+        format!(
+            "Lead: {}",
+            escape_html(lead.customer_name.as_deref().unwrap_or("Unknown"))
+        )
+    })
+}
+
+async fn reping_lead(pool: &MySqlPool, bot: &Bot, lead: &UnassignedLead) -> Result<bool, Error> {
+    if !claim_lead_escalation(
+        pool,
+        lead.customer_id,
+        lead.company_id,
+        EscalationStep::Reping,
+    )
+    .await?
+    {
+        return Ok(false);
+    }
+    let users = get_sales_users(pool, lead.company_id).await?;
+    let keyboard = assignment_keyboard(lead.customer_id, &users);
+    for message in list_manager_lead_messages(pool, lead.company_id, lead.customer_id).await? {
+        let text =
+            format_escalated_lead_message(&original_text(&message, lead), lead.threshold_minutes);
+        if let Err(error) = bot
+            .edit_message_text(ChatId(message.chat_id), MessageId(message.message_id), text)
+            .parse_mode(ParseMode::Html)
+            .reply_markup(keyboard.clone())
+            .await
+        {
+            tracing::error!(
+                ?error,
+                customer_id = lead.customer_id,
+                chat_id = message.chat_id,
+                message_id = message.message_id,
+                "Failed to re-ping manager about unassigned lead"
+            );
+        }
+    }
+    Ok(true)
+}
+
+async fn auto_assign_lead(
+    pool: &MySqlPool,
+    bot: &Bot,
+    lead: &UnassignedLead,
+) -> Result<bool, Error> {
+    let users = get_sales_users(pool, lead.company_id).await?;
+    let settings = list_rep_assignment_settings(pool, lead.company_id).await?;
+    let list_id = get_default_list_id_from_company_id(pool, lead.company_id).await?;
+    // The claim commits with the assignment, so a failed write leaves the lead to retry.
+    let mut tx = pool.begin().await?;
+    // Picked under the policy lock, so escalations and new leads keep the rotation. A lead
+    // nobody qualifies for rolls back unclaimed and is retried on the next run.
+    let last_assigned = lock_assignment_policy(&mut tx, lead.company_id)
+        .await?
+        .and_then(|policy| policy.last_assigned_user_id);
+    let Some(user_id) = pick_rep(
+        AssignmentMode::from_db(&lead.fallback_mode),
+        &rep_candidates(&users, &settings),
+        last_assigned,
+        &[],
+        None,
+    ) else {
+        tracing::warn!(
+            customer_id = lead.customer_id,
+            company_id = lead.company_id,
+            "No rep qualifies for escalation auto-assignment"
+        );
+        return Ok(false);
+    };
+    if !claim_lead_escalation(
+        &mut *tx,
+        lead.customer_id,
+        lead.company_id,
+        EscalationStep::AutoAssign,
+    )
+    .await?
+    {
+        return Ok(false);
+    }
+    // A manager may have tapped Assign since the lead was read; their choice stands.
+    if !assign_unassigned_lead(&mut *tx, lead.customer_id, user_id).await? {
+        tx.rollback().await?;
+        return Ok(false);
+    }
+    let deal = create_deal_in(&mut tx, lead.customer_id, list_id, 0, user_id).await?;
+    set_last_assigned_user(&mut *tx, lead.company_id, user_id).await?;
+    set_lead_escalation_assigned_user(
+        &mut *tx,
+        lead.customer_id,
+        EscalationStep::AutoAssign,
+        user_id,
+    )
+    .await?;
+    tx.commit().await?;
+    if deal.created {
+        if let Err(error) = schedule_templates_for_deal_list(
+            pool,
+            list_id,
+            lead.company_id,
+            deal.id,
+            lead.customer_id,
+            user_id,
+        )
+        .await
+        {
+            tracing::error!(
+                ?error,
+                deal_id = deal.id,
+                "Failed to schedule list drip emails for escalated lead"
+            );
+        }
+    }
+
+    let rep = users.iter().find(|user| user.id == user_id);
+    let rep_name = rep
+        .and_then(|user| user.name.as_deref())
+        .unwrap_or("Unknown");
+    for message in list_manager_lead_messages(pool, lead.company_id, lead.customer_id).await? {
+        let text = format_auto_assigned_lead_message(
+            &original_text(&message, lead),
+            rep_name,
+            lead.threshold_minutes,
+        );
+        if let Err(error) = bot
+            .edit_message_text(ChatId(message.chat_id), MessageId(message.message_id), text)
+            .parse_mode(ParseMode::Html)
+            .await
+        {
+            tracing::error!(
+                ?error,
+                customer_id = lead.customer_id,
+                chat_id = message.chat_id,
+                message_id = message.message_id,
+                "Failed to update manager message after auto-assignment"
+            );
+        }
+    }
+    if let Some(telegram_id) = rep.and_then(|user| user.telegram_id) {
+        let text = format!(
+            "You were assigned a lead. Click here: \n{}",
+            deal_project_url(i32::try_from(deal.id).unwrap_or(i32::MAX))
+        );
+        if let Err(error) = bot.send_message(ChatId(telegram_id), text).await {
+            tracing::error!(?error, user_id, "Failed to notify auto-assigned rep");
+        }
+    }
+    Ok(true)
+}
+
+/// Re-pings managers about leads nobody assigned and, after the second threshold, assigns
+/// them with the company's fallback rule. Each step fires once per lead.
+pub(crate) async fn escalate_unassigned_leads(pool: &MySqlPool) -> Result<usize, Error> {
+    let auto_assign_due = get_leads_due_for_auto_assign(pool).await?;
+    let reping_due = get_leads_due_for_reping(pool).await?;
+    if auto_assign_due.is_empty() && reping_due.is_empty() {
+        return Ok(0);
+    }
+    let token = match std::env::var("TELOXIDE_TOKEN") {
+        Ok(value) => value,
+        Err(error) => {
+            tracing::warn!(
+                ?error,
+                "TELOXIDE_TOKEN is not set; skipping lead escalations"
+            );
+            return Ok(0);
+        }
+    };
+    let bot = Bot::new(token);
+    let mut escalated = 0usize;
+    let mut assigned: Vec<i32> = Vec::new();
+
+    for lead in &auto_assign_due {
+        match auto_assign_lead(pool, &bot, lead).await {
+            Ok(true) => {
+                escalated += 1;
+                assigned.push(lead.customer_id);
+            }
+            Ok(false) => {}
+            Err(error) => tracing::error!(
+                ?error,
+                customer_id = lead.customer_id,
+                "Failed to auto-assign escalated lead"
+            ),
+        }
+    }
+    for lead in reping_due
+        .iter()
+        .filter(|lead| !assigned.contains(&lead.customer_id))
+    {
+        match reping_lead(pool, &bot, lead).await {
+            Ok(true) => escalated += 1,
+            Ok(false) => {}
+            Err(error) => tracing::error!(
+                ?error,
+                customer_id = lead.customer_id,
+                "Failed to re-ping managers about lead"
+            ),
+        }
+    }
+
+    Ok(escalated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use teloxide::types::InlineKeyboardButtonKind;
+
+    fn user(id: i32, position_id: i32, name: &str, mtd_lead_count: i64) -> SalesUser {
+        SalesUser {
+            id,
+            telegram_id: None,
+            name: Some(name.to_string()),
+            position_id,
+            mtd_lead_count,
+            user_position_id: id * 10,
+        }
+    }
+
+    #[test]
+    fn keyboard_lists_sales_workers_two_per_row() {
+        let users = [
+            user(1, 1, "Alex", 3),
+            user(2, 2, "Manager", 0),
+            user(3, 1, "Sam", 1),
+            user(4, 1, "Jo", 0),
+        ];
+        let keyboard = assignment_keyboard(42, &users);
+        let rows: Vec<Vec<(String, String)>> = keyboard
+            .inline_keyboard
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|button| match &button.kind {
+                        InlineKeyboardButtonKind::CallbackData(data) => {
+                            (button.text.clone(), data.clone())
+                        }
+                        _ => unreachable!(),
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    ("Alex: 3".to_string(), "assign:42:10".to_string()),
+                    ("Sam: 1".to_string(), "assign:42:30".to_string()),
+                ],
+                vec![("Jo: 0".to_string(), "assign:42:40".to_string())],
+            ]
+        );
+    }
+}
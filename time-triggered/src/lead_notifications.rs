@@ -0,0 +1,98 @@
+use crate::lead_escalation::assignment_keyboard;
+use common::crud::lead_notifications::{
+    get_due_delayed_lead_notifications, insert_lead_message, mark_delayed_lead_notification_sent,
+    DelayedLeadNotification,
+};
+use common::crud::user::get_sales_users;
+use common::telegram::html::split_html_message;
+use common::telegram::leads::LEAD_PART_LIMIT;
+use lambda_runtime::{tracing, Error};
+use sqlx::MySqlPool;
+use teloxide::prelude::*;
+use teloxide::types::ParseMode;
+
+async fn send_delayed_lead_notification(
+    pool: &MySqlPool,
+    bot: &Bot,
+    notification: &DelayedLeadNotification,
+) -> Result<(), Error> {
+    // Same as the immediate send: the assign keyboard only while nobody has the lead, on
+    // the last part of a lead too long for one message.
+    let mut keyboard = if notification.sales_rep.is_none() {
+        let users = get_sales_users(pool, notification.company_id).await?;
+        Some(assignment_keyboard(notification.customer_id, &users))
+    } else {
+        None
+    };
+    let parts = split_html_message(&notification.message_text, LEAD_PART_LIMIT);
+    let last_index = parts.len().saturating_sub(1);
+    let mut sent = Vec::with_capacity(parts.len());
+    for (index, part) in parts.into_iter().enumerate() {
+        let request = bot
+            .send_message(ChatId(notification.chat_id), part.clone())
+            .parse_mode(ParseMode::Html);
+        let part_keyboard = if index == last_index {
+            keyboard.take()
+        } else {
+            None
+        };
+        let message = match part_keyboard {
+            Some(keyboard) => request.reply_markup(keyboard).await?,
+            None => request.await?,
+        };
+        sent.push((message, part));
+    }
+    mark_delayed_lead_notification_sent(pool, notification.id).await?;
+    for (message, part) in sent {
+        insert_lead_message(
+            pool,
+            notification.customer_id,
+            notification.company_id,
+            notification.chat_id,
+            message.id.0,
+            &part,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Sends lead notifications held during a company's quiet hours. Assignment prompts for
+/// leads someone assigned in the meantime are dropped; failed sends are retried on the next
+/// run.
+pub(crate) async fn send_delayed_lead_notifications(pool: &MySqlPool) -> Result<usize, Error> {
+    let due = get_due_delayed_lead_notifications(pool).await?;
+    if due.is_empty() {
+        return Ok(0);
+    }
+    let token = match std::env::var("TELOXIDE_TOKEN") {
+        Ok(value) => value,
+        Err(error) => {
+            tracing::warn!(
+                ?error,
+                "TELOXIDE_TOKEN is not set; skipping delayed lead notifications"
+            );
+            return Ok(0);
+        }
+    };
+    let bot = Bot::new(token);
+    let mut sent = 0usize;
+
+    for notification in &due {
+        if notification.include_assignment_prompt && notification.sales_rep.is_some() {
+            mark_delayed_lead_notification_sent(pool, notification.id).await?;
+            continue;
+        }
+        match send_delayed_lead_notification(pool, &bot, notification).await {
+            Ok(()) => sent += 1,
+            Err(error) => tracing::error!(
+                ?error,
+                customer_id = notification.customer_id,
+                chat_id = notification.chat_id,
+                "Failed to send delayed lead notification"
+            ),
+        }
+    }
+
+    Ok(sent)
+}
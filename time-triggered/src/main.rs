@@ -2,7 +2,10 @@ use common::crud::setup::create_db_pool;
 use generic_handler::function_handler;
 use lambda_runtime::{run, tracing, Error};
 
+mod conversion_export;
 mod generic_handler;
+mod lead_escalation;
+mod lead_notifications;
 mod schemas;
 
 #[tokio::main]
@@ -0,0 +1,203 @@
+use common::crud::scheduled_emails::cancel_pending_scheduled_emails_for_address;
+use common::crud::suppressions::{SuppressionReason, add_email_suppression};
+use lambda_http::tracing;
+use sqlx::MySqlPool;
+
+use crate::amazonses::schemas::{BounceType, Mail, SesEvent, SesEventKind};
+use crate::crud::email::{NewEmailEvent, get_email_company_id, insert_email_event};
+use crate::libs::constants::{ERR_DB, OK_RESPONSE, internal_error};
+use crate::libs::types::BasicResponse;
+
+const MAX_DETAIL_CHARS: usize = 1000;
+
+/// One `email_events` row: SES reports bounces and complaints per recipient.
+#[derive(Debug, PartialEq, Eq)]
+pub struct EmailEventRow {
+    pub event_type: &'static str,
+    pub recipient_email: String,
+    pub bounce_type: Option<&'static str>,
+    pub bounce_sub_type: Option<String>,
+    pub detail: Option<String>,
+}
+
+impl EmailEventRow {
+    fn new(event_type: &'static str, recipient: &str, detail: Option<&str>) -> Self {
+        Self {
+            event_type,
+            recipient_email: recipient.trim().to_lowercase(),
+            bounce_type: None,
+            bounce_sub_type: None,
+            detail: detail.map(|value| value.chars().take(MAX_DETAIL_CHARS).collect()),
+        }
+    }
+
+    /// Why future emails to this recipient must stop, if they must.
+    pub fn suppression_reason(&self) -> Option<SuppressionReason> {
+        match (self.event_type, self.bounce_type) {
+            ("bounce", Some("Permanent")) => Some(SuppressionReason::Bounce),
+            ("complaint", _) => Some(SuppressionReason::Complaint),
+            _ => None,
+        }
+    }
+}
+
+const fn cancellation_message(reason: SuppressionReason) -> &'static str {
+    match reason {
+        SuppressionReason::Bounce => "Recipient bounced permanently",
+        SuppressionReason::Complaint => "Recipient complained",
+        SuppressionReason::Unsubscribe => "Recipient unsubscribed",
+        SuppressionReason::Manual => "Recipient is suppressed",
+    }
+}
+
+impl BounceType {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Undetermined => "Undetermined",
+            Self::Permanent => "Permanent",
+            Self::Transient => "Transient",
+        }
+    }
+}
+
+/// Opens are stored in `email_reads` and unknown event types are dropped, so both map to no rows.
+pub fn email_event_rows(event: &SesEventKind, mail: &Mail) -> Vec<EmailEventRow> {
+    let per_destination = |event_type: &'static str, detail: Option<&str>| -> Vec<EmailEventRow> {
+        mail.destination
+            .iter()
+            .map(|recipient| EmailEventRow::new(event_type, recipient, detail))
+            .collect()
+    };
+
+    match event {
+        SesEventKind::Send => per_destination("send", None),
+        SesEventKind::Delivery { delivery } => delivery
+            .recipients
+            .iter()
+            .map(|recipient| EmailEventRow::new("delivery", recipient, None))
+            .collect(),
+        SesEventKind::Bounce { bounce } => bounce
+            .bounced_recipients
+            .iter()
+            .map(|recipient| EmailEventRow {
+                bounce_type: Some(bounce.bounce_type.as_str()),
+                bounce_sub_type: Some(bounce.bounce_sub_type.clone()),
+                ..EmailEventRow::new(
+                    "bounce",
+                    &recipient.email_address,
+                    recipient.diagnostic_code.as_deref(),
+                )
+            })
+            .collect(),
+        SesEventKind::Complaint { complaint } => complaint
+            .complained_recipients
+            .iter()
+            .map(|recipient| {
+                EmailEventRow::new(
+                    "complaint",
+                    &recipient.email_address,
+                    complaint.complaint_feedback_type.as_deref(),
+                )
+            })
+            .collect(),
+        SesEventKind::Reject { reject } => per_destination("reject", Some(&reject.reason)),
+        SesEventKind::Click { click } => per_destination("click", Some(&click.link)),
+        SesEventKind::DeliveryDelay { delivery_delay } => delivery_delay
+            .delayed_recipients
+            .iter()
+            .map(|recipient| {
+                EmailEventRow::new(
+                    "delivery_delay",
+                    &recipient.email_address,
+                    Some(&delivery_delay.delay_type),
+                )
+            })
+            .collect(),
+        SesEventKind::Open { .. } | SesEventKind::Other => Vec::new(),
+    }
+}
+
+/// Stores every row of a non-open SES event against the outbound message. Recipients that
+/// bounced permanently or complained join the company suppression list and lose their
+/// pending drip emails.
+pub async fn record_ses_event(
+    pool: &MySqlPool,
+    event: &SesEvent,
+    message_id: Option<&str>,
+) -> BasicResponse {
+    let company_id = match message_id {
+        Some(message_id) => match get_email_company_id(pool, message_id).await {
+            Ok(company_id) => company_id,
+            Err(error) => {
+                tracing::error!(?error, message_id, "Failed to load company for SES event");
+                return internal_error(ERR_DB);
+            }
+        },
+        None => None,
+    };
+
+    for row in email_event_rows(&event.detail.event, &event.detail.mail) {
+        let new_event = NewEmailEvent {
+            event_id: &event.id,
+            ses_message_id: &event.detail.mail.message_id,
+            message_id,
+            company_id,
+            event_type: row.event_type,
+            recipient_email: &row.recipient_email,
+            bounce_type: row.bounce_type,
+            bounce_sub_type: row.bounce_sub_type.as_deref(),
+            detail: row.detail.as_deref(),
+        };
+        let inserted = match insert_email_event(pool, &new_event).await {
+            Ok(result) => result.rows_affected(),
+            Err(error) => {
+                tracing::error!(
+                    ?error,
+                    event_id = %event.id,
+                    event_type = row.event_type,
+                    "Failed to insert SES email event"
+                );
+                return internal_error(ERR_DB);
+            }
+        };
+
+        // 0 rows: redelivered event, already handled.
+        if inserted == 0 {
+            continue;
+        }
+        let (Some(reason), Some(company_id)) = (row.suppression_reason(), company_id) else {
+            continue;
+        };
+        if let Err(error) =
+            add_email_suppression(pool, company_id, &row.recipient_email, reason, "ses").await
+        {
+            tracing::error!(
+                ?error,
+                company_id,
+                "Failed to add suppression after SES event"
+            );
+        }
+        let message = cancellation_message(reason);
+        match cancel_pending_scheduled_emails_for_address(
+            pool,
+            company_id,
+            &row.recipient_email,
+            message,
+        )
+        .await
+        {
+            Ok(result) => tracing::info!(
+                company_id,
+                cancelled = result.rows_affected(),
+                reason = message,
+                "Suppressed scheduled emails after SES event"
+            ),
+            Err(error) => tracing::error!(
+                ?error,
+                company_id,
+                "Failed to cancel scheduled emails after SES event"
+            ),
+        }
+    }
+    OK_RESPONSE
+}
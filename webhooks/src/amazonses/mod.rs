@@ -1,3 +1,4 @@
+pub mod events;
 pub mod parse_email;
 pub mod process;
 pub mod routes;
@@ -3,24 +3,26 @@ use lambda_http::tracing;
 use sqlx::MySqlPool;
 
 use crate::amazon::bucket::{CustomClient, S3Bucket};
+use crate::amazonses::events::record_ses_event;
 use crate::amazonses::parse_email::parse_email;
 use crate::amazonses::process::{EmailInfo, process_reply_email};
-use crate::amazonses::schemas::{S3Event, SesEvent};
+use crate::amazonses::schemas::{S3Event, SesEvent, SesEventKind};
+use crate::axum_helpers::guards::SesWebhook;
 use crate::crud::email::{create_email_read, get_full_message_id};
-use crate::libs::constants::{BAD_REQUEST, NOT_FOUND_RESPONSE, OK_RESPONSE, internal_error};
+use crate::libs::constants::{
+    BAD_REQUEST, FORBIDDEN_RESPONSE, NOT_FOUND_RESPONSE, OK_RESPONSE, internal_error,
+};
 use crate::libs::types::BasicResponse;
 
-pub async fn read_receipt_handler(
+pub async fn ses_event_handler(
+    _: SesWebhook,
     State(pool): State<MySqlPool>,
     Json(info): Json<SesEvent>,
 ) -> BasicResponse {
-    let message_id = info.detail.mail.message_id;
-    let user_agent = info.detail.open.user_agent;
-    let ip_address = info.detail.open.ip_address;
+    let message_id = &info.detail.mail.message_id;
 
-    let final_message_id = match get_full_message_id(&pool, &message_id).await {
-        Ok(Some(message_id)) => message_id,
-        Ok(None) => return NOT_FOUND_RESPONSE,
+    let final_message_id = match get_full_message_id(&pool, message_id).await {
+        Ok(message_id) => message_id,
         Err(error) => {
             tracing::error!(
                 "Error fetching email read: {} from the db: {}",
@@ -30,7 +32,15 @@ pub async fn read_receipt_handler(
             return BAD_REQUEST;
         }
     };
-    let result = create_email_read(&pool, &final_message_id, &user_agent, &ip_address).await;
+
+    let SesEventKind::Open { open } = &info.detail.event else {
+        return record_ses_event(&pool, &info, final_message_id.as_deref()).await;
+    };
+    let Some(final_message_id) = final_message_id else {
+        return NOT_FOUND_RESPONSE;
+    };
+    let result =
+        create_email_read(&pool, &final_message_id, &open.user_agent, &open.ip_address).await;
     if let Err(error) = result {
         tracing::error!(
             "Error inserting email read: {} into the db: {}",
@@ -88,10 +98,28 @@ pub async fn process_ses_received_event<C: S3Bucket + Send + Sync + 'static>(
     process_reply_email(pool, client, email_info).await
 }
 
+/// `allowed_buckets_raw` is `SES_INBOUND_BUCKETS` (comma-separated); an empty
+/// list allows nothing.
+fn is_allowed_bucket(bucket: &str, allowed_buckets_raw: &str) -> bool {
+    allowed_buckets_raw
+        .split(',')
+        .map(str::trim)
+        .any(|allowed| !allowed.is_empty() && allowed == bucket)
+}
+
 pub async fn receive_handler(
+    _: SesWebhook,
     State(pool): State<MySqlPool>,
     Json(event): Json<S3Event>,
 ) -> BasicResponse {
+    let allowed_buckets = std::env::var("SES_INBOUND_BUCKETS").unwrap_or_default();
+    if !is_allowed_bucket(&event.detail.bucket.name, &allowed_buckets) {
+        tracing::error!(
+            bucket = %event.detail.bucket.name,
+            "Inbound email event for a bucket outside SES_INBOUND_BUCKETS"
+        );
+        return FORBIDDEN_RESPONSE;
+    }
     let custom_client = CustomClient {};
     process_ses_received_event(&pool, custom_client, &event).await
 }
@@ -99,6 +127,9 @@ pub async fn receive_handler(
 #[cfg(test)]
 mod local_tests {
     use super::*;
+    use crate::tests::data::ses_events_json::{
+        ses_bounce_event_json, ses_complaint_event_json, ses_delivery_event_json,
+    };
     use crate::tests::data::ses_open_json::ses_open_event_json;
     use crate::tests::data::ses_received::ses_received_json;
     use crate::tests::utils::{MockClient, get_emails, insert_email, insert_user, new_test_app};
@@ -153,6 +184,74 @@ mod local_tests {
         .await
     }
 
+    const SES_SECRET: &str = "test-ses-secret";
+
+    /// Same fixed values on every call: safe under parallel test execution since no other
+    /// test in this crate reads/writes these two env vars.
+    fn set_ses_env() {
+        unsafe {
+            std::env::set_var("SES_WEBHOOK_SECRET", SES_SECRET);
+            std::env::set_var("SES_INBOUND_BUCKETS", "granite-allowed-bucket");
+        }
+    }
+
+    #[test]
+    fn test_is_allowed_bucket() {
+        let allowed = "granite-ses-inbound-emails, other-bucket";
+        assert!(is_allowed_bucket("granite-ses-inbound-emails", allowed));
+        assert!(is_allowed_bucket("other-bucket", allowed));
+        assert!(!is_allowed_bucket("attacker-bucket", allowed));
+        assert!(!is_allowed_bucket("", allowed));
+        assert!(!is_allowed_bucket("granite-ses-inbound-emails", ""));
+        assert!(!is_allowed_bucket("", ","));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn ses_routes_reject_missing_or_wrong_secret(pool: MySqlPool) {
+        let app = new_test_app(pool.clone());
+        set_ses_env();
+
+        let response = app
+            .post("/ses/read-receipt")
+            .json(&ses_open_event_json())
+            .await;
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .post("/ses/read-receipt")
+            .add_header("x-ses-webhook-secret", "wrong")
+            .json(&ses_open_event_json())
+            .await;
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .post("/ses/receive-email")
+            .json(&ses_received_json::<serde_json::Value>())
+            .await;
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+        let reads = sqlx::query_scalar!("SELECT COUNT(*) FROM email_reads")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(reads, 0);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn receive_rejects_bucket_outside_allowlist(pool: MySqlPool) {
+        let app = new_test_app(pool.clone());
+        set_ses_env();
+
+        let response = app
+            .post("/ses/receive-email")
+            .add_header("x-ses-webhook-secret", SES_SECRET)
+            .json(&ses_received_json::<serde_json::Value>())
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+        assert!(get_emails(&pool).await.unwrap().is_empty());
+    }
+
     #[sqlx::test(migrations = "../migrations")]
     async fn open_event_success(pool: MySqlPool) {
         let app = new_test_app(pool.clone());
@@ -164,8 +263,10 @@ mod local_tests {
 
         insert_email(&pool, message_id).await.unwrap();
 
+        set_ses_env();
         let response = app
             .post("/ses/read-receipt")
+            .add_header("x-ses-webhook-secret", SES_SECRET)
             .json(&ses_open_event_json())
             .await;
 
@@ -178,6 +279,195 @@ mod local_tests {
         assert_eq!(result.ip_address.unwrap(), expected_ip);
     }
 
+    const SES_MESSAGE_ID: &str =
+        "010f019a9974b389-60efe038-3845-92e7-45c43cdc6ca2-000000@us-east-2.amazonses.com";
+
+    /// Outbound email for company 1 plus a pending drip email to the fixture recipient.
+    async fn insert_outbound_with_pending_drip(pool: &MySqlPool) {
+        insert_email(pool, SES_MESSAGE_ID).await.unwrap();
+        sqlx::query!(
+            "UPDATE emails SET company_id = 1 WHERE message_id = ?",
+            SES_MESSAGE_ID
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        let customer_id = sqlx::query!(
+            "INSERT INTO customers (name, company_id, source) VALUES ('Colin', 1, 'leads')"
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        let email_id = sqlx::query!(
+            "INSERT INTO customers_emails (customer_id, email) VALUES (?, 'colin99delahunty@gmail.com')",
+            customer_id
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        sqlx::query!(
+            "UPDATE customers SET email_id = ? WHERE id = ?",
+            email_id,
+            customer_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        let template_id = sqlx::query!(
+            "INSERT INTO email_templates (template_name, template_body, company_id, hour_delay, show_template) VALUES ('drip', 'Hi', 1, 24, 1)"
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        sqlx::query!(
+            "INSERT INTO scheduled_emails (template_id, deal_id, customer_id, user_id, company_id, send_at) \
+             VALUES (?, 1, ?, 1, 1, UTC_TIMESTAMP() + INTERVAL 1 DAY)",
+            template_id,
+            customer_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn drip_status(pool: &MySqlPool) -> (String, Option<String>) {
+        let row = sqlx::query!("SELECT status, error_message FROM scheduled_emails LIMIT 1")
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        (row.status, row.error_message)
+    }
+
+    async fn post_ses_event(app: &axum_test::TestServer, body: &serde_json::Value) -> StatusCode {
+        set_ses_env();
+        app.post("/ses/events")
+            .add_header("x-ses-webhook-secret", SES_SECRET)
+            .json(body)
+            .await
+            .status_code()
+    }
+
+    #[test]
+    fn parses_every_event_type() {
+        for (body, expected) in [
+            (ses_open_event_json(), "Open"),
+            (ses_bounce_event_json("Permanent"), "Bounce"),
+            (ses_complaint_event_json(), "Complaint"),
+            (ses_delivery_event_json(), "Delivery"),
+        ] {
+            let event: SesEvent = serde_json::from_value(body).unwrap();
+            let parsed = match event.detail.event {
+                SesEventKind::Open { .. } => "Open",
+                SesEventKind::Bounce { .. } => "Bounce",
+                SesEventKind::Complaint { .. } => "Complaint",
+                SesEventKind::Delivery { .. } => "Delivery",
+                _ => "Other",
+            };
+            assert_eq!(parsed, expected);
+        }
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn permanent_bounce_suppresses_pending_drip(pool: MySqlPool) {
+        let app = new_test_app(pool.clone());
+        insert_outbound_with_pending_drip(&pool).await;
+
+        let status = post_ses_event(&app, &ses_bounce_event_json("Permanent")).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let event = sqlx::query!(
+            "SELECT message_id, company_id, event_type, recipient_email, bounce_type, bounce_sub_type, detail FROM email_events"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(event.message_id.as_deref(), Some(SES_MESSAGE_ID));
+        assert_eq!(event.company_id, Some(1));
+        assert_eq!(event.event_type, "bounce");
+        assert_eq!(event.recipient_email, "colin99delahunty@gmail.com");
+        assert_eq!(event.bounce_type.as_deref(), Some("Permanent"));
+        assert_eq!(event.bounce_sub_type.as_deref(), Some("General"));
+        assert_eq!(
+            event.detail.as_deref(),
+            Some("smtp; 550 5.1.1 user unknown")
+        );
+
+        let (status, reason) = drip_status(&pool).await;
+        assert_eq!(status, "cancelled");
+        assert_eq!(reason.as_deref(), Some("Recipient bounced permanently"));
+        let suppressed = sqlx::query!("SELECT email, reason, source FROM email_suppressions")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(suppressed.email, "colin99delahunty@gmail.com");
+        assert_eq!(suppressed.reason, "bounce");
+        assert_eq!(suppressed.source, "ses");
+
+        // Redelivery of the same event is a no-op.
+        let status = post_ses_event(&app, &ses_bounce_event_json("Permanent")).await;
+        assert_eq!(status, StatusCode::OK);
+        let count = sqlx::query_scalar!("SELECT COUNT(*) FROM email_events")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn complaint_suppresses_pending_drip(pool: MySqlPool) {
+        let app = new_test_app(pool.clone());
+        insert_outbound_with_pending_drip(&pool).await;
+
+        let status = post_ses_event(&app, &ses_complaint_event_json()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, reason) = drip_status(&pool).await;
+        assert_eq!(status, "cancelled");
+        assert_eq!(reason.as_deref(), Some("Recipient complained"));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn transient_bounce_and_delivery_keep_pending_drip(pool: MySqlPool) {
+        let app = new_test_app(pool.clone());
+        insert_outbound_with_pending_drip(&pool).await;
+
+        let status = post_ses_event(&app, &ses_bounce_event_json("Transient")).await;
+        assert_eq!(status, StatusCode::OK);
+        let status = post_ses_event(&app, &ses_delivery_event_json()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = drip_status(&pool).await;
+        assert_eq!(status, "pending");
+        let suppressed = sqlx::query_scalar!("SELECT COUNT(*) FROM email_suppressions")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(suppressed, 0);
+        let types = sqlx::query_scalar!("SELECT event_type FROM email_events ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(types, vec!["bounce".to_string(), "delivery".to_string()]);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn event_for_unknown_message_is_recorded_without_company(pool: MySqlPool) {
+        let app = new_test_app(pool.clone());
+
+        let status = post_ses_event(&app, &ses_complaint_event_json()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let event = sqlx::query!("SELECT message_id, company_id FROM email_events")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(event.message_id, None);
+        assert_eq!(event.company_id, None);
+    }
+
     #[sqlx::test(migrations = "../migrations")]
     async fn received_success(pool: MySqlPool) {
         let message_id =
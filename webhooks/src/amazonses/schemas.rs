@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-// Receive read receipts
+// Receive SES event publishing (opens, bounces, complaints, ...)
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SesEvent {
@@ -20,10 +20,41 @@ pub struct SesEvent {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Detail {
-    #[serde(rename = "eventType")]
-    pub event_type: String,
     pub mail: Mail,
-    pub open: Open,
+    #[serde(flatten)]
+    pub event: SesEventKind,
+}
+
+/// SES names the payload field after the event type, e.g. `"eventType": "Bounce"`
+/// comes with a `"bounce"` object.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "eventType")]
+pub enum SesEventKind {
+    Send,
+    Delivery {
+        delivery: Delivery,
+    },
+    Bounce {
+        bounce: Bounce,
+    },
+    Complaint {
+        complaint: Complaint,
+    },
+    Reject {
+        reject: Reject,
+    },
+    Click {
+        click: Click,
+    },
+    DeliveryDelay {
+        #[serde(rename = "deliveryDelay")]
+        delivery_delay: DeliveryDelay,
+    },
+    Open {
+        open: Open,
+    },
+    #[serde(other)]
+    Other,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,11 +66,13 @@ pub struct Mail {
     #[serde(rename = "messageId")]
     pub message_id: String,
     pub destination: Vec<String>,
-    #[serde(rename = "headersTruncated")]
+    #[serde(rename = "headersTruncated", default)]
     pub headers_truncated: bool,
+    #[serde(default)]
     pub headers: Vec<Header>,
-    #[serde(rename = "commonHeaders")]
+    #[serde(rename = "commonHeaders", default)]
     pub common_headers: CommonHeaders,
+    #[serde(default)]
     pub tags: Tags,
 }
 
@@ -49,7 +82,8 @@ pub struct Header {
     pub value: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CommonHeaders {
     pub from: Vec<String>,
     pub to: Vec<String>,
@@ -58,7 +92,8 @@ pub struct CommonHeaders {
     pub subject: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Tags {
     #[serde(rename = "ses:source-tls-version")]
     pub ses_source_tls_version: Vec<String>,
@@ -88,6 +123,73 @@ pub struct Open {
     pub ip_address: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Delivery {
+    pub timestamp: String,
+    pub recipients: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BounceType {
+    Undetermined,
+    Permanent,
+    Transient,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bounce {
+    #[serde(rename = "bounceType")]
+    pub bounce_type: BounceType,
+    #[serde(rename = "bounceSubType")]
+    pub bounce_sub_type: String,
+    #[serde(rename = "bouncedRecipients")]
+    pub bounced_recipients: Vec<EventRecipient>,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Complaint {
+    #[serde(rename = "complainedRecipients")]
+    pub complained_recipients: Vec<EventRecipient>,
+    #[serde(rename = "complaintFeedbackType")]
+    pub complaint_feedback_type: Option<String>,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Reject {
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Click {
+    pub timestamp: String,
+    #[serde(rename = "userAgent")]
+    pub user_agent: String,
+    #[serde(rename = "ipAddress")]
+    pub ip_address: String,
+    pub link: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeliveryDelay {
+    #[serde(rename = "delayType")]
+    pub delay_type: String,
+    #[serde(rename = "delayedRecipients")]
+    pub delayed_recipients: Vec<EventRecipient>,
+    pub timestamp: String,
+}
+
+/// A recipient entry of a bounce, complaint or delivery delay.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventRecipient {
+    #[serde(rename = "emailAddress")]
+    pub email_address: String,
+    pub status: Option<String>,
+    #[serde(rename = "diagnosticCode")]
+    pub diagnostic_code: Option<String>,
+}
+
 // Receive emails
 
 #[derive(Debug, Serialize, Deserialize)]
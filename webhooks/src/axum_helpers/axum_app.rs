@@ -1,17 +1,29 @@
-use crate::amazonses::routes::{read_receipt_handler, receive_handler};
+use crate::amazonses::routes::{receive_handler, ses_event_handler};
 use crate::cloudtalk::receive::{call_received, sms_received, sms_sent, sync_cloudtalk};
 use crate::google::receive::address_information;
 use crate::libs::constants::OK_RESPONSE;
+use crate::meta::receive::{leadgen_received, leadgen_verify};
 use crate::middleware::request_logger::print_request_body;
-use crate::schemas::add_customer::NewLeadForm;
+use crate::schemas::add_customer::{LeadAttribution, NewLeadForm};
 use crate::telegram::cleanup::delete_lead_telegram_messages;
 use crate::telegram::crm_notify::crm_notify_handler;
+use crate::telegram::link::create_telegram_link;
 use crate::telegram::notifications_notify::notifications_notify_handler;
 use crate::telegram::receive::webhook_handler;
+use crate::telegram::replies::notifications_webhook_handler;
 use crate::template::receive::{get_complete_template, get_template_variables};
+use crate::webhooks::api_keys::{
+    create_company_api_key, list_company_api_keys, revoke_company_api_key,
+};
+use crate::webhooks::email_click::email_click;
 use crate::webhooks::receive::{
-    __path_new_lead_form, facebook_contact_form, new_lead_form, wordpress_contact_form,
+    __path_custom_lead_form, __path_new_lead_form, custom_lead_form, facebook_contact_form,
+    new_lead_form, wordpress_contact_form,
+};
+use crate::webhooks::suppressions::{
+    add_company_suppression, list_company_suppressions, remove_company_suppression,
 };
+use crate::webhooks::unsubscribe::{confirm_unsubscribe, unsubscribe_customer};
 use axum::{
     Json, Router,
     response::IntoResponse,
@@ -30,13 +42,20 @@ async fn health_check() -> impl IntoResponse {
     info(
         title = "Granite Manager Webhooks API",
         description = "Lead intake for Make, Zapier, and website forms.\n\n\
+Every request must send `Authorization: Bearer <api key>` with a key issued for the \
+`company_id` in the path.\n\n\
 For `/v1/webhooks/new-lead-form/{company_id}`:\n\
 - `referral_source` is optional; when set, prefer `website` or `facebook` for statistics.\n\
 - `form_name` is optional; when set, use the specific form id (e.g. `cabinet_quote`, \
-`facebook_form`, `facebook_cabinet_quote_form`, `quick_quote`)."
+`facebook_form`, `facebook_cabinet_quote_form`, `quick_quote`).\n\
+- `utm_source`, `utm_medium`, `utm_campaign`, `utm_term`, `utm_content`, `gclid`, `fbclid`, \
+`wbraid`, `landing_page` and `referrer` are optional attribution fields. The first submission \
+for a customer is kept as first touch; every submission updates last touch.\n\n\
+For `/v1/webhooks/forms/{company_id}/{form_name}`, fields are mapped by the form configured \
+for the company; unmapped fields are kept and shown in the lead notification."
     ),
-    paths(new_lead_form),
-    components(schemas(NewLeadForm))
+    paths(new_lead_form, custom_lead_form),
+    components(schemas(NewLeadForm, LeadAttribution))
 )]
 struct ApiDoc;
 
@@ -55,21 +74,51 @@ pub fn new_main_app(pool: MySqlPool) -> Router {
             "/facebook-contact-form/{company_id}",
             post(facebook_contact_form),
         )
+        .route("/meta/leadgen", get(leadgen_verify).post(leadgen_received))
         .route(
             "/v1/webhooks/new-lead-form/{company_id}",
             post(new_lead_form),
         )
+        .route(
+            "/v1/webhooks/forms/{company_id}/{form_name}",
+            post(custom_lead_form),
+        )
+        .route(
+            "/v1/api-keys/{company_id}",
+            get(list_company_api_keys).post(create_company_api_key),
+        )
+        .route(
+            "/v1/api-keys/{company_id}/{key_id}",
+            delete(revoke_company_api_key),
+        )
+        .route(
+            "/v1/email-suppressions/{company_id}",
+            get(list_company_suppressions)
+                .post(add_company_suppression)
+                .delete(remove_company_suppression),
+        )
+        .route(
+            "/v1/unsubscribe/{company_id}/{customer_id}/{signature}",
+            get(confirm_unsubscribe).post(unsubscribe_customer),
+        )
+        .route("/v1/email-click", get(email_click))
         .route("/telegram/webhook", post(webhook_handler))
+        .route(
+            "/telegram/notifications/webhook",
+            post(notifications_webhook_handler),
+        )
         .route(
             "/telegram/lead-messages/{company_id}/{customer_id}",
             delete(delete_lead_telegram_messages),
         )
+        .route("/telegram/link/{user_id}", post(create_telegram_link))
         .route("/telegram/crm-notify", post(crm_notify_handler))
         .route(
             "/telegram/notifications-notify",
             post(notifications_notify_handler),
         )
-        .route("/ses/read-receipt", post(read_receipt_handler))
+        .route("/ses/read-receipt", post(ses_event_handler))
+        .route("/ses/events", post(ses_event_handler))
         .route("/ses/receive-email", post(receive_handler))
         .route("/cloudtalk/sms/{company_id}", post(sms_received))
         .route("/cloudtalk/sms/sent/{company_id}", post(sms_sent))
@@ -1,17 +1,22 @@
+use crate::crud::api_keys::{
+    company_allows_lenient_lead_auth, find_active_api_key, hash_api_key, touch_api_key,
+};
 use crate::libs::types::BasicResponse;
 use crate::posthog::{PostHogEvent, client};
-use axum::extract::FromRequestParts;
+use axum::extract::{FromRef, FromRequestParts, Path};
 use axum::http::{StatusCode, request::Parts};
 use lambda_http::tracing;
+use sqlx::MySqlPool;
+use std::collections::HashMap;
 use std::env::var;
 use teloxide::prelude::*;
 use teloxide::types::InlineKeyboardMarkup;
-use teloxide::types::{Message, Recipient};
+use teloxide::types::{CallbackQuery, Message, ParseMode, Recipient};
 use uuid::{Uuid, uuid};
 
 use crate::axum_helpers::utils::get_remix_key;
 use crate::libs::constants::ERR_SEND_TELEGRAM;
-use crate::libs::constants::{FORBIDDEN_RESPONSE, internal_error};
+use crate::libs::constants::{BAD_REQUEST, ERR_DB, FORBIDDEN_RESPONSE, internal_error};
 
 pub(crate) const CORRECT_ID: Uuid = uuid!("9ca4dfa8-0eec-46cc-967f-3385624be883");
 
@@ -35,6 +40,18 @@ pub trait Telegram: Send + Sync {
         C: Into<Recipient> + Send,
         T: Into<String> + Send;
 
+    /// Like `send_repliable_message`, but delivered without a notification sound and
+    /// rendered as HTML.
+    fn send_quiet_message<C, T>(
+        &self,
+        chat: C,
+        text: T,
+        repliable: InlineKeyboardMarkup,
+    ) -> impl Future<Output = Result<Message, teloxide::RequestError>> + Send
+    where
+        C: Into<Recipient> + Send,
+        T: Into<String> + Send;
+
     fn edit_message_text<T>(
         &self,
         chat_id: i64,
@@ -44,11 +61,54 @@ pub trait Telegram: Send + Sync {
     where
         T: Into<String> + Send;
 
+    /// Like `edit_message_text`, but replaces the inline keyboard instead of removing it.
+    fn edit_repliable_message<T>(
+        &self,
+        chat_id: i64,
+        message_id: i32,
+        text: T,
+        repliable: InlineKeyboardMarkup,
+    ) -> impl Future<Output = Result<Message, BasicResponse>> + Send
+    where
+        T: Into<String> + Send;
+
     fn delete_message(
         &self,
         chat_id: i64,
         message_id: i32,
     ) -> impl Future<Output = Result<(), BasicResponse>> + Send;
+
+    /// Shows `text` as a toast to whoever pressed the button.
+    fn answer_callback_query<T>(
+        &self,
+        cb: &CallbackQuery,
+        text: T,
+    ) -> impl Future<Output = Result<(), BasicResponse>> + Send
+    where
+        T: Into<String> + Send;
+
+    /// Sends `text` rendered with `parse_mode`; the caller escapes it to match.
+    fn send_formatted_message<C, T>(
+        &self,
+        chat: C,
+        text: T,
+        parse_mode: ParseMode,
+        repliable: Option<InlineKeyboardMarkup>,
+    ) -> impl Future<Output = Result<Message, teloxide::RequestError>> + Send
+    where
+        C: Into<Recipient> + Send,
+        T: Into<String> + Send;
+
+    /// Like `edit_message_text`, but renders `text` with `parse_mode`.
+    fn edit_formatted_message<T>(
+        &self,
+        chat_id: i64,
+        message_id: i32,
+        text: T,
+        parse_mode: ParseMode,
+    ) -> impl Future<Output = Result<Message, BasicResponse>> + Send
+    where
+        T: Into<String> + Send;
 }
 
 #[derive(Clone)]
@@ -100,6 +160,26 @@ impl Telegram for TelegramBot {
         async move { bot.send_message(chat, text).reply_markup(repliable).await }
     }
 
+    fn send_quiet_message<C, T>(
+        &self,
+        chat: C,
+        text: T,
+        repliable: InlineKeyboardMarkup,
+    ) -> impl Future<Output = Result<Message, teloxide::RequestError>> + Send
+    where
+        C: Into<Recipient> + Send,
+        T: Into<String> + Send,
+    {
+        let bot = self.bot.clone();
+        async move {
+            bot.send_message(chat, text)
+                .parse_mode(ParseMode::Html)
+                .reply_markup(repliable)
+                .disable_notification(true)
+                .await
+        }
+    }
+
     fn edit_message_text<T>(
         &self,
         chat_id: i64,
@@ -132,6 +212,39 @@ impl Telegram for TelegramBot {
         }
     }
 
+    fn edit_repliable_message<T>(
+        &self,
+        chat_id: i64,
+        message_id: i32,
+        text: T,
+        repliable: InlineKeyboardMarkup,
+    ) -> impl Future<Output = Result<Message, BasicResponse>> + Send
+    where
+        T: Into<String> + Send,
+    {
+        let bot = self.bot.clone();
+        let message_id = teloxide::types::MessageId(message_id);
+
+        async move {
+            match bot
+                .edit_message_text(ChatId(chat_id), message_id, text)
+                .reply_markup(repliable)
+                .await
+            {
+                Ok(message) => Ok(message),
+                Err(err) => {
+                    tracing::error!(
+                        ?err,
+                        chat_id = chat_id,
+                        message_id = %message_id,
+                        ERR_SEND_TELEGRAM
+                    );
+                    Err(internal_error(ERR_SEND_TELEGRAM))
+                }
+            }
+        }
+    }
+
     fn delete_message(
         &self,
         chat_id: i64,
@@ -156,6 +269,81 @@ impl Telegram for TelegramBot {
             }
         }
     }
+
+    fn answer_callback_query<T>(
+        &self,
+        cb: &CallbackQuery,
+        text: T,
+    ) -> impl Future<Output = Result<(), BasicResponse>> + Send
+    where
+        T: Into<String> + Send,
+    {
+        let request = self.bot.answer_callback_query(cb.id.clone()).text(text);
+        async move {
+            match request.await {
+                Ok(_) => Ok(()),
+                Err(err) => {
+                    tracing::error!(?err, ERR_SEND_TELEGRAM);
+                    Err(internal_error(ERR_SEND_TELEGRAM))
+                }
+            }
+        }
+    }
+
+    fn send_formatted_message<C, T>(
+        &self,
+        chat: C,
+        text: T,
+        parse_mode: ParseMode,
+        repliable: Option<InlineKeyboardMarkup>,
+    ) -> impl Future<Output = Result<Message, teloxide::RequestError>> + Send
+    where
+        C: Into<Recipient> + Send,
+        T: Into<String> + Send,
+    {
+        let bot = self.bot.clone();
+        async move {
+            let request = bot.send_message(chat, text).parse_mode(parse_mode);
+            match repliable {
+                Some(repliable) => request.reply_markup(repliable).await,
+                None => request.await,
+            }
+        }
+    }
+
+    fn edit_formatted_message<T>(
+        &self,
+        chat_id: i64,
+        message_id: i32,
+        text: T,
+        parse_mode: ParseMode,
+    ) -> impl Future<Output = Result<Message, BasicResponse>> + Send
+    where
+        T: Into<String> + Send,
+    {
+        let bot = self.bot.clone();
+        let message_id = teloxide::types::MessageId(message_id);
+
+        async move {
+            match bot
+                .edit_message_text(ChatId(chat_id), message_id, text)
+                .parse_mode(parse_mode)
+                .reply_markup(InlineKeyboardMarkup::default())
+                .await
+            {
+                Ok(message) => Ok(message),
+                Err(err) => {
+                    tracing::error!(
+                        ?err,
+                        chat_id = chat_id,
+                        message_id = %message_id,
+                        ERR_SEND_TELEGRAM
+                    );
+                    Err(internal_error(ERR_SEND_TELEGRAM))
+                }
+            }
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -212,6 +400,26 @@ impl Telegram for NotificationsTelegramBot {
         async move { bot.send_message(chat, text).reply_markup(repliable).await }
     }
 
+    fn send_quiet_message<C, T>(
+        &self,
+        chat: C,
+        text: T,
+        repliable: InlineKeyboardMarkup,
+    ) -> impl Future<Output = Result<Message, teloxide::RequestError>> + Send
+    where
+        C: Into<Recipient> + Send,
+        T: Into<String> + Send,
+    {
+        let bot = self.bot.clone();
+        async move {
+            bot.send_message(chat, text)
+                .parse_mode(ParseMode::Html)
+                .reply_markup(repliable)
+                .disable_notification(true)
+                .await
+        }
+    }
+
     fn edit_message_text<T>(
         &self,
         chat_id: i64,
@@ -244,6 +452,39 @@ impl Telegram for NotificationsTelegramBot {
         }
     }
 
+    fn edit_repliable_message<T>(
+        &self,
+        chat_id: i64,
+        message_id: i32,
+        text: T,
+        repliable: InlineKeyboardMarkup,
+    ) -> impl Future<Output = Result<Message, BasicResponse>> + Send
+    where
+        T: Into<String> + Send,
+    {
+        let bot = self.bot.clone();
+        let message_id = teloxide::types::MessageId(message_id);
+
+        async move {
+            match bot
+                .edit_message_text(ChatId(chat_id), message_id, text)
+                .reply_markup(repliable)
+                .await
+            {
+                Ok(message) => Ok(message),
+                Err(err) => {
+                    tracing::error!(
+                        ?err,
+                        chat_id = chat_id,
+                        message_id = %message_id,
+                        ERR_SEND_TELEGRAM
+                    );
+                    Err(internal_error(ERR_SEND_TELEGRAM))
+                }
+            }
+        }
+    }
+
     fn delete_message(
         &self,
         chat_id: i64,
@@ -268,6 +509,81 @@ impl Telegram for NotificationsTelegramBot {
             }
         }
     }
+
+    fn answer_callback_query<T>(
+        &self,
+        cb: &CallbackQuery,
+        text: T,
+    ) -> impl Future<Output = Result<(), BasicResponse>> + Send
+    where
+        T: Into<String> + Send,
+    {
+        let request = self.bot.answer_callback_query(cb.id.clone()).text(text);
+        async move {
+            match request.await {
+                Ok(_) => Ok(()),
+                Err(err) => {
+                    tracing::error!(?err, ERR_SEND_TELEGRAM);
+                    Err(internal_error(ERR_SEND_TELEGRAM))
+                }
+            }
+        }
+    }
+
+    fn send_formatted_message<C, T>(
+        &self,
+        chat: C,
+        text: T,
+        parse_mode: ParseMode,
+        repliable: Option<InlineKeyboardMarkup>,
+    ) -> impl Future<Output = Result<Message, teloxide::RequestError>> + Send
+    where
+        C: Into<Recipient> + Send,
+        T: Into<String> + Send,
+    {
+        let bot = self.bot.clone();
+        async move {
+            let request = bot.send_message(chat, text).parse_mode(parse_mode);
+            match repliable {
+                Some(repliable) => request.reply_markup(repliable).await,
+                None => request.await,
+            }
+        }
+    }
+
+    fn edit_formatted_message<T>(
+        &self,
+        chat_id: i64,
+        message_id: i32,
+        text: T,
+        parse_mode: ParseMode,
+    ) -> impl Future<Output = Result<Message, BasicResponse>> + Send
+    where
+        T: Into<String> + Send,
+    {
+        let bot = self.bot.clone();
+        let message_id = teloxide::types::MessageId(message_id);
+
+        async move {
+            match bot
+                .edit_message_text(ChatId(chat_id), message_id, text)
+                .parse_mode(parse_mode)
+                .reply_markup(InlineKeyboardMarkup::default())
+                .await
+            {
+                Ok(message) => Ok(message),
+                Err(err) => {
+                    tracing::error!(
+                        ?err,
+                        chat_id = chat_id,
+                        message_id = %message_id,
+                        ERR_SEND_TELEGRAM
+                    );
+                    Err(internal_error(ERR_SEND_TELEGRAM))
+                }
+            }
+        }
+    }
 }
 
 impl<S> FromRequestParts<S> for TelegramBot
@@ -297,20 +613,89 @@ where
     }
 }
 
-pub struct MarketingUser {}
+/// Updates for the notifications bot, which shares `WEBHOOK_SECRET` with the lead bot.
+impl<S> FromRequestParts<S> for NotificationsTelegramBot
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
 
-impl MarketingUser {
-    pub const fn new(_: uuid::Uuid) -> Self {
-        Self {}
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let webhook_secret = var("WEBHOOK_SECRET").map_err(|e| {
+            tracing::error!(?e, "failed to read WEBHOOK_SECRET environment variable");
+            internal_error("failed to get webhook secret")
+        })?;
+
+        let secret = parts
+            .headers
+            .get("x-telegram-bot-api-secret-token")
+            .and_then(|v| v.to_str().ok())
+            .ok_or(FORBIDDEN_RESPONSE)?;
+
+        if secret != webhook_secret {
+            return Err(FORBIDDEN_RESPONSE);
+        }
+        let token = var("TELOXIDE_NOTIFICATIONS_TOKEN")
+            .or_else(|_| var("TELEGRAM_NOTIFICATIONS_BOT_TOKEN"))
+            .map_err(|e| {
+                tracing::error!(?e, "failed to read TELOXIDE_NOTIFICATIONS_TOKEN");
+                internal_error("failed to get notifications bot token")
+            })?;
+        Ok(Self {
+            bot: teloxide::Bot::new(token),
+        })
     }
 }
 
+/// Shared-secret auth for the SES `EventBridge` deliveries (read receipts and
+/// inbound email). The API destination sends `x-ses-webhook-secret`.
+pub struct SesWebhook;
+
+impl<S> FromRequestParts<S> for SesWebhook
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let webhook_secret = var("SES_WEBHOOK_SECRET").map_err(|e| {
+            tracing::error!(?e, "failed to read SES_WEBHOOK_SECRET environment variable");
+            internal_error("failed to get webhook secret")
+        })?;
+
+        let secret = parts
+            .headers
+            .get("x-ses-webhook-secret")
+            .and_then(|v| v.to_str().ok())
+            .ok_or(FORBIDDEN_RESPONSE)?;
+
+        if webhook_secret.is_empty() || secret != webhook_secret {
+            tracing::error!("SES webhook: missing or invalid secret header");
+            return Err(FORBIDDEN_RESPONSE);
+        }
+        Ok(Self)
+    }
+}
+
+/// A lead-intake caller authenticated by a per-company API key. Companies with
+/// `lead_auth_lenient` set are let through without a valid key while they migrate.
+pub struct MarketingUser {
+    pub company_id: i32,
+}
+
 fn parse_uuid_from_bearer(header: &str) -> Option<Uuid> {
     header
         .strip_prefix("Bearer ")
         .and_then(|token| Uuid::parse_str(token.trim()).ok())
 }
 
+fn parse_bearer_token(header: &str) -> Option<&str> {
+    header
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
 async fn report_to_posthog(message: &str) {
     let Ok(api_key) = std::env::var("POSTHOG_API_KEY") else {
         tracing::error!("POSTHOG_API_KEY not set");
@@ -328,38 +713,73 @@ async fn report_to_posthog(message: &str) {
 
 impl<S> FromRequestParts<S> for MarketingUser
 where
+    MySqlPool: FromRef<S>,
     S: Send + Sync,
 {
     type Rejection = (StatusCode, &'static str);
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        let headers = &parts.headers;
-        let raw_bearer = if let Some(v) = headers.get("authorization") {
-            v.to_str()
-        } else {
-            tracing::error!("Authorization header not found");
-            report_to_posthog("Authorization header not found").await;
-            return Ok(Self::new(CORRECT_ID));
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Ok(Path(params)) =
+            Path::<HashMap<String, String>>::from_request_parts(parts, state).await
+        else {
+            return Err(BAD_REQUEST);
         };
-        let Ok(clean_bearer) = raw_bearer else {
-            tracing::error!("Failed to parse authorization header");
-            report_to_posthog("Failed to parse authorization header").await;
-            return Ok(Self::new(CORRECT_ID));
+        let Some(company_id) = params
+            .get("company_id")
+            .and_then(|id| id.parse::<i32>().ok())
+        else {
+            return Err(BAD_REQUEST);
+        };
+        let pool = MySqlPool::from_ref(state);
+
+        let token = parts
+            .headers
+            .get("authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_bearer_token);
+
+        let failure = match token {
+            None => "Authorization header missing or malformed",
+            Some(token) => match find_active_api_key(&pool, &hash_api_key(token)).await {
+                Ok(Some(key)) if key.company_id == company_id => {
+                    if let Err(e) = touch_api_key(&pool, key.id).await {
+                        tracing::error!(?e, key_id = key.id, "Failed to update API key last use");
+                    }
+                    return Ok(Self { company_id });
+                }
+                Ok(Some(key)) => {
+                    tracing::error!(
+                        key_id = key.id,
+                        key_company_id = key.company_id,
+                        company_id = company_id,
+                        "API key used for another company"
+                    );
+                    report_to_posthog("API key used for another company").await;
+                    return Err(FORBIDDEN_RESPONSE);
+                }
+                Ok(None) => "Unknown or revoked API key",
+                Err(e) => {
+                    tracing::error!(?e, company_id = company_id, "Failed to look up API key");
+                    return Err(internal_error(ERR_DB));
+                }
+            },
         };
 
-        let Some(bearer_uuid) = parse_uuid_from_bearer(clean_bearer) else {
-            tracing::error!("Failed to parse bearer UUID: {}", clean_bearer);
-            report_to_posthog("Failed to parse bearer UUID").await;
-            return Ok(Self::new(CORRECT_ID));
+        let lenient = match company_allows_lenient_lead_auth(&pool, company_id).await {
+            Ok(lenient) => lenient,
+            Err(e) => {
+                tracing::error!(?e, company_id = company_id, "Failed to read lead auth mode");
+                return Err(internal_error(ERR_DB));
+            }
         };
 
-        if bearer_uuid != CORRECT_ID {
-            tracing::error!("Bearer UUID does not match");
-            report_to_posthog("Bearer UUID does not match").await;
-            return Ok(Self::new(CORRECT_ID));
+        tracing::error!(company_id = company_id, lenient = lenient, "{}", failure);
+        report_to_posthog(failure).await;
+        if lenient {
+            Ok(Self { company_id })
+        } else {
+            Err(FORBIDDEN_RESPONSE)
         }
-
-        Ok(Self::new(bearer_uuid))
     }
 }
 
@@ -403,7 +823,7 @@ where
 }
 
 /// Strict auth for the `CloudTalk` SMS webhook: rejects any request whose bearer
-/// token is missing/invalid/not equal to `CORRECT_ID` (unlike `MarketingUser`).
+/// token is missing/invalid/not equal to `CORRECT_ID`.
 pub struct CloudTalkWebhookUser;
 
 impl<S> FromRequestParts<S> for CloudTalkWebhookUser
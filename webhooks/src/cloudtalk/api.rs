@@ -1,4 +1,6 @@
-use crate::cloudtalk::schemas::{ContactPayload, ContactSearchEnvelope, CountriesEnvelope};
+use crate::cloudtalk::schemas::{
+    ContactPayload, ContactSearchEnvelope, CountriesEnvelope, SendSmsPayload,
+};
 use crate::cloudtalk::utils::{
     build_payload, coerce_id, extract_id, extract_phones, find_contact_id, is_united_states,
     upsert_contact,
@@ -244,6 +246,36 @@ pub async fn find_cloudtalk_contact_by_phone(
     Ok(None)
 }
 
+pub async fn send_cloudtalk_sms(
+    pool: &MySqlPool,
+    client: &Client,
+    company_id: u64,
+    payload: &SendSmsPayload,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let json: serde_json::Value = cloudtalk_request(
+        pool,
+        client,
+        "sms/send.json",
+        company_id,
+        Method::POST,
+        Some(payload),
+    )
+    .await?;
+
+    // CloudTalk answers 200 with the real status inside responseData.
+    let status = json
+        .pointer("/responseData/status")
+        .and_then(serde_json::Value::as_u64);
+    if status.is_some_and(|status| status != 200) {
+        let message = json
+            .pointer("/responseData/message")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or("unknown error");
+        return Err(format!("CloudTalk sms/send.json: {message}").into());
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod local_tests {
     use super::*;
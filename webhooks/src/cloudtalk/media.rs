@@ -298,7 +298,7 @@ pub async fn store_inbound_attachment(
     sms_id: i32,
     img: &ProcessedImage,
     position: i32,
-) -> Result<(), MediaError> {
+) -> Result<String, MediaError> {
     let bucket = std::env::var("STORAGE_BUCKET").map_err(|_| MediaError::Upload)?;
     let region = std::env::var("STORAGE_REGION").map_err(|_| MediaError::Upload)?;
 
@@ -335,7 +335,40 @@ pub async fn store_inbound_attachment(
     .await
     .map_err(|_| MediaError::Db)?;
 
-    Ok(())
+    Ok(s3_url)
+}
+
+// ===================== Component D: ingest =====================
+
+/// Caps the work one webhook delivery can trigger.
+const MAX_MEDIA_PER_MESSAGE: usize = 10;
+
+/// Fetch -> re-encode -> store for each URL, subject to the module's dedup contract.
+/// A failed item is logged by token and skipped; returns the stored public URLs in order.
+pub async fn ingest_inbound_media(
+    pool: &MySqlPool,
+    s3: &impl S3Bucket,
+    company_id: i32,
+    sms_id: i32,
+    urls: &[String],
+) -> Vec<String> {
+    let mut stored = Vec::new();
+    for url in urls.iter().take(MAX_MEDIA_PER_MESSAGE) {
+        let position = i32::try_from(stored.len()).unwrap_or(i32::MAX);
+        let result = async {
+            let bytes = fetch_inbound_media(url).await?;
+            let img = process_inbound_image(&bytes)?;
+            store_inbound_attachment(pool, s3, company_id, sms_id, &img, position).await
+        }
+        .await;
+        match result {
+            Ok(s3_url) => stored.push(s3_url),
+            Err(error) => {
+                tracing::warn!(%error, company_id, sms_id, "inbound media item skipped");
+            }
+        }
+    }
+    stored
 }
 
 #[cfg(test)]
@@ -821,5 +854,27 @@ mod tests {
             assert_eq!((rows[0].position, rows[0].width), (0, Some(100)));
             assert_eq!((rows[1].position, rows[1].width), (1, Some(200)));
         }
+
+        #[sqlx::test(migrations = "../migrations")]
+        async fn ingest_skips_items_that_fail_to_fetch(pool: MySqlPool) {
+            let sms_id = insert_parent_sms(&pool, 42).await;
+            let s3 = MockClient::new("unused");
+            let urls = vec![
+                "http://media.example.com/a.jpg".to_string(),
+                "not a url".to_string(),
+            ];
+
+            let stored = ingest_inbound_media(&pool, &s3, 42, sms_id, &urls).await;
+
+            assert!(stored.is_empty());
+            let count = sqlx::query_scalar!(
+                "SELECT COUNT(*) FROM cloudtalk_sms_attachments WHERE cloudtalk_sms_id = ?",
+                sms_id,
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            assert_eq!(count, 0);
+        }
     }
 }
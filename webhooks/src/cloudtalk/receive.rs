@@ -1,17 +1,22 @@
+use crate::amazon::bucket::CustomClient;
 use crate::axum_helpers::guards::{CloudTalkWebhookUser, NotificationsTelegramBot};
 use crate::cloudtalk::api::sync_customer_to_cloud_talk;
+use crate::cloudtalk::media::ingest_inbound_media;
 use crate::cloudtalk::schemas::{CloudtalkSMS, inbound_customer_phone_from_call_payload};
 use crate::crud::cloudtalk::{
     cancel_flow_enrollments_for_customer, cancel_flow_enrollments_on_reply, insert_inbound_sms,
     insert_outbound_sms,
 };
-use crate::crud::deals::{find_customer_id_by_phone_last10, maybe_move_deal_on_inbound_sms};
+use crate::crud::deals::{
+    find_customer_id_by_phone, find_deal_id_by_phone, maybe_move_deal_on_inbound_sms,
+};
 use crate::crud::users::get_user_id_by_cloudtalk_agent;
 use crate::libs::constants::{BAD_REQUEST, ERR_DB, OK_RESPONSE, internal_error};
 use crate::libs::types::BasicResponse;
 use crate::telegram::crm::{InboundSmsTelegramNotify, send_inbound_sms_telegram_notification};
 use axum::body::Bytes;
 use axum::extract::{Path, State};
+use common::utils::phone::PhoneNumber;
 use lambda_http::tracing;
 use reqwest::Client;
 use sqlx::MySqlPool;
@@ -40,17 +45,8 @@ pub async fn sms_received(
     Path(company_id): Path<i32>,
     body: Bytes,
 ) -> BasicResponse {
-    // TEMP MMS capture (inbound spike): remove after the real payload is captured.
     // `Bytes` (not `String`) so a non-UTF-8 body still reaches the parse-failure path below,
     // rather than being rejected earlier by axum's `String` extractor.
-    let digits = std::env::var("MMS_CAPTURE_DIGITS").unwrap_or_default();
-    if !digits.is_empty() {
-        let body_lossy = String::from_utf8_lossy(&body);
-        if body_lossy.contains(digits.as_str()) {
-            tracing::info!("MMS_CAPTURE company_id={company_id} body={body_lossy}");
-        }
-    }
-
     let Some(form) = parse_cloudtalk_sms(&body, "received") else {
         return BAD_REQUEST;
     };
@@ -71,15 +67,38 @@ pub async fn sms_received(
 
                 maybe_move_deal_on_inbound_sms(&pool, company_id, form.sender()).await;
 
+                // Media only for a real CloudTalk id: a NULL id is not deduped by INSERT IGNORE.
+                let image_urls = match (form.id, i32::try_from(result.last_insert_id())) {
+                    (Some(_), Ok(sms_id)) => {
+                        ingest_inbound_media(
+                            &pool,
+                            &CustomClient {},
+                            company_id,
+                            sms_id,
+                            &form.inbound_media_urls(),
+                        )
+                        .await
+                    }
+                    _ => Vec::new(),
+                };
+
                 if let Some(agent) = form.agent.as_deref() {
                     if let Ok(Some(user_id)) =
                         get_user_id_by_cloudtalk_agent(&pool, company_id, agent).await
                     {
                         let sender_phone = form.sender().to_string();
+                        let deal_id = find_deal_id_by_phone(&pool, company_id, form.sender())
+                            .await
+                            .ok()
+                            .flatten();
                         let payload = InboundSmsTelegramNotify {
                             receiver_user_id: user_id,
+                            company_id,
                             sender_phone,
+                            business_phone: form.recipient(),
+                            deal_id,
                             message: form.text.0.clone(),
+                            image_urls,
                         };
                         let bot = NotificationsTelegramBot::default();
                         if let Err(error) =
@@ -148,8 +167,11 @@ pub async fn call_received(
         );
     }
 
-    let last10 = phone_digits.to_string();
-    match find_customer_id_by_phone_last10(&pool, company_id, &last10).await {
+    let customer = match PhoneNumber::from_match_key(phone_digits) {
+        Some(phone) => find_customer_id_by_phone(&pool, company_id, &phone).await,
+        None => Ok(None),
+    };
+    match customer {
         Ok(Some(customer_id)) => {
             if let Err(error) =
                 cancel_flow_enrollments_for_customer(&pool, company_id, customer_id).await
@@ -205,7 +227,7 @@ pub async fn sync_cloudtalk(
 mod tests {
     use super::parse_cloudtalk_sms;
     use crate::axum_helpers::guards::CORRECT_ID;
-    use crate::tests::cloudtalk::{INBOUND_MMS_NULL_TEXT, INBOUND_SMS};
+    use crate::tests::cloudtalk::{INBOUND_MMS_NULL_TEXT, INBOUND_MMS_WITH_MEDIA, INBOUND_SMS};
     use crate::tests::utils::{insert_group_list, new_test_app};
     use axum::body::Bytes;
     use axum::http::StatusCode;
@@ -316,6 +338,31 @@ mod tests {
         assert_eq!(smss[0].sender, Some(6468956758));
     }
 
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_sms_received_with_unfetchable_media_still_stores_sms(pool: MySqlPool) {
+        let app = new_test_app(pool.clone());
+        let body: serde_json::Value =
+            serde_json::from_slice(INBOUND_MMS_WITH_MEDIA).expect("parse");
+
+        // No host is allowlisted in tests, so every fetch fails closed.
+        let response = app
+            .post("/cloudtalk/sms/42")
+            .authorization_bearer(CORRECT_ID.to_string())
+            .json(&body)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let smss = get_sms_received(&pool).await;
+        assert_eq!(smss.len(), 1);
+        assert_eq!(smss[0].text, "Kitchen photos");
+
+        let attachments = sqlx::query_scalar!("SELECT COUNT(*) FROM cloudtalk_sms_attachments")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(attachments, 0);
+    }
+
     #[sqlx::test(migrations = "../migrations")]
     async fn test_sms_rejected_without_bearer_token(pool: MySqlPool) {
         let app = new_test_app(pool.clone());
@@ -1,3 +1,4 @@
+use common::utils::phone::PhoneNumber;
 use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -7,6 +8,12 @@ pub struct CloudtalkSMS {
     recipient: CleanedPhone,
     pub text: CleanText,
     pub agent: Option<String>,
+    #[serde(default)]
+    media: MediaUrls,
+    #[serde(default)]
+    attachments: MediaUrls,
+    #[serde(default)]
+    media_urls: MediaUrls,
 }
 
 impl CloudtalkSMS {
@@ -16,6 +23,64 @@ impl CloudtalkSMS {
     pub const fn recipient(&self) -> u64 {
         self.recipient.0
     }
+
+    /// Every media URL across `media`, `attachments` and `media_urls`, first occurrence wins.
+    pub fn inbound_media_urls(&self) -> Vec<String> {
+        let mut urls: Vec<String> = Vec::new();
+        for url in self
+            .media
+            .0
+            .iter()
+            .chain(&self.attachments.0)
+            .chain(&self.media_urls.0)
+        {
+            if !urls.contains(url) {
+                urls.push(url.clone());
+            }
+        }
+        urls
+    }
+}
+
+/// CloudTalk has sent media as null, a single URL, a list of URLs, or a list of
+/// objects carrying the URL; all of them flatten to a list of URLs here.
+#[derive(Serialize, Debug, Default)]
+pub struct MediaUrls(Vec<String>);
+
+const MEDIA_URL_KEYS: [&str; 4] = ["url", "media_url", "link", "src"];
+
+fn collect_media_urls(value: &serde_json::Value, urls: &mut Vec<String>) {
+    match value {
+        serde_json::Value::String(raw) => {
+            let trimmed = raw.trim();
+            if !trimmed.is_empty() {
+                urls.push(trimmed.to_string());
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                collect_media_urls(item, urls);
+            }
+        }
+        serde_json::Value::Object(obj) => {
+            if let Some(url) = MEDIA_URL_KEYS.iter().find_map(|key| obj.get(*key)) {
+                collect_media_urls(url, urls);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl<'de> Deserialize<'de> for MediaUrls {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let mut urls = Vec::new();
+        collect_media_urls(&value, &mut urls);
+        Ok(Self(urls))
+    }
 }
 
 #[derive(Serialize, Debug)]
@@ -26,15 +91,14 @@ impl<'de> Deserialize<'de> for CleanedPhone {
     where
         D: Deserializer<'de>,
     {
-        // 1. Get the raw string from the JSON
         let raw_s = String::deserialize(deserializer)?;
+        if let Some(phone) = PhoneNumber::parse(&raw_s) {
+            return Ok(Self(phone.match_key()));
+        }
 
-        // 2. Clean the string: keep only digits
+        // Short codes and other non-E.164 senders keep their last 10 digits.
         let cleaned: String = raw_s.chars().filter(char::is_ascii_digit).collect();
-
         let stripped = get_last_n_chars(&cleaned, 10);
-
-        // 3. Parse to i64 (and handle errors if the string is empty/invalid)
         let num = stripped.parse::<u64>().map_err(serde::de::Error::custom)?;
 
         Ok(Self(num))
@@ -68,14 +132,10 @@ fn get_last_n_chars(s: &str, n: usize) -> &str {
     }
 }
 
-/// Last 10 digit characters as a number. `None` when there are fewer than 10 digits
-/// so a CAST of 0 cannot false-match an enrollment phone.
-pub fn phone_last10(raw: &str) -> Option<u64> {
-    let cleaned: String = raw.chars().filter(char::is_ascii_digit).collect();
-    if cleaned.len() < 10 {
-        return None;
-    }
-    get_last_n_chars(&cleaned, 10).parse().ok()
+/// [`PhoneNumber::match_key`] of a raw number. `None` when it is not a full number so a
+/// CAST of 0 cannot false-match an enrollment phone.
+pub fn phone_match_key(raw: &str) -> Option<u64> {
+    PhoneNumber::parse(raw).map(|phone| phone.match_key())
 }
 
 fn json_phone_raw(value: &serde_json::Value) -> Option<String> {
@@ -96,7 +156,7 @@ fn first_phone_in_object(obj: &serde_json::Map<String, serde_json::Value>) -> Op
     ];
     for key in PHONE_KEYS {
         if let Some(raw) = obj.get(key).and_then(json_phone_raw)
-            && let Some(digits) = phone_last10(&raw)
+            && let Some(digits) = phone_match_key(&raw)
         {
             return Some(digits);
         }
@@ -221,6 +281,14 @@ pub struct ContactPayload {
     pub country_id: Option<u64>,
 }
 
+/// Body of `POST sms/send.json`; numbers are E.164.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct SendSmsPayload {
+    pub sender: String,
+    pub recipient: String,
+    pub message: String,
+}
+
 #[derive(Serialize, Debug, Clone, PartialEq, Eq)]
 pub struct ContactNumber {
     pub public_number: String,
@@ -322,7 +390,7 @@ pub struct ContactSearchEnvelope {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tests::cloudtalk::{INBOUND_MMS_NULL_TEXT, INBOUND_SMS};
+    use crate::tests::cloudtalk::{INBOUND_MMS_NULL_TEXT, INBOUND_MMS_WITH_MEDIA, INBOUND_SMS};
     use serde_json;
 
     const MESSAGE_2: &[u8] =
@@ -365,6 +433,29 @@ mod tests {
         assert_eq!(sms.sender(), 6468956758);
     }
 
+    #[test]
+    fn test_null_media_yields_no_urls() {
+        let sms: CloudtalkSMS =
+            serde_json::from_slice(INBOUND_MMS_NULL_TEXT).expect("null media must parse");
+        assert!(sms.inbound_media_urls().is_empty());
+
+        let sms: CloudtalkSMS = serde_json::from_slice(INBOUND_SMS).expect("missing media");
+        assert!(sms.inbound_media_urls().is_empty());
+    }
+
+    #[test]
+    fn test_media_urls_from_every_shape() {
+        let sms: CloudtalkSMS = serde_json::from_slice(INBOUND_MMS_WITH_MEDIA).unwrap();
+        assert_eq!(
+            sms.inbound_media_urls(),
+            vec![
+                "https://media.example.com/a.jpg".to_string(),
+                "https://media.example.com/b.png".to_string(),
+                "https://media.example.com/c.gif".to_string(),
+            ]
+        );
+    }
+
     #[test]
     fn test_phone_without_prefix() {
         // Test that it still works if the '1' isn't there
@@ -374,11 +465,12 @@ mod tests {
     }
 
     #[test]
-    fn phone_last10_requires_ten_digits() {
-        assert_eq!(phone_last10("+1 (555) 123-4567"), Some(5_551_234_567));
-        assert_eq!(phone_last10("5551234567"), Some(5_551_234_567));
-        assert_eq!(phone_last10("555-1234"), None);
-        assert_eq!(phone_last10(""), None);
+    fn phone_match_key_requires_a_full_number() {
+        assert_eq!(phone_match_key("+1 (555) 123-4567"), Some(5_551_234_567));
+        assert_eq!(phone_match_key("5551234567"), Some(5_551_234_567));
+        assert_eq!(phone_match_key("+44 20 7946 0958"), Some(442_079_460_958));
+        assert_eq!(phone_match_key("555-1234"), None);
+        assert_eq!(phone_match_key(""), None);
     }
 
     #[test]
@@ -54,29 +54,8 @@ const US_STATES: [&str; 56] = [
     "WI", "WY", "DC", "PR", "VI", "GU", "AS", "MP",
 ];
 
-pub fn phone_digits_only(phone: &str) -> String {
-    phone.chars().filter(char::is_ascii_digit).collect()
-}
-
 pub fn normalize_to_e164(phone: Option<&str>) -> Option<String> {
-    let trimmed = phone?.trim();
-    if trimmed.is_empty() {
-        return None;
-    }
-
-    if trimmed.starts_with('+') {
-        return Some(format!("+{}", phone_digits_only(trimmed)));
-    }
-
-    let digits = phone_digits_only(trimmed);
-    if digits.len() == 10 {
-        return Some(format!("+1{digits}"));
-    }
-    if digits.len() == 11 && digits.starts_with('1') {
-        return Some(format!("+{digits}"));
-    }
-
-    None
+    phone.and_then(common::utils::phone::normalize_to_e164)
 }
 
 pub fn build_phones(customer: &CustomerWithMapping) -> Vec<ContactNumber> {
@@ -0,0 +1,188 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::MySqlPool;
+use sqlx::mysql::MySqlQueryResult;
+use std::fmt::Write;
+
+const API_KEY_PREFIX: &str = "gm_";
+const DISPLAY_PREFIX_LEN: usize = 11;
+
+pub struct ActiveApiKey {
+    pub id: i32,
+    pub company_id: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiKeySummary {
+    pub id: i32,
+    pub label: String,
+    pub key_prefix: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+/// Only the SHA-256 of a key is stored; the plaintext is shown once on creation.
+pub fn hash_api_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .fold(String::new(), |mut output, b| {
+            let _ = write!(output, "{b:02x}");
+            output
+        })
+}
+
+pub fn generate_api_key() -> String {
+    let bytes: [u8; 32] = rand::random();
+    bytes
+        .iter()
+        .fold(String::from(API_KEY_PREFIX), |mut output, b| {
+            let _ = write!(output, "{b:02x}");
+            output
+        })
+}
+
+/// Returns the new key id and the plaintext key, which is never retrievable again.
+pub async fn create_api_key(
+    pool: &MySqlPool,
+    company_id: i32,
+    label: &str,
+) -> Result<(u64, String), sqlx::Error> {
+    let key = generate_api_key();
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO company_api_keys (company_id, label, key_prefix, key_hash)
+        VALUES (?, ?, ?, ?)
+        "#,
+        company_id,
+        label,
+        &key[..DISPLAY_PREFIX_LEN],
+        hash_api_key(&key),
+    )
+    .execute(pool)
+    .await?;
+    Ok((result.last_insert_id(), key))
+}
+
+pub async fn find_active_api_key(
+    pool: &MySqlPool,
+    key_hash: &str,
+) -> Result<Option<ActiveApiKey>, sqlx::Error> {
+    sqlx::query_as!(
+        ActiveApiKey,
+        r#"
+        SELECT id, company_id
+        FROM company_api_keys
+        WHERE key_hash = ? AND revoked_at IS NULL
+        "#,
+        key_hash
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn touch_api_key(pool: &MySqlPool, id: i32) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"UPDATE company_api_keys SET last_used_at = UTC_TIMESTAMP() WHERE id = ?"#,
+        id
+    )
+    .execute(pool)
+    .await
+}
+
+pub async fn list_api_keys(
+    pool: &MySqlPool,
+    company_id: i32,
+) -> Result<Vec<ApiKeySummary>, sqlx::Error> {
+    sqlx::query_as!(
+        ApiKeySummary,
+        r#"
+        SELECT id, label, key_prefix, created_at, last_used_at, revoked_at
+        FROM company_api_keys
+        WHERE company_id = ?
+        ORDER BY id ASC
+        "#,
+        company_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn revoke_api_key(
+    pool: &MySqlPool,
+    company_id: i32,
+    id: i32,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE company_api_keys
+        SET revoked_at = UTC_TIMESTAMP()
+        WHERE id = ? AND company_id = ? AND revoked_at IS NULL
+        "#,
+        id,
+        company_id
+    )
+    .execute(pool)
+    .await
+}
+
+pub async fn company_allows_lenient_lead_auth(
+    pool: &MySqlPool,
+    company_id: i32,
+) -> Result<bool, sqlx::Error> {
+    let lenient = sqlx::query_scalar!(
+        r#"SELECT lead_auth_lenient AS "lead_auth_lenient!: bool" FROM company WHERE id = ?"#,
+        company_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(lenient.unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_keys_are_unique_and_prefixed() {
+        let first = generate_api_key();
+        let second = generate_api_key();
+        assert!(first.starts_with(API_KEY_PREFIX));
+        assert_eq!(first.len(), API_KEY_PREFIX.len() + 64);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_hash_api_key_is_stable_hex() {
+        let hash = hash_api_key("gm_example");
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, hash_api_key("gm_example"));
+        assert_ne!(hash, hash_api_key("gm_example2"));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_revoked_key_is_not_active(pool: MySqlPool) {
+        let (id, key) = create_api_key(&pool, 1, "Zapier").await.unwrap();
+        let hash = hash_api_key(&key);
+
+        let active = find_active_api_key(&pool, &hash).await.unwrap().unwrap();
+        assert_eq!(active.company_id, 1);
+
+        let revoked = revoke_api_key(&pool, 1, i32::try_from(id).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(revoked.rows_affected(), 1);
+        assert!(find_active_api_key(&pool, &hash).await.unwrap().is_none());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_revoke_is_scoped_to_company(pool: MySqlPool) {
+        let (id, _) = create_api_key(&pool, 1, "Website").await.unwrap();
+
+        let revoked = revoke_api_key(&pool, 2, i32::try_from(id).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(revoked.rows_affected(), 0);
+    }
+}
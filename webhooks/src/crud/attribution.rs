@@ -0,0 +1,72 @@
+use crate::schemas::add_customer::LeadAttribution;
+use sqlx::MySqlPool;
+use sqlx::mysql::MySqlQueryResult;
+
+/// Kept once, from the submission that created the customer.
+pub async fn insert_first_touch(
+    pool: &MySqlPool,
+    customer_id: i32,
+    attribution: &LeadAttribution,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT IGNORE INTO lead_attributions
+            (customer_id, touch, utm_source, utm_medium, utm_campaign, utm_term, utm_content,
+             gclid, fbclid, wbraid, landing_page, referrer)
+        VALUES (?, 'first', ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        customer_id,
+        attribution.utm_source,
+        attribution.utm_medium,
+        attribution.utm_campaign,
+        attribution.utm_term,
+        attribution.utm_content,
+        attribution.gclid,
+        attribution.fbclid,
+        attribution.wbraid,
+        attribution.landing_page,
+        attribution.referrer
+    )
+    .execute(pool)
+    .await
+}
+
+/// Replaced by every submission, including repeat leads.
+pub async fn upsert_last_touch(
+    pool: &MySqlPool,
+    customer_id: i32,
+    attribution: &LeadAttribution,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO lead_attributions
+            (customer_id, touch, utm_source, utm_medium, utm_campaign, utm_term, utm_content,
+             gclid, fbclid, wbraid, landing_page, referrer)
+        VALUES (?, 'last', ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE
+            utm_source = VALUES(utm_source),
+            utm_medium = VALUES(utm_medium),
+            utm_campaign = VALUES(utm_campaign),
+            utm_term = VALUES(utm_term),
+            utm_content = VALUES(utm_content),
+            gclid = VALUES(gclid),
+            fbclid = VALUES(fbclid),
+            wbraid = VALUES(wbraid),
+            landing_page = VALUES(landing_page),
+            referrer = VALUES(referrer)
+        "#,
+        customer_id,
+        attribution.utm_source,
+        attribution.utm_medium,
+        attribution.utm_campaign,
+        attribution.utm_term,
+        attribution.utm_content,
+        attribution.gclid,
+        attribution.fbclid,
+        attribution.wbraid,
+        attribution.landing_page,
+        attribution.referrer
+    )
+    .execute(pool)
+    .await
+}
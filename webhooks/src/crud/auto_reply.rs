@@ -0,0 +1,105 @@
+use chrono::NaiveTime;
+use sqlx::MySqlPool;
+
+pub struct AutoReplyPolicy {
+    pub timezone: String,
+    pub fallback_user_id: Option<i32>,
+    pub sms_in_hours: Option<String>,
+    pub sms_after_hours: Option<String>,
+    pub email_subject: String,
+    pub email_in_hours: Option<String>,
+    pub email_after_hours: Option<String>,
+}
+
+pub struct BusinessHours {
+    pub weekday: i8,
+    pub opens_at: NaiveTime,
+    pub closes_at: NaiveTime,
+}
+
+pub async fn get_auto_reply_policy(
+    pool: &MySqlPool,
+    company_id: i32,
+) -> Result<Option<AutoReplyPolicy>, sqlx::Error> {
+    sqlx::query_as!(
+        AutoReplyPolicy,
+        r#"
+        SELECT timezone, fallback_user_id, sms_in_hours, sms_after_hours,
+               email_subject, email_in_hours, email_after_hours
+        FROM lead_auto_reply_policies
+        WHERE company_id = ? AND is_active = 1
+        "#,
+        company_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn list_business_hours(
+    pool: &MySqlPool,
+    company_id: i32,
+) -> Result<Vec<BusinessHours>, sqlx::Error> {
+    sqlx::query_as!(
+        BusinessHours,
+        r#"
+        SELECT weekday, opens_at, closes_at
+        FROM company_business_hours
+        WHERE company_id = ?
+        "#,
+        company_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_inactive_policy_is_ignored(pool: MySqlPool) {
+        sqlx::query!(
+            r#"
+            INSERT INTO lead_auto_reply_policies (company_id, timezone, sms_after_hours, is_active)
+            VALUES (1, 'America/Indiana/Indianapolis', 'We will call you at 9am', 0)
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert!(get_auto_reply_policy(&pool, 1).await.unwrap().is_none());
+
+        sqlx::query!("UPDATE lead_auto_reply_policies SET is_active = 1 WHERE company_id = 1")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let policy = get_auto_reply_policy(&pool, 1).await.unwrap().unwrap();
+        assert_eq!(policy.timezone, "America/Indiana/Indianapolis");
+        assert_eq!(
+            policy.sms_after_hours.as_deref(),
+            Some("We will call you at 9am")
+        );
+        assert!(policy.email_in_hours.is_none());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_lists_business_hours(pool: MySqlPool) {
+        sqlx::query!(
+            r#"
+            INSERT INTO company_business_hours (company_id, weekday, opens_at, closes_at)
+            VALUES (1, 0, '09:00:00', '17:30:00')
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let hours = list_business_hours(&pool, 1).await.unwrap();
+        assert_eq!(hours.len(), 1);
+        assert_eq!(hours[0].weekday, 0);
+        assert_eq!(hours[0].opens_at, NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+        assert_eq!(
+            hours[0].closes_at,
+            NaiveTime::from_hms_opt(17, 30, 0).unwrap()
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::cloudtalk::schemas::{CloudtalkSMS, phone_last10};
+use crate::cloudtalk::schemas::{CloudtalkSMS, phone_match_key};
 use base64::{Engine as _, engine::general_purpose::STANDARD};
 use sqlx::MySqlPool;
 use sqlx::mysql::MySqlQueryResult;
@@ -117,6 +117,39 @@ pub async fn insert_outbound_sms(
     .await
 }
 
+/// Stores an SMS we sent through the API; the `sms_sent` echo merges into this row.
+pub async fn insert_api_sent_sms(
+    pool: &MySqlPool,
+    company_id: i32,
+    sender_user_id: i32,
+    sender: u64,
+    recipient: u64,
+    text: &str,
+    error_message: Option<&str>,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    let status = if error_message.is_some() {
+        "failed"
+    } else {
+        "sent"
+    };
+    sqlx::query!(
+        r#"
+        INSERT INTO cloudtalk_sms
+            (sender, recipient, text, sender_user_id, company_id, direction, status, error_message)
+        VALUES (?, ?, ?, ?, ?, 'outbound', ?, ?)
+        "#,
+        sender,
+        recipient,
+        text,
+        sender_user_id,
+        company_id,
+        status,
+        error_message,
+    )
+    .execute(pool)
+    .await
+}
+
 pub struct CustomerWithMapping {
     // From customers table
     pub id: i32,
@@ -336,7 +369,7 @@ pub async fn cancel_flow_enrollments_for_customer(
 
     if let Some(row) = phones {
         for raw in [row.phone, row.phone_2] {
-            if let Some(digits) = raw.as_deref().and_then(phone_last10) {
+            if let Some(digits) = raw.as_deref().and_then(phone_match_key) {
                 affected += cancel_flow_enrollments_on_reply(pool, company_id, digits).await?;
             }
         }
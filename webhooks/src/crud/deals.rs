@@ -1,6 +1,7 @@
 use common::crud::scheduled_emails::{
     cancel_pending_scheduled_emails_for_deal, reschedule_templates_for_deal_list,
 };
+use common::utils::phone::PhoneNumber;
 use lambda_http::tracing;
 use sqlx::MySqlPool;
 
@@ -157,7 +158,7 @@ async fn reschedule_drip_after_move(
                 ?error,
                 deal_id = deal.id,
                 list_id = next_list.id,
-                "Failed to reschedule drip emails after moving deal to contacted"
+                "Failed to reschedule drip emails after moving deal"
             );
         }
     } else if let Err(error) = cancel_pending_scheduled_emails_for_deal(pool, deal.id).await {
@@ -191,6 +192,42 @@ pub async fn move_deal_to_contacted_if_uncontacted(
     Ok(true)
 }
 
+/// Moves the deal to the next list of its group, returning that list's name, or `None`
+/// when it is already in the last one.
+pub async fn move_deal_to_next_stage(
+    pool: &MySqlPool,
+    deal_id: u64,
+) -> Result<Option<String>, sqlx::Error> {
+    let Some(deal) = load_deal_move_context(pool, deal_id).await? else {
+        return Ok(None);
+    };
+    let Some(next_list) = next_list_in_group(pool, &deal).await? else {
+        return Ok(None);
+    };
+
+    apply_deal_list_move(pool, &deal, &next_list).await?;
+    reschedule_drip_after_move(pool, &deal, &next_list).await;
+    Ok(Some(next_list.name))
+}
+
+pub async fn get_deal_company_id(
+    pool: &MySqlPool,
+    deal_id: u64,
+) -> Result<Option<i32>, sqlx::Error> {
+    let company_id = sqlx::query_scalar!(
+        r#"
+        SELECT c.company_id
+        FROM deals d
+        INNER JOIN customers c ON c.id = d.customer_id
+        WHERE d.id = ? AND d.deleted_at IS NULL
+        "#,
+        deal_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(company_id.flatten())
+}
+
 pub async fn find_customer_id_by_email(
     pool: &MySqlPool,
     company_id: i32,
@@ -214,11 +251,15 @@ pub async fn find_customer_id_by_email(
     .await
 }
 
-pub async fn find_customer_id_by_phone_last10(
+/// Matches the CloudTalk contact first, then the customer's own numbers. Customers the
+/// phone backfill has not reached yet still match North American numbers on the last ten
+/// digits.
+pub async fn find_customer_id_by_phone(
     pool: &MySqlPool,
     company_id: i32,
-    last10: &str,
+    phone: &PhoneNumber,
 ) -> Result<Option<i32>, sqlx::Error> {
+    let e164 = phone.e164();
     if let Some(customer_id) = sqlx::query_scalar!(
         r#"
         SELECT c.id
@@ -226,16 +267,13 @@ pub async fn find_customer_id_by_phone_last10(
         INNER JOIN cloudtalk_contacts cc ON cc.customer_id = c.id
         WHERE cc.company_id = ?
           AND c.deleted_at IS NULL
-          AND (
-            RIGHT(cc.phone_e164_1, 10) = ?
-            OR RIGHT(cc.phone_e164_2, 10) = ?
-          )
+          AND (cc.phone_e164_1 = ? OR cc.phone_e164_2 = ?)
         ORDER BY c.id DESC
         LIMIT 1
         "#,
         company_id,
-        last10,
-        last10
+        e164,
+        e164
     )
     .fetch_optional(pool)
     .await?
@@ -243,6 +281,9 @@ pub async fn find_customer_id_by_phone_last10(
         return Ok(Some(customer_id));
     }
 
+    let legacy_last10 = phone
+        .is_north_american()
+        .then(|| phone.match_key().to_string());
     sqlx::query_scalar!(
         r#"
         SELECT c.id
@@ -250,15 +291,21 @@ pub async fn find_customer_id_by_phone_last10(
         WHERE c.company_id = ?
           AND c.deleted_at IS NULL
           AND (
-            RIGHT(REGEXP_REPLACE(COALESCE(c.phone, ''), '[^0-9]', ''), 10) = ?
-            OR RIGHT(REGEXP_REPLACE(COALESCE(c.phone_2, ''), '[^0-9]', ''), 10) = ?
+            c.phone_e164 = ?
+            OR c.phone_2_e164 = ?
+            OR (c.phone_e164 IS NULL
+                AND RIGHT(REGEXP_REPLACE(COALESCE(c.phone, ''), '[^0-9]', ''), 10) = ?)
+            OR (c.phone_2_e164 IS NULL
+                AND RIGHT(REGEXP_REPLACE(COALESCE(c.phone_2, ''), '[^0-9]', ''), 10) = ?)
           )
         ORDER BY c.id DESC
         LIMIT 1
         "#,
         company_id,
-        last10,
-        last10
+        e164,
+        e164,
+        legacy_last10,
+        legacy_last10
     )
     .fetch_optional(pool)
     .await
@@ -339,20 +386,32 @@ pub async fn maybe_cancel_flow_on_inbound_email(pool: &MySqlPool, send: &SendEma
     }
 }
 
+/// The open deal of the customer texting from `sender`.
+pub async fn find_deal_id_by_phone(
+    pool: &MySqlPool,
+    company_id: i32,
+    sender: u64,
+) -> Result<Option<u64>, sqlx::Error> {
+    let Some(phone) = PhoneNumber::from_match_key(sender) else {
+        return Ok(None);
+    };
+    let Some(customer_id) = find_customer_id_by_phone(pool, company_id, &phone).await? else {
+        return Ok(None);
+    };
+    Ok(get_existing_deal(pool, customer_id)
+        .await?
+        .map(|deal| deal.id))
+}
+
 pub async fn move_deal_on_inbound_sms(
     pool: &MySqlPool,
     company_id: i32,
     sender: u64,
 ) -> Result<bool, sqlx::Error> {
-    let last10 = sender.to_string();
-    let Some(customer_id) = find_customer_id_by_phone_last10(pool, company_id, &last10).await?
-    else {
+    let Some(deal_id) = find_deal_id_by_phone(pool, company_id, sender).await? else {
         return Ok(false);
     };
-    let Some(deal) = get_existing_deal(pool, customer_id).await? else {
-        return Ok(false);
-    };
-    move_deal_to_contacted_if_uncontacted(pool, deal.id).await
+    move_deal_to_contacted_if_uncontacted(pool, deal_id).await
 }
 
 pub async fn maybe_move_deal_on_inbound_sms(pool: &MySqlPool, company_id: i32, sender: u64) {
@@ -652,12 +711,37 @@ mod tests {
             .unwrap();
         assert_eq!(by_email, Some(board.customer_id));
 
-        let by_phone = find_customer_id_by_phone_last10(&pool, board.company_id, "3173161456")
+        let phone = PhoneNumber::parse("3173161456").unwrap();
+        let by_phone = find_customer_id_by_phone(&pool, board.company_id, &phone)
             .await
             .unwrap();
         assert_eq!(by_phone, Some(board.customer_id));
     }
 
+    #[sqlx::test(migrations = "../migrations")]
+    async fn finds_customer_by_international_phone(pool: MySqlPool) {
+        let board = setup_board(&pool, Some("+44 2079460958")).await;
+        sqlx::query!(
+            r#"UPDATE customers SET phone_e164 = '+442079460958' WHERE id = ?"#,
+            board.customer_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let phone = PhoneNumber::from_match_key(442_079_460_958).unwrap();
+        let by_phone = find_customer_id_by_phone(&pool, board.company_id, &phone)
+            .await
+            .unwrap();
+        assert_eq!(by_phone, Some(board.customer_id));
+
+        let other = PhoneNumber::parse("+44 2079460959").unwrap();
+        let by_other = find_customer_id_by_phone(&pool, board.company_id, &other)
+            .await
+            .unwrap();
+        assert_eq!(by_other, None);
+    }
+
     #[sqlx::test(migrations = "../migrations")]
     async fn finds_customer_by_cloudtalk_contact_phone(pool: MySqlPool) {
         let board = setup_board(&pool, None).await;
@@ -670,7 +754,8 @@ mod tests {
         .await
         .unwrap();
 
-        let by_phone = find_customer_id_by_phone_last10(&pool, board.company_id, "6468956758")
+        let phone = PhoneNumber::parse("6468956758").unwrap();
+        let by_phone = find_customer_id_by_phone(&pool, board.company_id, &phone)
             .await
             .unwrap();
         assert_eq!(by_phone, Some(board.customer_id));
@@ -0,0 +1,204 @@
+use sqlx::MySqlPool;
+use sqlx::mysql::MySqlQueryResult;
+
+/// A customer that shares a phone, email or ZIP with an incoming lead.
+#[derive(Debug, Clone)]
+pub struct DuplicateCandidate {
+    pub id: i32,
+    pub name: Option<String>,
+    pub sales_rep: Option<i32>,
+    pub phone: Option<String>,
+    pub phone_2: Option<String>,
+    pub phone_e164: Option<String>,
+    pub phone_2_e164: Option<String>,
+    /// Every address in `customers_emails`, newline separated.
+    pub emails: Option<String>,
+    pub postal_code: Option<String>,
+    pub address: Option<String>,
+    pub latest_deal_id: Option<u64>,
+}
+
+/// What the lead is looked up by; see `libs::duplicates::LeadIdentity`.
+pub struct DuplicateLookup<'a> {
+    pub phone_e164: Option<&'a str>,
+    pub phone: Option<&'a str>,
+    pub canonical_email: Option<&'a str>,
+    pub postal_code: Option<&'a str>,
+}
+
+/// Candidates only; scoring happens in `libs::duplicates`. Emails are compared in the same
+/// canonical form as `canonical_email`, so `J.Doe+quote@gmail.com` finds `jdoe@gmail.com`.
+/// Phone and email matches rank ahead of ZIP-only neighbours, so a busy ZIP can never push
+/// an exact match out of the capped list.
+pub async fn find_duplicate_candidates(
+    pool: &MySqlPool,
+    company_id: i32,
+    lookup: &DuplicateLookup<'_>,
+) -> Result<Vec<DuplicateCandidate>, sqlx::Error> {
+    sqlx::query_as!(
+        DuplicateCandidate,
+        r#"
+        SELECT
+            c.id,
+            c.name,
+            c.sales_rep,
+            c.phone,
+            c.phone_2,
+            c.phone_e164,
+            c.phone_2_e164,
+            GROUP_CONCAT(ce.email SEPARATOR '\n') AS "emails?: String",
+            c.postal_code,
+            c.address,
+            (SELECT MAX(d.id) FROM deals d WHERE d.customer_id = c.id AND d.deleted_at IS NULL)
+                AS "latest_deal_id?: u64"
+        FROM (
+          SELECT
+            m.id,
+            COALESCE(
+              m.phone_e164 = ?
+              OR m.phone_2_e164 = ?
+              OR (m.phone_e164 IS NULL AND m.phone = ?)
+              OR EXISTS (
+                SELECT 1
+                FROM customers_emails e
+                WHERE e.customer_id = m.id
+                  AND CONCAT(
+                    IF(SUBSTRING_INDEX(LOWER(TRIM(e.email)), '@', -1) IN ('gmail.com', 'googlemail.com'),
+                       REPLACE(SUBSTRING_INDEX(SUBSTRING_INDEX(LOWER(TRIM(e.email)), '@', 1), '+', 1), '.', ''),
+                       SUBSTRING_INDEX(SUBSTRING_INDEX(LOWER(TRIM(e.email)), '@', 1), '+', 1)),
+                    '@',
+                    IF(SUBSTRING_INDEX(LOWER(TRIM(e.email)), '@', -1) = 'googlemail.com',
+                       'gmail.com',
+                       SUBSTRING_INDEX(LOWER(TRIM(e.email)), '@', -1))
+                  ) = ?
+              ),
+              FALSE
+            ) AS is_exact,
+            COALESCE(LEFT(m.postal_code, 5) = ?, FALSE) AS same_zip
+          FROM customers m
+          WHERE m.company_id = ?
+            AND m.deleted_at IS NULL
+        ) matched
+        INNER JOIN customers c ON c.id = matched.id
+        LEFT JOIN customers_emails ce ON ce.customer_id = c.id
+        WHERE matched.is_exact OR matched.same_zip
+        GROUP BY c.id, matched.is_exact
+        ORDER BY matched.is_exact DESC, c.id DESC
+        LIMIT 25
+        "#,
+        lookup.phone_e164,
+        lookup.phone_e164,
+        lookup.phone,
+        lookup.canonical_email,
+        lookup.postal_code,
+        company_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn flag_possible_duplicate(
+    pool: &MySqlPool,
+    customer_id: i32,
+    duplicate_of_customer_id: i32,
+    score: u32,
+    reasons: &str,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO customer_duplicate_flags (customer_id, duplicate_of_customer_id, score, reasons)
+        VALUES (?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE score = VALUES(score), reasons = VALUES(reasons)
+        "#,
+        customer_id,
+        duplicate_of_customer_id,
+        score,
+        reasons
+    )
+    .execute(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn insert_customer(pool: &MySqlPool, name: &str, phone: &str, postal_code: &str) -> i32 {
+        let id = sqlx::query!(
+            "INSERT INTO customers (name, company_id, phone, postal_code, source) VALUES (?, 1, ?, ?, 'leads')",
+            name,
+            phone,
+            postal_code
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        i32::try_from(id).unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn finds_candidates_by_gmail_alias_and_zip(pool: MySqlPool) {
+        let by_email = insert_customer(&pool, "Jane Doe", "317-555-0101", "46201").await;
+        sqlx::query!(
+            "INSERT INTO customers_emails (customer_id, email) VALUES (?, 'J.Doe+quote@GoogleMail.com')",
+            by_email
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let by_zip = insert_customer(&pool, "John Doe", "317-555-0102", "46201-1234").await;
+        insert_customer(&pool, "Elsewhere", "317-555-0103", "46032").await;
+
+        let lookup = DuplicateLookup {
+            phone_e164: None,
+            phone: None,
+            canonical_email: Some("jdoe@gmail.com"),
+            postal_code: None,
+        };
+        let found = find_duplicate_candidates(&pool, 1, &lookup).await.unwrap();
+        assert_eq!(
+            found.iter().map(|c| c.id).collect::<Vec<_>>(),
+            vec![by_email]
+        );
+        assert_eq!(
+            found[0].emails.as_deref(),
+            Some("J.Doe+quote@GoogleMail.com")
+        );
+
+        let lookup = DuplicateLookup {
+            postal_code: Some("46201"),
+            ..lookup
+        };
+        let found = find_duplicate_candidates(&pool, 1, &lookup).await.unwrap();
+        assert_eq!(
+            found.iter().map(|c| c.id).collect::<Vec<_>>(),
+            vec![by_email, by_zip]
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn exact_phone_match_survives_a_busy_zip(pool: MySqlPool) {
+        let by_phone = insert_customer(&pool, "Old Customer", "317-555-0100", "46032").await;
+        sqlx::query!(
+            "UPDATE customers SET phone_e164 = '+13175550100' WHERE id = ?",
+            by_phone
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        for index in 0..30 {
+            insert_customer(&pool, &format!("Neighbour {index}"), "", "46201").await;
+        }
+
+        let lookup = DuplicateLookup {
+            phone_e164: Some("+13175550100"),
+            phone: Some("317-555-0100"),
+            canonical_email: None,
+            postal_code: Some("46201"),
+        };
+        let found = find_duplicate_candidates(&pool, 1, &lookup).await.unwrap();
+        assert_eq!(found.len(), 25);
+        assert_eq!(found[0].id, by_phone);
+    }
+}
@@ -47,6 +47,58 @@ pub async fn create_email_read(
     .await
 }
 
+pub struct NewEmailEvent<'a> {
+    pub event_id: &'a str,
+    pub ses_message_id: &'a str,
+    pub message_id: Option<&'a str>,
+    pub company_id: Option<i32>,
+    pub event_type: &'static str,
+    pub recipient_email: &'a str,
+    pub bounce_type: Option<&'static str>,
+    pub bounce_sub_type: Option<&'a str>,
+    pub detail: Option<&'a str>,
+}
+
+/// `INSERT IGNORE` on (`event_id`, `recipient_email`): a redelivered event affects 0 rows.
+pub async fn insert_email_event(
+    pool: &MySqlPool,
+    event: &NewEmailEvent<'_>,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT IGNORE INTO email_events (
+            event_id, ses_message_id, message_id, company_id, event_type,
+            recipient_email, bounce_type, bounce_sub_type, detail
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        event.event_id,
+        event.ses_message_id,
+        event.message_id,
+        event.company_id,
+        event.event_type,
+        event.recipient_email,
+        event.bounce_type,
+        event.bounce_sub_type,
+        event.detail,
+    )
+    .execute(pool)
+    .await
+}
+
+pub async fn get_email_company_id(
+    pool: &MySqlPool,
+    message_id: &str,
+) -> Result<Option<i32>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT company_id FROM emails WHERE message_id = ? LIMIT 1"#,
+        message_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map(std::option::Option::flatten)
+}
+
 pub struct PriorEmail {
     pub thread_id: Option<String>,
     pub receiver_user_id: Option<i32>,
@@ -369,6 +421,172 @@ pub async fn get_inbound_email_notify_context(
     .await
 }
 
+/// The customer's latest message in a thread, which a reply from Telegram answers.
+#[derive(Debug)]
+pub struct ThreadReplyContext {
+    pub subject: Option<String>,
+    pub sender_email: Option<String>,
+    pub message_id: Option<String>,
+    pub company_id: Option<i32>,
+    pub deal_id: Option<u64>,
+    pub customer_id: Option<i32>,
+}
+
+pub async fn get_thread_reply_context(
+    pool: &MySqlPool,
+    thread_id: &str,
+) -> Result<Option<ThreadReplyContext>, sqlx::Error> {
+    sqlx::query_as!(
+        ThreadReplyContext,
+        r#"
+        SELECT
+            e.subject,
+            e.sender_email,
+            e.message_id,
+            e.company_id,
+            e.deal_id,
+            COALESCE(
+                d.customer_id,
+                (
+                    SELECT c.id
+                    FROM customers c
+                    INNER JOIN customers_emails ce ON ce.customer_id = c.id
+                    WHERE c.company_id = e.company_id
+                      AND c.deleted_at IS NULL
+                      AND LOWER(TRIM(SUBSTRING_INDEX(SUBSTRING_INDEX(ce.email, '<', -1), '>', 1))) =
+                          LOWER(TRIM(SUBSTRING_INDEX(SUBSTRING_INDEX(e.sender_email, '<', -1), '>', 1)))
+                    LIMIT 1
+                )
+            ) AS "customer_id?: i32"
+        FROM emails e
+        LEFT JOIN deals d ON d.id = e.deal_id AND d.deleted_at IS NULL
+        WHERE e.deleted_at IS NULL
+          AND e.thread_id = ?
+          AND e.sender_user_id IS NULL
+        ORDER BY e.sent_at DESC, e.id DESC
+        LIMIT 1
+        "#,
+        thread_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Message ids of the thread, oldest first, for the `References` header of a reply.
+pub async fn list_thread_message_ids(
+    pool: &MySqlPool,
+    thread_id: &str,
+) -> Result<Vec<String>, sqlx::Error> {
+    let ids = sqlx::query_scalar!(
+        r#"
+        SELECT message_id
+        FROM emails
+        WHERE thread_id = ?
+          AND deleted_at IS NULL
+          AND message_id IS NOT NULL
+        ORDER BY sent_at, id
+        "#,
+        thread_id
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(ids.into_iter().flatten().collect())
+}
+
+pub struct NewEmailClick<'a> {
+    pub company_id: i32,
+    pub customer_id: i32,
+    pub scheduled_email_id: Option<i32>,
+    pub message_id: Option<&'a str>,
+    pub url: &'a str,
+    pub user_agent: Option<&'a str>,
+    pub ip_address: Option<&'a str>,
+}
+
+pub async fn create_email_click(
+    pool: &MySqlPool,
+    click: &NewEmailClick<'_>,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO email_clicks (
+            company_id, customer_id, scheduled_email_id, message_id, url, user_agent, ip_address
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+        click.company_id,
+        click.customer_id,
+        click.scheduled_email_id,
+        click.message_id,
+        click.url,
+        click.user_agent,
+        click.ip_address,
+    )
+    .execute(pool)
+    .await
+}
+
+/// Records the first click on a link by a customer; false when it was already recorded.
+/// The unique key makes concurrent clicks agree on which one is first.
+pub async fn claim_first_email_click(
+    pool: &MySqlPool,
+    company_id: i32,
+    customer_id: i32,
+    url: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        INSERT IGNORE INTO email_click_firsts (company_id, customer_id, url_hash)
+        VALUES (?, ?, SHA2(?, 256))
+        "#,
+        company_id,
+        customer_id,
+        url
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_scheduled_email_message_id(
+    pool: &MySqlPool,
+    company_id: i32,
+    scheduled_email_id: i32,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT message_id FROM scheduled_emails WHERE id = ? AND company_id = ?"#,
+        scheduled_email_id,
+        company_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map(std::option::Option::flatten)
+}
+
+pub struct EmailClickNotifyContext {
+    pub customer_name: Option<String>,
+    pub sales_rep: Option<i32>,
+}
+
+pub async fn get_email_click_notify_context(
+    pool: &MySqlPool,
+    company_id: i32,
+    customer_id: i32,
+) -> Result<Option<EmailClickNotifyContext>, sqlx::Error> {
+    sqlx::query_as!(
+        EmailClickNotifyContext,
+        r#"
+        SELECT name AS customer_name, sales_rep
+        FROM customers
+        WHERE id = ? AND company_id = ? AND deleted_at IS NULL
+        "#,
+        customer_id,
+        company_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,68 @@
+use sqlx::MySqlPool;
+use std::collections::HashMap;
+
+pub struct LeadFormDefinition {
+    pub form_name: String,
+    /// Incoming JSON key to canonical field name, e.g. `"Cell" -> "phone"`.
+    pub field_map: HashMap<String, String>,
+    pub referral_source: Option<String>,
+}
+
+pub async fn get_lead_form_definition(
+    pool: &MySqlPool,
+    company_id: i32,
+    form_name: &str,
+) -> Result<Option<LeadFormDefinition>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT form_name, CAST(field_map AS CHAR) AS "field_map!: String", referral_source
+        FROM lead_form_definitions
+        WHERE company_id = ? AND form_name = ?
+        "#,
+        company_id,
+        form_name
+    )
+    .fetch_optional(pool)
+    .await?;
+    row.map(|row| {
+        let field_map =
+            serde_json::from_str(&row.field_map).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        Ok(LeadFormDefinition {
+            form_name: row.form_name,
+            field_map,
+            referral_source: row.referral_source,
+        })
+    })
+    .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_get_lead_form_definition(pool: MySqlPool) {
+        sqlx::query!(
+            r#"INSERT INTO lead_form_definitions (company_id, form_name, field_map, referral_source)
+               VALUES (1, 'kitchen_quiz', '{"Full Name": "name", "Cell": "phone"}', 'website')"#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let definition = get_lead_form_definition(&pool, 1, "kitchen_quiz")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(definition.form_name, "kitchen_quiz");
+        assert_eq!(definition.field_map["Cell"], "phone");
+        assert_eq!(definition.referral_source.as_deref(), Some("website"));
+
+        assert!(
+            get_lead_form_definition(&pool, 1, "missing")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
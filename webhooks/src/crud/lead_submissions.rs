@@ -0,0 +1,93 @@
+use sqlx::MySqlPool;
+use sqlx::mysql::MySqlQueryResult;
+
+/// Claims `key` for `ttl_minutes`; `false` means a live claim already exists and the
+/// request is a replay. An expired claim is cleared first so the key can be reused.
+pub async fn claim_lead_submission(
+    pool: &MySqlPool,
+    company_id: i32,
+    key: &str,
+    ttl_minutes: i32,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM lead_submissions
+        WHERE company_id = ? AND idempotency_key = ? AND expires_at < NOW()
+        "#,
+        company_id,
+        key
+    )
+    .execute(pool)
+    .await?;
+    let result = sqlx::query!(
+        r#"
+        INSERT IGNORE INTO lead_submissions (company_id, idempotency_key, expires_at)
+        VALUES (?, ?, NOW() + INTERVAL ? MINUTE)
+        "#,
+        company_id,
+        key,
+        ttl_minutes
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Drops a claim whose request failed, so the sender's retry is processed.
+pub async fn release_lead_submission(
+    pool: &MySqlPool,
+    company_id: i32,
+    key: &str,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"DELETE FROM lead_submissions WHERE company_id = ? AND idempotency_key = ?"#,
+        company_id,
+        key
+    )
+    .execute(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_claim_is_single_use_until_released(pool: MySqlPool) {
+        assert!(
+            claim_lead_submission(&pool, 1, "key:abc", 60)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !claim_lead_submission(&pool, 1, "key:abc", 60)
+                .await
+                .unwrap()
+        );
+
+        release_lead_submission(&pool, 1, "key:abc").await.unwrap();
+        assert!(
+            claim_lead_submission(&pool, 1, "key:abc", 60)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_expired_claim_can_be_reused(pool: MySqlPool) {
+        assert!(
+            claim_lead_submission(&pool, 1, "hash:abc", 10)
+                .await
+                .unwrap()
+        );
+        sqlx::query!("UPDATE lead_submissions SET expires_at = NOW() - INTERVAL 1 MINUTE")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(
+            claim_lead_submission(&pool, 1, "hash:abc", 10)
+                .await
+                .unwrap()
+        );
+    }
+}
@@ -1,4 +1,9 @@
 use crate::schemas::add_customer::{FaceBookContactForm, NewLeadForm, WordpressContactForm};
+use crate::schemas::form_lead::FormLead;
+pub use common::crud::leads::{
+    CreatedDeal, assign_lead, create_deal, create_deal_in, get_default_list_id_from_company_id,
+};
+use common::utils::phone::normalize_to_e164;
 use sqlx::mysql::MySqlQueryResult;
 use sqlx::{MySqlPool, query};
 
@@ -45,6 +50,23 @@ async fn set_customer_email(
     Ok(())
 }
 
+/// Keeps `phone_e164` in step with the display number just written to `phone`.
+async fn set_customer_phone_e164(
+    pool: &MySqlPool,
+    customer_id: i32,
+    phone: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let phone_e164 = phone.and_then(normalize_to_e164);
+    query!(
+        r#"UPDATE customers SET phone_e164 = ? WHERE id = ?"#,
+        phone_e164,
+        customer_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
 pub async fn create_lead_from_wordpress(
     pool: &MySqlPool,
     data: &WordpressContactForm,
@@ -76,6 +98,7 @@ pub async fn create_lead_from_wordpress(
     .await?;
     let customer_id = i32::try_from(result.last_insert_id()).unwrap_or(0);
     set_customer_email(pool, customer_id, data.email.as_deref()).await?;
+    set_customer_phone_e164(pool, customer_id, data.phone.as_deref()).await?;
     Ok(result)
 }
 
@@ -108,6 +131,7 @@ pub async fn update_lead_from_wordpress(
     .execute(pool)
     .await?;
     set_customer_email(pool, id, data.email.as_deref()).await?;
+    set_customer_phone_e164(pool, id, data.phone.as_deref()).await?;
     Ok(result)
 }
 
@@ -137,6 +161,7 @@ pub async fn create_lead_from_facebook(
     .await?;
     let customer_id = i32::try_from(result.last_insert_id()).unwrap_or(0);
     set_customer_email(pool, customer_id, data.email.as_deref()).await?;
+    set_customer_phone_e164(pool, customer_id, data.phone.as_deref()).await?;
     Ok(result)
 }
 
@@ -164,98 +189,10 @@ pub async fn update_lead_from_facebook(
     .execute(pool)
     .await?;
     set_customer_email(pool, id, data.email.as_deref()).await?;
+    set_customer_phone_e164(pool, id, data.phone.as_deref()).await?;
     Ok(result)
 }
 
-pub async fn assign_lead(
-    pool: &MySqlPool,
-    lead_id: i32,
-    user_id: i32,
-) -> Result<MySqlQueryResult, sqlx::Error> {
-    return query!(
-        r#"UPDATE customers SET sales_rep = ?, assigned_date = NOW() WHERE id = ?"#,
-        user_id,
-        lead_id,
-    )
-    .execute(pool)
-    .await;
-}
-
-pub struct CreatedDeal {
-    pub id: u64,
-    pub created: bool,
-}
-
-impl CreatedDeal {
-    pub fn last_insert_id(&self) -> u64 {
-        self.id
-    }
-}
-
-pub async fn create_deal(
-    pool: &MySqlPool,
-    customer_id: i32,
-    list_id: i32,
-    next_pos: i32,
-    sales_rep: i32,
-) -> Result<CreatedDeal, sqlx::Error> {
-    let mut tx = pool.begin().await?;
-    sqlx::query_scalar!(
-        r#"SELECT id FROM customers WHERE id = ? FOR UPDATE"#,
-        customer_id
-    )
-    .fetch_optional(&mut *tx)
-    .await?;
-
-    let existing_id = sqlx::query_scalar!(
-        r#"SELECT id FROM deals
-           WHERE customer_id = ?
-             AND deleted_at IS NULL
-             AND is_won IS NULL
-           ORDER BY id ASC
-           LIMIT 1"#,
-        customer_id
-    )
-    .fetch_optional(&mut *tx)
-    .await?;
-
-    if let Some(existing_id) = existing_id {
-        query!(
-            r#"UPDATE deals
-               SET user_id = ?
-               WHERE id = ?
-                 AND deleted_at IS NULL
-                 AND (user_id IS NULL OR user_id <> ?)"#,
-            sales_rep,
-            existing_id,
-            sales_rep,
-        )
-        .execute(&mut *tx)
-        .await?;
-        tx.commit().await?;
-        return Ok(CreatedDeal {
-            id: existing_id,
-            created: false,
-        });
-    }
-
-    let result = query!(
-        r#"INSERT INTO deals (customer_id, status, list_id, position, user_id) VALUES (?,?,?,?,?)"#,
-        customer_id,
-        "New Customer",
-        list_id,
-        next_pos,
-        sales_rep,
-    )
-    .execute(&mut *tx)
-    .await?;
-    tx.commit().await?;
-    Ok(CreatedDeal {
-        id: result.last_insert_id(),
-        created: true,
-    })
-}
-
 pub async fn create_lead_from_new_lead_form(
     pool: &MySqlPool,
     data: &NewLeadForm,
@@ -294,6 +231,7 @@ pub async fn create_lead_from_new_lead_form(
     .await?;
     let customer_id = i32::try_from(result.last_insert_id()).unwrap_or(0);
     set_customer_email(pool, customer_id, data.email.as_deref()).await?;
+    set_customer_phone_e164(pool, customer_id, data.phone.as_deref()).await?;
     Ok(result)
 }
 
@@ -335,6 +273,51 @@ pub async fn update_lead_from_new_lead_form(
     .execute(pool)
     .await?;
     set_customer_email(pool, id, data.email.as_deref()).await?;
+    set_customer_phone_e164(pool, id, data.phone.as_deref()).await?;
+    Ok(result)
+}
+
+/// Merges into what earlier submissions stored, so a repeat lead keeps old answers.
+async fn set_customer_extra_fields(
+    pool: &MySqlPool,
+    customer_id: i32,
+    data: &FormLead,
+) -> Result<(), sqlx::Error> {
+    if data.extra_fields.is_empty() {
+        return Ok(());
+    }
+    let extra_fields = serde_json::Value::Object(data.extra_fields.clone()).to_string();
+    query!(
+        r#"UPDATE customers
+           SET extra_fields = JSON_MERGE_PATCH(COALESCE(extra_fields, JSON_OBJECT()), CAST(? AS JSON))
+           WHERE id = ?"#,
+        extra_fields,
+        customer_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn create_lead_from_form(
+    pool: &MySqlPool,
+    data: &FormLead,
+    company_id: i32,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    let result = create_lead_from_new_lead_form(pool, &data.lead, company_id).await?;
+    let customer_id = i32::try_from(result.last_insert_id()).unwrap_or(0);
+    set_customer_extra_fields(pool, customer_id, data).await?;
+    Ok(result)
+}
+
+pub async fn update_lead_from_form(
+    pool: &MySqlPool,
+    data: &FormLead,
+    company_id: i32,
+    id: i32,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    let result = update_lead_from_new_lead_form(pool, &data.lead, company_id, id).await?;
+    set_customer_extra_fields(pool, id, data).await?;
     Ok(result)
 }
 
@@ -349,35 +332,6 @@ pub struct Deal {
     pub user_id: Option<i32>,
 }
 
-pub async fn find_existing_customer(
-    pool: &MySqlPool,
-    email: Option<&str>,
-    phone: Option<&str>,
-    company_id: i32,
-) -> Result<Option<ExistingCustomer>, sqlx::Error> {
-    if email.is_none() && phone.is_none() {
-        return Ok(None);
-    }
-    sqlx::query_as!(
-        ExistingCustomer,
-        r#"
-        SELECT c.id, c.name, c.sales_rep
-        FROM customers c
-        LEFT JOIN customers_emails ce ON ce.id = c.email_id
-        WHERE c.company_id = ?
-          AND c.deleted_at IS NULL
-          AND (ce.email = ? OR c.phone = ?)
-        ORDER BY c.id DESC
-        LIMIT 1
-        "#,
-        company_id,
-        email,
-        phone
-    )
-    .fetch_optional(pool)
-    .await
-}
-
 pub async fn get_existing_deal(
     pool: &MySqlPool,
     customer_id: i32,
@@ -391,29 +345,6 @@ pub async fn get_existing_deal(
     .await
 }
 
-pub async fn get_default_list_id_from_company_id(
-    pool: &MySqlPool,
-    company_id: i32,
-) -> Result<i32, sqlx::Error> {
-    let id = sqlx::query_scalar!(
-        r#"
-        SELECT dl.id
-        FROM deals_list dl
-        INNER JOIN groups_list gl ON dl.group_id = gl.id
-        WHERE gl.company_id = ?
-          AND gl.is_default = 1
-          AND dl.deleted_at IS NULL
-          AND gl.deleted_at IS NULL
-        ORDER BY dl.position ASC, dl.id ASC
-        LIMIT 1
-        "#,
-        company_id
-    )
-    .fetch_optional(pool)
-    .await?;
-    Ok(id.unwrap_or(1))
-}
-
 pub async fn create_deal_from_lead(
     pool: &MySqlPool,
     lead_id: i32,
@@ -448,6 +379,7 @@ pub async fn update_deal_list_id(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::schemas::add_customer::LeadAttribution;
     use crate::tests::utils::*;
 
     async fn insert_company(pool: &MySqlPool) -> Result<i32, sqlx::Error> {
@@ -726,6 +658,7 @@ mod tests {
             campaign_name: None,
             adset_name: None,
             ad_name: None,
+            attribution: LeadAttribution::default(),
         };
         let created = create_lead_from_facebook(&pool, &first, company_id)
             .await
@@ -0,0 +1,19 @@
+use sqlx::MySqlPool;
+
+pub struct MetaLeadPage {
+    pub company_id: i32,
+    pub access_token: String,
+}
+
+pub async fn get_meta_lead_page(
+    pool: &MySqlPool,
+    page_id: &str,
+) -> Result<Option<MetaLeadPage>, sqlx::Error> {
+    sqlx::query_as!(
+        MetaLeadPage,
+        r#"SELECT company_id, access_token FROM meta_lead_pages WHERE page_id = ?"#,
+        page_id
+    )
+    .fetch_optional(pool)
+    .await
+}
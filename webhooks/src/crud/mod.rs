@@ -1,8 +1,22 @@
+pub mod api_keys;
+pub mod attribution;
+pub mod auto_reply;
 pub mod cloudtalk;
 pub mod company;
 pub mod deals;
+pub mod duplicates;
 pub mod email;
+pub mod lead_forms;
+pub mod lead_submissions;
 pub mod leads;
+pub mod meta;
+pub mod notification_rules;
+pub mod rep_commands;
+pub mod spam;
+pub mod telegram_groups;
+pub mod telegram_links;
 pub mod telegram_messages;
+pub mod telegram_replies;
+pub mod unsubscribe;
 pub mod user_position;
 pub mod users;
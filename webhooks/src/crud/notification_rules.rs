@@ -0,0 +1,105 @@
+use chrono::NaiveTime;
+use sqlx::MySqlPool;
+
+/// Customer fields the routing rules can match on.
+#[derive(Debug, Default)]
+pub struct LeadRoutingAttributes {
+    pub form_name: Option<String>,
+    pub referral_source: Option<String>,
+    pub postal_code: Option<String>,
+    pub city: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct NotificationRule {
+    pub id: i32,
+    pub form_name: Option<String>,
+    pub referral_source: Option<String>,
+    pub postal_prefix: Option<String>,
+    pub city: Option<String>,
+    pub starts_at: Option<NaiveTime>,
+    pub ends_at: Option<NaiveTime>,
+    pub chat_id: Option<i64>,
+}
+
+#[derive(Debug)]
+pub struct NotificationSettings {
+    pub timezone: String,
+    pub quiet_starts_at: Option<NaiveTime>,
+    pub quiet_ends_at: Option<NaiveTime>,
+    pub quiet_mode: String,
+    pub on_call_user_id: Option<i32>,
+}
+
+pub async fn get_lead_routing_attributes(
+    pool: &MySqlPool,
+    customer_id: i32,
+) -> Result<Option<LeadRoutingAttributes>, sqlx::Error> {
+    sqlx::query_as!(
+        LeadRoutingAttributes,
+        r#"
+        SELECT form_name, referral_source, postal_code, city
+        FROM customers
+        WHERE id = ?
+        "#,
+        customer_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Rules in evaluation order.
+pub async fn list_notification_rules(
+    pool: &MySqlPool,
+    company_id: i32,
+) -> Result<Vec<NotificationRule>, sqlx::Error> {
+    sqlx::query_as!(
+        NotificationRule,
+        r#"
+        SELECT id, form_name, referral_source, postal_prefix, city, starts_at, ends_at, chat_id
+        FROM lead_notification_rules
+        WHERE company_id = ?
+        ORDER BY priority, id
+        "#,
+        company_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Telegram chats of the rule's users; users who never linked Telegram are left out.
+pub async fn list_rule_recipient_telegram_ids(
+    pool: &MySqlPool,
+    rule_id: i32,
+) -> Result<Vec<i64>, sqlx::Error> {
+    let ids = sqlx::query_scalar!(
+        r#"
+        SELECT u.telegram_id
+        FROM lead_notification_rule_users r
+        INNER JOIN users u ON u.id = r.user_id
+        WHERE r.rule_id = ?
+          AND u.telegram_id IS NOT NULL
+        "#,
+        rule_id
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(ids.into_iter().flatten().collect())
+}
+
+pub async fn get_notification_settings(
+    pool: &MySqlPool,
+    company_id: i32,
+) -> Result<Option<NotificationSettings>, sqlx::Error> {
+    sqlx::query_as!(
+        NotificationSettings,
+        r#"
+        SELECT timezone, quiet_starts_at, quiet_ends_at, quiet_mode, on_call_user_id
+        FROM lead_notification_settings
+        WHERE company_id = ?
+        "#,
+        company_id
+    )
+    .fetch_optional(pool)
+    .await
+}
@@ -0,0 +1,341 @@
+use chrono::{DateTime, Utc};
+use sqlx::MySqlPool;
+
+/// A CRM user who linked the lead bot, with the company their commands are scoped to.
+#[derive(Debug)]
+pub struct TelegramRep {
+    pub user_id: i32,
+    pub company_id: i32,
+}
+
+#[derive(Debug)]
+pub struct OpenDeal {
+    pub id: u64,
+    pub stage: String,
+    pub customer_name: Option<String>,
+    pub title: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct DueActivity {
+    pub deal_id: u64,
+    pub message: String,
+    pub customer_name: Option<String>,
+    pub due_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct CustomerMatch {
+    pub id: i32,
+    pub name: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct RepStats {
+    pub mtd_leads: i64,
+    pub mtd_won: i64,
+}
+
+pub async fn get_telegram_rep(
+    pool: &MySqlPool,
+    telegram_id: i64,
+) -> Result<Option<TelegramRep>, sqlx::Error> {
+    sqlx::query_as!(
+        TelegramRep,
+        r#"
+        SELECT id AS user_id, company_id AS "company_id!"
+        FROM users
+        WHERE telegram_id = ?
+          AND is_deleted = 0
+          AND company_id IS NOT NULL
+        LIMIT 1
+        "#,
+        telegram_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// The rep's deals that are neither won nor lost, in pipeline order.
+pub async fn list_open_deals(
+    pool: &MySqlPool,
+    rep: &TelegramRep,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<OpenDeal>, sqlx::Error> {
+    sqlx::query_as!(
+        OpenDeal,
+        r#"
+        SELECT d.id, dl.name AS stage, c.name AS customer_name, d.title
+        FROM deals d
+        INNER JOIN deals_list dl ON dl.id = d.list_id
+        INNER JOIN customers c ON c.id = d.customer_id
+        WHERE d.user_id = ?
+          AND c.company_id = ?
+          AND d.deleted_at IS NULL
+          AND d.is_won IS NULL
+          AND c.deleted_at IS NULL
+        ORDER BY dl.position, dl.id, d.position, d.id
+        LIMIT ? OFFSET ?
+        "#,
+        rep.user_id,
+        rep.company_id,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Open activity reminders due by the end of today (UTC), the same rows the deadline
+/// reminder job sends.
+pub async fn list_due_activities(
+    pool: &MySqlPool,
+    rep: &TelegramRep,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<DueActivity>, sqlx::Error> {
+    sqlx::query_as!(
+        DueActivity,
+        r#"
+        SELECT
+            n.deal_id AS "deal_id!",
+            n.message,
+            c.name AS customer_name,
+            n.due_at
+        FROM notifications n
+        JOIN deals d ON d.id = n.deal_id AND d.deleted_at IS NULL
+        JOIN customers c ON c.id = d.customer_id
+        WHERE n.user_id = ?
+          AND c.company_id = ?
+          AND n.notification_type = 'activity_deadline_reminder'
+          AND n.is_done = 0
+          AND n.due_at < UTC_DATE() + INTERVAL 1 DAY
+          AND EXISTS (
+            SELECT 1 FROM deal_activities da
+            WHERE da.deal_id = n.deal_id
+              AND da.deleted_at IS NULL
+              AND da.is_completed = 0
+              AND LEFT(da.name, 255) = n.message
+          )
+        ORDER BY n.due_at, n.id
+        LIMIT ? OFFSET ?
+        "#,
+        rep.user_id,
+        rep.company_id,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// The rep's customers whose name contains `name` or whose phone contains `digits`.
+/// `%term%` for `LIKE ... ESCAPE '!'`, with the term's own wildcards matched literally.
+fn contains_pattern(term: &str) -> String {
+    let mut pattern = String::with_capacity(term.len() + 2);
+    pattern.push('%');
+    for character in term.chars() {
+        if matches!(character, '!' | '%' | '_') {
+            pattern.push('!');
+        }
+        pattern.push(character);
+    }
+    pattern.push('%');
+    pattern
+}
+
+pub async fn find_rep_customers(
+    pool: &MySqlPool,
+    rep: &TelegramRep,
+    name: &str,
+    digits: &str,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<CustomerMatch>, sqlx::Error> {
+    let name_pattern = contains_pattern(name);
+    let digits_pattern = contains_pattern(digits);
+    sqlx::query_as!(
+        CustomerMatch,
+        r#"
+        SELECT c.id, c.name, c.phone, ce.email
+        FROM customers c
+        LEFT JOIN customers_emails ce ON ce.id = c.email_id
+        WHERE c.sales_rep = ?
+          AND c.company_id = ?
+          AND c.deleted_at IS NULL
+          AND (
+            c.name LIKE ? ESCAPE '!'
+            OR (
+              ? <> ''
+              AND (
+                REGEXP_REPLACE(c.phone, '[^0-9]', '') LIKE ? ESCAPE '!'
+                OR REGEXP_REPLACE(c.phone_2, '[^0-9]', '') LIKE ? ESCAPE '!'
+              )
+            )
+          )
+        ORDER BY c.name, c.id
+        LIMIT ? OFFSET ?
+        "#,
+        rep.user_id,
+        rep.company_id,
+        name_pattern,
+        digits,
+        digits_pattern,
+        digits_pattern,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Month-to-date leads, counted like the assignment buttons do, and deals marked won.
+pub async fn get_rep_stats(pool: &MySqlPool, rep: &TelegramRep) -> Result<RepStats, sqlx::Error> {
+    sqlx::query_as!(
+        RepStats,
+        r#"
+        SELECT
+            (
+                SELECT COUNT(*)
+                FROM customers c
+                WHERE c.sales_rep = ?
+                  AND c.company_id = ?
+                  AND c.source = 'leads'
+                  AND c.assigned_date >= DATE_FORMAT(NOW(), '%Y-%m-01')
+                  AND c.deleted_at IS NULL
+            ) AS "mtd_leads!: i64",
+            (
+                SELECT COUNT(*)
+                FROM deals d
+                INNER JOIN customers c ON c.id = d.customer_id
+                WHERE d.user_id = ?
+                  AND c.company_id = ?
+                  AND d.is_won = 1
+                  AND d.won_at >= DATE_FORMAT(NOW(), '%Y-%m-01')
+                  AND d.deleted_at IS NULL
+            ) AS "mtd_won!: i64"
+        "#,
+        rep.user_id,
+        rep.company_id,
+        rep.user_id,
+        rep.company_id
+    )
+    .fetch_one(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crud::leads::create_deal;
+    use crate::tests::utils::insert_user;
+
+    async fn insert_customer(pool: &MySqlPool, company_id: i32, name: &str, rep: i32) -> i32 {
+        let id = sqlx::query!(
+            r#"
+            INSERT INTO customers (name, company_id, phone, source, sales_rep, assigned_date)
+            VALUES (?, ?, '(317) 555-0142', 'leads', ?, NOW())
+            "#,
+            name,
+            company_id,
+            rep
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        i32::try_from(id).unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_rep_queries_stay_in_the_rep_company(pool: MySqlPool) {
+        let user_id = insert_user(&pool, "rep@example.com", Some(456))
+            .await
+            .unwrap();
+        let rep = get_telegram_rep(&pool, 456).await.unwrap().unwrap();
+        assert_eq!((rep.user_id, rep.company_id), (user_id, 1));
+
+        let other_company = sqlx::query!("INSERT INTO company (name) VALUES ('Other Co')")
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_id();
+        let customer_id = insert_customer(&pool, 1, "Jane Stone", user_id).await;
+        insert_customer(
+            &pool,
+            i32::try_from(other_company).unwrap(),
+            "Jane Other",
+            user_id,
+        )
+        .await;
+
+        let by_name = find_rep_customers(&pool, &rep, "jane", "", 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(by_name.len(), 1);
+        assert_eq!(by_name[0].name.as_deref(), Some("Jane Stone"));
+        let by_phone = find_rep_customers(&pool, &rep, "0142", "0142", 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(by_phone.len(), 1);
+        let wildcard = find_rep_customers(&pool, &rep, "%", "", 10, 0)
+            .await
+            .unwrap();
+        assert!(wildcard.is_empty());
+        sqlx::query!(
+            "UPDATE customers SET phone_2 = '(812) 555-0199' WHERE id = ?",
+            customer_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let by_second_phone = find_rep_customers(&pool, &rep, "0199", "0199", 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(by_second_phone.len(), 1);
+
+        let stats = get_rep_stats(&pool, &rep).await.unwrap();
+        assert_eq!((stats.mtd_leads, stats.mtd_won), (1, 0));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_won_this_month_counts_when_the_deal_was_won(pool: MySqlPool) {
+        let user_id = insert_user(&pool, "rep@example.com", Some(456))
+            .await
+            .unwrap();
+        let rep = get_telegram_rep(&pool, 456).await.unwrap().unwrap();
+        let customer_id = insert_customer(&pool, 1, "Jane Stone", user_id).await;
+        let deal = create_deal(&pool, customer_id, 1, 0, user_id)
+            .await
+            .unwrap();
+
+        sqlx::query!("UPDATE deals SET is_won = 1 WHERE id = ?", deal.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(get_rep_stats(&pool, &rep).await.unwrap().mtd_won, 1);
+
+        // Won last month and edited today
+        sqlx::query!(
+            "UPDATE deals SET won_at = NOW() - INTERVAL 40 DAY, updated_at = NOW() WHERE id = ?",
+            deal.id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert_eq!(get_rep_stats(&pool, &rep).await.unwrap().mtd_won, 0);
+
+        sqlx::query!("UPDATE deals SET is_won = NULL WHERE id = ?", deal.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let won_at = sqlx::query_scalar!("SELECT won_at FROM deals WHERE id = ?", deal.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert!(won_at.is_none());
+    }
+}
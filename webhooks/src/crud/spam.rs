@@ -0,0 +1,208 @@
+use sqlx::MySqlPool;
+use sqlx::mysql::MySqlQueryResult;
+
+pub struct SpamSettings {
+    pub quarantine_score: i32,
+    pub max_identical_per_hour: i32,
+    pub blocked_email_domains: Option<String>,
+    pub blocked_phone_prefixes: Option<String>,
+}
+
+pub struct SpamRuleWeight {
+    pub rule: String,
+    pub weight: i32,
+}
+
+pub struct QuarantinedLead {
+    pub company_id: i32,
+    pub payload: String,
+}
+
+pub async fn get_spam_settings(
+    pool: &MySqlPool,
+    company_id: i32,
+) -> Result<Option<SpamSettings>, sqlx::Error> {
+    sqlx::query_as!(
+        SpamSettings,
+        r#"
+        SELECT quarantine_score, max_identical_per_hour, blocked_email_domains, blocked_phone_prefixes
+        FROM lead_spam_settings
+        WHERE company_id = ?
+        "#,
+        company_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn list_spam_rule_weights(
+    pool: &MySqlPool,
+    company_id: i32,
+) -> Result<Vec<SpamRuleWeight>, sqlx::Error> {
+    sqlx::query_as!(
+        SpamRuleWeight,
+        r#"SELECT rule, weight FROM lead_spam_rules WHERE company_id = ?"#,
+        company_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Records `fingerprint` and returns how many times it was seen in the last hour before
+/// this one. Rows older than a day are dropped on the way.
+pub async fn record_lead_fingerprint(
+    pool: &MySqlPool,
+    company_id: i32,
+    fingerprint: &str,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM lead_spam_fingerprints
+        WHERE company_id = ? AND created_at < NOW() - INTERVAL 1 DAY
+        "#,
+        company_id
+    )
+    .execute(pool)
+    .await?;
+    let seen = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!: i64"
+        FROM lead_spam_fingerprints
+        WHERE company_id = ? AND fingerprint = ? AND created_at > NOW() - INTERVAL 1 HOUR
+        "#,
+        company_id,
+        fingerprint
+    )
+    .fetch_one(pool)
+    .await?;
+    sqlx::query!(
+        r#"INSERT INTO lead_spam_fingerprints (company_id, fingerprint) VALUES (?, ?)"#,
+        company_id,
+        fingerprint
+    )
+    .execute(pool)
+    .await?;
+    Ok(seen)
+}
+
+pub async fn insert_quarantined_lead(
+    pool: &MySqlPool,
+    company_id: i32,
+    payload: &str,
+    score: u32,
+    reasons: &str,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO quarantined_leads (company_id, payload, score, reasons)
+        VALUES (?, ?, ?, ?)
+        "#,
+        company_id,
+        payload,
+        score,
+        reasons
+    )
+    .execute(pool)
+    .await
+}
+
+pub async fn get_quarantined_lead(
+    pool: &MySqlPool,
+    id: i32,
+) -> Result<Option<QuarantinedLead>, sqlx::Error> {
+    sqlx::query_as!(
+        QuarantinedLead,
+        r#"SELECT company_id, payload FROM quarantined_leads WHERE id = ?"#,
+        id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// `false` when another manager already reviewed the lead.
+pub async fn review_quarantined_lead(
+    pool: &MySqlPool,
+    id: i32,
+    status: &str,
+    reviewed_by: i32,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE quarantined_leads
+        SET status = ?, reviewed_by = ?, reviewed_at = NOW()
+        WHERE id = ? AND status = 'pending'
+        "#,
+        status,
+        reviewed_by,
+        id
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Undoes an approval whose lead could not be created, so a manager can approve it again.
+pub async fn reopen_quarantined_lead(
+    pool: &MySqlPool,
+    id: i32,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE quarantined_leads
+        SET status = 'pending', reviewed_by = NULL, reviewed_at = NULL
+        WHERE id = ? AND status = 'approved'
+        "#,
+        id
+    )
+    .execute(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::utils::insert_user;
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_fingerprint_counts_recent_repeats_only(pool: MySqlPool) {
+        assert_eq!(record_lead_fingerprint(&pool, 1, "abc").await.unwrap(), 0);
+        assert_eq!(record_lead_fingerprint(&pool, 1, "abc").await.unwrap(), 1);
+        assert_eq!(record_lead_fingerprint(&pool, 1, "other").await.unwrap(), 0);
+
+        sqlx::query!(
+            "UPDATE lead_spam_fingerprints SET created_at = NOW() - INTERVAL 2 HOUR WHERE fingerprint = 'abc'"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert_eq!(record_lead_fingerprint(&pool, 1, "abc").await.unwrap(), 0);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_quarantined_lead_is_reviewed_once(pool: MySqlPool) {
+        let id = insert_quarantined_lead(&pool, 1, "{}", 80, "disposable email")
+            .await
+            .unwrap()
+            .last_insert_id();
+        let id = i32::try_from(id).unwrap();
+        let user_id = insert_user(&pool, "manager@example.com", Some(456))
+            .await
+            .unwrap();
+
+        assert!(
+            review_quarantined_lead(&pool, id, "rejected", user_id)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !review_quarantined_lead(&pool, id, "approved", user_id)
+                .await
+                .unwrap()
+        );
+        let status = sqlx::query_scalar!("SELECT status FROM quarantined_leads WHERE id = ?", id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(status, "rejected");
+    }
+}
@@ -0,0 +1,165 @@
+use crate::libs::constants::SALES_MANAGER;
+use sqlx::MySqlPool;
+use sqlx::mysql::MySqlQueryResult;
+
+/// A sales manager who linked their Telegram account.
+pub struct LinkedManager {
+    pub user_id: i32,
+    pub company_id: i32,
+}
+
+/// Every company where this Telegram account is a sales manager. A user can manage more
+/// than one company, so callers must pick the company they act for.
+pub async fn list_linked_managers(
+    pool: &MySqlPool,
+    telegram_id: i64,
+) -> Result<Vec<LinkedManager>, sqlx::Error> {
+    sqlx::query_as!(
+        LinkedManager,
+        r#"
+        SELECT u.id AS user_id, up.company_id
+        FROM users u
+        INNER JOIN users_positions up
+            ON up.user_id = u.id
+            AND up.position_id = ?
+        WHERE u.telegram_id = ?
+        ORDER BY up.company_id
+        "#,
+        SALES_MANAGER,
+        telegram_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Makes `chat_id` the company's lead group, replacing its previous group. A group can
+/// only serve one company, so an older link of the same chat is dropped.
+pub async fn link_lead_group(
+    pool: &MySqlPool,
+    company_id: i32,
+    chat_id: i64,
+    title: Option<&str>,
+    user_id: i32,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM telegram_lead_groups WHERE chat_id = ? AND company_id <> ?",
+        chat_id,
+        company_id
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO telegram_lead_groups (company_id, chat_id, title, linked_by_user_id)
+        VALUES (?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE
+            chat_id = VALUES(chat_id),
+            title = VALUES(title),
+            linked_by_user_id = VALUES(linked_by_user_id),
+            linked_at = CURRENT_TIMESTAMP
+        "#,
+        company_id,
+        chat_id,
+        title,
+        user_id
+    )
+    .execute(pool)
+    .await
+}
+
+pub async fn unlink_lead_group(pool: &MySqlPool, chat_id: i64) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        "DELETE FROM telegram_lead_groups WHERE chat_id = ?",
+        chat_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Telegram gives a group a new id when it becomes a supergroup.
+pub async fn migrate_lead_group(
+    pool: &MySqlPool,
+    old_chat_id: i64,
+    new_chat_id: i64,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        "UPDATE telegram_lead_groups SET chat_id = ? WHERE chat_id = ?",
+        new_chat_id,
+        old_chat_id
+    )
+    .execute(pool)
+    .await
+}
+
+pub async fn get_lead_group_chat_id(
+    pool: &MySqlPool,
+    company_id: i32,
+) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar!(
+        "SELECT chat_id FROM telegram_lead_groups WHERE company_id = ?",
+        company_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libs::constants::SALES_WORKER;
+    use crate::tests::utils::{assigned_user_position, positioned_user};
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_only_managers_are_linked_managers(pool: MySqlPool) {
+        let manager = positioned_user(&pool, 1, SALES_MANAGER, 456).await;
+        positioned_user(&pool, 1, SALES_WORKER, 123).await;
+
+        let linked = list_linked_managers(&pool, 456).await.unwrap();
+        assert_eq!(linked.len(), 1);
+        assert_eq!((linked[0].user_id, linked[0].company_id), (manager, 1));
+        assert!(list_linked_managers(&pool, 123).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_manager_of_two_companies_is_linked_to_both(pool: MySqlPool) {
+        let manager = positioned_user(&pool, 1, SALES_MANAGER, 456).await;
+        let other_company = sqlx::query!("INSERT INTO company (name) VALUES ('Other Co')")
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_id();
+        let other_company = i32::try_from(other_company).unwrap();
+        assigned_user_position(&pool, other_company, SALES_MANAGER, manager)
+            .await
+            .unwrap();
+
+        let companies: Vec<i32> = list_linked_managers(&pool, 456)
+            .await
+            .unwrap()
+            .iter()
+            .map(|linked| linked.company_id)
+            .collect();
+        assert_eq!(companies, vec![1, other_company]);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_relinking_replaces_the_company_group(pool: MySqlPool) {
+        let manager = positioned_user(&pool, 1, SALES_MANAGER, 456).await;
+        link_lead_group(&pool, 1, -100, Some("Leads"), manager)
+            .await
+            .unwrap();
+        link_lead_group(&pool, 1, -200, Some("Leads 2"), manager)
+            .await
+            .unwrap();
+        assert_eq!(get_lead_group_chat_id(&pool, 1).await.unwrap(), Some(-200));
+
+        migrate_lead_group(&pool, -200, -100_200).await.unwrap();
+        assert_eq!(
+            get_lead_group_chat_id(&pool, 1).await.unwrap(),
+            Some(-100_200)
+        );
+        assert_eq!(unlink_lead_group(&pool, -100_200).await.unwrap(), 1);
+        assert_eq!(get_lead_group_chat_id(&pool, 1).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,202 @@
+use sqlx::MySqlPool;
+use sqlx::mysql::MySqlQueryResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkAttemptOutcome {
+    CodeSent,
+    CodeRejected,
+    Linked,
+    LockedOut,
+    LinkRejected,
+}
+
+impl LinkAttemptOutcome {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::CodeSent => "code_sent",
+            Self::CodeRejected => "code_rejected",
+            Self::Linked => "linked",
+            Self::LockedOut => "locked_out",
+            Self::LinkRejected => "link_rejected",
+        }
+    }
+}
+
+/// Confirmation code a chat was sent for an account.
+#[derive(Debug)]
+pub struct PendingTelegramCode {
+    pub user_id: i32,
+    pub code: i32,
+    pub expired: bool,
+}
+
+pub async fn record_link_attempt(
+    pool: &MySqlPool,
+    chat_id: i64,
+    user_id: Option<i32>,
+    outcome: LinkAttemptOutcome,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO telegram_link_attempts (chat_id, user_id, outcome)
+        VALUES (?, ?, ?)
+        "#,
+        chat_id,
+        user_id,
+        outcome.as_str()
+    )
+    .execute(pool)
+    .await
+}
+
+/// Rejected codes and links in the last `minutes`, from `chat_id` or against `user_id`.
+pub async fn count_recent_link_failures(
+    pool: &MySqlPool,
+    chat_id: i64,
+    user_id: Option<i32>,
+    minutes: u32,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*)
+        FROM telegram_link_attempts
+        WHERE (chat_id = ? OR user_id = ?)
+          AND outcome IN ('code_rejected', 'link_rejected')
+          AND created_at > NOW() - INTERVAL ? MINUTE
+        "#,
+        chat_id,
+        user_id,
+        minutes
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Codes emailed in the last `minutes`, to `chat_id` or for `user_id`.
+pub async fn count_recent_codes_sent(
+    pool: &MySqlPool,
+    chat_id: i64,
+    user_id: Option<i32>,
+    minutes: u32,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*)
+        FROM telegram_link_attempts
+        WHERE (chat_id = ? OR user_id = ?)
+          AND outcome = 'code_sent'
+          AND created_at > NOW() - INTERVAL ? MINUTE
+        "#,
+        chat_id,
+        user_id,
+        minutes
+    )
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn get_pending_telegram_code(
+    pool: &MySqlPool,
+    chat_id: i64,
+) -> Result<Option<PendingTelegramCode>, sqlx::Error> {
+    sqlx::query_as!(
+        PendingTelegramCode,
+        r#"
+        SELECT
+            id AS user_id,
+            telegram_conf_code AS "code!",
+            COALESCE(telegram_conf_expires_at <= UTC_TIMESTAMP(), 1) AS "expired!: bool"
+        FROM users
+        WHERE temp_telegram_id = ? AND telegram_conf_code IS NOT NULL
+        LIMIT 1
+        "#,
+        chat_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Invalidates the code so the chat has to request a new one.
+pub async fn clear_telegram_code(
+    pool: &MySqlPool,
+    user_id: i32,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET telegram_conf_code = NULL, telegram_conf_expires_at = NULL
+        WHERE id = ?
+        "#,
+        user_id
+    )
+    .execute(pool)
+    .await
+}
+
+/// Stores a deep-link token for a live user; no rows are affected for an unknown one.
+pub async fn insert_link_token(
+    pool: &MySqlPool,
+    nonce: &str,
+    user_id: i32,
+    ttl_minutes: u32,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO telegram_link_tokens (nonce, user_id, expires_at)
+        SELECT ?, id, UTC_TIMESTAMP() + INTERVAL ? MINUTE
+        FROM users
+        WHERE id = ? AND is_deleted = 0
+        "#,
+        nonce,
+        ttl_minutes,
+        user_id
+    )
+    .execute(pool)
+    .await
+}
+
+/// Marks an unexpired token as used by `chat_id` and returns its user; a token works once.
+pub async fn consume_link_token(
+    pool: &MySqlPool,
+    nonce: &str,
+    chat_id: i64,
+) -> Result<Option<i32>, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE telegram_link_tokens
+        SET used_at = UTC_TIMESTAMP(), used_by_chat_id = ?
+        WHERE nonce = ? AND used_at IS NULL AND expires_at > UTC_TIMESTAMP()
+        "#,
+        chat_id,
+        nonce
+    )
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(None);
+    }
+    sqlx::query_scalar!(
+        r#"SELECT user_id FROM telegram_link_tokens WHERE nonce = ?"#,
+        nonce
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn link_telegram_account(
+    pool: &MySqlPool,
+    user_id: i32,
+    telegram_id: i64,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET telegram_id = ?, telegram_conf_code = NULL, telegram_conf_expires_at = NULL
+        WHERE id = ?
+        "#,
+        telegram_id,
+        user_id
+    )
+    .execute(pool)
+    .await
+}
@@ -5,6 +5,8 @@ pub struct TelegramLeadMessage {
     pub id: u64,
     pub chat_id: i64,
     pub message_id: i32,
+    /// HTML as sent; `None` for messages stored before the text was kept.
+    pub message_text: Option<String>,
 }
 
 pub async fn insert_telegram_lead_message(
@@ -13,16 +15,30 @@ pub async fn insert_telegram_lead_message(
     company_id: i32,
     chat_id: i64,
     message_id: i32,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    insert_telegram_lead_message_with_text(pool, customer_id, company_id, chat_id, message_id, None)
+        .await
+}
+
+/// Keeps the text as sent so the escalation lambda can re-edit the message later.
+pub async fn insert_telegram_lead_message_with_text(
+    pool: &MySqlPool,
+    customer_id: i32,
+    company_id: i32,
+    chat_id: i64,
+    message_id: i32,
+    message_text: Option<&str>,
 ) -> Result<MySqlQueryResult, sqlx::Error> {
     query!(
         r#"
-        INSERT INTO telegram_lead_messages (customer_id, company_id, chat_id, message_id)
-        VALUES (?, ?, ?, ?)
+        INSERT INTO telegram_lead_messages (customer_id, company_id, chat_id, message_id, message_text)
+        VALUES (?, ?, ?, ?, ?)
         "#,
         customer_id,
         company_id,
         chat_id,
-        message_id
+        message_id,
+        message_text
     )
     .execute(pool)
     .await
@@ -35,7 +51,7 @@ pub async fn list_active_telegram_lead_messages(
 ) -> Result<Vec<TelegramLeadMessage>, sqlx::Error> {
     let rows = sqlx::query!(
         r#"
-        SELECT id, chat_id, message_id
+        SELECT id, chat_id, message_id, message_text
         FROM telegram_lead_messages
         WHERE company_id = ?
           AND customer_id = ?
@@ -53,10 +69,12 @@ pub async fn list_active_telegram_lead_messages(
             id: row.id,
             chat_id: row.chat_id,
             message_id: row.message_id,
+            message_text: row.message_text,
         })
         .collect())
 }
 
+/// Lead messages in sales managers' private chats and in the company's lead groups.
 pub async fn list_active_manager_telegram_lead_messages(
     pool: &MySqlPool,
     company_id: i32,
@@ -64,17 +82,34 @@ pub async fn list_active_manager_telegram_lead_messages(
 ) -> Result<Vec<TelegramLeadMessage>, sqlx::Error> {
     let rows = sqlx::query!(
         r#"
-        SELECT DISTINCT tlm.id, tlm.chat_id, tlm.message_id
+        SELECT tlm.id, tlm.chat_id, tlm.message_id, tlm.message_text
         FROM telegram_lead_messages tlm
-        INNER JOIN users u
-            ON u.telegram_id = tlm.chat_id
-            AND u.company_id = tlm.company_id
-        INNER JOIN users_positions up
-            ON up.user_id = u.id
-            AND up.position_id = 2
         WHERE tlm.company_id = ?
           AND tlm.customer_id = ?
           AND tlm.deleted_at IS NULL
+          AND (
+            EXISTS (
+                SELECT 1
+                FROM users u
+                INNER JOIN users_positions up
+                    ON up.user_id = u.id
+                    AND up.position_id = 2
+                WHERE u.telegram_id = tlm.chat_id
+                  AND u.company_id = tlm.company_id
+            )
+            OR EXISTS (
+                SELECT 1
+                FROM telegram_lead_groups g
+                WHERE g.chat_id = tlm.chat_id
+                  AND g.company_id = tlm.company_id
+            )
+            OR EXISTS (
+                SELECT 1
+                FROM lead_notification_rules r
+                WHERE r.chat_id = tlm.chat_id
+                  AND r.company_id = tlm.company_id
+            )
+          )
         "#,
         company_id,
         customer_id
@@ -88,6 +123,7 @@ pub async fn list_active_manager_telegram_lead_messages(
             id: row.id,
             chat_id: row.chat_id,
             message_id: row.message_id,
+            message_text: row.message_text,
         })
         .collect())
 }
@@ -0,0 +1,79 @@
+use sqlx::MySqlPool;
+use sqlx::mysql::MySqlQueryResult;
+
+/// The conversation a notification message belongs to.
+#[derive(Debug)]
+pub struct ReplyTarget {
+    pub user_id: i32,
+    pub channel: String,
+    pub thread_id: Option<String>,
+    pub company_id: Option<i32>,
+    pub customer_phone: Option<u64>,
+    pub business_phone: Option<u64>,
+}
+
+pub async fn insert_email_reply_target(
+    pool: &MySqlPool,
+    chat_id: i64,
+    message_id: i32,
+    user_id: i32,
+    thread_id: &str,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO telegram_reply_targets (chat_id, message_id, user_id, channel, thread_id)
+        VALUES (?, ?, ?, 'email', ?)
+        "#,
+        chat_id,
+        message_id,
+        user_id,
+        thread_id
+    )
+    .execute(pool)
+    .await
+}
+
+pub async fn insert_sms_reply_target(
+    pool: &MySqlPool,
+    chat_id: i64,
+    message_id: i32,
+    user_id: i32,
+    company_id: i32,
+    customer_phone: u64,
+    business_phone: u64,
+) -> Result<MySqlQueryResult, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO telegram_reply_targets
+            (chat_id, message_id, user_id, channel, company_id, customer_phone, business_phone)
+        VALUES (?, ?, ?, 'sms', ?, ?, ?)
+        "#,
+        chat_id,
+        message_id,
+        user_id,
+        company_id,
+        customer_phone,
+        business_phone
+    )
+    .execute(pool)
+    .await
+}
+
+pub async fn get_reply_target(
+    pool: &MySqlPool,
+    chat_id: i64,
+    message_id: i32,
+) -> Result<Option<ReplyTarget>, sqlx::Error> {
+    sqlx::query_as!(
+        ReplyTarget,
+        r#"
+        SELECT user_id, channel, thread_id, company_id, customer_phone, business_phone
+        FROM telegram_reply_targets
+        WHERE chat_id = ? AND message_id = ?
+        "#,
+        chat_id,
+        message_id
+    )
+    .fetch_optional(pool)
+    .await
+}
@@ -0,0 +1,41 @@
+use sqlx::MySqlPool;
+
+/// Every address on file for the customer, so none of them keeps receiving drips.
+pub async fn get_customer_emails(
+    pool: &MySqlPool,
+    company_id: i32,
+    customer_id: i32,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT ce.email
+        FROM customers_emails ce
+        JOIN customers c ON c.id = ce.customer_id
+        WHERE c.id = ? AND c.company_id = ?
+        "#,
+        customer_id,
+        company_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn cancel_flow_enrollments_on_unsubscribe(
+    pool: &MySqlPool,
+    company_id: i32,
+    customer_id: i32,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"UPDATE sms_flow_enrollments
+           SET status = 'cancelled',
+               error_message = 'Customer unsubscribed',
+               updated_at = UTC_TIMESTAMP()
+           WHERE company_id = ? AND customer_id = ?
+             AND status IN ('active', 'paused')"#,
+        company_id,
+        customer_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
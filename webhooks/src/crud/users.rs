@@ -1,15 +1,6 @@
+pub use common::crud::user::{SalesUser, get_sales_users};
 use sqlx::MySqlPool;
 
-#[derive(Debug)]
-pub struct SalesUser {
-    pub id: i32,
-    pub telegram_id: Option<i64>,
-    pub name: Option<String>,
-    pub position_id: i32,
-    pub mtd_lead_count: i64,
-    pub user_position_id: i32,
-}
-
 pub struct UserTgInfo {
     pub telegram_id: Option<i64>,
     pub name: Option<String>,
@@ -23,49 +14,10 @@ pub struct UserNotificationsTgInfo {
     pub telegram_activity_notifications: bool,
 }
 
-pub async fn get_sales_users(
-    pool: &MySqlPool,
-    company_id: i32,
-) -> Result<Vec<SalesUser>, sqlx::Error> {
-    sqlx::query_as!(
-        SalesUser,
-        r#"
-        SELECT
-            u.id,
-            u.telegram_id,
-            u.name,
-            up.position_id,
-            up.id as user_position_id,
-            COUNT(c.id) as mtd_lead_count
-        FROM users u
-        INNER JOIN users_positions up ON u.id = up.user_id
-        LEFT JOIN customers c ON u.id = c.sales_rep
-            AND c.source = 'leads'
-            AND c.assigned_date >= DATE_FORMAT(NOW(), '%Y-%m-01')
-            AND c.company_id = u.company_id
-            AND c.deleted_at IS NULL
-        WHERE u.company_id = ?
-        AND (up.position_id = 1 OR up.position_id = 2)
-        GROUP BY u.id, u.telegram_id, u.name, up.position_id, user_position_id
-        "#,
-        company_id
-    )
-    .fetch_all(pool)
-    .await
-}
-
-pub async fn set_telegram_id(pool: &MySqlPool, telegram_id: i64) -> Result<(), sqlx::Error> {
-    sqlx::query!(
-        r#"
-        UPDATE users SET telegram_id = ? WHERE temp_telegram_id = ?
-        "#,
-        telegram_id,
-        telegram_id,
-    )
-    .execute(pool)
-    .await?;
-
-    Ok(())
+/// CRM user who linked the notifications bot.
+pub struct NotificationsUser {
+    pub id: i32,
+    pub company_id: Option<i32>,
 }
 
 pub async fn user_has_telegram_id(pool: &MySqlPool, telegram_id: i64) -> Result<bool, sqlx::Error> {
@@ -78,32 +30,24 @@ pub async fn user_has_telegram_id(pool: &MySqlPool, telegram_id: i64) -> Result<
     Ok(user.is_some())
 }
 
-pub async fn get_user_telegram_token(
-    pool: &MySqlPool,
-    telegram_id: i64,
-) -> Result<Option<i32>, sqlx::Error> {
-    let result = sqlx::query_scalar!(
-        r#"SELECT telegram_conf_code FROM users WHERE temp_telegram_id = ?"#,
-        telegram_id
-    )
-    .fetch_optional(pool)
-    .await;
-
-    match result {
-        Ok(token) => Ok(token.flatten()),
-        Err(e) => Err(e),
-    }
-}
-
+/// Stores a confirmation code for the account of `email` that expires after `ttl_minutes`.
 pub async fn set_user_telegram_token(
     pool: &MySqlPool,
     telegram_id: i64,
     token: i32,
     email: &str,
+    ttl_minutes: u32,
 ) -> Result<(), sqlx::Error> {
     sqlx::query!(
-        r#"UPDATE users SET telegram_conf_code = ?, temp_telegram_id = ? WHERE email = ?"#,
+        r#"
+        UPDATE users
+        SET telegram_conf_code = ?,
+            telegram_conf_expires_at = UTC_TIMESTAMP() + INTERVAL ? MINUTE,
+            temp_telegram_id = ?
+        WHERE email = ?
+        "#,
         token,
+        ttl_minutes,
         telegram_id,
         email
     )
@@ -125,6 +69,19 @@ pub async fn get_user_tg_info(
     .await
 }
 
+pub async fn get_user_cloudtalk_phone_number(
+    pool: &MySqlPool,
+    user_id: i32,
+) -> Result<Option<String>, sqlx::Error> {
+    let number = sqlx::query_scalar!(
+        r#"SELECT cloudtalk_phone_number FROM users WHERE id = ?"#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(number.flatten())
+}
+
 pub async fn get_user_notifications_tg_info(
     pool: &MySqlPool,
     user_id: i32,
@@ -146,6 +103,24 @@ pub async fn get_user_notifications_tg_info(
     .await
 }
 
+pub async fn get_notifications_user(
+    pool: &MySqlPool,
+    telegram_id: i64,
+) -> Result<Option<NotificationsUser>, sqlx::Error> {
+    sqlx::query_as!(
+        NotificationsUser,
+        r#"
+        SELECT id, company_id
+        FROM users
+        WHERE notifications_telegram_id = ? AND is_deleted = 0
+        LIMIT 1
+        "#,
+        telegram_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
 pub async fn get_user_id_by_cloudtalk_agent(
     pool: &MySqlPool,
     company_id: i32,
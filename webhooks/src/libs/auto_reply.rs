@@ -0,0 +1,333 @@
+use crate::cloudtalk::api::send_cloudtalk_sms;
+use crate::cloudtalk::schemas::SendSmsPayload;
+use crate::crud::auto_reply::{BusinessHours, get_auto_reply_policy, list_business_hours};
+use crate::crud::cloudtalk::{company_has_cloud_talk, insert_api_sent_sms};
+use crate::crud::users::get_user_cloudtalk_phone_number;
+use crate::schemas::add_customer::LeadPayload;
+use chrono::{Datelike, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use common::amazon::email::{assigned_sender_from, send_message_from};
+use common::crud::outbound_email::{OutboundEmail, record_outbound_email};
+use common::crud::suppressions::get_email_suppression_reason;
+use common::crud::template::{TemplateVariableData, fetch_template_variable_data};
+use common::utils::phone::PhoneNumber;
+use common::utils::template::replace_template_variables;
+use lambda_http::tracing;
+use reqwest::Client;
+use sqlx::MySqlPool;
+
+/// Who the reply goes out as and which conversation it is recorded in.
+struct AutoReply {
+    company_id: i32,
+    customer_id: i32,
+    user_id: i32,
+    deal_id: Option<i32>,
+}
+
+/// Whether `local` falls inside the opening hours of its weekday. Closing time is
+/// exclusive, so a lead at 17:00 sharp gets the after-hours reply.
+pub fn is_within_business_hours(hours: &[BusinessHours], local: NaiveDateTime) -> bool {
+    let weekday = local.weekday().num_days_from_monday();
+    let time = local.time();
+    hours.iter().any(|day| {
+        u32::try_from(day.weekday).is_ok_and(|day_weekday| day_weekday == weekday)
+            && day.opens_at <= time
+            && time < day.closes_at
+    })
+}
+
+fn local_now(timezone: &str) -> Option<NaiveDateTime> {
+    let timezone: Tz = timezone.parse().ok()?;
+    Some(Utc::now().with_timezone(&timezone).naive_local())
+}
+
+async fn send_sms_reply(pool: &MySqlPool, reply: &AutoReply, phone: &str, text: String) {
+    match company_has_cloud_talk(pool, reply.company_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::warn!(
+                company_id = reply.company_id,
+                "Skipping SMS auto-reply, CloudTalk is not configured"
+            );
+            return;
+        }
+        Err(e) => {
+            tracing::error!(
+                ?e,
+                company_id = reply.company_id,
+                "Failed to check CloudTalk"
+            );
+            return;
+        }
+    }
+    let Some(recipient) = PhoneNumber::parse(phone) else {
+        tracing::warn!(
+            customer_id = reply.customer_id,
+            "Skipping SMS auto-reply, lead phone is not a full number"
+        );
+        return;
+    };
+    let sender = match get_user_cloudtalk_phone_number(pool, reply.user_id).await {
+        Ok(number) => number.as_deref().and_then(PhoneNumber::parse),
+        Err(e) => {
+            tracing::error!(
+                ?e,
+                user_id = reply.user_id,
+                "Failed to get CloudTalk number"
+            );
+            return;
+        }
+    };
+    let Some(sender) = sender else {
+        tracing::warn!(
+            user_id = reply.user_id,
+            "Skipping SMS auto-reply, user has no CloudTalk number"
+        );
+        return;
+    };
+    let Ok(company_id) = u64::try_from(reply.company_id) else {
+        return;
+    };
+    let payload = SendSmsPayload {
+        sender: sender.e164(),
+        recipient: recipient.e164(),
+        message: text,
+    };
+    let error = send_cloudtalk_sms(pool, &Client::new(), company_id, &payload)
+        .await
+        .err()
+        .map(|e| e.to_string());
+    if let Some(error) = &error {
+        tracing::error!(
+            %error,
+            customer_id = reply.customer_id,
+            "Failed to send SMS auto-reply"
+        );
+    }
+    if let Err(e) = insert_api_sent_sms(
+        pool,
+        reply.company_id,
+        reply.user_id,
+        sender.match_key(),
+        recipient.match_key(),
+        &payload.message,
+        error.as_deref(),
+    )
+    .await
+    {
+        tracing::error!(
+            ?e,
+            customer_id = reply.customer_id,
+            "Failed to record SMS auto-reply"
+        );
+    }
+}
+
+async fn send_email_reply(
+    pool: &MySqlPool,
+    reply: &AutoReply,
+    data: &TemplateVariableData,
+    address: &str,
+    subject: &str,
+    template: &str,
+) {
+    match get_email_suppression_reason(pool, reply.company_id, address).await {
+        Ok(None) => {}
+        Ok(Some(reason)) => {
+            tracing::warn!(
+                customer_id = reply.customer_id,
+                %reason,
+                "Skipping email auto-reply, recipient is suppressed"
+            );
+            return;
+        }
+        Err(e) => {
+            tracing::error!(
+                ?e,
+                customer_id = reply.customer_id,
+                "Failed to check suppressions"
+            );
+            return;
+        }
+    }
+    let subject = replace_template_variables(subject, data);
+    let html_body = replace_template_variables(template, data);
+    let from = assigned_sender_from(
+        data.company
+            .as_ref()
+            .and_then(|company| company.domain.as_deref()),
+        data.user.email.as_deref(),
+        data.user.email_name.as_deref(),
+    );
+    let message_id = match send_message_from(&[address], &subject, &html_body, &from).await {
+        Ok(message_id) => message_id,
+        Err(e) => {
+            tracing::error!(
+                ?e,
+                customer_id = reply.customer_id,
+                "Failed to send email auto-reply"
+            );
+            return;
+        }
+    };
+    if let Err(e) = record_outbound_email(
+        pool,
+        &OutboundEmail {
+            user_id: reply.user_id,
+            customer_id: Some(reply.customer_id),
+            company_id: reply.company_id,
+            deal_id: reply.deal_id,
+            subject: &subject,
+            html_body: &html_body,
+            sender_from: &from,
+            recipient_email: address,
+            message_id: &message_id,
+            thread_id: None,
+        },
+    )
+    .await
+    {
+        tracing::error!(
+            ?e,
+            customer_id = reply.customer_id,
+            "Failed to record email auto-reply"
+        );
+    }
+}
+
+/// Sends the company's instant reply to a lead as `rep_id`, or as the policy's fallback
+/// user while the lead is unassigned. Failures are only logged; the lead is already saved.
+pub async fn send_lead_auto_reply<V: LeadPayload>(
+    pool: &MySqlPool,
+    company_id: i32,
+    customer_id: i32,
+    rep_id: Option<i32>,
+    deal_id: Option<u64>,
+    form: &V,
+) {
+    let policy = match get_auto_reply_policy(pool, company_id).await {
+        Ok(Some(policy)) => policy,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::error!(?e, company_id, "Failed to get auto-reply policy");
+            return;
+        }
+    };
+    let Some(user_id) = rep_id.or(policy.fallback_user_id) else {
+        tracing::warn!(company_id, "Skipping auto-reply, no rep or fallback user");
+        return;
+    };
+    let Some(now) = local_now(&policy.timezone) else {
+        tracing::error!(
+            company_id,
+            timezone = %policy.timezone,
+            "Skipping auto-reply, unknown timezone"
+        );
+        return;
+    };
+    let hours = match list_business_hours(pool, company_id).await {
+        Ok(hours) => hours,
+        Err(e) => {
+            tracing::error!(?e, company_id, "Failed to get business hours");
+            return;
+        }
+    };
+    let (sms, email) = if is_within_business_hours(&hours, now) {
+        (&policy.sms_in_hours, &policy.email_in_hours)
+    } else {
+        (&policy.sms_after_hours, &policy.email_after_hours)
+    };
+    let sms = sms.as_deref().zip(form.phone());
+    let email = email.as_deref().zip(form.email());
+    if sms.is_none() && email.is_none() {
+        return;
+    }
+    let reply = AutoReply {
+        company_id,
+        customer_id,
+        user_id,
+        deal_id: deal_id.and_then(|id| i32::try_from(id).ok()),
+    };
+    let data = match fetch_template_variable_data(
+        pool,
+        user_id,
+        reply.deal_id,
+        Some(customer_id),
+        company_id,
+    )
+    .await
+    {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::error!(?e, customer_id, "Failed to load auto-reply template data");
+            return;
+        }
+    };
+    if let Some((template, phone)) = sms {
+        send_sms_reply(
+            pool,
+            &reply,
+            phone,
+            replace_template_variables(template, &data),
+        )
+        .await;
+    }
+    if let Some((template, address)) = email {
+        send_email_reply(
+            pool,
+            &reply,
+            &data,
+            address,
+            &policy.email_subject,
+            template,
+        )
+        .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, NaiveTime};
+
+    fn weekdays_nine_to_five() -> Vec<BusinessHours> {
+        (0..5)
+            .map(|weekday| BusinessHours {
+                weekday,
+                opens_at: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                closes_at: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            })
+            .collect()
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // 2026-10-19 is a Monday
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_open_during_weekday_hours() {
+        let hours = weekdays_nine_to_five();
+        assert!(is_within_business_hours(&hours, at(19, 9, 0)));
+        assert!(is_within_business_hours(&hours, at(23, 16, 59)));
+    }
+
+    #[test]
+    fn test_closed_outside_hours_and_on_weekends() {
+        let hours = weekdays_nine_to_five();
+        assert!(!is_within_business_hours(&hours, at(19, 8, 59)));
+        assert!(!is_within_business_hours(&hours, at(19, 17, 0)));
+        assert!(!is_within_business_hours(&hours, at(19, 21, 0)));
+        assert!(!is_within_business_hours(&hours, at(24, 12, 0)));
+        assert!(!is_within_business_hours(&[], at(19, 12, 0)));
+    }
+
+    #[test]
+    fn test_unknown_timezone_is_rejected() {
+        assert!(local_now("America/Indiana/Indianapolis").is_some());
+        assert!(local_now("Indy").is_none());
+    }
+}
@@ -19,7 +19,6 @@ pub const fn not_acceptable(error: &'static str) -> (StatusCode, &'static str) {
     (StatusCode::NOT_ACCEPTABLE, error)
 }
 
-pub const SALES_WORKER: i32 = 1;
-pub const SALES_MANAGER: i32 = 2;
+pub use common::crud::user::{SALES_MANAGER, SALES_WORKER};
 
 pub const TELEGRAM_UPDATES_CHANNEL_URL: &str = "https://t.me/granite_manager";
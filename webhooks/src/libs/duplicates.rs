@@ -0,0 +1,394 @@
+use crate::crud::duplicates::{DuplicateCandidate, DuplicateLookup, find_duplicate_candidates};
+use crate::crud::leads::ExistingCustomer;
+use crate::schemas::add_customer::LeadPayload;
+use crate::telegram::utils::lead_url;
+use common::telegram::html::escape_html;
+use common::utils::phone::normalize_to_e164;
+use sqlx::MySqlPool;
+use std::fmt::Write as _;
+
+const PHONE_SCORE: u32 = 60;
+const EMAIL_SCORE: u32 = 60;
+const FULL_NAME_SCORE: u32 = 25;
+const LAST_NAME_SCORE: u32 = 10;
+const ADDRESS_SCORE: u32 = 25;
+const POSTAL_CODE_SCORE: u32 = 10;
+/// A phone or email match alone reuses the customer, as exact matching always did.
+pub const SAME_CUSTOMER_SCORE: u32 = 60;
+/// Same name and ZIP, or the same street address under another name, is probably the
+/// same household but needs a person to merge it.
+pub const POSSIBLE_DUPLICATE_SCORE: u32 = 35;
+const MAX_POSSIBLE_DUPLICATES: usize = 3;
+
+const DIRECTIONS: [&str; 8] = ["n", "s", "e", "w", "north", "south", "east", "west"];
+const STREET_SUFFIXES: [&str; 21] = [
+    "st", "street", "ave", "avenue", "rd", "road", "dr", "drive", "ln", "lane", "ct", "court",
+    "blvd", "way", "pl", "place", "cir", "circle", "pkwy", "parkway", "trl",
+];
+const UNIT_MARKERS: [&str; 4] = ["apt", "unit", "suite", "ste"];
+
+/// Lowercased and without a `+tag`; Gmail also ignores dots and answers to googlemail.com.
+/// Must stay in step with the SQL in `find_duplicate_candidates`.
+pub fn canonical_email(email: &str) -> Option<String> {
+    let email = email.trim().to_lowercase();
+    let (local, domain) = email.split_once('@')?;
+    let domain = domain.rsplit('@').next().unwrap_or(domain);
+    let local = local.split('+').next().unwrap_or(local);
+    let domain = if domain == "googlemail.com" {
+        "gmail.com"
+    } else {
+        domain
+    };
+    let local = if domain == "gmail.com" {
+        local.replace('.', "")
+    } else {
+        local.to_string()
+    };
+    (!local.is_empty() && !domain.is_empty()).then(|| format!("{local}@{domain}"))
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// US ZIPs only, so `46201-1234` and `46201` compare equal.
+fn zip5(postal_code: &str) -> Option<String> {
+    let zip: String = postal_code.trim().chars().take(5).collect();
+    (zip.len() == 5 && zip.chars().all(|c| c.is_ascii_digit())).then_some(zip)
+}
+
+/// House number and street name, e.g. `123 N. Main Street Apt 4` -> `123 main`.
+fn street_key(address: &str) -> Option<String> {
+    let before_unit = address.split('#').next().unwrap_or(address);
+    let tokens: Vec<String> = words(before_unit)
+        .into_iter()
+        .take_while(|word| !UNIT_MARKERS.contains(&word.as_str()))
+        .filter(|word| {
+            !DIRECTIONS.contains(&word.as_str()) && !STREET_SUFFIXES.contains(&word.as_str())
+        })
+        .collect();
+    let house_number = tokens.first()?;
+    if tokens.len() < 2 || !house_number.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(tokens.join(" "))
+}
+
+/// The normalized parts of an incoming lead that duplicates are scored on.
+pub struct LeadIdentity {
+    phone_e164: Option<String>,
+    phone: Option<String>,
+    email: Option<String>,
+    name: Vec<String>,
+    zip: Option<String>,
+    street: Option<String>,
+}
+
+impl LeadIdentity {
+    pub fn from_payload<V: LeadPayload>(form: &V) -> Self {
+        Self {
+            phone_e164: form.phone().and_then(normalize_to_e164),
+            phone: form.phone().map(str::to_string),
+            email: form.email().and_then(canonical_email),
+            name: words(form.name()),
+            zip: form.postal_code().and_then(zip5),
+            street: form.address().and_then(street_key),
+        }
+    }
+
+    const fn is_searchable(&self) -> bool {
+        self.phone.is_some() || self.email.is_some() || self.zip.is_some()
+    }
+
+    fn lookup(&self) -> DuplicateLookup<'_> {
+        DuplicateLookup {
+            phone_e164: self.phone_e164.as_deref(),
+            phone: self.phone.as_deref(),
+            canonical_email: self.email.as_deref(),
+            postal_code: self.zip.as_deref(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ScoredCandidate {
+    pub candidate: DuplicateCandidate,
+    pub score: u32,
+    pub reasons: Vec<&'static str>,
+}
+
+pub fn score_candidate(lead: &LeadIdentity, candidate: DuplicateCandidate) -> ScoredCandidate {
+    let mut score = 0;
+    let mut reasons = Vec::new();
+
+    let candidate_phones = [
+        candidate
+            .phone_e164
+            .clone()
+            .or_else(|| candidate.phone.as_deref().and_then(normalize_to_e164)),
+        candidate
+            .phone_2_e164
+            .clone()
+            .or_else(|| candidate.phone_2.as_deref().and_then(normalize_to_e164)),
+    ];
+    if let Some(phone) = &lead.phone_e164
+        && candidate_phones.iter().flatten().any(|p| p == phone)
+    {
+        score += PHONE_SCORE;
+        reasons.push("phone");
+    }
+
+    if let Some(email) = &lead.email
+        && candidate
+            .emails
+            .as_deref()
+            .unwrap_or_default()
+            .lines()
+            .filter_map(canonical_email)
+            .any(|e| &e == email)
+    {
+        score += EMAIL_SCORE;
+        reasons.push("email");
+    }
+
+    let candidate_name = candidate.name.as_deref().map(words).unwrap_or_default();
+    let mut lead_name = lead.name.clone();
+    let mut sorted_candidate_name = candidate_name.clone();
+    lead_name.sort();
+    sorted_candidate_name.sort();
+    if !lead_name.is_empty() && lead_name == sorted_candidate_name {
+        score += FULL_NAME_SCORE;
+        reasons.push("name");
+    } else if lead.name.len() > 1
+        && candidate_name.len() > 1
+        && lead.name.last() == candidate_name.last()
+    {
+        score += LAST_NAME_SCORE;
+        reasons.push("last name");
+    }
+
+    if let Some(street) = &lead.street
+        && candidate.address.as_deref().and_then(street_key).as_ref() == Some(street)
+    {
+        score += ADDRESS_SCORE;
+        reasons.push("address");
+    }
+
+    if let Some(zip) = &lead.zip
+        && candidate.postal_code.as_deref().and_then(zip5).as_ref() == Some(zip)
+    {
+        score += POSTAL_CODE_SCORE;
+        reasons.push("ZIP");
+    }
+
+    ScoredCandidate {
+        candidate,
+        score: score.min(100),
+        reasons,
+    }
+}
+
+pub enum DuplicateMatch {
+    /// Confident enough to treat the lead as a repeat of this customer.
+    Existing(ExistingCustomer),
+    /// Create the lead, but flag these for a manager to merge.
+    Possible(Vec<ScoredCandidate>),
+    None,
+}
+
+pub fn classify(lead: &LeadIdentity, candidates: Vec<DuplicateCandidate>) -> DuplicateMatch {
+    let mut scored: Vec<ScoredCandidate> = candidates
+        .into_iter()
+        .map(|candidate| score_candidate(lead, candidate))
+        .filter(|scored| scored.score >= POSSIBLE_DUPLICATE_SCORE)
+        .collect();
+    scored.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(b.candidate.id.cmp(&a.candidate.id))
+    });
+    match scored.first() {
+        Some(best) if best.score >= SAME_CUSTOMER_SCORE => {
+            DuplicateMatch::Existing(ExistingCustomer {
+                id: best.candidate.id,
+                name: best.candidate.name.clone(),
+                sales_rep: best.candidate.sales_rep,
+            })
+        }
+        Some(_) => {
+            scored.truncate(MAX_POSSIBLE_DUPLICATES);
+            DuplicateMatch::Possible(scored)
+        }
+        None => DuplicateMatch::None,
+    }
+}
+
+pub async fn match_existing_customer<V: LeadPayload>(
+    pool: &MySqlPool,
+    company_id: i32,
+    form: &V,
+) -> Result<DuplicateMatch, sqlx::Error> {
+    let lead = LeadIdentity::from_payload(form);
+    if !lead.is_searchable() {
+        return Ok(DuplicateMatch::None);
+    }
+    let candidates = find_duplicate_candidates(pool, company_id, &lead.lookup()).await?;
+    Ok(classify(&lead, candidates))
+}
+
+/// Appended to the manager message so both records are one tap away; the note is HTML.
+pub fn possible_duplicate_note(customer_id: i32, duplicates: &[ScoredCandidate]) -> String {
+    let mut note = format!("\n⚠️ Possible duplicate: this lead (customer #{customer_id}) matches");
+    for duplicate in duplicates {
+        let candidate = &duplicate.candidate;
+        write!(
+            note,
+            "\n- customer #{} {} ({})",
+            candidate.id,
+            escape_html(candidate.name.as_deref().unwrap_or("Unknown")),
+            duplicate.reasons.join(", ")
+        )
+        .unwrap();
+        if let Some(deal_id) = candidate.latest_deal_id {
+            write!(note, ": {}", escape_html(&lead_url(deal_id))).unwrap();
+        }
+    }
+    note.push_str("\nMerge them in the CRM if they are the same household.");
+    note
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crud::leads::create_lead_from_new_lead_form;
+    use crate::schemas::add_customer::NewLeadForm;
+    use serde_json::json;
+
+    fn lead(value: serde_json::Value) -> LeadIdentity {
+        let form: NewLeadForm = serde_json::from_value(value).unwrap();
+        LeadIdentity::from_payload(&form)
+    }
+
+    fn candidate(id: i32) -> DuplicateCandidate {
+        DuplicateCandidate {
+            id,
+            name: Some("Jane Doe".to_string()),
+            sales_rep: Some(7),
+            phone: Some("(317) 555-0101".to_string()),
+            phone_2: None,
+            phone_e164: None,
+            phone_2_e164: Some("+13175550199".to_string()),
+            emails: Some("old@example.com\nJ.Doe+quote@GoogleMail.com".to_string()),
+            postal_code: Some("46201-1234".to_string()),
+            address: Some("123 North Main Street Apt 4".to_string()),
+            latest_deal_id: Some(90),
+        }
+    }
+
+    #[test]
+    fn canonical_email_collapses_aliases() {
+        assert_eq!(
+            canonical_email(" J.Doe+quote@GoogleMail.com ").as_deref(),
+            Some("jdoe@gmail.com")
+        );
+        assert_eq!(
+            canonical_email("j.doe+crm@example.com").as_deref(),
+            Some("j.doe@example.com")
+        );
+        assert_eq!(canonical_email("not-an-email"), None);
+    }
+
+    #[test]
+    fn street_key_ignores_suffix_direction_and_unit() {
+        assert_eq!(
+            street_key("123 N. Main Street Apt 4").as_deref(),
+            Some("123 main")
+        );
+        assert_eq!(street_key("123 Main St #4").as_deref(), Some("123 main"));
+        assert_eq!(street_key("Main St"), None);
+    }
+
+    #[test]
+    fn phone_on_either_column_or_email_alias_is_the_same_customer() {
+        for identity in [
+            lead(json!({ "name": "Someone", "phone": "+1 317 555 0199" })),
+            lead(json!({ "name": "Someone", "phone": "317.555.0101" })),
+            lead(json!({ "name": "Someone", "email": "jdoe@gmail.com" })),
+        ] {
+            match classify(&identity, vec![candidate(1)]) {
+                DuplicateMatch::Existing(existing) => {
+                    assert_eq!(existing.id, 1);
+                    assert_eq!(existing.sales_rep, Some(7));
+                }
+                _ => panic!("expected an existing customer"),
+            }
+        }
+    }
+
+    #[test]
+    fn same_household_without_contact_match_is_only_possible() {
+        let spouse = lead(json!({
+            "name": "John Doe",
+            "phone": "317-555-0300",
+            "postal_code": "46201",
+            "address": "123 Main St"
+        }));
+        let DuplicateMatch::Possible(found) = classify(&spouse, vec![candidate(1)]) else {
+            panic!("expected a possible duplicate");
+        };
+        assert_eq!(found[0].score, 45);
+        assert_eq!(found[0].reasons, vec!["last name", "address", "ZIP"]);
+
+        let note = possible_duplicate_note(2, &found);
+        assert!(note.contains("customer #2"));
+        assert!(note.contains("customer #1 Jane Doe (last name, address, ZIP)"));
+        assert!(note.contains(&lead_url(90)));
+    }
+
+    #[test]
+    fn name_and_zip_alone_is_possible_but_neighbours_are_not() {
+        let same_name = lead(json!({ "name": "doe jane", "postal_code": "46201" }));
+        assert!(matches!(
+            classify(&same_name, vec![candidate(1)]),
+            DuplicateMatch::Possible(_)
+        ));
+
+        let neighbour = lead(json!({ "name": "Sam Roe", "postal_code": "46201" }));
+        assert!(matches!(
+            classify(&neighbour, vec![candidate(1)]),
+            DuplicateMatch::None
+        ));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn matches_stored_lead_by_any_phone_format(pool: MySqlPool) {
+        let stored: NewLeadForm = serde_json::from_value(json!({
+            "name": "Nia Patel",
+            "phone": "317-555-0100 ext. 4"
+        }))
+        .unwrap();
+        let created = create_lead_from_new_lead_form(&pool, &stored, 1)
+            .await
+            .unwrap();
+        let customer_id = i32::try_from(created.last_insert_id()).unwrap();
+
+        for phone in ["+1 (317) 555-0100", "3175550100"] {
+            let form: NewLeadForm =
+                serde_json::from_value(json!({ "name": "N. Patel", "phone": phone })).unwrap();
+            match match_existing_customer(&pool, 1, &form).await.unwrap() {
+                DuplicateMatch::Existing(existing) => assert_eq!(existing.id, customer_id),
+                _ => panic!("expected {phone} to match"),
+            }
+        }
+        let other: NewLeadForm =
+            serde_json::from_value(json!({ "name": "Someone", "phone": "317-555-0101" })).unwrap();
+        assert!(matches!(
+            match_existing_customer(&pool, 1, &other).await.unwrap(),
+            DuplicateMatch::None
+        ));
+    }
+}
@@ -0,0 +1,157 @@
+use axum::http::HeaderMap;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+
+pub const IDEMPOTENCY_HEADER: &str = "idempotency-key";
+const MAX_HEADER_KEY_LEN: usize = 200;
+/// Width of `lead_submissions.idempotency_key`.
+const MAX_STORED_KEY_LEN: usize = 255;
+/// Senders that pass a key retry for up to a day (Zapier, Make).
+const HEADER_KEY_TTL_MINUTES: i32 = 24 * 60;
+/// Without a key only identical payloads this close together count as a double submit.
+const PAYLOAD_HASH_TTL_MINUTES: i32 = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmissionKey {
+    pub value: String,
+    pub ttl_minutes: i32,
+}
+
+/// Strings are trimmed and lowercased so `Jack@Gmail.com ` and `jack@gmail.com` match.
+fn normalize(value: Value) -> Value {
+    match value {
+        Value::String(text) => Value::String(text.trim().to_lowercase()),
+        Value::Array(items) => Value::Array(items.into_iter().map(normalize).collect()),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key, normalize(value)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn payload_hash<T: Serialize>(route: &str, payload: &T) -> String {
+    let normalized = serde_json::to_value(payload).map_or(Value::Null, normalize);
+    let mut hasher = Sha256::new();
+    hasher.update(route.as_bytes());
+    hasher.update(b":");
+    hasher.update(normalized.to_string().as_bytes());
+    hex_digest(hasher)
+}
+
+fn hex_digest(hasher: Sha256) -> String {
+    hasher
+        .finalize()
+        .iter()
+        .fold(String::new(), |mut output, b| {
+            let _ = write!(output, "{b:02x}");
+            output
+        })
+}
+
+/// The sender's `Idempotency-Key` when present, otherwise a hash of the route and the
+/// normalized payload. Keys are scoped per company by the caller.
+pub fn submission_key<T: Serialize>(
+    headers: &HeaderMap,
+    route: &str,
+    payload: &T,
+) -> SubmissionKey {
+    let header_key = headers
+        .get(IDEMPOTENCY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty() && value.len() <= MAX_HEADER_KEY_LEN);
+    match header_key {
+        Some(key) => external_key(route, key),
+        None => SubmissionKey {
+            value: format!("hash:{}", payload_hash(route, payload)),
+            ttl_minutes: PAYLOAD_HASH_TTL_MINUTES,
+        },
+    }
+}
+
+/// Key for a submission the sender already identifies, kept as long as a header key.
+/// Keys too long for the column are stored as a hash of the route and id instead.
+pub fn external_key(route: &str, id: &str) -> SubmissionKey {
+    let mut value = format!("key:{route}:{id}");
+    if value.len() > MAX_STORED_KEY_LEN {
+        let mut hasher = Sha256::new();
+        hasher.update(route.as_bytes());
+        hasher.update(b":");
+        hasher.update(id.as_bytes());
+        value = format!("keyhash:{}", hex_digest(hasher));
+    }
+    SubmissionKey {
+        value,
+        ttl_minutes: HEADER_KEY_TTL_MINUTES,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use serde_json::json;
+
+    #[test]
+    fn test_header_key_wins_over_payload() {
+        let mut headers = HeaderMap::new();
+        headers.insert(IDEMPOTENCY_HEADER, HeaderValue::from_static(" zap-123 "));
+        let first = submission_key(&headers, "new-lead-form", &json!({ "name": "Jack" }));
+        let second = submission_key(&headers, "new-lead-form", &json!({ "name": "Jill" }));
+        assert_eq!(first, second);
+        assert_eq!(first.value, "key:new-lead-form:zap-123");
+        assert_eq!(first.ttl_minutes, HEADER_KEY_TTL_MINUTES);
+    }
+
+    #[test]
+    fn test_payload_hash_ignores_case_whitespace_and_nulls() {
+        let headers = HeaderMap::new();
+        let first = submission_key(
+            &headers,
+            "wordpress",
+            &json!({ "name": "Jack", "Email": "Jack@Gmail.com ", "Zip": null }),
+        );
+        let second = submission_key(
+            &headers,
+            "wordpress",
+            &json!({ "name": "jack", "Email": "jack@gmail.com" }),
+        );
+        assert_eq!(first, second);
+        assert!(first.value.starts_with("hash:"));
+        assert_eq!(first.ttl_minutes, PAYLOAD_HASH_TTL_MINUTES);
+    }
+
+    #[test]
+    fn test_payload_hash_is_route_and_content_specific() {
+        let headers = HeaderMap::new();
+        let lead = json!({ "name": "Jack" });
+        let wordpress = submission_key(&headers, "wordpress", &lead);
+        assert_ne!(wordpress, submission_key(&headers, "facebook", &lead));
+        assert_ne!(
+            wordpress,
+            submission_key(&headers, "wordpress", &json!({ "name": "Jill" }))
+        );
+    }
+
+    #[test]
+    fn test_longest_form_and_header_keys_fit_the_column() {
+        let route = format!("forms/{}", "f".repeat(100));
+        let header = "k".repeat(MAX_HEADER_KEY_LEN);
+        let mut headers = HeaderMap::new();
+        headers.insert(IDEMPOTENCY_HEADER, HeaderValue::from_str(&header).unwrap());
+
+        let key = submission_key(&headers, &route, &json!({ "name": "Jack" }));
+        assert!(key.value.len() <= MAX_STORED_KEY_LEN);
+        assert_eq!(
+            key,
+            submission_key(&headers, &route, &json!({ "name": "Jill" }))
+        );
+        assert_ne!(key, external_key(&route, &"j".repeat(MAX_HEADER_KEY_LEN)));
+        assert_eq!(key.ttl_minutes, HEADER_KEY_TTL_MINUTES);
+    }
+}
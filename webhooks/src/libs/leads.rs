@@ -1,20 +1,35 @@
 use crate::axum_helpers::guards::Telegram;
 use crate::cloudtalk::api::sync_customer_to_cloud_talk;
+use crate::crud::attribution::{insert_first_touch, upsert_last_touch};
+use crate::crud::duplicates::flag_possible_duplicate;
 use crate::crud::leads::{
-    Deal, ExistingCustomer, create_deal_from_lead, find_existing_customer,
+    Deal, ExistingCustomer, assign_lead, create_deal_from_lead, create_deal_in,
     get_default_list_id_from_company_id, get_existing_deal, update_deal_list_id,
 };
-use crate::crud::users::get_user_tg_info;
+use crate::crud::users::{SalesUser, get_sales_users, get_user_tg_info};
+use crate::libs::auto_reply::send_lead_auto_reply;
 use crate::libs::constants::{CREATED_RESPONSE, ERR_DB, internal_error};
+use crate::libs::duplicates::{
+    DuplicateMatch, ScoredCandidate, match_existing_customer, possible_duplicate_note,
+};
+use crate::libs::spam::{quarantine_lead, screen_lead};
 use crate::libs::types::BasicResponse;
-use crate::schemas::add_customer::LeadPayload;
+use crate::schemas::add_customer::{LeadPayload, StoredLead};
 use crate::telegram::send::{
-    persist_lead_message, send_plain_message_to_chat, send_telegram_duplicate_notification,
-    send_telegram_manager_assign,
+    persist_lead_message, send_plain_message_to_chat, send_telegram_auto_assign_notification,
+    send_telegram_duplicate_notification, send_telegram_manager_assign,
 };
 use crate::telegram::utils::lead_url;
 use common::amazon::email::send_message;
-use common::crud::scheduled_emails::reschedule_templates_for_deal_list;
+use common::crud::assignment::{
+    get_assignment_policy, get_customer_postal_code, list_assignment_territories,
+    list_rep_assignment_settings, lock_assignment_policy, set_last_assigned_user,
+};
+use common::crud::scheduled_emails::{
+    reschedule_templates_for_deal_list, schedule_templates_for_deal_list,
+};
+use common::telegram::html::escape_html;
+use common::utils::assignment::{AssignmentMode, pick_rep, rep_candidates};
 use lambda_http::tracing;
 use reqwest::Client;
 use sqlx::MySqlPool;
@@ -22,6 +37,183 @@ use sqlx::MySqlPool;
 const REGISTER_SUBJECT: &str = "Granite Manager";
 const REGISTER_MESSAGE: &str = "Connect Telegram in the CRM to receive lead notifications: https://granite-manager.com/link-telegram-both";
 
+struct AutoAssigned {
+    user_id: i32,
+    deal_id: u64,
+}
+
+/// Applies the company's assignment policy to a freshly inserted lead. `None` means the
+/// company assigns manually or no rep qualified, so managers pick in Telegram as before.
+async fn auto_assign_new_lead(
+    pool: &MySqlPool,
+    company_id: i32,
+    customer_id: i32,
+    users: &[SalesUser],
+) -> Result<Option<AutoAssigned>, sqlx::Error> {
+    let Some(policy) = get_assignment_policy(pool, company_id).await? else {
+        return Ok(None);
+    };
+    if policy.mode == AssignmentMode::Manual {
+        return Ok(None);
+    }
+    let settings = list_rep_assignment_settings(pool, company_id).await?;
+    let candidates = rep_candidates(users, &settings);
+    let (territories, postal_code) = if policy.mode == AssignmentMode::Territory {
+        (
+            list_assignment_territories(pool, company_id).await?,
+            get_customer_postal_code(pool, customer_id).await?,
+        )
+    } else {
+        (Vec::new(), None)
+    };
+    let list_id = get_default_list_id_from_company_id(pool, company_id).await?;
+
+    let mut tx = pool.begin().await?;
+    // Picked under the policy lock, so leads arriving together keep the rotation.
+    let Some(policy) = lock_assignment_policy(&mut tx, company_id).await? else {
+        return Ok(None);
+    };
+    let Some(user_id) = pick_rep(
+        policy.mode,
+        &candidates,
+        policy.last_assigned_user_id,
+        &territories,
+        postal_code.as_deref(),
+    ) else {
+        tracing::info!(
+            company_id = company_id,
+            customer_id = customer_id,
+            "No rep qualifies for auto-assignment, falling back to manual"
+        );
+        return Ok(None);
+    };
+    assign_lead(&mut *tx, customer_id, user_id).await?;
+    let deal = create_deal_in(&mut tx, customer_id, list_id, 0, user_id).await?;
+    set_last_assigned_user(&mut *tx, company_id, user_id).await?;
+    tx.commit().await?;
+    if deal.created {
+        if let Err(e) = schedule_templates_for_deal_list(
+            pool,
+            list_id,
+            company_id,
+            deal.id,
+            customer_id,
+            user_id,
+        )
+        .await
+        {
+            tracing::error!(
+                ?e,
+                deal_id = deal.id,
+                list_id = list_id,
+                "Failed to schedule list drip emails for auto-assigned lead"
+            );
+        }
+    }
+    Ok(Some(AutoAssigned {
+        user_id,
+        deal_id: deal.id,
+    }))
+}
+
+async fn notify_auto_assigned_rep<T>(pool: &MySqlPool, assigned: &AutoAssigned, bot: &T)
+where
+    T: Telegram + Send + Sync + 'static + Clone,
+{
+    let user_info = match get_user_tg_info(pool, assigned.user_id).await {
+        Ok(Some(info)) => info,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::error!(?e, user_id = assigned.user_id, "Failed to get user info");
+            return;
+        }
+    };
+    let lead_link = lead_url(assigned.deal_id);
+    if let Some(telegram_id) = user_info.telegram_id {
+        let message = format!("You were assigned a lead. Click here: \n{lead_link}");
+        if let Err(e) = send_plain_message_to_chat(telegram_id, &message, bot).await {
+            tracing::error!(?e, user_id = assigned.user_id, "Employee notify failed");
+        }
+        return;
+    }
+    let message =
+        format!("You were assigned a lead. Click here: {lead_link}\n\n{REGISTER_MESSAGE}");
+    if let Err(e) = send_message(&[&user_info.email], "Lead assigned", &message).await {
+        tracing::error!(?e, email = user_info.email, "Error sending email");
+    }
+}
+
+/// A new customer gets both touches; a repeat lead only moves last-touch. Submissions
+/// without any attribution keep what was stored before.
+async fn record_attribution<V: LeadPayload>(
+    pool: &MySqlPool,
+    customer_id: i32,
+    form: &V,
+    is_new_customer: bool,
+) {
+    let attribution = form.attribution();
+    if attribution.is_empty() {
+        return;
+    }
+    if is_new_customer && let Err(e) = insert_first_touch(pool, customer_id, attribution).await {
+        tracing::error!(?e, customer_id, "Failed to save first-touch attribution");
+    }
+    if let Err(e) = upsert_last_touch(pool, customer_id, attribution).await {
+        tracing::error!(?e, customer_id, "Failed to save last-touch attribution");
+    }
+}
+
+/// Returns the assignment when the lead was assigned by policy and the rep and managers
+/// were told.
+async fn try_auto_assign<T>(
+    pool: &MySqlPool,
+    company_id: i32,
+    customer_id: i32,
+    lead_text: String,
+    bot: &T,
+) -> Option<AutoAssigned>
+where
+    T: Telegram + Send + Sync + 'static + Clone,
+{
+    let users = match get_sales_users(pool, company_id).await {
+        Ok(users) => users,
+        Err(e) => {
+            tracing::error!(?e, company_id = company_id, "Error fetching users");
+            return None;
+        }
+    };
+    let assigned = match auto_assign_new_lead(pool, company_id, customer_id, &users).await {
+        Ok(Some(assigned)) => assigned,
+        Ok(None) => return None,
+        Err(e) => {
+            tracing::error!(
+                ?e,
+                company_id = company_id,
+                customer_id = customer_id,
+                "Failed to auto-assign lead"
+            );
+            return None;
+        }
+    };
+    let assigned_name = users
+        .iter()
+        .find(|u| u.id == assigned.user_id)
+        .and_then(|u| u.name.clone())
+        .unwrap_or_else(|| "Unknown".to_string());
+    notify_auto_assigned_rep(pool, &assigned, bot).await;
+    send_telegram_auto_assign_notification(
+        pool,
+        company_id,
+        customer_id,
+        &users,
+        &assigned_name,
+        lead_text,
+        bot,
+    )
+    .await;
+    Some(assigned)
+}
+
 async fn handle_repeat_lead<T, V: LeadPayload>(
     existing: &ExistingCustomer,
     deal: Deal,
@@ -41,6 +233,7 @@ where
             "Failed to update lead"
         );
     }
+    record_attribution(pool, existing.id, form, false).await;
     let default_list_id = match get_default_list_id_from_company_id(pool, company_id).await {
         Ok(id) => id,
         Err(e) => {
@@ -76,6 +269,15 @@ where
             );
         }
     }
+    send_lead_auto_reply(
+        pool,
+        company_id,
+        existing.id,
+        deal.user_id,
+        Some(deal.id),
+        form,
+    )
+    .await;
     let customer_id = u64::try_from(existing.id).unwrap();
     let user_info = match get_user_tg_info(pool, deal.user_id.unwrap()).await {
         Ok(Some(info)) => info,
@@ -83,8 +285,8 @@ where
             let name = existing.name.as_deref();
             let message = format!(
                 "You received a REPEATED lead {}, click here: {}",
-                name.unwrap_or("Unknown"),
-                lead_url(deal.id)
+                escape_html(name.unwrap_or("Unknown")),
+                escape_html(&lead_url(deal.id))
             );
             match send_telegram_manager_assign(pool, company_id, message, customer_id, false, bot)
                 .await
@@ -131,7 +333,8 @@ where
     let tg_result = send_plain_message_to_chat(clean_tg_id, &repeted_lead_message, bot).await;
     match tg_result {
         Ok(message) => {
-            persist_lead_message(pool, existing.id, company_id, &message).await;
+            let text = escape_html(&repeted_lead_message);
+            persist_lead_message(pool, existing.id, company_id, &message, &text).await;
         }
         Err(request_error) => {
             tracing::error!(
@@ -194,6 +397,7 @@ where
             "Failed to update lead"
         );
     }
+    record_attribution(pool, existing.id, form, false).await;
     let clean_id = u64::try_from(existing.id).unwrap();
     let message = format!(
         "You received a REPEATED lead with no sales rep \n{form}",
@@ -212,10 +416,39 @@ where
     }
 }
 
+/// Flags each possible duplicate against the new customer and returns the note for the
+/// manager message.
+async fn flag_possible_duplicates(
+    pool: &MySqlPool,
+    customer_id: i32,
+    duplicates: &[ScoredCandidate],
+) -> String {
+    for duplicate in duplicates {
+        if let Err(e) = flag_possible_duplicate(
+            pool,
+            customer_id,
+            duplicate.candidate.id,
+            duplicate.score,
+            &duplicate.reasons.join(", "),
+        )
+        .await
+        {
+            tracing::error!(
+                ?e,
+                customer_id,
+                duplicate_of = duplicate.candidate.id,
+                "Failed to flag possible duplicate"
+            );
+        }
+    }
+    possible_duplicate_note(customer_id, duplicates)
+}
+
 async fn new_lead<T, V: LeadPayload>(
     pool: &MySqlPool,
     company_id: i32,
     form: &V,
+    possible_duplicates: &[ScoredCandidate],
     bot: &T,
 ) -> BasicResponse
 where
@@ -228,30 +461,85 @@ where
             return internal_error("Error creating lead from New Lead Form");
         }
     };
-    let tg_result = send_telegram_manager_assign(
-        pool,
-        company_id,
-        &form.to_string(),
-        result.last_insert_id(),
-        true,
-        bot,
-    )
-    .await;
-    if tg_result.is_err() {
-        tracing::error!(
-            ?tg_result,
-            company_id = company_id,
-            "Error sending message to Telegram"
-        );
-    }
     let customer_id = i32::try_from(result.last_insert_id()).unwrap_or(0);
+    let mut lead_text = form.to_string();
+    if customer_id > 0 {
+        record_attribution(pool, customer_id, form, true).await;
+        if !possible_duplicates.is_empty() {
+            lead_text
+                .push_str(&flag_possible_duplicates(pool, customer_id, possible_duplicates).await);
+        }
+    }
+    let auto_assigned = if customer_id > 0 {
+        try_auto_assign(pool, company_id, customer_id, lead_text.clone(), bot).await
+    } else {
+        None
+    };
+    if auto_assigned.is_none() {
+        let tg_result = send_telegram_manager_assign(
+            pool,
+            company_id,
+            &lead_text,
+            result.last_insert_id(),
+            true,
+            bot,
+        )
+        .await;
+        if tg_result.is_err() {
+            tracing::error!(
+                ?tg_result,
+                company_id = company_id,
+                "Error sending message to Telegram"
+            );
+        }
+    }
     if customer_id > 0 {
+        send_lead_auto_reply(
+            pool,
+            company_id,
+            customer_id,
+            auto_assigned.as_ref().map(|assigned| assigned.user_id),
+            auto_assigned.as_ref().map(|assigned| assigned.deal_id),
+            form,
+        )
+        .await;
         let client = Client::new();
         let _ = sync_customer_to_cloud_talk(pool, &client, customer_id).await;
     }
     CREATED_RESPONSE
 }
 
+/// Leads that would create a customer are screened for spam first, unless a manager
+/// already approved them from the quarantine.
+async fn screen_new_lead<T, V: LeadPayload>(
+    pool: &MySqlPool,
+    company_id: i32,
+    form: &V,
+    possible_duplicates: &[ScoredCandidate],
+    screen_spam: bool,
+    bot: &T,
+) -> BasicResponse
+where
+    T: Telegram + Send + Sync + 'static + Clone,
+{
+    if screen_spam {
+        match screen_lead(pool, company_id, form).await {
+            Ok(Some(verdict)) => {
+                return quarantine_lead(pool, company_id, form, &verdict, bot).await;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                tracing::error!(
+                    ?e,
+                    company_id = company_id,
+                    "Failed to screen lead for spam"
+                );
+            }
+        }
+    }
+    new_lead(pool, company_id, form, possible_duplicates, bot).await
+}
+
 pub async fn process_lead<T, V: LeadPayload>(
     pool: &MySqlPool,
     company_id: i32,
@@ -261,10 +549,45 @@ pub async fn process_lead<T, V: LeadPayload>(
 where
     T: Telegram + Send + Sync + 'static + Clone,
 {
-    let existing = match find_existing_customer(pool, form.email(), form.phone(), company_id).await
-    {
-        Ok(Some(v)) => v,
-        Ok(None) => return new_lead(pool, company_id, form, bot).await,
+    route_lead(pool, company_id, form, true, bot).await
+}
+
+/// Creates a lead a manager approved from the spam quarantine.
+pub async fn process_approved_lead<T>(
+    pool: &MySqlPool,
+    company_id: i32,
+    lead: &StoredLead,
+    bot: &T,
+) -> BasicResponse
+where
+    T: Telegram + Send + Sync + 'static + Clone,
+{
+    match lead {
+        StoredLead::Wordpress(form) => route_lead(pool, company_id, form, false, bot).await,
+        StoredLead::Facebook(form) => route_lead(pool, company_id, form, false, bot).await,
+        StoredLead::NewLead(form) => route_lead(pool, company_id, form, false, bot).await,
+        StoredLead::Form(form) => route_lead(pool, company_id, form, false, bot).await,
+    }
+}
+
+async fn route_lead<T, V: LeadPayload>(
+    pool: &MySqlPool,
+    company_id: i32,
+    form: &V,
+    screen_spam: bool,
+    bot: &T,
+) -> BasicResponse
+where
+    T: Telegram + Send + Sync + 'static + Clone,
+{
+    let existing = match match_existing_customer(pool, company_id, form).await {
+        Ok(DuplicateMatch::Existing(v)) => v,
+        Ok(DuplicateMatch::Possible(duplicates)) => {
+            return screen_new_lead(pool, company_id, form, &duplicates, screen_spam, bot).await;
+        }
+        Ok(DuplicateMatch::None) => {
+            return screen_new_lead(pool, company_id, form, &[], screen_spam, bot).await;
+        }
         Err(e) => {
             tracing::error!(?e, company_id = company_id, "Failed to check existing lead");
             return internal_error(ERR_DB);
@@ -294,3 +617,223 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crud::leads::create_deal;
+    use crate::libs::constants::{SALES_MANAGER, SALES_WORKER};
+    use crate::schemas::add_customer::NewLeadForm;
+    use crate::tests::telegram::MockTelegram;
+    use crate::tests::utils::positioned_user;
+    use serde_json::json;
+
+    async fn set_policy(pool: &MySqlPool, mode: &str) {
+        sqlx::query!(
+            "INSERT INTO lead_assignment_policies (company_id, mode) VALUES (1, ?)",
+            mode
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn submit_lead(pool: &MySqlPool, bot: &MockTelegram, postal_code: &str) -> i32 {
+        let lead: NewLeadForm = serde_json::from_value(json!({
+            "name": "Auto Lead",
+            "phone": "+13175550101",
+            "postal_code": postal_code
+        }))
+        .unwrap();
+        let response = process_lead(pool, 1, &lead, bot).await;
+        assert_eq!(response, CREATED_RESPONSE);
+        sqlx::query_scalar!("SELECT id FROM customers WHERE name = 'Auto Lead'")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_least_mtd_policy_assigns_and_notifies(pool: MySqlPool) {
+        set_policy(&pool, "least_mtd").await;
+        let busy_id = positioned_user(&pool, 1, SALES_WORKER, 111).await;
+        let free_id = positioned_user(&pool, 1, SALES_WORKER, 222).await;
+        positioned_user(&pool, 1, SALES_MANAGER, 999).await;
+        sqlx::query!(
+            "INSERT INTO customers (name, company_id, source, sales_rep, assigned_date) VALUES ('Old', 1, 'leads', ?, NOW())",
+            busy_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let bot = MockTelegram::new();
+
+        let customer_id = submit_lead(&pool, &bot, "46220").await;
+
+        let sales_rep =
+            sqlx::query_scalar!("SELECT sales_rep FROM customers WHERE id = ?", customer_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(sales_rep, Some(free_id));
+        let deal_user = sqlx::query_scalar!(
+            "SELECT user_id FROM deals WHERE customer_id = ?",
+            customer_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(deal_user, Some(free_id));
+        let last = sqlx::query_scalar!(
+            "SELECT last_assigned_user_id FROM lead_assignment_policies WHERE company_id = 1"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(last, Some(free_id));
+
+        let sent = bot.sent.lock().unwrap().clone();
+        let rep_notice = sent.iter().find(|entry| entry.0 == 222).unwrap();
+        assert!(rep_notice.1.contains("You were assigned a lead"));
+        let manager_notice = sent.iter().find(|entry| entry.0 == 999).unwrap();
+        assert!(manager_notice.1.contains("Lead auto-assigned to"));
+        assert!(manager_notice.2.is_none());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_territory_without_match_falls_back_to_manual(pool: MySqlPool) {
+        set_policy(&pool, "territory").await;
+        let rep_id = positioned_user(&pool, 1, SALES_WORKER, 111).await;
+        positioned_user(&pool, 1, SALES_MANAGER, 999).await;
+        sqlx::query!(
+            "INSERT INTO lead_assignment_territories (company_id, user_id, postal_prefix) VALUES (1, ?, '462')",
+            rep_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let bot = MockTelegram::new();
+
+        let customer_id = submit_lead(&pool, &bot, "90210").await;
+
+        let sales_rep =
+            sqlx::query_scalar!("SELECT sales_rep FROM customers WHERE id = ?", customer_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(sales_rep, None);
+        let sent = bot.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, 999);
+        assert!(sent[0].2.is_some());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_repeat_lead_updates_last_touch_only(pool: MySqlPool) {
+        let sales_id = positioned_user(&pool, 1, SALES_WORKER, 111).await;
+        positioned_user(&pool, 1, SALES_MANAGER, 999).await;
+        let bot = MockTelegram::new();
+        let first: NewLeadForm = serde_json::from_value(json!({
+            "name": "Tracked Lead",
+            "phone": "+13175550102",
+            "utm_source": "google",
+            "utm_medium": "cpc",
+            "gclid": "gclid-1",
+            "landing_page": "https://example.com/kitchens"
+        }))
+        .unwrap();
+        assert_eq!(process_lead(&pool, 1, &first, &bot).await, CREATED_RESPONSE);
+        let customer_id =
+            sqlx::query_scalar!("SELECT id FROM customers WHERE name = 'Tracked Lead'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        create_deal(&pool, customer_id, 1, 0, sales_id)
+            .await
+            .unwrap();
+
+        let repeat: NewLeadForm = serde_json::from_value(json!({
+            "name": "Tracked Lead",
+            "phone": "+13175550102",
+            "utm_source": "facebook",
+            "fbclid": "fbclid-1"
+        }))
+        .unwrap();
+        assert_eq!(
+            process_lead(&pool, 1, &repeat, &bot).await,
+            CREATED_RESPONSE
+        );
+
+        let touches = sqlx::query!(
+            "SELECT touch, utm_source, gclid, fbclid, landing_page FROM lead_attributions WHERE customer_id = ? ORDER BY touch",
+            customer_id
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(touches.len(), 2);
+        assert_eq!(touches[0].touch, "first");
+        assert_eq!(touches[0].utm_source.as_deref(), Some("google"));
+        assert_eq!(touches[0].gclid.as_deref(), Some("gclid-1"));
+        assert_eq!(
+            touches[0].landing_page.as_deref(),
+            Some("https://example.com/kitchens")
+        );
+        assert_eq!(touches[1].touch, "last");
+        assert_eq!(touches[1].utm_source.as_deref(), Some("facebook"));
+        assert_eq!(touches[1].fbclid.as_deref(), Some("fbclid-1"));
+        assert_eq!(touches[1].gclid, None);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_same_household_creates_lead_flagged_as_possible_duplicate(pool: MySqlPool) {
+        positioned_user(&pool, 1, SALES_MANAGER, 999).await;
+        let bot = MockTelegram::new();
+        let first: NewLeadForm = serde_json::from_value(json!({
+            "name": "Jane Doe",
+            "phone": "+13175550110",
+            "postal_code": "46201",
+            "address": "123 Main St"
+        }))
+        .unwrap();
+        assert_eq!(process_lead(&pool, 1, &first, &bot).await, CREATED_RESPONSE);
+        let spouse: NewLeadForm = serde_json::from_value(json!({
+            "name": "John Doe",
+            "phone": "+13175550111",
+            "postal_code": "46201-1234",
+            "address": "123 N Main Street"
+        }))
+        .unwrap();
+        assert_eq!(
+            process_lead(&pool, 1, &spouse, &bot).await,
+            CREATED_RESPONSE
+        );
+
+        let ids =
+            sqlx::query_scalar!("SELECT id FROM customers WHERE name LIKE '% Doe' ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(ids.len(), 2);
+        let flag = sqlx::query!(
+            "SELECT duplicate_of_customer_id, score, reasons FROM customer_duplicate_flags WHERE customer_id = ?",
+            ids[1]
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(flag.duplicate_of_customer_id, ids[0]);
+        assert_eq!(flag.score, 45);
+        assert_eq!(flag.reasons, "last name, address, ZIP");
+
+        let sent = bot.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 2);
+        assert!(!sent[0].1.contains("Possible duplicate"));
+        assert!(sent[1].1.contains("Possible duplicate"));
+        assert!(
+            sent[1]
+                .1
+                .contains(&format!("customer #{} Jane Doe", ids[0]))
+        );
+    }
+}
@@ -1,3 +1,8 @@
+pub mod auto_reply;
 pub mod constants;
+pub mod duplicates;
+pub mod idempotency;
 pub mod leads;
+pub mod notification_routing;
+pub mod spam;
 pub mod types;
@@ -0,0 +1,496 @@
+use crate::crud::notification_rules::{
+    LeadRoutingAttributes, NotificationRule, NotificationSettings, get_lead_routing_attributes,
+    get_notification_settings, list_notification_rules, list_rule_recipient_telegram_ids,
+};
+use crate::crud::users::get_user_tg_info;
+use chrono::{DateTime, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use lambda_http::tracing;
+use sqlx::MySqlPool;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Recipients {
+    /// Every sales manager with Telegram, the behavior for companies without rules.
+    AllManagers,
+    Chats(Vec<i64>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct NotificationPlan {
+    pub recipients: Recipients,
+    /// Set during quiet hours in delay mode; UTC.
+    pub send_after: Option<NaiveDateTime>,
+}
+
+impl NotificationPlan {
+    pub const fn all_managers() -> Self {
+        Self {
+            recipients: Recipients::AllManagers,
+            send_after: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum QuietHours {
+    OnCall(Option<i32>),
+    DelayUntil(NaiveDateTime),
+}
+
+/// Whether `time` is in `[starts_at, ends_at)`. A window that starts after it ends wraps
+/// midnight, and a window missing either end is always open.
+pub fn in_time_window(
+    starts_at: Option<NaiveTime>,
+    ends_at: Option<NaiveTime>,
+    time: NaiveTime,
+) -> bool {
+    match (starts_at, ends_at) {
+        (Some(start), Some(end)) if start <= end => start <= time && time < end,
+        (Some(start), Some(end)) => start <= time || time < end,
+        _ => true,
+    }
+}
+
+fn matches_text(condition: Option<&str>, value: Option<&str>) -> bool {
+    condition
+        .map(str::trim)
+        .filter(|condition| !condition.is_empty())
+        .is_none_or(|condition| {
+            value.is_some_and(|value| value.trim().eq_ignore_ascii_case(condition))
+        })
+}
+
+fn matches_postal_prefix(prefix: Option<&str>, postal_code: Option<&str>) -> bool {
+    prefix
+        .map(str::trim)
+        .filter(|prefix| !prefix.is_empty())
+        .is_none_or(|prefix| postal_code.is_some_and(|code| code.trim().starts_with(prefix)))
+}
+
+pub fn rule_matches(
+    rule: &NotificationRule,
+    lead: &LeadRoutingAttributes,
+    time: NaiveTime,
+) -> bool {
+    matches_text(rule.form_name.as_deref(), lead.form_name.as_deref())
+        && matches_text(
+            rule.referral_source.as_deref(),
+            lead.referral_source.as_deref(),
+        )
+        && matches_postal_prefix(rule.postal_prefix.as_deref(), lead.postal_code.as_deref())
+        && matches_text(rule.city.as_deref(), lead.city.as_deref())
+        && in_time_window(rule.starts_at, rule.ends_at, time)
+}
+
+pub fn first_matching_rule<'a>(
+    rules: &'a [NotificationRule],
+    lead: &LeadRoutingAttributes,
+    time: NaiveTime,
+) -> Option<&'a NotificationRule> {
+    rules.iter().find(|rule| rule_matches(rule, lead, time))
+}
+
+fn company_timezone(settings: Option<&NotificationSettings>) -> Tz {
+    settings.map_or(Tz::UTC, |settings| {
+        settings.timezone.parse().unwrap_or_else(|_| {
+            tracing::error!(
+                timezone = %settings.timezone,
+                "Unknown lead notification timezone, using UTC"
+            );
+            Tz::UTC
+        })
+    })
+}
+
+fn quiet_hours(
+    settings: &NotificationSettings,
+    timezone: Tz,
+    now: DateTime<Utc>,
+) -> Option<QuietHours> {
+    let (starts_at, ends_at) = (settings.quiet_starts_at?, settings.quiet_ends_at?);
+    let local = now.with_timezone(&timezone).naive_local();
+    if !in_time_window(Some(starts_at), Some(ends_at), local.time()) {
+        return None;
+    }
+    if settings.quiet_mode == "on_call" {
+        return Some(QuietHours::OnCall(settings.on_call_user_id));
+    }
+    let mut end_date = local.date();
+    if local.time() >= ends_at {
+        end_date = end_date.succ_opt()?;
+    }
+    // Only `None` when the end falls in a DST gap; the lead then goes out right away.
+    let ends = timezone
+        .from_local_datetime(&end_date.and_time(ends_at))
+        .earliest()?;
+    Some(QuietHours::DelayUntil(ends.naive_utc()))
+}
+
+async fn evaluate_plan(
+    pool: &MySqlPool,
+    company_id: i32,
+    customer_id: i32,
+    now: DateTime<Utc>,
+) -> Result<NotificationPlan, sqlx::Error> {
+    let rules = list_notification_rules(pool, company_id).await?;
+    let settings = get_notification_settings(pool, company_id).await?;
+    let timezone = company_timezone(settings.as_ref());
+    let mut plan = NotificationPlan::all_managers();
+
+    if !rules.is_empty() {
+        let lead = get_lead_routing_attributes(pool, customer_id)
+            .await?
+            .unwrap_or_default();
+        let local_time = now.with_timezone(&timezone).naive_local().time();
+        if let Some(rule) = first_matching_rule(&rules, &lead, local_time) {
+            let mut chat_ids = list_rule_recipient_telegram_ids(pool, rule.id).await?;
+            chat_ids.extend(rule.chat_id);
+            if chat_ids.is_empty() {
+                tracing::warn!(
+                    company_id,
+                    rule_id = rule.id,
+                    "Lead notification rule has nobody on Telegram, notifying all managers"
+                );
+            } else {
+                plan.recipients = Recipients::Chats(chat_ids);
+            }
+        }
+    }
+
+    match settings
+        .as_ref()
+        .and_then(|settings| quiet_hours(settings, timezone, now))
+    {
+        Some(QuietHours::OnCall(user_id)) => {
+            let telegram_id = match user_id {
+                Some(user_id) => get_user_tg_info(pool, user_id)
+                    .await?
+                    .and_then(|user| user.telegram_id),
+                None => None,
+            };
+            match telegram_id {
+                Some(telegram_id) => plan.recipients = Recipients::Chats(vec![telegram_id]),
+                None => tracing::warn!(
+                    company_id,
+                    "No on-call user on Telegram during quiet hours, using the usual recipients"
+                ),
+            }
+        }
+        Some(QuietHours::DelayUntil(send_after)) => plan.send_after = Some(send_after),
+        None => {}
+    }
+    Ok(plan)
+}
+
+/// Who gets a new lead notification and when, from the company's rules and quiet hours.
+/// The first matching rule wins; no match, or any error, notifies every manager now.
+pub async fn plan_lead_notification(
+    pool: &MySqlPool,
+    company_id: i32,
+    customer_id: i32,
+) -> NotificationPlan {
+    match evaluate_plan(pool, company_id, customer_id, Utc::now()).await {
+        Ok(plan) => plan,
+        Err(e) => {
+            tracing::error!(
+                ?e,
+                company_id,
+                customer_id,
+                "Failed to evaluate lead notification rules"
+            );
+            NotificationPlan::all_managers()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crud::users::get_sales_users;
+    use crate::libs::constants::SALES_MANAGER;
+    use crate::telegram::send::{
+        send_telegram_auto_assign_notification, send_telegram_manager_assign,
+    };
+    use crate::tests::telegram::MockTelegram;
+    use crate::tests::utils::positioned_user;
+    use chrono::TimeDelta;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn rule(id: i32) -> NotificationRule {
+        NotificationRule {
+            id,
+            form_name: None,
+            referral_source: None,
+            postal_prefix: None,
+            city: None,
+            starts_at: None,
+            ends_at: None,
+            chat_id: None,
+        }
+    }
+
+    fn lead() -> LeadRoutingAttributes {
+        LeadRoutingAttributes {
+            form_name: Some("Kitchen Quote".to_string()),
+            referral_source: Some("google".to_string()),
+            postal_code: Some("46220-1234".to_string()),
+            city: Some("Indianapolis".to_string()),
+        }
+    }
+
+    fn settings(mode: &str) -> NotificationSettings {
+        NotificationSettings {
+            timezone: "America/Indiana/Indianapolis".to_string(),
+            quiet_starts_at: Some(time(21, 0)),
+            quiet_ends_at: Some(time(8, 0)),
+            quiet_mode: mode.to_string(),
+            on_call_user_id: Some(7),
+        }
+    }
+
+    #[test]
+    fn test_time_window_wraps_midnight() {
+        assert!(in_time_window(
+            Some(time(9, 0)),
+            Some(time(17, 0)),
+            time(9, 0)
+        ));
+        assert!(!in_time_window(
+            Some(time(9, 0)),
+            Some(time(17, 0)),
+            time(17, 0)
+        ));
+        assert!(in_time_window(
+            Some(time(21, 0)),
+            Some(time(8, 0)),
+            time(23, 30)
+        ));
+        assert!(in_time_window(
+            Some(time(21, 0)),
+            Some(time(8, 0)),
+            time(7, 59)
+        ));
+        assert!(!in_time_window(
+            Some(time(21, 0)),
+            Some(time(8, 0)),
+            time(12, 0)
+        ));
+        assert!(in_time_window(None, Some(time(8, 0)), time(12, 0)));
+    }
+
+    #[test]
+    fn test_rule_conditions_must_all_match() {
+        let mut by_form = rule(1);
+        by_form.form_name = Some(" kitchen quote ".to_string());
+        by_form.postal_prefix = Some("462".to_string());
+        assert!(rule_matches(&by_form, &lead(), time(12, 0)));
+
+        by_form.city = Some("Carmel".to_string());
+        assert!(!rule_matches(&by_form, &lead(), time(12, 0)));
+
+        let mut by_source = rule(2);
+        by_source.referral_source = Some("facebook".to_string());
+        assert!(!rule_matches(
+            &by_source,
+            &LeadRoutingAttributes::default(),
+            time(12, 0)
+        ));
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let mut after_hours = rule(1);
+        after_hours.starts_at = Some(time(17, 0));
+        after_hours.ends_at = Some(time(9, 0));
+        let catch_all = rule(2);
+        let rules = [after_hours, catch_all];
+
+        assert_eq!(
+            first_matching_rule(&rules, &lead(), time(18, 0)).map(|rule| rule.id),
+            Some(1)
+        );
+        assert_eq!(
+            first_matching_rule(&rules, &lead(), time(10, 0)).map(|rule| rule.id),
+            Some(2)
+        );
+        assert!(first_matching_rule(&[], &lead(), time(10, 0)).is_none());
+    }
+
+    #[test]
+    fn test_quiet_hours_delay_until_local_morning() {
+        let timezone = company_timezone(Some(&settings("delay")));
+        // 23:30 in Indianapolis (UTC-4)
+        let night = Utc.with_ymd_and_hms(2026, 10, 20, 3, 30, 0).unwrap();
+        assert_eq!(
+            quiet_hours(&settings("delay"), timezone, night),
+            Some(QuietHours::DelayUntil(
+                Utc.with_ymd_and_hms(2026, 10, 20, 12, 0, 0)
+                    .unwrap()
+                    .naive_utc()
+            ))
+        );
+        assert_eq!(
+            quiet_hours(&settings("on_call"), timezone, night),
+            Some(QuietHours::OnCall(Some(7)))
+        );
+        let noon = Utc.with_ymd_and_hms(2026, 10, 20, 16, 0, 0).unwrap();
+        assert_eq!(quiet_hours(&settings("delay"), timezone, noon), None);
+    }
+
+    async fn insert_lead(pool: &MySqlPool, form_name: &str) -> u64 {
+        sqlx::query!(
+            "INSERT INTO customers (name, company_id, source, form_name) VALUES ('Routed Lead', 1, 'leads', ?)",
+            form_name
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_id()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_matching_rule_routes_to_its_users_and_group(pool: MySqlPool) {
+        positioned_user(&pool, 1, SALES_MANAGER, 456).await;
+        let kitchen_manager = positioned_user(&pool, 1, SALES_MANAGER, 789).await;
+        let rule_id = sqlx::query!(
+            "INSERT INTO lead_notification_rules (company_id, form_name, chat_id) VALUES (1, 'Kitchen Quote', -100123)"
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        sqlx::query!(
+            "INSERT INTO lead_notification_rule_users (rule_id, user_id) VALUES (?, ?)",
+            rule_id,
+            kitchen_manager
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let bot = MockTelegram::new();
+
+        let routed = insert_lead(&pool, "Kitchen Quote").await;
+        send_telegram_manager_assign(&pool, 1, "Lead", routed, true, &bot)
+            .await
+            .unwrap();
+        let mut chats: Vec<i64> = bot.sent.lock().unwrap().iter().map(|m| m.0).collect();
+        chats.sort_unstable();
+        assert_eq!(chats, vec![-100_123, 789]);
+
+        bot.sent.lock().unwrap().clear();
+        let other = insert_lead(&pool, "Bath Quote").await;
+        send_telegram_manager_assign(&pool, 1, "Lead", other, true, &bot)
+            .await
+            .unwrap();
+        let mut chats: Vec<i64> = bot.sent.lock().unwrap().iter().map(|m| m.0).collect();
+        chats.sort_unstable();
+        assert_eq!(chats, vec![456, 789]);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_quiet_hours_delay_queues_instead_of_sending(pool: MySqlPool) {
+        positioned_user(&pool, 1, SALES_MANAGER, 456).await;
+        let now = Utc::now().time();
+        sqlx::query!(
+            r#"
+            INSERT INTO lead_notification_settings (company_id, quiet_starts_at, quiet_ends_at)
+            VALUES (1, ?, ?)
+            "#,
+            now - TimeDelta::hours(1),
+            now + TimeDelta::hours(1)
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let bot = MockTelegram::new();
+
+        let customer_id = insert_lead(&pool, "Kitchen Quote").await;
+        send_telegram_manager_assign(&pool, 1, "Night lead", customer_id, true, &bot)
+            .await
+            .unwrap();
+
+        assert!(bot.sent.lock().unwrap().is_empty());
+        let queued = sqlx::query_scalar!("SELECT chat_id FROM delayed_lead_notifications")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(queued, vec![456]);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_auto_assign_notice_follows_rules_and_quiet_hours(pool: MySqlPool) {
+        positioned_user(&pool, 1, SALES_MANAGER, 456).await;
+        let kitchen_manager = positioned_user(&pool, 1, SALES_MANAGER, 789).await;
+        let rule_id = sqlx::query!(
+            "INSERT INTO lead_notification_rules (company_id, form_name) VALUES (1, 'Kitchen Quote')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        sqlx::query!(
+            "INSERT INTO lead_notification_rule_users (rule_id, user_id) VALUES (?, ?)",
+            rule_id,
+            kitchen_manager
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let users = get_sales_users(&pool, 1).await.unwrap();
+        let bot = MockTelegram::new();
+        let customer_id = i32::try_from(insert_lead(&pool, "Kitchen Quote").await).unwrap();
+
+        send_telegram_auto_assign_notification(
+            &pool,
+            1,
+            customer_id,
+            &users,
+            "Rep",
+            "Lead".to_string(),
+            &bot,
+        )
+        .await;
+        let chats: Vec<i64> = bot.sent.lock().unwrap().iter().map(|m| m.0).collect();
+        assert_eq!(chats, vec![789]);
+
+        bot.sent.lock().unwrap().clear();
+        let now = Utc::now().time();
+        sqlx::query!(
+            r#"
+            INSERT INTO lead_notification_settings (company_id, quiet_starts_at, quiet_ends_at)
+            VALUES (1, ?, ?)
+            "#,
+            now - TimeDelta::hours(1),
+            now + TimeDelta::hours(1)
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        send_telegram_auto_assign_notification(
+            &pool,
+            1,
+            customer_id,
+            &users,
+            "Rep",
+            "Lead".to_string(),
+            &bot,
+        )
+        .await;
+
+        assert!(bot.sent.lock().unwrap().is_empty());
+        let queued = sqlx::query!(
+            r#"
+            SELECT chat_id, include_assignment_prompt as "include_assignment_prompt: bool"
+            FROM delayed_lead_notifications
+            "#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(queued.chat_id, 789);
+        assert!(!queued.include_assignment_prompt);
+    }
+}
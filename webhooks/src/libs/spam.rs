@@ -0,0 +1,507 @@
+use crate::axum_helpers::guards::Telegram;
+use crate::crud::spam::{
+    SpamRuleWeight, SpamSettings, get_spam_settings, insert_quarantined_lead,
+    list_spam_rule_weights, record_lead_fingerprint,
+};
+use crate::libs::constants::{CREATED_RESPONSE, ERR_DB, internal_error};
+use crate::libs::types::BasicResponse;
+use crate::schemas::add_customer::LeadPayload;
+use crate::telegram::send::send_telegram_quarantine_review;
+use common::telegram::html::escape_html;
+use common::utils::phone::PhoneNumber;
+use lambda_http::tracing;
+use sha2::{Digest, Sha256};
+use sqlx::MySqlPool;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Throwaway inbox providers; subdomains match too. Companies add their own in
+/// `lead_spam_settings.blocked_email_domains`.
+const DISPOSABLE_EMAIL_DOMAINS: [&str; 14] = [
+    "mailinator.com",
+    "guerrillamail.com",
+    "sharklasers.com",
+    "10minutemail.com",
+    "tempmail.com",
+    "temp-mail.org",
+    "yopmail.com",
+    "trashmail.com",
+    "getnada.com",
+    "dispostable.com",
+    "maildrop.cc",
+    "throwawaymail.com",
+    "fakeinbox.com",
+    "mailnesia.com",
+];
+
+const LINK_MARKERS: [&str; 5] = ["http://", "https://", "www.", "[url", "<a "];
+
+/// Longer runs of consonants than this only come from mashing the keyboard.
+const MAX_CONSONANT_RUN: usize = 6;
+
+/// The parts of a lead the rules look at.
+pub struct LeadSignals<'a> {
+    pub name: &'a str,
+    pub email: Option<&'a str>,
+    pub phone: Option<&'a str>,
+    pub comments: Vec<&'a str>,
+    /// Earlier submissions with the same fingerprint in the last hour.
+    pub identical_last_hour: i64,
+}
+
+impl<'a> LeadSignals<'a> {
+    pub fn from_payload<V: LeadPayload>(form: &'a V) -> Self {
+        Self {
+            name: form.name(),
+            email: form.email(),
+            phone: form.phone(),
+            comments: form.comments(),
+            identical_last_hour: 0,
+        }
+    }
+
+    /// Same for every resubmission of the same details, whatever the form or ad it came from.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        for part in [
+            self.name,
+            self.email.unwrap_or(""),
+            self.phone.unwrap_or(""),
+        ]
+        .into_iter()
+        .chain(self.comments.iter().copied())
+        {
+            hasher.update(part.trim().to_lowercase().as_bytes());
+            hasher.update(b"\n");
+        }
+        hasher
+            .finalize()
+            .iter()
+            .fold(String::new(), |mut output, b| {
+                let _ = write!(output, "{b:02x}");
+                output
+            })
+    }
+}
+
+pub struct SpamConfig {
+    pub quarantine_score: u32,
+    pub max_identical_per_hour: i64,
+    pub blocked_email_domains: Vec<String>,
+    pub blocked_phone_prefixes: Vec<String>,
+    weights: HashMap<String, u32>,
+}
+
+fn lines(text: Option<&str>) -> Vec<String> {
+    text.unwrap_or_default()
+        .lines()
+        .map(|line| line.trim().to_lowercase())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+impl SpamConfig {
+    pub fn new(settings: &SpamSettings, weights: Vec<SpamRuleWeight>) -> Self {
+        Self {
+            quarantine_score: u32::try_from(settings.quarantine_score).unwrap_or(0),
+            max_identical_per_hour: settings.max_identical_per_hour.into(),
+            blocked_email_domains: lines(settings.blocked_email_domains.as_deref()),
+            blocked_phone_prefixes: lines(settings.blocked_phone_prefixes.as_deref()),
+            weights: weights
+                .into_iter()
+                .map(|row| (row.rule, u32::try_from(row.weight).unwrap_or(0)))
+                .collect(),
+        }
+    }
+
+    fn weight(&self, rule: &dyn SpamRule) -> u32 {
+        self.weights
+            .get(rule.key())
+            .copied()
+            .unwrap_or_else(|| rule.default_weight())
+    }
+}
+
+/// One check of the screening pipeline. A new check implements this and is added to
+/// [`RULES`]; companies tune it through `lead_spam_rules` by its key.
+pub trait SpamRule {
+    /// `lead_spam_rules.rule`.
+    fn key(&self) -> &'static str;
+    /// Shown to managers next to the score.
+    fn label(&self) -> &'static str;
+    fn default_weight(&self) -> u32;
+    fn matches(&self, lead: &LeadSignals, config: &SpamConfig) -> bool;
+}
+
+struct GibberishName;
+struct DisposableEmail;
+struct LinkInMessage;
+struct BadPhone;
+struct RepeatedSubmission;
+
+pub const RULES: [&dyn SpamRule; 5] = [
+    &GibberishName,
+    &DisposableEmail,
+    &LinkInMessage,
+    &BadPhone,
+    &RepeatedSubmission,
+];
+
+fn is_gibberish_word(word: &str) -> bool {
+    if !word.chars().all(|c| c.is_ascii_alphabetic()) || word.len() < 4 {
+        return false;
+    }
+    let lower = word.to_ascii_lowercase();
+    let is_vowel = |c: char| "aeiouy".contains(c);
+    let mut run = 0;
+    let mut longest_run = 0;
+    for c in lower.chars() {
+        run = if is_vowel(c) { 0 } else { run + 1 };
+        longest_run = longest_run.max(run);
+    }
+    let inner_capitals = word
+        .chars()
+        .skip(1)
+        .filter(char::is_ascii_uppercase)
+        .count();
+    let has_lowercase = word.chars().any(|c| c.is_ascii_lowercase());
+    !lower.chars().any(is_vowel)
+        || longest_run > MAX_CONSONANT_RUN
+        || (inner_capitals >= 3 && has_lowercase)
+}
+
+impl SpamRule for GibberishName {
+    fn key(&self) -> &'static str {
+        "gibberish_name"
+    }
+
+    fn label(&self) -> &'static str {
+        "gibberish name"
+    }
+
+    fn default_weight(&self) -> u32 {
+        40
+    }
+
+    fn matches(&self, lead: &LeadSignals, _: &SpamConfig) -> bool {
+        lead.name.chars().any(|c| c.is_ascii_digit())
+            || lead.name.split_whitespace().any(is_gibberish_word)
+    }
+}
+
+impl SpamRule for DisposableEmail {
+    fn key(&self) -> &'static str {
+        "disposable_email"
+    }
+
+    fn label(&self) -> &'static str {
+        "disposable email"
+    }
+
+    fn default_weight(&self) -> u32 {
+        40
+    }
+
+    fn matches(&self, lead: &LeadSignals, config: &SpamConfig) -> bool {
+        let Some((_, domain)) = lead.email.and_then(|email| email.trim().rsplit_once('@')) else {
+            return false;
+        };
+        let domain = domain.to_lowercase();
+        DISPOSABLE_EMAIL_DOMAINS
+            .iter()
+            .copied()
+            .chain(config.blocked_email_domains.iter().map(String::as_str))
+            .any(|blocked| {
+                domain == blocked
+                    || domain
+                        .strip_suffix(blocked)
+                        .is_some_and(|sub| sub.ends_with('.'))
+            })
+    }
+}
+
+impl SpamRule for LinkInMessage {
+    fn key(&self) -> &'static str {
+        "link_in_message"
+    }
+
+    fn label(&self) -> &'static str {
+        "link in message"
+    }
+
+    fn default_weight(&self) -> u32 {
+        30
+    }
+
+    fn matches(&self, lead: &LeadSignals, _: &SpamConfig) -> bool {
+        lead.comments.iter().any(|comment| {
+            let comment = comment.to_lowercase();
+            LINK_MARKERS.iter().any(|marker| comment.contains(marker))
+        })
+    }
+}
+
+/// Unparseable numbers, one digit repeated, `1234567890` and the fictional 555-01xx range.
+fn is_bad_phone(phone: &str, blocked_prefixes: &[String]) -> bool {
+    let Some(parsed) = PhoneNumber::parse(phone) else {
+        return true;
+    };
+    let e164 = parsed.e164();
+    let national = if parsed.is_north_american() {
+        &e164[2..]
+    } else {
+        &e164[1..]
+    };
+    let repeated = national.chars().all(|c| national.starts_with(c));
+    let sequential = ["0123456789", "1234567890", "9876543210"]
+        .iter()
+        .any(|run| national.contains(run));
+    let fictional = parsed.is_north_american() && national[3..].starts_with("55501");
+    repeated
+        || sequential
+        || fictional
+        || blocked_prefixes
+            .iter()
+            .any(|prefix| e164.starts_with(prefix.as_str()))
+}
+
+impl SpamRule for BadPhone {
+    fn key(&self) -> &'static str {
+        "bad_phone"
+    }
+
+    fn label(&self) -> &'static str {
+        "fake phone"
+    }
+
+    fn default_weight(&self) -> u32 {
+        40
+    }
+
+    fn matches(&self, lead: &LeadSignals, config: &SpamConfig) -> bool {
+        lead.phone
+            .filter(|phone| !phone.trim().is_empty())
+            .is_some_and(|phone| is_bad_phone(phone, &config.blocked_phone_prefixes))
+    }
+}
+
+impl SpamRule for RepeatedSubmission {
+    fn key(&self) -> &'static str {
+        "repeated_submission"
+    }
+
+    fn label(&self) -> &'static str {
+        "repeated submission"
+    }
+
+    fn default_weight(&self) -> u32 {
+        60
+    }
+
+    fn matches(&self, lead: &LeadSignals, config: &SpamConfig) -> bool {
+        lead.identical_last_hour >= config.max_identical_per_hour
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct SpamVerdict {
+    pub score: u32,
+    pub reasons: Vec<&'static str>,
+}
+
+pub fn score_lead(lead: &LeadSignals, config: &SpamConfig) -> SpamVerdict {
+    let mut verdict = SpamVerdict {
+        score: 0,
+        reasons: Vec::new(),
+    };
+    for rule in RULES {
+        let weight = config.weight(rule);
+        if weight > 0 && rule.matches(lead, config) {
+            verdict.score += weight;
+            verdict.reasons.push(rule.label());
+        }
+    }
+    verdict
+}
+
+/// `Some` when the company screens its leads and this one scored at or above its
+/// quarantine score. Companies without `lead_spam_settings` accept every lead.
+pub async fn screen_lead<V: LeadPayload>(
+    pool: &MySqlPool,
+    company_id: i32,
+    form: &V,
+) -> Result<Option<SpamVerdict>, sqlx::Error> {
+    let Some(settings) = get_spam_settings(pool, company_id).await? else {
+        return Ok(None);
+    };
+    let config = SpamConfig::new(&settings, list_spam_rule_weights(pool, company_id).await?);
+    let mut lead = LeadSignals::from_payload(form);
+    lead.identical_last_hour =
+        record_lead_fingerprint(pool, company_id, &lead.fingerprint()).await?;
+    let verdict = score_lead(&lead, &config);
+    Ok((verdict.score >= config.quarantine_score).then_some(verdict))
+}
+
+/// Stores the lead for review instead of creating a customer and asks managers to
+/// approve or reject it. The sender still gets `201` so bots learn nothing.
+pub async fn quarantine_lead<T, V: LeadPayload>(
+    pool: &MySqlPool,
+    company_id: i32,
+    form: &V,
+    verdict: &SpamVerdict,
+    bot: &T,
+) -> BasicResponse
+where
+    T: Telegram + Send + Sync + 'static + Clone,
+{
+    let payload = match serde_json::to_string(&form.stored()) {
+        Ok(payload) => payload,
+        Err(e) => {
+            tracing::error!(?e, company_id, "Failed to serialize quarantined lead");
+            return internal_error("Failed to serialize quarantined lead");
+        }
+    };
+    let reasons = verdict.reasons.join(", ");
+    let quarantine_id =
+        match insert_quarantined_lead(pool, company_id, &payload, verdict.score, &reasons).await {
+            Ok(result) => result.last_insert_id(),
+            Err(e) => {
+                tracing::error!(?e, company_id, "Failed to quarantine lead");
+                return internal_error(ERR_DB);
+            }
+        };
+    tracing::info!(
+        company_id,
+        quarantine_id,
+        score = verdict.score,
+        reasons = %reasons,
+        "Quarantined likely spam lead"
+    );
+    let message = format!(
+        "🛡 Held as possible spam (score {}: {})\n\n{}\nApprove to create the lead or reject to discard it.",
+        verdict.score,
+        escape_html(&reasons),
+        form.to_string().trim_end()
+    );
+    send_telegram_quarantine_review(pool, company_id, quarantine_id, &message, bot).await;
+    CREATED_RESPONSE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SpamConfig {
+        SpamConfig::new(
+            &SpamSettings {
+                quarantine_score: 50,
+                max_identical_per_hour: 3,
+                blocked_email_domains: Some("spam-co.net\n".to_string()),
+                blocked_phone_prefixes: Some("+1900".to_string()),
+            },
+            Vec::new(),
+        )
+    }
+
+    fn lead<'a>(name: &'a str, email: Option<&'a str>, phone: Option<&'a str>) -> LeadSignals<'a> {
+        LeadSignals {
+            name,
+            email,
+            phone,
+            comments: Vec::new(),
+            identical_last_hour: 0,
+        }
+    }
+
+    #[test]
+    fn real_leads_score_zero() {
+        for name in ["Jane Doe", "Mkrtchyan", "DeAngelo McDonald", "Олена Коваль"] {
+            let verdict = score_lead(
+                &lead(name, Some("jane@gmail.com"), Some("317-555-2368")),
+                &config(),
+            );
+            assert_eq!(verdict.score, 0, "{name}");
+        }
+    }
+
+    #[test]
+    fn flags_gibberish_names() {
+        for name in ["xkcdqwrt", "Jane asdfghjkl", "rTgHkLmn", "Bot 123"] {
+            assert!(
+                GibberishName.matches(&lead(name, None, None), &config()),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn flags_disposable_and_blocked_domains() {
+        for email in ["a@mailinator.com", "a@eu.mailinator.com", "a@SPAM-CO.net"] {
+            assert!(
+                DisposableEmail.matches(&lead("Jane", Some(email), None), &config()),
+                "{email}"
+            );
+        }
+        assert!(
+            !DisposableEmail.matches(&lead("Jane", Some("a@notmailinator.com"), None), &config())
+        );
+    }
+
+    #[test]
+    fn flags_fake_phones() {
+        for phone in [
+            "555-555-5555",
+            "123-456-7890",
+            "317-555-0199",
+            "+1 900 555 2368",
+            "12345",
+        ] {
+            assert!(
+                BadPhone.matches(&lead("Jane", None, Some(phone)), &config()),
+                "{phone}"
+            );
+        }
+        assert!(!BadPhone.matches(&lead("Jane", None, Some("+44 20 7946 0958")), &config()));
+    }
+
+    #[test]
+    fn combines_weights_and_honors_overrides() {
+        let mut spam = lead("Jane", Some("a@yopmail.com"), Some("555-555-5555"));
+        spam.comments = vec!["Cheap SEO at https://example.com"];
+        let verdict = score_lead(&spam, &config());
+        assert_eq!(verdict.score, 110);
+        assert_eq!(
+            verdict.reasons,
+            vec!["disposable email", "link in message", "fake phone"]
+        );
+
+        let config = SpamConfig::new(
+            &SpamSettings {
+                quarantine_score: 50,
+                max_identical_per_hour: 3,
+                blocked_email_domains: None,
+                blocked_phone_prefixes: None,
+            },
+            vec![SpamRuleWeight {
+                rule: "link_in_message".to_string(),
+                weight: 0,
+            }],
+        );
+        assert_eq!(score_lead(&spam, &config).score, 80);
+
+        let mut repeated = lead("Jane", None, None);
+        repeated.identical_last_hour = 3;
+        assert_eq!(
+            score_lead(&repeated, &config).reasons,
+            vec!["repeated submission"]
+        );
+    }
+
+    #[test]
+    fn fingerprint_ignores_case_and_spacing() {
+        let a = lead("Jane Doe", Some("Jane@Gmail.com"), None);
+        let b = lead(" jane doe ", Some("jane@gmail.com"), None);
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        assert_ne!(a.fingerprint(), lead("John Doe", None, None).fingerprint());
+    }
+}
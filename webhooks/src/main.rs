@@ -17,6 +17,7 @@ pub mod cloudtalk;
 pub mod crud;
 pub mod google;
 pub mod libs;
+pub mod meta;
 pub mod middleware;
 pub mod posthog;
 pub mod schemas;
@@ -0,0 +1,46 @@
+use crate::meta::schemas::GraphLead;
+use reqwest::Client;
+
+const GRAPH_URL: &str = "https://graph.facebook.com/v21.0";
+const LEAD_FIELDS: &str = "field_data,ad_name,adset_name,campaign_name";
+
+#[derive(thiserror::Error, Debug)]
+pub enum GraphError {
+    #[error("Graph API error: {0}")]
+    Api(String),
+    #[error("Network: {0}")]
+    Net(#[from] reqwest::Error),
+}
+
+/// Reads leads from the Meta Graph API; tests swap in a mock.
+pub trait MetaGraph: Send + Sync {
+    fn fetch_lead<'a>(
+        &'a self,
+        leadgen_id: &'a str,
+        access_token: &'a str,
+    ) -> impl Future<Output = Result<GraphLead, GraphError>> + Send + 'a;
+}
+
+#[derive(Clone, Default)]
+pub struct GraphClient {
+    client: Client,
+}
+
+impl MetaGraph for GraphClient {
+    async fn fetch_lead(
+        &self,
+        leadgen_id: &str,
+        access_token: &str,
+    ) -> Result<GraphLead, GraphError> {
+        let response = self
+            .client
+            .get(format!("{GRAPH_URL}/{leadgen_id}?fields={LEAD_FIELDS}"))
+            .bearer_auth(access_token)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(GraphError::Api(response.text().await?));
+        }
+        Ok(response.json().await?)
+    }
+}
@@ -0,0 +1,257 @@
+use crate::axum_helpers::guards::{Telegram, TelegramBot};
+use crate::crud::meta::get_meta_lead_page;
+use crate::libs::constants::{
+    ERR_DB, FORBIDDEN_RESPONSE, MALFORMED_RESPONSE, OK_RESPONSE, internal_error,
+};
+use crate::libs::idempotency::external_key;
+use crate::libs::types::BasicResponse;
+use crate::meta::graph::{GraphClient, MetaGraph};
+use crate::meta::schemas::{LeadgenWebhook, NewLeadgen};
+use crate::webhooks::receive::submit_lead_with_key;
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
+use bytes::Bytes;
+use common::utils::signing::verify_hex_signature;
+use lambda_http::tracing;
+use serde::Deserialize;
+use sqlx::MySqlPool;
+use std::env::var;
+
+const SIGNATURE_HEADER: &str = "x-hub-signature-256";
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyQuery {
+    #[serde(rename = "hub.mode")]
+    pub mode: Option<String>,
+    #[serde(rename = "hub.verify_token")]
+    pub verify_token: Option<String>,
+    #[serde(rename = "hub.challenge")]
+    pub challenge: Option<String>,
+}
+
+/// The challenge to echo back when Meta's subscription check carries our verify token.
+fn subscription_challenge(query: VerifyQuery, verify_token: &str) -> Option<String> {
+    let subscribing = query.mode.as_deref() == Some("subscribe");
+    let token_matches =
+        !verify_token.is_empty() && query.verify_token.as_deref() == Some(verify_token);
+    if subscribing && token_matches {
+        query.challenge
+    } else {
+        None
+    }
+}
+
+fn has_valid_signature(headers: &HeaderMap, body: &[u8], app_secret: &str) -> bool {
+    headers
+        .get(SIGNATURE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("sha256="))
+        .is_some_and(|signature| {
+            !app_secret.is_empty() && verify_hex_signature(app_secret, body, signature)
+        })
+}
+
+/// `GET` handshake Meta sends when the webhook subscription is saved.
+pub async fn leadgen_verify(Query(query): Query<VerifyQuery>) -> Response {
+    let verify_token = var("META_VERIFY_TOKEN").unwrap_or_default();
+    match subscription_challenge(query, &verify_token) {
+        Some(challenge) => challenge.into_response(),
+        None => {
+            tracing::error!("Meta webhook verification with a wrong or missing token");
+            FORBIDDEN_RESPONSE.into_response()
+        }
+    }
+}
+
+pub async fn leadgen_received(
+    State(pool): State<MySqlPool>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> BasicResponse {
+    let app_secret = var("META_APP_SECRET").unwrap_or_default();
+    if !has_valid_signature(&headers, &body, &app_secret) {
+        tracing::error!("Meta webhook: missing or invalid X-Hub-Signature-256");
+        return FORBIDDEN_RESPONSE;
+    }
+    let webhook: LeadgenWebhook = match serde_json::from_slice(&body) {
+        Ok(webhook) => webhook,
+        Err(e) => {
+            tracing::error!(?e, "Malformed Meta webhook");
+            return MALFORMED_RESPONSE;
+        }
+    };
+    let graph = GraphClient::default();
+    let tg_bot = TelegramBot::default();
+    process_leadgen_webhook(&pool, &graph, &webhook, &tg_bot).await
+}
+
+/// Creates every new lead in the delivery. Any failure is returned so Meta retries the
+/// delivery; leads that already went through are skipped on the retry.
+pub async fn process_leadgen_webhook<G, T>(
+    pool: &MySqlPool,
+    graph: &G,
+    webhook: &LeadgenWebhook,
+    bot: &T,
+) -> BasicResponse
+where
+    G: MetaGraph,
+    T: Telegram + Send + Sync + 'static + Clone,
+{
+    let mut response = OK_RESPONSE;
+    for leadgen in webhook.new_leads() {
+        let result = process_leadgen(pool, graph, &leadgen, bot).await;
+        if !result.0.is_success() {
+            response = result;
+        }
+    }
+    response
+}
+
+async fn process_leadgen<G, T>(
+    pool: &MySqlPool,
+    graph: &G,
+    leadgen: &NewLeadgen,
+    bot: &T,
+) -> BasicResponse
+where
+    G: MetaGraph,
+    T: Telegram + Send + Sync + 'static + Clone,
+{
+    let page = match get_meta_lead_page(pool, &leadgen.page_id).await {
+        Ok(Some(page)) => page,
+        Ok(None) => {
+            tracing::warn!(page_id = %leadgen.page_id, "Lead for a page with no company");
+            return OK_RESPONSE;
+        }
+        Err(e) => {
+            tracing::error!(?e, page_id = %leadgen.page_id, "Failed to load Meta page");
+            return internal_error(ERR_DB);
+        }
+    };
+    let lead = match graph
+        .fetch_lead(&leadgen.leadgen_id, &page.access_token)
+        .await
+    {
+        Ok(lead) => lead,
+        Err(e) => {
+            tracing::error!(?e, leadgen_id = %leadgen.leadgen_id, "Failed to fetch Meta lead");
+            return internal_error("Failed to fetch lead from Meta");
+        }
+    };
+    // A lead without a name will not get one on a retry, so it is not sent back to Meta.
+    let form = match lead.into_contact_form() {
+        Ok(form) => form,
+        Err(e) => {
+            tracing::error!(?e, leadgen_id = %leadgen.leadgen_id, "Meta lead has no name");
+            return OK_RESPONSE;
+        }
+    };
+    let key = external_key("meta-leadgen", &leadgen.leadgen_id);
+    submit_lead_with_key(page.company_id, pool.clone(), &key, form, bot).await
+}
+
+#[cfg(test)]
+mod local_tests {
+    use super::*;
+    use crate::libs::constants::SALES_MANAGER;
+    use crate::tests::telegram::MockTelegram;
+    use crate::tests::utils::{MockGraph, positioned_user};
+    use axum::http::HeaderValue;
+    use serde_json::json;
+
+    fn verify_query(mode: &str, token: &str) -> VerifyQuery {
+        VerifyQuery {
+            mode: Some(mode.to_string()),
+            verify_token: Some(token.to_string()),
+            challenge: Some("1158201444".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_challenge_needs_subscribe_mode_and_token() {
+        assert_eq!(
+            subscription_challenge(verify_query("subscribe", "tok"), "tok").as_deref(),
+            Some("1158201444")
+        );
+        assert!(subscription_challenge(verify_query("subscribe", "bad"), "tok").is_none());
+        assert!(subscription_challenge(verify_query("unsubscribe", "tok"), "tok").is_none());
+        assert!(subscription_challenge(verify_query("subscribe", ""), "").is_none());
+    }
+
+    #[test]
+    fn test_signature_header_is_checked() {
+        let body = br#"{"object":"page","entry":[]}"#;
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            SIGNATURE_HEADER,
+            HeaderValue::from_static(
+                "sha256=0000000000000000000000000000000000000000000000000000000000000000",
+            ),
+        );
+        assert!(!has_valid_signature(&headers, body, "app-secret"));
+        assert!(!has_valid_signature(&HeaderMap::new(), body, "app-secret"));
+    }
+
+    fn webhook(leadgen_id: &str) -> LeadgenWebhook {
+        serde_json::from_value(json!({
+            "object": "page",
+            "entry": [{
+                "id": "1122",
+                "changes": [{
+                    "field": "leadgen",
+                    "value": { "leadgen_id": leadgen_id, "page_id": "1122" }
+                }]
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_leadgen_creates_lead_once(pool: MySqlPool) {
+        sqlx::query!(
+            "INSERT INTO meta_lead_pages (page_id, company_id, access_token) VALUES ('1122', 1, 'page-token')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        positioned_user(&pool, 1, SALES_MANAGER, 456).await;
+        let graph = MockGraph::new(json!({
+            "field_data": [
+                { "name": "full_name", "values": ["Jane Doe"] },
+                { "name": "phone_number", "values": ["+13175551212"] }
+            ],
+            "campaign_name": "Indianapolis / LeadAds"
+        }));
+        let bot = MockTelegram::new();
+
+        let response = process_leadgen_webhook(&pool, &graph, &webhook("444"), &bot).await;
+        assert!(response.0.is_success());
+        let response = process_leadgen_webhook(&pool, &graph, &webhook("444"), &bot).await;
+        assert!(response.0.is_success());
+
+        let names = sqlx::query_scalar!("SELECT name FROM customers")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(names, vec![Some("Jane Doe".to_string())]);
+        assert_eq!(
+            *graph.requests.lock().unwrap(),
+            vec![
+                ("444".to_string(), "page-token".to_string()),
+                ("444".to_string(), "page-token".to_string())
+            ]
+        );
+        assert_eq!(bot.sent.lock().unwrap().len(), 1);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_leadgen_for_unknown_page_is_acknowledged(pool: MySqlPool) {
+        let graph = MockGraph::new(json!({}));
+        let bot = MockTelegram::new();
+
+        let response = process_leadgen_webhook(&pool, &graph, &webhook("444"), &bot).await;
+        assert!(response.0.is_success());
+        assert!(graph.requests.lock().unwrap().is_empty());
+    }
+}
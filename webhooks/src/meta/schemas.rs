@@ -0,0 +1,201 @@
+use crate::schemas::add_customer::FaceBookContactForm;
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+
+/// Meta sends ids as strings, but its test tool has sent them as numbers.
+fn id_string<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::String(id) => Ok(id),
+        Value::Number(id) => Ok(id.to_string()),
+        other => Err(serde::de::Error::custom(format!("unexpected id {other}"))),
+    }
+}
+
+/// Body of a Page webhook delivery; only `leadgen` changes are read.
+#[derive(Debug, Deserialize)]
+pub struct LeadgenWebhook {
+    #[serde(default)]
+    pub entry: Vec<LeadgenEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeadgenEntry {
+    #[serde(default)]
+    pub changes: Vec<LeadgenChange>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeadgenChange {
+    pub field: String,
+    pub value: Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewLeadgen {
+    #[serde(deserialize_with = "id_string")]
+    pub leadgen_id: String,
+    #[serde(deserialize_with = "id_string")]
+    pub page_id: String,
+}
+
+impl LeadgenWebhook {
+    pub fn new_leads(&self) -> Vec<NewLeadgen> {
+        self.entry
+            .iter()
+            .flat_map(|entry| &entry.changes)
+            .filter(|change| change.field == "leadgen")
+            .filter_map(|change| serde_json::from_value(change.value.clone()).ok())
+            .collect()
+    }
+}
+
+/// A lead as returned by `GET /{leadgen_id}`.
+#[derive(Debug, Deserialize)]
+pub struct GraphLead {
+    #[serde(default)]
+    pub field_data: Vec<GraphLeadField>,
+    pub ad_name: Option<String>,
+    pub adset_name: Option<String>,
+    pub campaign_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GraphLeadField {
+    pub name: String,
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+
+/// Meta's standard question keys and the [`FaceBookContactForm`] key they fill.
+const STANDARD_FIELDS: [(&str, &str); 6] = [
+    ("full_name", "name"),
+    ("email", "email"),
+    ("phone_number", "phone"),
+    ("city", "city"),
+    ("zip_code", "zip"),
+    ("post_code", "zip"),
+];
+
+impl GraphLead {
+    /// Builds the same payload Make/Zapier post to `facebook_contact_form`. Custom
+    /// questions are kept as `question: answer` lines in the details.
+    pub fn into_contact_form(self) -> Result<FaceBookContactForm, serde_json::Error> {
+        let mut form = Map::new();
+        let mut details = Vec::new();
+        let mut first_name = None;
+        let mut last_name = None;
+        for field in self.field_data {
+            let answer = field.values.join(", ");
+            if answer.trim().is_empty() {
+                continue;
+            }
+            match field.name.as_str() {
+                "first_name" => first_name = Some(answer),
+                "last_name" => last_name = Some(answer),
+                name => match STANDARD_FIELDS.iter().find(|(meta, _)| *meta == name) {
+                    Some((_, key)) => {
+                        form.insert((*key).to_string(), Value::String(answer));
+                    }
+                    None => details.push(format!("{}: {answer}", name.replace('_', " "))),
+                },
+            }
+        }
+        if !form.contains_key("name") {
+            let name = [first_name, last_name]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !name.is_empty() {
+                form.insert("name".to_string(), Value::String(name));
+            }
+        }
+        if !details.is_empty() {
+            form.insert("share".to_string(), Value::String(details.join("\n")));
+        }
+        for (key, value) in [
+            ("campaign", self.campaign_name),
+            ("adsetname", self.adset_name),
+            ("adname", self.ad_name),
+        ] {
+            if let Some(value) = value {
+                form.insert(key.to_string(), Value::String(value));
+            }
+        }
+        serde_json::from_value(Value::Object(form))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::add_customer::LeadPayload;
+    use serde_json::json;
+
+    #[test]
+    fn test_reads_leadgen_changes_only() {
+        let webhook: LeadgenWebhook = serde_json::from_value(json!({
+            "object": "page",
+            "entry": [{
+                "id": "1122",
+                "time": 1_760_000_000,
+                "changes": [
+                    { "field": "leadgen", "value": { "leadgen_id": "444", "page_id": "1122", "form_id": "9" } },
+                    { "field": "leadgen", "value": { "leadgen_id": 555, "page_id": 1122 } },
+                    { "field": "feed", "value": { "item": "post" } }
+                ]
+            }]
+        }))
+        .unwrap();
+
+        let leads = webhook.new_leads();
+        assert_eq!(leads.len(), 2);
+        assert_eq!(leads[0].leadgen_id, "444");
+        assert_eq!(leads[1].leadgen_id, "555");
+        assert_eq!(leads[1].page_id, "1122");
+    }
+
+    #[test]
+    fn test_maps_graph_lead_to_contact_form() {
+        let lead: GraphLead = serde_json::from_value(json!({
+            "id": "444",
+            "created_time": "2026-10-18T21:00:00+0000",
+            "field_data": [
+                { "name": "first_name", "values": ["Jane"] },
+                { "name": "last_name", "values": ["Doe"] },
+                { "name": "phone_number", "values": ["+13175551212"] },
+                { "name": "email", "values": ["jane@example.com"] },
+                { "name": "zip_code", "values": ["46220"] },
+                { "name": "what_are_you_remodeling?", "values": ["Kitchen"] }
+            ],
+            "campaign_name": "Indianapolis / LeadAds",
+            "adset_name": "Kitchen 35+",
+            "ad_name": "Reel 3"
+        }))
+        .unwrap();
+
+        let form = lead.into_contact_form().unwrap();
+        assert_eq!(form.name(), "Jane Doe");
+        assert_eq!(form.phone(), Some("317-555-1212"));
+        assert_eq!(form.email(), Some("jane@example.com"));
+        assert_eq!(form.postal_code(), Some("46220"));
+        assert_eq!(form.comments(), vec!["what are you remodeling?: Kitchen"]);
+        assert_eq!(
+            form.campaign_name.as_deref(),
+            Some("Indianapolis / LeadAds")
+        );
+        assert_eq!(form.ad_name.as_deref(), Some("Reel 3"));
+    }
+
+    #[test]
+    fn test_lead_without_name_is_rejected() {
+        let lead: GraphLead = serde_json::from_value(json!({
+            "field_data": [{ "name": "email", "values": ["jane@example.com"] }]
+        }))
+        .unwrap();
+        assert!(lead.into_contact_form().is_err());
+    }
+}
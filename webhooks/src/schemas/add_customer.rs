@@ -2,40 +2,91 @@ use crate::crud::leads::{
     create_lead_from_facebook, create_lead_from_new_lead_form, create_lead_from_wordpress,
     update_lead_from_facebook, update_lead_from_new_lead_form, update_lead_from_wordpress,
 };
+use crate::schemas::form_lead::FormLead;
+use common::telegram::html::HtmlMessage;
+use common::utils::phone::PhoneNumber;
 use serde::de::Deserializer;
 use serde::{Deserialize, Serialize};
 use sqlx::MySqlPool;
 use sqlx::mysql::MySqlQueryResult;
 use std::fmt;
 use std::fmt::Display;
-use std::fmt::Write as _;
 use utoipa::ToSchema;
 
+/// Display format from [`PhoneNumber`]; numbers that do not parse keep their digits.
 fn clean_phone<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
 where
     D: Deserializer<'de>,
 {
     let opt = Option::<String>::deserialize(deserializer)?;
     Ok(opt.map(|s| {
-        let digits: String = s.chars().filter(char::is_ascii_digit).collect();
-
-        let digits = if digits.starts_with('1') && digits.len() == 11 {
-            &digits[1..]
-        } else {
-            &digits
-        };
-
-        if digits.len() == 10 {
-            format!("{}-{}-{}", &digits[0..3], &digits[3..6], &digits[6..10])
-        } else {
-            digits.to_string()
-        }
+        PhoneNumber::parse(&s).map_or_else(
+            || s.chars().filter(char::is_ascii_digit).collect(),
+            |phone| phone.to_string(),
+        )
     }))
 }
 
+/// Where the lead came from, as sent by the landing page or ad platform.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, ToSchema)]
+pub struct LeadAttribution {
+    #[schema(example = "google")]
+    pub utm_source: Option<String>,
+
+    #[schema(example = "cpc")]
+    pub utm_medium: Option<String>,
+
+    #[schema(example = "kitchen_remodel_spring")]
+    pub utm_campaign: Option<String>,
+
+    pub utm_term: Option<String>,
+
+    pub utm_content: Option<String>,
+
+    /// Google Ads click id.
+    pub gclid: Option<String>,
+
+    /// Meta (Facebook, Instagram) click id.
+    pub fbclid: Option<String>,
+
+    /// Google Ads click id for iOS web traffic.
+    pub wbraid: Option<String>,
+
+    /// Full URL of the page the visitor first landed on.
+    #[schema(example = "https://example.com/kitchens?utm_source=google")]
+    pub landing_page: Option<String>,
+
+    /// URL of the page that sent the visitor to the landing page.
+    pub referrer: Option<String>,
+}
+
+impl LeadAttribution {
+    pub const fn is_empty(&self) -> bool {
+        self.utm_source.is_none()
+            && self.utm_medium.is_none()
+            && self.utm_campaign.is_none()
+            && self.utm_term.is_none()
+            && self.utm_content.is_none()
+            && self.gclid.is_none()
+            && self.fbclid.is_none()
+            && self.wbraid.is_none()
+            && self.landing_page.is_none()
+            && self.referrer.is_none()
+    }
+}
+
+/// `Display` renders the lead card sent to managers, as Telegram HTML.
 pub trait LeadPayload: Display + Send + Sync {
+    fn name(&self) -> &str;
     fn email(&self) -> Option<&str>;
     fn phone(&self) -> Option<&str>;
+    fn postal_code(&self) -> Option<&str>;
+    fn address(&self) -> Option<&str>;
+    fn attribution(&self) -> &LeadAttribution;
+    /// Free text the visitor typed, screened for links by `libs::spam`.
+    fn comments(&self) -> Vec<&str>;
+    /// Owned copy tagged with its form, for leads stored before they are processed.
+    fn stored(&self) -> StoredLead;
     // fn referral_source(&self) -> &'static str;
 
     fn insert(
@@ -52,6 +103,16 @@ pub trait LeadPayload: Display + Send + Sync {
     ) -> impl Future<Output = Result<MySqlQueryResult, sqlx::Error>> + Send;
 }
 
+/// Any lead form, tagged so a stored lead is read back as the form it came from.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", content = "form", rename_all = "snake_case")]
+pub enum StoredLead {
+    Wordpress(WordpressContactForm),
+    Facebook(FaceBookContactForm),
+    NewLead(NewLeadForm),
+    Form(FormLead),
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct WordpressContactForm {
     pub name: String,
@@ -97,9 +158,16 @@ pub struct WordpressContactForm {
 
     #[serde(rename = "File")]
     pub attached_file: Option<String>,
+
+    #[serde(flatten)]
+    pub attribution: LeadAttribution,
 }
 
 impl LeadPayload for WordpressContactForm {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
     fn email(&self) -> Option<&str> {
         self.email.as_deref()
     }
@@ -108,6 +176,26 @@ impl LeadPayload for WordpressContactForm {
         self.phone.as_deref()
     }
 
+    fn postal_code(&self) -> Option<&str> {
+        self.postal_code.as_deref()
+    }
+
+    fn address(&self) -> Option<&str> {
+        self.address.as_deref()
+    }
+
+    fn attribution(&self) -> &LeadAttribution {
+        &self.attribution
+    }
+
+    fn comments(&self) -> Vec<&str> {
+        self.your_message.iter().map(String::as_str).collect()
+    }
+
+    fn stored(&self) -> StoredLead {
+        StoredLead::Wordpress(self.clone())
+    }
+
     fn insert(
         &self,
         pool: &MySqlPool,
@@ -128,39 +216,23 @@ impl LeadPayload for WordpressContactForm {
 
 impl fmt::Display for WordpressContactForm {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let message = format!(
-            "Name: {}\n\
-           Phone: {}\n\
-           Email: {}\n\
-           Address: {}\n\
-           Zip: {}\n\
-           Remodeling Type: {}\n\
-           Project Size: {}\n\
-           Contacted: {}\n\
-           Remove and Dispose: {}\n\
-           Improve Offer: {}\n\
-           Sink: {}\n\
-           Backsplash: {}\n\
-           Stove: {}\n\
-           Your Message: {}\n\
-           Attached File: {}",
-            self.name,
-            self.phone.as_deref().unwrap_or("N/A"),
-            self.email.as_deref().unwrap_or("N/A"),
-            self.address.as_deref().unwrap_or("N/A"),
-            self.postal_code.as_deref().unwrap_or("N/A"),
-            self.remodal_type.as_deref().unwrap_or("N/A"),
-            self.project_size.as_deref().unwrap_or("N/A"),
-            self.contact_time.as_deref().unwrap_or("N/A"),
-            self.remove_and_dispose.as_deref().unwrap_or("N/A"),
-            self.improve_offer.as_deref().unwrap_or("N/A"),
-            self.sink.as_deref().unwrap_or("N/A"),
-            self.backsplash.as_deref().unwrap_or("N/A"),
-            self.kitchen_stove.as_deref().unwrap_or("N/A"),
-            self.your_message.as_deref().unwrap_or("N/A"),
-            self.attached_file.as_deref().unwrap_or("N/A")
-        );
-        write!(f, "{message}")
+        let card = HtmlMessage::new()
+            .field("Name", &self.name)
+            .optional_field("Phone", self.phone.as_deref())
+            .optional_field("Email", self.email.as_deref())
+            .optional_field("Address", self.address.as_deref())
+            .optional_field("Zip", self.postal_code.as_deref())
+            .optional_field("Remodeling Type", self.remodal_type.as_deref())
+            .optional_field("Project Size", self.project_size.as_deref())
+            .optional_field("Contacted", self.contact_time.as_deref())
+            .optional_field("Remove and Dispose", self.remove_and_dispose.as_deref())
+            .optional_field("Improve Offer", self.improve_offer.as_deref())
+            .optional_field("Sink", self.sink.as_deref())
+            .optional_field("Backsplash", self.backsplash.as_deref())
+            .optional_field("Stove", self.kitchen_stove.as_deref())
+            .optional_field("Your Message", self.your_message.as_deref())
+            .optional_field("Attached File", self.attached_file.as_deref());
+        write!(f, "{}", card.build())
     }
 }
 
@@ -194,9 +266,16 @@ pub struct FaceBookContactForm {
 
     #[serde(rename = "adname")]
     pub ad_name: Option<String>,
+
+    #[serde(flatten)]
+    pub attribution: LeadAttribution,
 }
 
 impl LeadPayload for FaceBookContactForm {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
     fn email(&self) -> Option<&str> {
         self.email.as_deref()
     }
@@ -205,6 +284,26 @@ impl LeadPayload for FaceBookContactForm {
         self.phone.as_deref()
     }
 
+    fn postal_code(&self) -> Option<&str> {
+        self.postal_code.as_deref()
+    }
+
+    fn address(&self) -> Option<&str> {
+        None
+    }
+
+    fn attribution(&self) -> &LeadAttribution {
+        &self.attribution
+    }
+
+    fn comments(&self) -> Vec<&str> {
+        self.details.iter().map(String::as_str).collect()
+    }
+
+    fn stored(&self) -> StoredLead {
+        StoredLead::Facebook(self.clone())
+    }
+
     fn insert(
         &self,
         pool: &MySqlPool,
@@ -225,29 +324,18 @@ impl LeadPayload for FaceBookContactForm {
 
 impl fmt::Display for FaceBookContactForm {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let message = format!(
-            "Name: {}\n\
-               Phone: {}\n\
-               Remove and Dispose: {}\n\
-               Email: {}\n\
-               City: {}\n\
-               Zip: {}\n\
-               Details: {}\n\
-               Campaign: {}\n\
-               Adset: {}\n\
-               Ad: {}",
-            self.name,
-            self.phone.as_deref().unwrap_or("N/A"),
-            self.remove_and_dispose.as_deref().unwrap_or("N/A"),
-            self.email.as_deref().unwrap_or("N/A"),
-            self.city.as_deref().unwrap_or("N/A"),
-            self.postal_code.as_deref().unwrap_or("N/A"),
-            self.details.as_deref().unwrap_or("N/A"),
-            self.campaign_name.as_deref().unwrap_or("N/A"),
-            self.adset_name.as_deref().unwrap_or("N/A"),
-            self.ad_name.as_deref().unwrap_or("N/A")
-        );
-        write!(f, "{message}")
+        let card = HtmlMessage::new()
+            .field("Name", &self.name)
+            .optional_field("Phone", self.phone.as_deref())
+            .optional_field("Remove and Dispose", self.remove_and_dispose.as_deref())
+            .optional_field("Email", self.email.as_deref())
+            .optional_field("City", self.city.as_deref())
+            .optional_field("Zip", self.postal_code.as_deref())
+            .optional_field("Details", self.details.as_deref())
+            .optional_field("Campaign", self.campaign_name.as_deref())
+            .optional_field("Adset", self.adset_name.as_deref())
+            .optional_field("Ad", self.ad_name.as_deref());
+        write!(f, "{}", card.build())
     }
 }
 
@@ -258,7 +346,11 @@ impl fmt::Display for FaceBookContactForm {
         "email": "jane@example.com",
         "phone": "+13175551212",
         "referral_source": "website",
-        "form_name": "cabinet_quote"
+        "form_name": "cabinet_quote",
+        "utm_source": "google",
+        "utm_medium": "cpc",
+        "gclid": "EAIaIQobChMI",
+        "landing_page": "https://example.com/kitchens"
     })
 )]
 pub struct NewLeadForm {
@@ -322,9 +414,17 @@ pub struct NewLeadForm {
     #[serde(default)]
     #[schema(example = "cabinet_quote")]
     pub form_name: Option<String>,
+
+    /// Optional UTM parameters, ad click ids and landing page, sent as top-level keys.
+    #[serde(flatten)]
+    pub attribution: LeadAttribution,
 }
 
 impl LeadPayload for NewLeadForm {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
     fn email(&self) -> Option<&str> {
         self.email.as_deref()
     }
@@ -333,6 +433,29 @@ impl LeadPayload for NewLeadForm {
         self.phone.as_deref()
     }
 
+    fn postal_code(&self) -> Option<&str> {
+        self.postal_code.as_deref()
+    }
+
+    fn address(&self) -> Option<&str> {
+        self.address.as_deref()
+    }
+
+    fn attribution(&self) -> &LeadAttribution {
+        &self.attribution
+    }
+
+    fn comments(&self) -> Vec<&str> {
+        [&self.your_message, &self.details]
+            .into_iter()
+            .filter_map(Option::as_deref)
+            .collect()
+    }
+
+    fn stored(&self) -> StoredLead {
+        StoredLead::NewLead(self.clone())
+    }
+
     fn insert(
         &self,
         pool: &MySqlPool,
@@ -352,76 +475,31 @@ impl LeadPayload for NewLeadForm {
 }
 impl fmt::Display for NewLeadForm {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut message = format!("Name: {}\n", self.name);
-
-        if let Some(phone) = &self.phone {
-            writeln!(message, "Phone: {phone}").unwrap();
-        }
-        if let Some(email) = &self.email {
-            writeln!(message, "Email: {email}").unwrap();
-        }
-        if let Some(postal_code) = &self.postal_code {
-            writeln!(message, "Postal Code: {postal_code}").unwrap();
-        }
-        if let Some(address) = &self.address {
-            writeln!(message, "Address: {address}").unwrap();
-        }
-        if let Some(city) = &self.city {
-            writeln!(message, "City: {city}").unwrap();
-        }
-        if let Some(remodal_type) = &self.remodal_type {
-            writeln!(message, "Remodel Type: {remodal_type}").unwrap();
-        }
-        if let Some(project_size) = &self.project_size {
-            writeln!(message, "Project Size: {project_size}").unwrap();
-        }
-        if let Some(contact_time) = &self.contact_time {
-            writeln!(message, "Best Time to Contact: {contact_time}").unwrap();
-        }
-        if let Some(when_start) = &self.when_start {
-            writeln!(message, "Start Date: {when_start}").unwrap();
-        }
-        if let Some(remove_and_dispose) = &self.remove_and_dispose {
-            writeln!(message, "Tear Out: {remove_and_dispose}").unwrap();
-        }
-        if let Some(improve_offer) = &self.improve_offer {
-            writeln!(message, "Improve Offer: {improve_offer}").unwrap();
-        }
-        if let Some(sink) = &self.sink {
-            writeln!(message, "Sink: {sink}").unwrap();
-        }
-        if let Some(kitchen_stove) = &self.kitchen_stove {
-            writeln!(message, "Stove Type: {kitchen_stove}").unwrap();
-        }
-        if let Some(backsplash) = &self.backsplash {
-            writeln!(message, "Backsplash: {backsplash}").unwrap();
-        }
-        if let Some(your_message) = &self.your_message {
-            writeln!(message, "Message: {your_message}").unwrap();
-        }
-        if let Some(details) = &self.details {
-            writeln!(message, "Details: {details}").unwrap();
-        }
-        if let Some(ad_name) = &self.ad_name {
-            writeln!(message, "Ad Name: {ad_name}").unwrap();
-        }
-        if let Some(adset_name) = &self.adset_name {
-            writeln!(message, "Adset Name: {adset_name}").unwrap();
-        }
-        if let Some(campaign_name) = &self.campaign_name {
-            writeln!(message, "Campaign Name: {campaign_name}").unwrap();
-        }
-        if let Some(attached_file) = &self.attached_file {
-            writeln!(message, "File: {attached_file}").unwrap();
-        }
-        if let Some(referral_source) = &self.referral_source {
-            writeln!(message, "Referral Source: {referral_source}").unwrap();
-        }
-        if let Some(form_name) = &self.form_name {
-            writeln!(message, "Form Name: {form_name}").unwrap();
-        }
-
-        write!(f, "{message}")
+        let card = HtmlMessage::new()
+            .field("Name", &self.name)
+            .optional_field("Phone", self.phone.as_deref())
+            .optional_field("Email", self.email.as_deref())
+            .optional_field("Postal Code", self.postal_code.as_deref())
+            .optional_field("Address", self.address.as_deref())
+            .optional_field("City", self.city.as_deref())
+            .optional_field("Remodel Type", self.remodal_type.as_deref())
+            .optional_field("Project Size", self.project_size.as_deref())
+            .optional_field("Best Time to Contact", self.contact_time.as_deref())
+            .optional_field("Start Date", self.when_start.as_deref())
+            .optional_field("Tear Out", self.remove_and_dispose.as_deref())
+            .optional_field("Improve Offer", self.improve_offer.as_deref())
+            .optional_field("Sink", self.sink.as_deref())
+            .optional_field("Stove Type", self.kitchen_stove.as_deref())
+            .optional_field("Backsplash", self.backsplash.as_deref())
+            .optional_field("Message", self.your_message.as_deref())
+            .optional_field("Details", self.details.as_deref())
+            .optional_field("Ad Name", self.ad_name.as_deref())
+            .optional_field("Adset Name", self.adset_name.as_deref())
+            .optional_field("Campaign Name", self.campaign_name.as_deref())
+            .optional_field("File", self.attached_file.as_deref())
+            .optional_field("Referral Source", self.referral_source.as_deref())
+            .optional_field("Form Name", self.form_name.as_deref());
+        write!(f, "{}", card.build())
     }
 }
 
@@ -514,6 +592,17 @@ mod tests {
         assert_eq!(lead.phone.unwrap(), "317-750-6474");
     }
 
+    #[test]
+    fn test_clean_phone_keeps_extension_and_country_code() {
+        let data = json!({ "name": "Test", "phone": "(317) 750-6474 x204" });
+        let lead: NewLeadForm = serde_json::from_value(data).unwrap();
+        assert_eq!(lead.phone.unwrap(), "317-750-6474 ext. 204");
+
+        let data = json!({ "name": "Test", "phone": "+44 20 7946 0958" });
+        let lead: NewLeadForm = serde_json::from_value(data).unwrap();
+        assert_eq!(lead.phone.unwrap(), "+44 2079460958");
+    }
+
     #[test]
     fn test_wordpress_contact_form_deserialize() {
         let data = json!({
@@ -556,21 +645,21 @@ mod tests {
         );
 
         let text = form.to_string();
-        assert!(text.contains("Name: John Doe"));
-        assert!(text.contains("Phone: 317-750-6474"));
-        assert!(text.contains("Email: john@example.com"));
-        assert!(text.contains("Address: 123 Main St"));
-        assert!(text.contains("Zip: 46201"));
-        assert!(text.contains("Remodeling Type: Kitchen"));
-        assert!(text.contains("Project Size: Medium"));
-        assert!(text.contains("Contacted: Evening"));
-        assert!(text.contains("Remove and Dispose: Yes"));
-        assert!(text.contains("Improve Offer: New counters"));
-        assert!(text.contains("Sink: Undermount"));
-        assert!(text.contains("Backsplash: Tile"));
-        assert!(text.contains("Stove: Gas"));
-        assert!(text.contains("Your Message: Looking for a quote"));
-        assert!(text.contains("Attached File: https://www.google.com"));
+        assert!(text.contains("<b>Name:</b> John Doe"));
+        assert!(text.contains("<b>Phone:</b> 317-750-6474"));
+        assert!(text.contains("<b>Email:</b> john@example.com"));
+        assert!(text.contains("<b>Address:</b> 123 Main St"));
+        assert!(text.contains("<b>Zip:</b> 46201"));
+        assert!(text.contains("<b>Remodeling Type:</b> Kitchen"));
+        assert!(text.contains("<b>Project Size:</b> Medium"));
+        assert!(text.contains("<b>Contacted:</b> Evening"));
+        assert!(text.contains("<b>Remove and Dispose:</b> Yes"));
+        assert!(text.contains("<b>Improve Offer:</b> New counters"));
+        assert!(text.contains("<b>Sink:</b> Undermount"));
+        assert!(text.contains("<b>Backsplash:</b> Tile"));
+        assert!(text.contains("<b>Stove:</b> Gas"));
+        assert!(text.contains("<b>Your Message:</b> Looking for a quote"));
+        assert!(text.contains("<b>Attached File:</b> https://www.google.com"));
     }
 
     #[test]
@@ -602,16 +691,16 @@ mod tests {
         assert_eq!(form.ad_name.clone().unwrap(), "Kitchen Ad 1");
 
         let text = form.to_string();
-        assert!(text.contains("Name: Jane Smith"));
-        assert!(text.contains("Phone: 812-374-4195"));
-        assert!(text.contains("Remove and Dispose: No"));
-        assert!(text.contains("Email: jane@example.com"));
-        assert!(text.contains("City: Columbus"));
-        assert!(text.contains("Zip: 47201"));
-        assert!(text.contains("Details: Full kitchen remodel"));
-        assert!(text.contains("Campaign: Fall Promo"));
-        assert!(text.contains("Adset: Indiana Leads"));
-        assert!(text.contains("Ad: Kitchen Ad 1"));
+        assert!(text.contains("<b>Name:</b> Jane Smith"));
+        assert!(text.contains("<b>Phone:</b> 812-374-4195"));
+        assert!(text.contains("<b>Remove and Dispose:</b> No"));
+        assert!(text.contains("<b>Email:</b> jane@example.com"));
+        assert!(text.contains("<b>City:</b> Columbus"));
+        assert!(text.contains("<b>Zip:</b> 47201"));
+        assert!(text.contains("<b>Details:</b> Full kitchen remodel"));
+        assert!(text.contains("<b>Campaign:</b> Fall Promo"));
+        assert!(text.contains("<b>Adset:</b> Indiana Leads"));
+        assert!(text.contains("<b>Ad:</b> Kitchen Ad 1"));
     }
 
     #[test]
@@ -706,7 +795,38 @@ mod tests {
         let form: NewLeadForm = serde_json::from_value(data).unwrap();
         assert_eq!(form.referral_source.as_deref(), Some("website"));
         assert_eq!(form.form_name.as_deref(), Some("cabinet_quote"));
-        assert!(form.to_string().contains("Form Name: cabinet_quote"));
+        assert!(form.to_string().contains("<b>Form Name:</b> cabinet_quote"));
+    }
+
+    #[test]
+    fn test_lead_forms_deserialize_attribution() {
+        let data = json!({
+            "name": "Test",
+            "utm_source": "google",
+            "utm_campaign": "spring",
+            "gclid": "abc123",
+            "landing_page": "https://example.com/kitchens",
+            "referrer": "https://www.google.com/"
+        });
+        let form: NewLeadForm = serde_json::from_value(data.clone()).unwrap();
+        assert_eq!(form.attribution.utm_source.as_deref(), Some("google"));
+        assert_eq!(form.attribution.utm_campaign.as_deref(), Some("spring"));
+        assert_eq!(form.attribution.gclid.as_deref(), Some("abc123"));
+        assert_eq!(
+            form.attribution.referrer.as_deref(),
+            Some("https://www.google.com/")
+        );
+
+        let mut facebook = data;
+        facebook["phone"] = json!("3175551212");
+        let form: FaceBookContactForm = serde_json::from_value(facebook).unwrap();
+        assert_eq!(
+            form.attribution.landing_page.as_deref(),
+            Some("https://example.com/kitchens")
+        );
+
+        let form: NewLeadForm = serde_json::from_value(json!({ "name": "Test" })).unwrap();
+        assert!(form.attribution.is_empty());
     }
 
     #[test]
@@ -716,4 +836,20 @@ mod tests {
         assert_eq!(form.referral_source, None);
         assert_eq!(form.form_name, None);
     }
+
+    #[test]
+    fn test_lead_card_escapes_customer_text_and_skips_missing_fields() {
+        let data = json!({
+            "name": "<b>Bob</b> & Co",
+            "Message": "Budget < 5k",
+            "Email": "   "
+        });
+        let form: WordpressContactForm = serde_json::from_value(data).unwrap();
+
+        let text = form.to_string();
+        assert!(text.contains("<b>Name:</b> &lt;b&gt;Bob&lt;/b&gt; &amp; Co"));
+        assert!(text.contains("<b>Your Message:</b> Budget &lt; 5k"));
+        assert!(!text.contains("Email"));
+        assert!(!text.contains("N/A"));
+    }
 }
@@ -0,0 +1,244 @@
+use crate::crud::lead_forms::LeadFormDefinition;
+use crate::crud::leads::{create_lead_from_form, update_lead_from_form};
+use crate::schemas::add_customer::{LeadAttribution, LeadPayload, NewLeadForm, StoredLead};
+use common::telegram::html::HtmlMessage;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::MySqlPool;
+use sqlx::mysql::MySqlQueryResult;
+use std::fmt;
+
+/// JSON keys of [`NewLeadForm`]; a form definition maps incoming keys onto these.
+pub const CANONICAL_FIELDS: &[&str] = &[
+    "name",
+    "email",
+    "phone",
+    "postal_code",
+    "address",
+    "city",
+    "remodel_type",
+    "project_size",
+    "contact_time",
+    "start_date",
+    "tear_out",
+    "improve_offer",
+    "sink",
+    "stove_type",
+    "backsplash",
+    "your_message",
+    "details",
+    "ad_name",
+    "adset_name",
+    "campaign_name",
+    "file",
+    "referral_source",
+    "utm_source",
+    "utm_medium",
+    "utm_campaign",
+    "utm_term",
+    "utm_content",
+    "gclid",
+    "fbclid",
+    "wbraid",
+    "landing_page",
+    "referrer",
+];
+
+/// A lead posted to a company-defined form. Keys without a customers column are kept
+/// in `extra_fields` and shown in the Telegram message.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FormLead {
+    pub lead: NewLeadForm,
+    pub extra_fields: Map<String, Value>,
+}
+
+fn as_text(value: Value) -> Value {
+    match value {
+        Value::String(_) => value,
+        other => Value::String(other.to_string()),
+    }
+}
+
+fn is_blank(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(text) => text.trim().is_empty(),
+        _ => false,
+    }
+}
+
+impl FormLead {
+    pub fn from_payload(
+        definition: &LeadFormDefinition,
+        payload: Map<String, Value>,
+    ) -> Result<Self, serde_json::Error> {
+        let mut canonical = Map::new();
+        let mut extra_fields = Map::new();
+        for (key, value) in payload {
+            if is_blank(&value) {
+                continue;
+            }
+            let target = definition.field_map.get(&key).unwrap_or(&key);
+            if CANONICAL_FIELDS.contains(&target.as_str()) {
+                canonical.insert(target.clone(), as_text(value));
+            } else {
+                extra_fields.insert(target.clone(), value);
+            }
+        }
+        canonical.insert(
+            "form_name".to_string(),
+            Value::String(definition.form_name.clone()),
+        );
+        if let Some(referral_source) = &definition.referral_source {
+            canonical
+                .entry("referral_source")
+                .or_insert_with(|| Value::String(referral_source.clone()));
+        }
+        let lead = serde_json::from_value(Value::Object(canonical))?;
+        Ok(Self { lead, extra_fields })
+    }
+}
+
+impl LeadPayload for FormLead {
+    fn name(&self) -> &str {
+        self.lead.name()
+    }
+
+    fn email(&self) -> Option<&str> {
+        self.lead.email()
+    }
+
+    fn phone(&self) -> Option<&str> {
+        self.lead.phone()
+    }
+
+    fn postal_code(&self) -> Option<&str> {
+        self.lead.postal_code()
+    }
+
+    fn address(&self) -> Option<&str> {
+        self.lead.address()
+    }
+
+    fn attribution(&self) -> &LeadAttribution {
+        &self.lead.attribution
+    }
+
+    fn comments(&self) -> Vec<&str> {
+        let mut comments = self.lead.comments();
+        comments.extend(self.extra_fields.values().filter_map(Value::as_str));
+        comments
+    }
+
+    fn stored(&self) -> StoredLead {
+        StoredLead::Form(self.clone())
+    }
+
+    fn insert(
+        &self,
+        pool: &MySqlPool,
+        company_id: i32,
+    ) -> impl Future<Output = Result<MySqlQueryResult, sqlx::Error>> + Send {
+        create_lead_from_form(pool, self, company_id)
+    }
+
+    fn update(
+        &self,
+        pool: &MySqlPool,
+        company_id: i32,
+        id: i32,
+    ) -> impl Future<Output = Result<MySqlQueryResult, sqlx::Error>> {
+        update_lead_from_form(pool, self, company_id, id)
+    }
+}
+
+impl fmt::Display for FormLead {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.lead)?;
+        for (key, value) in &self.extra_fields {
+            let text = match value {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            write!(f, "\n{}", HtmlMessage::new().field(key, &text).build())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn definition() -> LeadFormDefinition {
+        LeadFormDefinition {
+            form_name: "kitchen_quiz".to_string(),
+            field_map: HashMap::from([
+                ("Full Name".to_string(), "name".to_string()),
+                ("Cell".to_string(), "phone".to_string()),
+                ("How soon?".to_string(), "start_date".to_string()),
+                ("budget_usd".to_string(), "Budget".to_string()),
+            ]),
+            referral_source: Some("website".to_string()),
+        }
+    }
+
+    fn payload(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_maps_fields_and_keeps_extras() {
+        let lead = FormLead::from_payload(
+            &definition(),
+            payload(json!({
+                "Full Name": "Jane Doe",
+                "Cell": "(317) 555-1212",
+                "How soon?": "This month",
+                "email": "jane@example.com",
+                "budget_usd": 15000,
+                "Cabinet color": "White",
+                "Notes": ""
+            })),
+        )
+        .unwrap();
+
+        assert_eq!(lead.lead.name, "Jane Doe");
+        assert_eq!(lead.lead.phone.as_deref(), Some("317-555-1212"));
+        assert_eq!(lead.lead.when_start.as_deref(), Some("This month"));
+        assert_eq!(lead.lead.email.as_deref(), Some("jane@example.com"));
+        assert_eq!(lead.lead.form_name.as_deref(), Some("kitchen_quiz"));
+        assert_eq!(lead.lead.referral_source.as_deref(), Some("website"));
+        assert_eq!(
+            Value::Object(lead.extra_fields.clone()),
+            json!({ "Budget": 15000, "Cabinet color": "White" })
+        );
+
+        let text = lead.to_string();
+        assert!(text.contains("<b>Form Name:</b> kitchen_quiz"));
+        assert!(text.contains("\n<b>Budget:</b> 15000"));
+        assert!(text.contains("\n<b>Cabinet color:</b> White"));
+    }
+
+    #[test]
+    fn test_payload_referral_source_wins() {
+        let lead = FormLead::from_payload(
+            &definition(),
+            payload(json!({ "Full Name": "Jane", "referral_source": "facebook" })),
+        )
+        .unwrap();
+        assert_eq!(lead.lead.referral_source.as_deref(), Some("facebook"));
+    }
+
+    #[test]
+    fn test_missing_name_is_rejected() {
+        let result =
+            FormLead::from_payload(&definition(), payload(json!({ "Cell": "3175551212" })));
+        assert!(result.is_err());
+    }
+}
@@ -1,2 +1,3 @@
 pub mod add_customer;
 pub mod documenso;
+pub mod form_lead;
@@ -0,0 +1,231 @@
+use crate::axum_helpers::guards::Telegram;
+use crate::crud::deals::{
+    get_deal_company_id, move_deal_to_contacted_if_uncontacted, move_deal_to_next_stage,
+};
+use crate::crud::users::{NotificationsUser, get_notifications_user};
+use crate::libs::constants::{ERR_DB, FORBIDDEN_RESPONSE, OK_RESPONSE, internal_error};
+use crate::libs::types::BasicResponse;
+use common::crud::notifications::snooze_activity_deadline_reminder;
+use common::telegram::actions::{
+    CrmAction, crm_action_markup, crm_actions_secret_from_env, parse_crm_action,
+};
+use common::telegram::html::escape_html;
+use lambda_http::tracing;
+use sqlx::MySqlPool;
+use teloxide::types::MaybeInaccessibleMessage;
+use teloxide::types::{CallbackQuery, InlineKeyboardMarkup, ParseMode};
+
+const ACTION_FORBIDDEN: &str = "This button isn't for you.";
+const ACTION_NOT_FOUND: &str = "This deal is no longer in the CRM.";
+
+/// Inline keyboard with `rows` signed for `telegram_id`, or `None` while actions are disabled.
+pub fn crm_action_keyboard(
+    telegram_id: i64,
+    rows: Vec<Vec<CrmAction>>,
+) -> Option<InlineKeyboardMarkup> {
+    let secret = crm_actions_secret_from_env()?;
+    Some(crm_action_markup(&secret, telegram_id, rows))
+}
+
+async fn deal_belongs_to(
+    pool: &MySqlPool,
+    user: &NotificationsUser,
+    deal_id: u64,
+) -> Result<bool, sqlx::Error> {
+    let company_id = get_deal_company_id(pool, deal_id).await?;
+    Ok(company_id.is_some() && company_id == user.company_id)
+}
+
+async fn run_action(
+    pool: &MySqlPool,
+    user: &NotificationsUser,
+    action: CrmAction,
+) -> Result<String, sqlx::Error> {
+    let outcome = match action {
+        CrmAction::MarkContacted(deal_id) | CrmAction::NextStage(deal_id)
+            if !deal_belongs_to(pool, user, deal_id).await? =>
+        {
+            ACTION_NOT_FOUND.to_string()
+        }
+        CrmAction::MarkContacted(deal_id) => {
+            if move_deal_to_contacted_if_uncontacted(pool, deal_id).await? {
+                "✅ Marked as contacted.".to_string()
+            } else {
+                "The deal is already past its first stage.".to_string()
+            }
+        }
+        CrmAction::NextStage(deal_id) => match move_deal_to_next_stage(pool, deal_id).await? {
+            Some(list_name) => format!("➡️ Moved to {list_name}."),
+            None => "The deal is already in its last stage.".to_string(),
+        },
+        CrmAction::SnoozeHour(notification_id) | CrmAction::SnoozeDay(notification_id) => {
+            let (minutes, label) = if matches!(action, CrmAction::SnoozeHour(_)) {
+                (60, "1 hour")
+            } else {
+                (24 * 60, "1 day")
+            };
+            if snooze_activity_deadline_reminder(pool, notification_id, user.id, minutes).await? > 0
+            {
+                format!("⏰ Snoozed for {label}.")
+            } else {
+                "This reminder is already done.".to_string()
+            }
+        }
+    };
+    Ok(outcome)
+}
+
+async fn handle_crm_action_inner<T: Telegram>(
+    pool: &MySqlPool,
+    bot: &T,
+    cb: &CallbackQuery,
+    secret: &str,
+) -> BasicResponse {
+    let telegram_id = i64::try_from(cb.from.id.0).unwrap_or_default();
+    let Some(action) = cb
+        .data
+        .as_deref()
+        .and_then(|data| parse_crm_action(secret, data, telegram_id))
+    else {
+        let _ = bot.answer_callback_query(cb, ACTION_FORBIDDEN).await;
+        return FORBIDDEN_RESPONSE;
+    };
+    let user = match get_notifications_user(pool, telegram_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            let _ = bot.answer_callback_query(cb, ACTION_FORBIDDEN).await;
+            return FORBIDDEN_RESPONSE;
+        }
+        Err(e) => {
+            tracing::error!(?e, telegram_id, "Failed to load notifications user");
+            return internal_error(ERR_DB);
+        }
+    };
+    let outcome = match run_action(pool, &user, action).await {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            tracing::error!(?e, user_id = user.id, ?action, "Failed to run CRM action");
+            return internal_error(ERR_DB);
+        }
+    };
+    let _ = bot.answer_callback_query(cb, outcome.clone()).await;
+    if let Some(MaybeInaccessibleMessage::Regular(message)) = &cb.message {
+        let text = message.html_text().unwrap_or_default();
+        let _ = bot
+            .edit_formatted_message(
+                message.chat.id.0,
+                message.id.0,
+                format!("{text}\n\n{}", escape_html(&outcome)),
+                ParseMode::Html,
+            )
+            .await;
+    }
+    OK_RESPONSE
+}
+
+/// Runs a button pressed under a CRM notification and records the result on the message.
+pub async fn handle_crm_action<T: Telegram>(
+    pool: &MySqlPool,
+    bot: &T,
+    cb: &CallbackQuery,
+) -> BasicResponse {
+    let Some(secret) = crm_actions_secret_from_env() else {
+        tracing::warn!("TELEGRAM_ACTIONS_SECRET is not set; ignoring CRM action");
+        return OK_RESPONSE;
+    };
+    handle_crm_action_inner(pool, bot, cb, &secret).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::telegram::{MockTelegram, generate_message, telegram_user};
+    use crate::tests::utils::insert_user;
+    use axum::http::StatusCode;
+    use common::telegram::actions::crm_action_callback_data;
+
+    const SECRET: &str = "test-actions-secret";
+
+    fn callback(from: u64, data: String) -> CallbackQuery {
+        CallbackQuery {
+            id: "a".into(),
+            from: telegram_user(from),
+            message: Some(MaybeInaccessibleMessage::Regular(Box::new(
+                generate_message(456, "⏰ Call Jane"),
+            ))),
+            inline_message_id: None,
+            chat_instance: String::new(),
+            data: Some(data),
+            game_short_name: None,
+        }
+    }
+
+    async fn insert_reminder(pool: &MySqlPool) -> u64 {
+        let user_id = insert_user(pool, "rep@example.com", Some(123))
+            .await
+            .unwrap();
+        sqlx::query!(
+            "UPDATE users SET notifications_telegram_id = 456 WHERE id = ?",
+            user_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            r#"
+            INSERT INTO notifications (user_id, message, notification_type, actor_name, due_at)
+            VALUES (?, 'Call Jane', 'activity_deadline_reminder', '__telegram_sent__', UTC_TIMESTAMP())
+            "#,
+            user_id
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_id()
+    }
+
+    async fn is_due(pool: &MySqlPool, notification_id: u64) -> bool {
+        sqlx::query_scalar!(
+            r#"SELECT due_at <= UTC_TIMESTAMP() AS "due!: bool" FROM notifications WHERE id = ?"#,
+            notification_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_snooze_moves_reminder_and_edits_message(pool: MySqlPool) {
+        let notification_id = insert_reminder(&pool).await;
+        let data = crm_action_callback_data(SECRET, CrmAction::SnoozeHour(notification_id), 456);
+        let bot = MockTelegram::new();
+
+        let res = handle_crm_action_inner(&pool, &bot, &callback(456, data), SECRET).await;
+
+        assert_eq!(res.0, StatusCode::OK);
+        assert!(!is_due(&pool, notification_id).await);
+        assert_eq!(
+            bot.answered.lock().unwrap()[0],
+            (456, "⏰ Snoozed for 1 hour.".to_string())
+        );
+        assert_eq!(
+            bot.edited.lock().unwrap()[0].2,
+            "⏰ Call Jane\n\n⏰ Snoozed for 1 hour."
+        );
+        assert_eq!(bot.parse_modes.lock().unwrap()[0], (456, ParseMode::Html));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_forwarded_button_is_rejected(pool: MySqlPool) {
+        let notification_id = insert_reminder(&pool).await;
+        let data = crm_action_callback_data(SECRET, CrmAction::SnoozeDay(notification_id), 456);
+        let bot = MockTelegram::new();
+
+        let res = handle_crm_action_inner(&pool, &bot, &callback(789, data), SECRET).await;
+
+        assert_eq!(res.0, StatusCode::FORBIDDEN);
+        assert!(is_due(&pool, notification_id).await);
+        assert_eq!(bot.answered.lock().unwrap()[0].1, ACTION_FORBIDDEN);
+        assert!(bot.edited.lock().unwrap().is_empty());
+    }
+}
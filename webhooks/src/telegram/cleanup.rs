@@ -150,6 +150,44 @@ mod tests {
         assert_eq!(bot.sent.lock().unwrap().len(), 2);
     }
 
+    #[sqlx::test(migrations = "../migrations")]
+    async fn persists_every_part_of_a_long_lead(pool: MySqlPool) {
+        use crate::telegram::send::send_telegram_manager_assign;
+        use common::telegram::html::MESSAGE_LIMIT;
+
+        let company_id = 1;
+        let customer_id = insert_customer(&pool, company_id).await;
+        let bot = MockTelegram::new();
+        positioned_user(&pool, company_id, SALES_MANAGER, 456).await;
+        let card = "<b>Your Message:</b> long enough to need a second part\n".repeat(120);
+
+        send_telegram_manager_assign(
+            &pool,
+            company_id,
+            card,
+            u64::try_from(customer_id).unwrap(),
+            true,
+            &bot,
+        )
+        .await
+        .unwrap();
+
+        let sent = bot.sent.lock().unwrap().clone();
+        assert!(sent.len() > 1);
+        assert!(
+            sent.iter()
+                .all(|message| message.1.chars().count() <= MESSAGE_LIMIT)
+        );
+        let (last, earlier) = sent.split_last().unwrap();
+        assert!(last.2.is_some());
+        assert!(last.1.ends_with("Choose a salesperson."));
+        assert!(earlier.iter().all(|message| message.2.is_none()));
+        assert_eq!(
+            active_message_count(&pool, company_id, customer_id).await,
+            i64::try_from(sent.len()).unwrap()
+        );
+    }
+
     #[sqlx::test(migrations = "../migrations")]
     async fn cleanup_deletes_all_tracked_messages(pool: MySqlPool) {
         let company_id = 1;
@@ -290,11 +328,11 @@ mod tests {
         positioned_user(&pool, company_id, SALES_MANAGER, 456).await;
         positioned_user(&pool, company_id, SALES_MANAGER, 789).await;
 
-        let sales_message =
-            send_plain_message_to_chat(123, "You received a REPEATED lead Test", &bot)
-                .await
-                .unwrap();
-        persist_lead_message(&pool, customer_id, company_id, &sales_message).await;
+        let sales_text = "You received a REPEATED lead Test";
+        let sales_message = send_plain_message_to_chat(123, sales_text, &bot)
+            .await
+            .unwrap();
+        persist_lead_message(&pool, customer_id, company_id, &sales_message, sales_text).await;
 
         send_telegram_duplicate_notification(
             &pool,
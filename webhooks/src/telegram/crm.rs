@@ -1,15 +1,23 @@
+use common::telegram::actions::deal_action_rows;
 use common::telegram::crm::{
-    format_activity_notification, format_email_notification, format_sms_notification,
+    format_activity_notification, format_email_click_notification, format_email_notification,
+    format_sms_notification,
 };
+use common::telegram::html::{MESSAGE_LIMIT, split_html_message};
 
 use crate::axum_helpers::guards::Telegram;
+use crate::crud::telegram_replies::{insert_email_reply_target, insert_sms_reply_target};
 use crate::crud::users::get_user_notifications_tg_info;
 use crate::libs::constants::ERR_SEND_TELEGRAM;
 use crate::libs::constants::internal_error;
 use crate::libs::types::BasicResponse;
+use crate::telegram::actions::crm_action_keyboard;
 use lambda_http::tracing;
 use sqlx::MySqlPool;
 use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardMarkup, ParseMode};
+
+const REPLY_HINT: &str = "Reply to this message to answer the customer.";
 
 pub struct CrmTelegramNotify {
     pub user_id: i32,
@@ -30,8 +38,20 @@ pub struct InboundEmailTelegramNotify {
 
 pub struct InboundSmsTelegramNotify {
     pub receiver_user_id: i32,
+    pub company_id: i32,
     pub sender_phone: String,
+    /// Company number the SMS was sent to; replies go out from it.
+    pub business_phone: u64,
+    /// Open deal of the sender, for the action buttons.
+    pub deal_id: Option<u64>,
     pub message: String,
+    pub image_urls: Vec<String>,
+}
+
+pub struct EmailClickTelegramNotify {
+    pub receiver_user_id: i32,
+    pub customer_name: Option<String>,
+    pub url: String,
 }
 
 pub async fn send_crm_telegram_notification<T>(
@@ -70,7 +90,12 @@ where
         &payload.message,
         payload.deal_id,
     );
-    send_plain_crm_message(bot, telegram_id, &text).await
+    let actions = u64::try_from(payload.deal_id)
+        .ok()
+        .and_then(|deal_id| crm_action_keyboard(telegram_id, deal_action_rows(deal_id)));
+    send_crm_message(bot, telegram_id, &text, actions)
+        .await
+        .map(|_| ())
 }
 
 pub async fn send_inbound_email_telegram_notification<T>(
@@ -108,7 +133,32 @@ where
         payload.deal_id,
         &payload.thread_id,
     );
-    send_plain_crm_message(bot, telegram_id, &text).await
+    let actions = payload
+        .deal_id
+        .and_then(|deal_id| crm_action_keyboard(telegram_id, deal_action_rows(deal_id)));
+    let message = send_crm_message(
+        bot,
+        telegram_id,
+        &format!("{text}\n\n{REPLY_HINT}"),
+        actions,
+    )
+    .await?;
+    if let Err(error) = insert_email_reply_target(
+        pool,
+        telegram_id,
+        message.id.0,
+        payload.receiver_user_id,
+        &payload.thread_id,
+    )
+    .await
+    {
+        tracing::error!(
+            ?error,
+            thread_id = %payload.thread_id,
+            "Failed to record email reply target"
+        );
+    }
+    Ok(())
 }
 
 pub async fn send_inbound_sms_telegram_notification<T>(
@@ -145,7 +195,77 @@ where
         .chars()
         .filter(|character| character.is_ascii_digit())
         .collect();
-    let text = format_sms_notification(&payload.sender_phone, &payload.message, &phone_digits);
+    let text = format_sms_notification(
+        &payload.sender_phone,
+        &payload.message,
+        &payload.image_urls,
+        &phone_digits,
+    );
+    let actions = payload
+        .deal_id
+        .and_then(|deal_id| crm_action_keyboard(telegram_id, deal_action_rows(deal_id)));
+    let Ok(customer_phone) = phone_digits.parse::<u64>() else {
+        return send_crm_message(bot, telegram_id, &text, actions)
+            .await
+            .map(|_| ());
+    };
+    let message = send_crm_message(
+        bot,
+        telegram_id,
+        &format!("{text}\n\n{REPLY_HINT}"),
+        actions,
+    )
+    .await?;
+    if let Err(error) = insert_sms_reply_target(
+        pool,
+        telegram_id,
+        message.id.0,
+        payload.receiver_user_id,
+        payload.company_id,
+        customer_phone,
+        payload.business_phone,
+    )
+    .await
+    {
+        tracing::error!(
+            ?error,
+            company_id = payload.company_id,
+            "Failed to record sms reply target"
+        );
+    }
+    Ok(())
+}
+
+pub async fn send_email_click_telegram_notification<T>(
+    pool: &MySqlPool,
+    bot: &T,
+    payload: &EmailClickTelegramNotify,
+) -> Result<(), BasicResponse>
+where
+    T: Telegram + Send + Sync,
+{
+    let user = match get_user_notifications_tg_info(pool, payload.receiver_user_id).await {
+        Ok(value) => value,
+        Err(error) => {
+            tracing::error!(
+                ?error,
+                receiver_user_id = payload.receiver_user_id,
+                "Failed to load receiver telegram info for email click"
+            );
+            return Err(internal_error(ERR_SEND_TELEGRAM));
+        }
+    };
+    let Some(user) = user else {
+        return Ok(());
+    };
+    if !user.telegram_email_notifications {
+        return Ok(());
+    }
+    let Some(telegram_id) = user.notifications_telegram_id else {
+        return Ok(());
+    };
+
+    let text = format_email_click_notification(payload.customer_name.as_deref(), &payload.url);
     send_plain_crm_message(bot, telegram_id, &text).await
 }
 
@@ -177,18 +297,84 @@ async fn send_plain_crm_message<T>(
 where
     T: Telegram + Send + Sync,
 {
-    match bot
-        .send_message(ChatId(telegram_id), text.to_string())
+    send_crm_message(bot, telegram_id, text, None)
         .await
-    {
-        Ok(_) => Ok(()),
-        Err(error) => {
-            tracing::error!(
-                ?error,
-                telegram_id = telegram_id,
-                "Failed to send CRM telegram notification"
-            );
-            Err(internal_error(ERR_SEND_TELEGRAM))
+        .map(|_| ())
+}
+
+/// Sends an HTML notification, split to fit Telegram's limit; the keyboard goes on the last
+/// part, which is the message returned.
+async fn send_crm_message<T>(
+    bot: &T,
+    telegram_id: i64,
+    text: &str,
+    mut actions: Option<InlineKeyboardMarkup>,
+) -> Result<Message, BasicResponse>
+where
+    T: Telegram + Send + Sync,
+{
+    let parts = split_html_message(text, MESSAGE_LIMIT);
+    let last_index = parts.len().saturating_sub(1);
+    let mut last_message = None;
+    for (index, part) in parts.into_iter().enumerate() {
+        let keyboard = if index == last_index {
+            actions.take()
+        } else {
+            None
+        };
+        match bot
+            .send_formatted_message(ChatId(telegram_id), part, ParseMode::Html, keyboard)
+            .await
+        {
+            Ok(message) => last_message = Some(message),
+            Err(error) => {
+                tracing::error!(
+                    ?error,
+                    telegram_id = telegram_id,
+                    "Failed to send CRM telegram notification"
+                );
+                return Err(internal_error(ERR_SEND_TELEGRAM));
+            }
         }
     }
+    last_message.ok_or_else(|| internal_error(ERR_SEND_TELEGRAM))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::telegram::MockTelegram;
+
+    #[tokio::test]
+    async fn long_notifications_are_split_with_actions_on_the_last_part() {
+        let bot = MockTelegram::new();
+        let text = format_activity_notification(
+            "note_added",
+            Some("Jane"),
+            None,
+            &"<note>\n".repeat(400),
+            12,
+        );
+
+        let message = send_crm_message(&bot, 456, &text, Some(InlineKeyboardMarkup::default()))
+            .await
+            .unwrap();
+
+        let sent = bot.sent.lock().unwrap();
+        assert_eq!(sent.len(), 2);
+        assert!(
+            sent.iter()
+                .all(|(_, part, _)| part.chars().count() <= MESSAGE_LIMIT)
+        );
+        assert!(sent[0].2.is_none());
+        assert!(sent[1].2.is_some());
+        assert_eq!(message.text(), Some(sent[1].1.as_str()));
+        assert!(
+            bot.parse_modes
+                .lock()
+                .unwrap()
+                .iter()
+                .all(|(_, mode)| *mode == ParseMode::Html)
+        );
+    }
 }
@@ -0,0 +1,275 @@
+use crate::axum_helpers::guards::{RemixBackend, Telegram};
+use crate::crud::telegram_links::{
+    LinkAttemptOutcome, consume_link_token, count_recent_link_failures, insert_link_token,
+    link_telegram_account, record_link_attempt,
+};
+use crate::crud::users::user_has_telegram_id;
+use crate::libs::constants::{ERR_DB, NOT_FOUND_RESPONSE, OK_RESPONSE, internal_error};
+use crate::libs::types::BasicResponse;
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use common::utils::signing::{sign_payload_truncated, verify_truncated_payload};
+use lambda_http::tracing;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use teloxide::types::ChatId;
+
+/// Emailed codes stop working after this long.
+pub const CODE_TTL_MINUTES: u32 = 10;
+/// Deep links from the CRM stop working after this long.
+const LINK_TTL_MINUTES: u32 = 15;
+/// Rejected codes or links, per chat or per account, before linking locks for `LOCKOUT_MINUTES`.
+pub const MAX_LINK_FAILURES: i64 = 5;
+pub const LOCKOUT_MINUTES: u32 = 15;
+/// Codes emailed per chat or per account each hour.
+pub const MAX_CODES_PER_HOUR: i64 = 3;
+
+/// Keeps `<nonce>_<signature>` within Telegram's 64-character start parameter.
+const SIGNATURE_BYTES: usize = 12;
+const ERR_LINK_NOT_CONFIGURED: &str = "telegram_link_not_configured";
+
+pub const LINKED: &str = "Accepted, you are now registered.";
+pub const ALREADY_REGISTERED: &str = "You are already registered";
+pub const LINKING_LOCKED: &str = "Too many attempts. Please try again later.";
+const LINK_INVALID: &str =
+    "This link is invalid or has expired. Please open a new one from the CRM.";
+
+fn link_payload(nonce: &str) -> String {
+    format!("telegram-link:{nonce}")
+}
+
+pub fn link_token(secret: &str, nonce: &str) -> String {
+    format!(
+        "{nonce}_{}",
+        sign_payload_truncated(secret, &link_payload(nonce), SIGNATURE_BYTES)
+    )
+}
+
+/// Nonce of a `link_token`, only when the signature matches.
+pub fn parse_link_token<'a>(secret: &str, token: &'a str) -> Option<&'a str> {
+    let (nonce, signature) = token.split_once('_')?;
+    (nonce.len() == 32
+        && nonce.bytes().all(|byte| byte.is_ascii_hexdigit())
+        && verify_truncated_payload(secret, &link_payload(nonce), signature, SIGNATURE_BYTES))
+    .then_some(nonce)
+}
+
+fn link_secret_from_env() -> Option<String> {
+    std::env::var("TELEGRAM_LINK_SECRET")
+        .ok()
+        .filter(|secret| !secret.is_empty())
+}
+
+/// Logs instead of failing: the audit trail must not block linking.
+pub async fn audit_link_attempt(
+    pool: &MySqlPool,
+    chat_id: ChatId,
+    user_id: Option<i32>,
+    outcome: LinkAttemptOutcome,
+) {
+    if let Err(e) = record_link_attempt(pool, chat_id.0, user_id, outcome).await {
+        tracing::error!(
+            ?e,
+            chat_id = chat_id.0,
+            ?outcome,
+            "Failed to record link attempt"
+        );
+    }
+}
+
+pub async fn is_linking_locked(
+    pool: &MySqlPool,
+    chat_id: ChatId,
+    user_id: Option<i32>,
+) -> Result<bool, sqlx::Error> {
+    Ok(
+        count_recent_link_failures(pool, chat_id.0, user_id, LOCKOUT_MINUTES).await?
+            >= MAX_LINK_FAILURES,
+    )
+}
+
+#[derive(Debug, Serialize)]
+pub struct TelegramLink {
+    pub url: String,
+    pub expires_in_minutes: u32,
+}
+
+/// One-time `t.me` link that connects the bot to `user_id` without an email code.
+pub async fn create_telegram_link(
+    _: RemixBackend,
+    State(pool): State<MySqlPool>,
+    Path(user_id): Path<i32>,
+) -> Result<Json<TelegramLink>, BasicResponse> {
+    let (Some(secret), Ok(bot_username)) = (
+        link_secret_from_env(),
+        std::env::var("TELEGRAM_BOT_USERNAME"),
+    ) else {
+        tracing::error!("TELEGRAM_LINK_SECRET and TELEGRAM_BOT_USERNAME must be set");
+        return Err(internal_error(ERR_LINK_NOT_CONFIGURED));
+    };
+    let nonce = format!("{:032x}", rand::random::<u128>());
+    match insert_link_token(&pool, &nonce, user_id, LINK_TTL_MINUTES).await {
+        Ok(result) if result.rows_affected() > 0 => Ok(Json(TelegramLink {
+            url: format!(
+                "https://t.me/{}?start={}",
+                bot_username.trim_start_matches('@'),
+                link_token(&secret, &nonce)
+            ),
+            expires_in_minutes: LINK_TTL_MINUTES,
+        })),
+        Ok(_) => Err(NOT_FOUND_RESPONSE),
+        Err(e) => {
+            tracing::error!(?e, user_id, "Failed to create telegram link token");
+            Err(internal_error(ERR_DB))
+        }
+    }
+}
+
+async fn link_with_token<T: Telegram>(
+    pool: &MySqlPool,
+    bot: &T,
+    chat_id: ChatId,
+    secret: &str,
+    token: &str,
+) -> BasicResponse {
+    match user_has_telegram_id(pool, chat_id.0).await {
+        Ok(false) => {}
+        Ok(true) => {
+            return bot
+                .send_message(chat_id, ALREADY_REGISTERED)
+                .await
+                .map_or_else(|e| e, |_| OK_RESPONSE);
+        }
+        Err(e) => {
+            tracing::error!(
+                ?e,
+                chat_id = chat_id.0,
+                "Failed to check if user has telegram id"
+            );
+            return internal_error(ERR_DB);
+        }
+    }
+    match is_linking_locked(pool, chat_id, None).await {
+        Ok(false) => {}
+        Ok(true) => {
+            audit_link_attempt(pool, chat_id, None, LinkAttemptOutcome::LockedOut).await;
+            return bot
+                .send_message(chat_id, LINKING_LOCKED)
+                .await
+                .map_or_else(|e| e, |_| OK_RESPONSE);
+        }
+        Err(e) => {
+            tracing::error!(?e, chat_id = chat_id.0, "Failed to check linking lockout");
+            return internal_error(ERR_DB);
+        }
+    }
+    let user_id = match parse_link_token(secret, token) {
+        Some(nonce) => match consume_link_token(pool, nonce, chat_id.0).await {
+            Ok(user_id) => user_id,
+            Err(e) => {
+                tracing::error!(?e, chat_id = chat_id.0, "Failed to use telegram link token");
+                return internal_error(ERR_DB);
+            }
+        },
+        None => None,
+    };
+    let Some(user_id) = user_id else {
+        tracing::warn!(chat_id = chat_id.0, "Rejected telegram link token");
+        audit_link_attempt(pool, chat_id, None, LinkAttemptOutcome::LinkRejected).await;
+        return bot
+            .send_message(chat_id, LINK_INVALID)
+            .await
+            .map_or_else(|e| e, |_| (StatusCode::OK, "Invalid link"));
+    };
+    if let Err(e) = link_telegram_account(pool, user_id, chat_id.0).await {
+        tracing::error!(
+            ?e,
+            chat_id = chat_id.0,
+            user_id,
+            "Failed to set telegram id"
+        );
+        return internal_error(ERR_DB);
+    }
+    audit_link_attempt(pool, chat_id, Some(user_id), LinkAttemptOutcome::Linked).await;
+    bot.send_message(chat_id, LINKED)
+        .await
+        .map_or_else(|e| e, |_| OK_RESPONSE)
+}
+
+/// `/start <token>` from a CRM deep link.
+pub async fn handle_link_token<T: Telegram>(
+    pool: &MySqlPool,
+    bot: &T,
+    chat_id: ChatId,
+    token: &str,
+) -> BasicResponse {
+    let Some(secret) = link_secret_from_env() else {
+        tracing::warn!("TELEGRAM_LINK_SECRET is not set; ignoring telegram link token");
+        return bot
+            .send_message(chat_id, LINK_INVALID)
+            .await
+            .map_or_else(|e| e, |_| OK_RESPONSE);
+    };
+    link_with_token(pool, bot, chat_id, &secret, token).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::telegram::MockTelegram;
+    use crate::tests::utils::insert_user;
+
+    const SECRET: &str = "test-link-secret";
+    const NONCE: &str = "0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn link_token_fits_start_parameter_and_round_trips() {
+        let token = link_token(SECRET, NONCE);
+        assert!(token.len() <= 64);
+        assert!(
+            token
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-')
+        );
+        assert_eq!(parse_link_token(SECRET, &token), Some(NONCE));
+        assert_eq!(parse_link_token("other", &token), None);
+        assert_eq!(parse_link_token(SECRET, &token.replacen('0', "1", 1)), None);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_link_token_links_once(pool: MySqlPool) {
+        let user_id = insert_user(&pool, "rep@example.com", None).await.unwrap();
+        insert_link_token(&pool, NONCE, user_id, LINK_TTL_MINUTES)
+            .await
+            .unwrap();
+        let token = link_token(SECRET, NONCE);
+        let bot = MockTelegram::new();
+
+        let res = link_with_token(&pool, &bot, ChatId(77), SECRET, &token).await;
+        assert_eq!(res.0, StatusCode::OK);
+        let telegram_id =
+            sqlx::query_scalar!("SELECT telegram_id FROM users WHERE id = ?", user_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(telegram_id, Some(77));
+
+        link_with_token(&pool, &bot, ChatId(78), SECRET, &token).await;
+        let sent = bot.sent.lock().unwrap();
+        assert_eq!(sent[0].1, LINKED);
+        assert_eq!(sent[1].1, LINK_INVALID);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_forged_link_tokens_lock_the_chat(pool: MySqlPool) {
+        let bot = MockTelegram::new();
+        for _ in 0..MAX_LINK_FAILURES {
+            link_with_token(&pool, &bot, ChatId(5), SECRET, &link_token("forged", NONCE)).await;
+        }
+        link_with_token(&pool, &bot, ChatId(5), SECRET, &link_token(SECRET, NONCE)).await;
+
+        let sent = bot.sent.lock().unwrap();
+        assert_eq!(sent.last().unwrap().1, LINKING_LOCKED);
+    }
+}
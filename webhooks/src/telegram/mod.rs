@@ -1,7 +1,11 @@
+pub mod actions;
 pub mod cleanup;
 pub mod crm;
 pub mod crm_notify;
+pub mod link;
 pub mod notifications_notify;
 pub mod receive;
+pub mod rep_commands;
+pub mod replies;
 pub mod send;
 pub mod utils;
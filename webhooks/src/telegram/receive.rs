@@ -2,38 +2,78 @@ use crate::axum_helpers::guards::{Telegram, TelegramBot};
 use crate::cloudtalk::api::sync_customer_to_cloud_talk;
 use crate::crud::leads::create_deal;
 use crate::crud::leads::{assign_lead, get_default_list_id_from_company_id};
+use crate::crud::spam::{get_quarantined_lead, reopen_quarantined_lead, review_quarantined_lead};
+use crate::crud::telegram_groups::{
+    link_lead_group, list_linked_managers, migrate_lead_group, unlink_lead_group,
+};
+use crate::crud::telegram_links::{
+    LinkAttemptOutcome, clear_telegram_code, count_recent_codes_sent, get_pending_telegram_code,
+    link_telegram_account,
+};
 use crate::crud::telegram_messages::list_active_manager_telegram_lead_messages;
 use crate::crud::user_position::get_user_position;
-use crate::crud::users::{email_exists, get_user_tg_info, user_has_telegram_id};
-use crate::crud::users::{get_user_telegram_token, set_telegram_id, set_user_telegram_token};
+use crate::crud::users::{email_exists, get_sales_users, get_user_tg_info, user_has_telegram_id};
+use crate::crud::users::{get_id_by_email, set_user_telegram_token};
 use crate::libs::constants::{ERR_DB, ERR_SEND_EMAIL, OK_RESPONSE};
-use crate::libs::constants::{FORBIDDEN_RESPONSE, internal_error};
+use crate::libs::constants::{
+    FORBIDDEN_RESPONSE, NOT_FOUND_RESPONSE, SALES_MANAGER, internal_error,
+};
+use crate::libs::leads::process_approved_lead;
 use crate::libs::types::BasicResponse;
+use crate::schemas::add_customer::StoredLead;
+use crate::telegram::actions::handle_crm_action;
+use crate::telegram::link::{
+    ALREADY_REGISTERED, CODE_TTL_MINUTES, LINKED, LINKING_LOCKED, MAX_CODES_PER_HOUR,
+    audit_link_attempt, handle_link_token, is_linking_locked,
+};
+use crate::telegram::rep_commands::{handle_rep_command, handle_rep_page};
 use crate::telegram::utils::extract_message;
 use crate::telegram::utils::parse_code;
-use crate::telegram::utils::{gen_code, lead_url, parse_assign, parse_slash_email};
+use crate::telegram::utils::{
+    QuarantineDecision, gen_code, is_command, lead_url, parse_assign, parse_quarantine_review,
+    parse_rep_page, parse_slash_email,
+};
 use axum::extract::State;
 use axum::http::StatusCode;
 use common::amazon::email::send_message;
 use common::crud::scheduled_emails::schedule_templates_for_deal_list;
+use common::telegram::html::escape_html;
 use lambda_http::tracing;
 use reqwest::Client;
 use sqlx::MySqlPool;
 use teloxide::prelude::*;
-use teloxide::types::{ChatId, Update, UpdateKind};
+use teloxide::types::{
+    ChatId, ChatMemberUpdated, MaybeInaccessibleMessage, ParseMode, Update, UpdateKind,
+};
 
 const MESSAGE: &str = r"
 Invalid message. Please send one of the following commands:
 /email <email>
 <code>
+/leads - your open deals
+/today - activities due today
+/find <name or phone> - search your customers
+/stats - this month's leads and won deals
 ";
 
+const CODE_EXPIRED: &str = "This code has expired. Send /email <email> to get a new one.";
+
+const GROUP_LINKED: &str = "This group now gets new leads. Managers can assign them here.";
+const GROUP_LINK_FORBIDDEN: &str =
+    "Only a sales manager who linked Telegram to the CRM can connect this group.";
+const GROUP_LINK_AMBIGUOUS: &str =
+    "You manage more than one company. Ask a manager of only this company to connect the group.";
+const APPROVAL_FAILED: &str = "Could not create this lead. Please try approving it again.";
+
+/// Appends `status` to every part of the lead sent to managers. Parts stored without their
+/// text get `fallback`, the message whose button was pressed.
 async fn update_manager_lead_messages<T: Telegram>(
     pool: &MySqlPool,
     bot: &T,
     company_id: i32,
     lead_id: i32,
-    content: &str,
+    fallback: &str,
+    status: &str,
 ) {
     let messages = match list_active_manager_telegram_lead_messages(pool, company_id, lead_id).await
     {
@@ -50,8 +90,14 @@ async fn update_manager_lead_messages<T: Telegram>(
     };
 
     for message in messages {
+        let original = message.message_text.as_deref().unwrap_or(fallback);
         if let Err(error) = bot
-            .edit_message_text(message.chat_id, message.message_id, content.to_string())
+            .edit_formatted_message(
+                message.chat_id,
+                message.message_id,
+                format!("{}\n\n{status}", original.trim_end()),
+                ParseMode::Html,
+            )
             .await
         {
             tracing::error!(
@@ -81,15 +127,44 @@ async fn handle_start_command<T: Telegram>(
     };
     if has_tg_id {
         return bot
-            .send_message(chat_id, "You are already registered")
+            .send_message(chat_id, ALREADY_REGISTERED)
             .await
             .map_or_else(
                 |e| e,
                 |_| (StatusCode::OK, "User already has a telegram id"),
             );
     }
+    let user_id = match get_id_by_email(pool, email).await {
+        Ok(user_id) => user_id,
+        Err(e) => {
+            tracing::error!(?e, email = email, "Failed to get user by email");
+            return internal_error(ERR_DB);
+        }
+    };
+    let throttled = match (
+        is_linking_locked(pool, chat_id, user_id).await,
+        count_recent_codes_sent(pool, chat_id.0, user_id, 60).await,
+    ) {
+        (Ok(locked), Ok(codes_sent)) => locked || codes_sent >= MAX_CODES_PER_HOUR,
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::error!(?e, chat_id = chat_id.0, "Failed to check linking limits");
+            return internal_error(ERR_DB);
+        }
+    };
+    if throttled {
+        tracing::warn!(
+            chat_id = chat_id.0,
+            email = email,
+            "Telegram link code throttled"
+        );
+        audit_link_attempt(pool, chat_id, user_id, LinkAttemptOutcome::LockedOut).await;
+        return bot
+            .send_message(chat_id, LINKING_LOCKED)
+            .await
+            .map_or_else(|e| e, |_| (StatusCode::OK, "Too many attempts"));
+    }
     let code = gen_code();
-    let db_result = set_user_telegram_token(pool, chat_id.0, code, email).await;
+    let db_result = set_user_telegram_token(pool, chat_id.0, code, email, CODE_TTL_MINUTES).await;
     if db_result.is_err() {
         tracing::error!(
             ?db_result,
@@ -99,10 +174,11 @@ async fn handle_start_command<T: Telegram>(
         );
         return internal_error(ERR_DB);
     }
+    audit_link_attempt(pool, chat_id, user_id, LinkAttemptOutcome::CodeSent).await;
     let message_result = send_message(
         &[email],
         "Graninte Manager Code",
-        &format!("Your code is: {code}"),
+        &format!("Your code is: {code}. It expires in {CODE_TTL_MINUTES} minutes."),
     )
     .await;
     if let Err(e) = message_result {
@@ -136,15 +212,15 @@ async fn handle_telegram_code<T: Telegram>(
     };
     if has_tg_id {
         return bot
-            .send_message(chat_id, "You are already registered")
+            .send_message(chat_id, ALREADY_REGISTERED)
             .await
             .map_or_else(
                 |e| e,
                 |_| (StatusCode::OK, "User already has a telegram id"),
             );
     }
-    let db_code = match get_user_telegram_token(pool, chat_id.0).await {
-        Ok(Some(db_code)) => db_code,
+    let pending = match get_pending_telegram_code(pool, chat_id.0).await {
+        Ok(Some(pending)) => pending,
         Ok(None) => {
             tracing::error!(
                 chat_id = chat_id.0,
@@ -160,28 +236,163 @@ async fn handle_telegram_code<T: Telegram>(
             return internal_error(ERR_DB);
         }
     };
-    if db_code == code {
-        if let Err(e) = set_telegram_id(pool, chat_id.0).await {
+    let user_id = Some(pending.user_id);
+    let locked = match is_linking_locked(pool, chat_id, user_id).await {
+        Ok(locked) => locked,
+        Err(e) => {
+            tracing::error!(?e, chat_id = chat_id.0, "Failed to check linking lockout");
+            return internal_error(ERR_DB);
+        }
+    };
+    if locked || pending.expired {
+        // Clear the code so guessing cannot resume once the lockout ends.
+        if let Err(e) = clear_telegram_code(pool, pending.user_id).await {
+            tracing::error!(?e, chat_id = chat_id.0, "Failed to clear telegram code");
+            return internal_error(ERR_DB);
+        }
+        let reply = if locked {
+            audit_link_attempt(pool, chat_id, user_id, LinkAttemptOutcome::LockedOut).await;
+            LINKING_LOCKED
+        } else {
+            CODE_EXPIRED
+        };
+        return bot
+            .send_message(chat_id, reply)
+            .await
+            .map_or_else(|e| e, |_| (StatusCode::OK, "Invalid code"));
+    }
+    if pending.code == code {
+        if let Err(e) = link_telegram_account(pool, pending.user_id, chat_id.0).await {
             tracing::error!(?e, chat_id = chat_id.0, "Failed to set telegram id");
             return internal_error(ERR_DB);
         }
+        audit_link_attempt(pool, chat_id, user_id, LinkAttemptOutcome::Linked).await;
         return bot
-            .send_message(chat_id, "Accepted, you are now registered.")
+            .send_message(chat_id, LINKED)
             .await
             .map_or_else(|e| e, |_| OK_RESPONSE);
     }
+    tracing::warn!(chat_id = chat_id.0, "Invalid telegram link code");
+    audit_link_attempt(pool, chat_id, user_id, LinkAttemptOutcome::CodeRejected).await;
     bot.send_message(chat_id, "Invalid code")
         .await
         .map_or_else(|e| e, |_| (StatusCode::OK, "Invalid code"))
 }
 
+/// `/linkgroup` from a linked manager makes the group their company's lead room.
+async fn handle_link_group<T: Telegram>(msg: &Message, pool: &MySqlPool, bot: &T) -> BasicResponse {
+    let chat_id = msg.chat.id;
+    let telegram_id = msg
+        .from
+        .as_ref()
+        .and_then(|user| i64::try_from(user.id.0).ok())
+        .unwrap_or_default();
+    let managers = match list_linked_managers(pool, telegram_id).await {
+        Ok(managers) => managers,
+        Err(e) => {
+            tracing::error!(?e, telegram_id, "Failed to get linked manager");
+            return internal_error(ERR_DB);
+        }
+    };
+    let manager = match managers.as_slice() {
+        [manager] => manager,
+        [] => {
+            tracing::warn!(
+                chat_id = chat_id.0,
+                telegram_id,
+                "Lead group link attempted by someone who is not a linked manager"
+            );
+            return bot
+                .send_message(chat_id, GROUP_LINK_FORBIDDEN)
+                .await
+                .map_or_else(|e| e, |_| FORBIDDEN_RESPONSE);
+        }
+        _ => {
+            tracing::warn!(
+                chat_id = chat_id.0,
+                telegram_id,
+                "Lead group link attempted by a manager of several companies"
+            );
+            return bot
+                .send_message(chat_id, GROUP_LINK_AMBIGUOUS)
+                .await
+                .map_or_else(|e| e, |_| FORBIDDEN_RESPONSE);
+        }
+    };
+    if let Err(e) = link_lead_group(
+        pool,
+        manager.company_id,
+        chat_id.0,
+        msg.chat.title(),
+        manager.user_id,
+    )
+    .await
+    {
+        tracing::error!(?e, chat_id = chat_id.0, "Failed to link lead group");
+        return internal_error(ERR_DB);
+    }
+    bot.send_message(chat_id, GROUP_LINKED)
+        .await
+        .map_or_else(|e| e, |_| OK_RESPONSE)
+}
+
+/// Groups only get commands addressed to the bot; other chatter is ignored.
+async fn handle_group_message<T: Telegram>(
+    msg: &Message,
+    pool: &MySqlPool,
+    bot: &T,
+) -> BasicResponse {
+    if let Some(new_chat_id) = msg.migrate_to_chat_id() {
+        if let Err(e) = migrate_lead_group(pool, msg.chat.id.0, new_chat_id.0).await {
+            tracing::error!(?e, chat_id = msg.chat.id.0, "Failed to move lead group");
+            return internal_error(ERR_DB);
+        }
+        return OK_RESPONSE;
+    }
+    let Some(text) = msg.text() else {
+        return OK_RESPONSE;
+    };
+    if is_command(text, "/linkgroup") {
+        return handle_link_group(msg, pool, bot).await;
+    }
+    OK_RESPONSE
+}
+
+/// Forgets the lead group once the bot is removed from it, so leads go back to managers.
+async fn handle_bot_membership(update: &ChatMemberUpdated, pool: &MySqlPool) -> BasicResponse {
+    if update.new_chat_member.is_present() {
+        return OK_RESPONSE;
+    }
+    match unlink_lead_group(pool, update.chat.id.0).await {
+        Ok(0) => OK_RESPONSE,
+        Ok(_) => {
+            tracing::warn!(chat_id = update.chat.id.0, "Bot removed from lead group");
+            OK_RESPONSE
+        }
+        Err(e) => {
+            tracing::error!(
+                ?e,
+                chat_id = update.chat.id.0,
+                "Failed to unlink lead group"
+            );
+            internal_error(ERR_DB)
+        }
+    }
+}
+
 async fn handle_message<T: Telegram>(msg: Message, pool: &MySqlPool, bot: &T) -> BasicResponse {
     let chat_id = msg.chat.id; // ChatId
+    if !chat_id.is_user() {
+        return handle_group_message(&msg, pool, bot).await;
+    }
     let Some(text) = msg.text() else {
         return OK_RESPONSE;
     };
 
     if text.starts_with("/start") {
+        if let Some(token) = text.split_whitespace().nth(1) {
+            return handle_link_token(pool, bot, chat_id, token).await;
+        }
         let full_message = "Welcome to our bot! Please send: /email <email>";
         return bot
             .send_message(chat_id, full_message)
@@ -209,6 +420,10 @@ async fn handle_message<T: Telegram>(msg: Message, pool: &MySqlPool, bot: &T) ->
         return handle_telegram_code(pool, bot, chat_id, code).await;
     }
 
+    if let Some(response) = handle_rep_command(pool, bot, chat_id, text).await {
+        return response;
+    }
+
     bot.send_message(chat_id, MESSAGE)
         .await
         .map_or_else(|e| e, |_| (StatusCode::OK, "Invalid code"))
@@ -235,6 +450,28 @@ async fn handle_assign_lead<T: Telegram>(
             return internal_error(ERR_DB);
         }
     };
+    // Anyone in a lead group can tap the keyboard, so only the company's managers count.
+    if !message.chat().id.is_user() {
+        let telegram_id = i64::try_from(cb.from.id.0).unwrap_or_default();
+        match list_linked_managers(pool, telegram_id).await {
+            Ok(managers)
+                if managers
+                    .iter()
+                    .any(|manager| manager.company_id == position.company_id) => {}
+            Ok(_) => {
+                tracing::warn!(
+                    lead_id,
+                    telegram_id,
+                    "Lead group assignment by someone who is not a manager"
+                );
+                return FORBIDDEN_RESPONSE;
+            }
+            Err(e) => {
+                tracing::error!(?e, telegram_id, "Failed to get linked manager");
+                return internal_error(ERR_DB);
+            }
+        }
+    }
     let lead_result = assign_lead(pool, lead_id, position.user_id).await;
     if let Err(e) = lead_result {
         tracing::error!(
@@ -264,7 +501,7 @@ async fn handle_assign_lead<T: Telegram>(
     let former_message = extract_message(&message).unwrap_or_default();
 
     let user_name = tg_info.name.unwrap_or_else(|| "Unknown".to_string());
-    let full_content = format!("{former_message}\n\nLead assigned to {user_name}");
+    let status = format!("Lead assigned to {}", escape_html(&user_name));
 
     let list_id = get_default_list_id_from_company_id(pool, position.company_id)
         .await
@@ -281,7 +518,15 @@ async fn handle_assign_lead<T: Telegram>(
             return internal_error(ERR_DB);
         }
     };
-    update_manager_lead_messages(pool, bot, position.company_id, lead_id, &full_content).await;
+    update_manager_lead_messages(
+        pool,
+        bot,
+        position.company_id,
+        lead_id,
+        &former_message,
+        &status,
+    )
+    .await;
     if result.created {
         schedule_templates_for_deal_list(
             pool,
@@ -330,17 +575,117 @@ async fn handle_assign_lead<T: Telegram>(
     OK_RESPONSE
 }
 
-async fn handle_callback<T: Telegram>(
-    cb: CallbackQuery,
+/// Only managers of the lead's company may approve or reject it. The first decision
+/// wins; later clicks just mark the message as already reviewed.
+async fn handle_quarantine_review<T>(
     pool: &MySqlPool,
+    quarantine_id: i32,
+    decision: QuarantineDecision,
     bot: &T,
-) -> BasicResponse {
+    cb: CallbackQuery,
+) -> BasicResponse
+where
+    T: Telegram + Send + Sync + 'static + Clone,
+{
+    let Some(MaybeInaccessibleMessage::Regular(message)) = &cb.message else {
+        return (StatusCode::NOT_FOUND, "Invalid message");
+    };
+    let lead = match get_quarantined_lead(pool, quarantine_id).await {
+        Ok(Some(lead)) => lead,
+        Ok(None) => return NOT_FOUND_RESPONSE,
+        Err(e) => {
+            tracing::error!(?e, quarantine_id, "Failed to get quarantined lead");
+            return internal_error(ERR_DB);
+        }
+    };
+    let reviewer_telegram_id = i64::try_from(cb.from.id.0).unwrap_or_default();
+    let reviewer = match get_sales_users(pool, lead.company_id).await {
+        Ok(users) => users.into_iter().find(|user| {
+            user.position_id == SALES_MANAGER && user.telegram_id == Some(reviewer_telegram_id)
+        }),
+        Err(e) => {
+            tracing::error!(?e, company_id = lead.company_id, "Error fetching users");
+            return internal_error(ERR_DB);
+        }
+    };
+    let Some(reviewer) = reviewer else {
+        tracing::error!(
+            quarantine_id,
+            telegram_id = reviewer_telegram_id,
+            "Quarantined lead reviewed by someone who is not a manager"
+        );
+        return FORBIDDEN_RESPONSE;
+    };
+
+    let claimed =
+        match review_quarantined_lead(pool, quarantine_id, decision.status(), reviewer.id).await {
+            Ok(claimed) => claimed,
+            Err(e) => {
+                tracing::error!(?e, quarantine_id, "Failed to review quarantined lead");
+                return internal_error(ERR_DB);
+            }
+        };
+    let former_message = message.html_text().unwrap_or_default();
+    let reviewer_name = escape_html(reviewer.name.as_deref().unwrap_or("Unknown"));
+    let outcome = if !claimed {
+        "Already reviewed by another manager".to_string()
+    } else if decision == QuarantineDecision::Reject {
+        format!("🚫 Rejected by {reviewer_name}")
+    } else {
+        let created = match serde_json::from_str::<StoredLead>(&lead.payload) {
+            Ok(stored) => process_approved_lead(pool, lead.company_id, &stored, bot).await,
+            Err(e) => {
+                tracing::error!(?e, quarantine_id, "Failed to read quarantined lead");
+                internal_error("Failed to read quarantined lead")
+            }
+        };
+        if !created.0.is_success() {
+            // Keep the buttons and the pending status so the approval can be retried.
+            if let Err(e) = reopen_quarantined_lead(pool, quarantine_id).await {
+                tracing::error!(?e, quarantine_id, "Failed to reopen quarantined lead");
+            }
+            let _ = bot.answer_callback_query(&cb, APPROVAL_FAILED).await;
+            return created;
+        }
+        format!("✅ Approved by {reviewer_name}")
+    };
+    if let Err(e) = bot
+        .edit_formatted_message(
+            message.chat.id.0,
+            message.id.0,
+            format!("{former_message}\n\n{outcome}"),
+            ParseMode::Html,
+        )
+        .await
+    {
+        tracing::error!(
+            ?e,
+            quarantine_id,
+            "Failed to update quarantined lead message"
+        );
+    }
+    OK_RESPONSE
+}
+
+async fn handle_callback<T>(cb: CallbackQuery, pool: &MySqlPool, bot: &T) -> BasicResponse
+where
+    T: Telegram + Send + Sync + 'static + Clone,
+{
     let Some(data) = &cb.data else {
         return OK_RESPONSE;
     };
     if let Some((lead_id, user_position_id)) = parse_assign(data) {
         return handle_assign_lead(pool, lead_id, user_position_id, bot, cb).await;
     }
+    if let Some((decision, quarantine_id)) = parse_quarantine_review(data) {
+        return handle_quarantine_review(pool, quarantine_id, decision, bot, cb).await;
+    }
+    if let Some((list, page, query)) = parse_rep_page(data) {
+        return handle_rep_page(pool, bot, &cb, list, page, query).await;
+    }
+    if data.starts_with("crm:") {
+        return handle_crm_action(pool, bot, &cb).await;
+    }
     OK_RESPONSE
 }
 
@@ -352,6 +697,7 @@ pub async fn webhook_handler(
     match update.kind {
         UpdateKind::Message(msg) => handle_message(msg, &pool, &tg_bot).await,
         UpdateKind::CallbackQuery(cb) => handle_callback(cb, &pool, &tg_bot).await,
+        UpdateKind::MyChatMember(update) => handle_bot_membership(&update, &pool).await,
         _ => OK_RESPONSE,
     }
 }
@@ -360,6 +706,7 @@ pub async fn webhook_handler(
 mod local_tests {
     use super::*;
     use crate::schemas::add_customer::NewLeadForm;
+    use crate::telegram::link::MAX_LINK_FAILURES;
     use crate::tests::telegram::{MockTelegram, generate_message, telegram_user};
     use crate::tests::utils::{assigned_user_position, insert_user, positioned_user};
     use crate::webhooks::receive::new_lead_form_inner;
@@ -624,7 +971,13 @@ mod local_tests {
             .await
             .unwrap();
         sqlx::query!(
-            "UPDATE users SET telegram_conf_code = 555, temp_telegram_id = 3 WHERE id = ?",
+            r#"
+            UPDATE users
+            SET telegram_conf_code = 555,
+                telegram_conf_expires_at = UTC_TIMESTAMP() + INTERVAL 10 MINUTE,
+                temp_telegram_id = 3
+            WHERE id = ?
+            "#,
             user_id
         )
         .execute(&pool)
@@ -646,7 +999,13 @@ mod local_tests {
             .await
             .unwrap();
         sqlx::query!(
-            "UPDATE users SET telegram_conf_code = 999, temp_telegram_id = 4 WHERE id = ?",
+            r#"
+            UPDATE users
+            SET telegram_conf_code = 999,
+                telegram_conf_expires_at = UTC_TIMESTAMP() + INTERVAL 10 MINUTE,
+                temp_telegram_id = 4
+            WHERE id = ?
+            "#,
             user_id
         )
         .execute(&pool)
@@ -666,6 +1025,89 @@ mod local_tests {
         assert!(sent[0].1.contains("Accepted, you are now registered"));
     }
 
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_code_expired(pool: MySqlPool) {
+        let bot = MockTelegram::new();
+
+        let user_id = create_default_user(&pool, "test@example.com")
+            .await
+            .unwrap();
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET telegram_conf_code = 999,
+                telegram_conf_expires_at = UTC_TIMESTAMP() - INTERVAL 1 MINUTE,
+                temp_telegram_id = 4
+            WHERE id = ?
+            "#,
+            user_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        handle_telegram_code(&pool, &bot, chat_id(4), 999).await;
+
+        let user = sqlx::query!(
+            "SELECT telegram_id, telegram_conf_code FROM users WHERE id = ?",
+            user_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(user.telegram_id, None);
+        assert_eq!(user.telegram_conf_code, None);
+        assert_eq!(bot.sent.lock().unwrap()[0].1, CODE_EXPIRED);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_code_locks_after_repeated_failures(pool: MySqlPool) {
+        let bot = MockTelegram::new();
+
+        let user_id = create_default_user(&pool, "test@example.com")
+            .await
+            .unwrap();
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET telegram_conf_code = 999,
+                telegram_conf_expires_at = UTC_TIMESTAMP() + INTERVAL 10 MINUTE,
+                temp_telegram_id = 4
+            WHERE id = ?
+            "#,
+            user_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        for guess in 0..MAX_LINK_FAILURES {
+            handle_telegram_code(
+                &pool,
+                &bot,
+                chat_id(4),
+                100_000 + i32::try_from(guess).unwrap(),
+            )
+            .await;
+        }
+        handle_telegram_code(&pool, &bot, chat_id(4), 999).await;
+
+        let user = sqlx::query!("SELECT telegram_id FROM users WHERE id = ?", user_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(user.telegram_id, None);
+        assert_eq!(bot.sent.lock().unwrap().last().unwrap().1, LINKING_LOCKED);
+        let rejected: i64 = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM telegram_link_attempts WHERE outcome = 'code_rejected' AND user_id = ?",
+            user_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(rejected, MAX_LINK_FAILURES);
+    }
+
     // -----------------------------
     // handle_message
     // -----------------------------
@@ -961,8 +1403,18 @@ mod local_tests {
         let edited_pairs: Vec<(i64, i32)> = edited.iter().map(|entry| (entry.0, entry.1)).collect();
         assert_eq!(edited_pairs, expected_pairs);
 
-        let expected_text = format!("{original_text}\n\nLead assigned to Alex Sales");
+        // Each message keeps its own stored text, not the text of the one that was pressed.
+        let stored_text = persisted[0].message_text.clone().unwrap();
+        assert_ne!(stored_text, original_text);
+        let expected_text = format!("{}\n\nLead assigned to Alex Sales", stored_text.trim_end());
         assert!(edited.iter().all(|entry| entry.2 == expected_text));
+        assert!(
+            bot.parse_modes
+                .lock()
+                .unwrap()
+                .iter()
+                .all(|(_, mode)| *mode == ParseMode::Html)
+        );
 
         let assigned_notice = bot
             .sent
@@ -1154,4 +1606,206 @@ mod local_tests {
             expected_send_at
         );
     }
+
+    fn review_callback(from: u64, data: String) -> CallbackQuery {
+        let inner_m = generate_message(456, "🛡 Held as possible spam");
+        CallbackQuery {
+            id: "a".into(),
+            from: telegram_user(from),
+            message: Some(MaybeInaccessibleMessage::Regular(Box::new(inner_m))),
+            inline_message_id: None,
+            chat_instance: "".into(),
+            data: Some(data),
+            game_short_name: None,
+        }
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_spam_lead_is_quarantined_until_a_manager_approves(pool: MySqlPool) {
+        positioned_user(&pool, 1, 1, 123).await;
+        positioned_user(&pool, 1, 2, 456).await;
+        sqlx::query!("INSERT INTO lead_spam_settings (company_id) VALUES (1)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let bot = MockTelegram::new();
+        let lead: NewLeadForm = serde_json::from_value(json!({
+            "name": "Cheap Backlinks",
+            "email": "seo@mailinator.com",
+            "phone": "555-555-5555",
+            "your_message": "Rank #1 on Google: https://example.com"
+        }))
+        .unwrap();
+
+        let res = new_lead_form_inner(1, pool.clone(), lead, &bot).await;
+        assert_eq!(res.0, StatusCode::CREATED);
+        let customers = sqlx::query_scalar!("SELECT COUNT(*) FROM customers")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(customers, 0);
+        let sent = bot.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, 456);
+        assert!(
+            sent[0]
+                .1
+                .contains("disposable email, link in message, fake phone")
+        );
+        let approve = match sent[0].2.clone().unwrap().inline_keyboard[0][0]
+            .clone()
+            .kind
+        {
+            InlineKeyboardButtonKind::CallbackData(data) => data,
+            _ => unreachable!(),
+        };
+
+        let res = handle_callback(review_callback(123, approve.clone()), &pool, &bot).await;
+        assert_eq!(res, FORBIDDEN_RESPONSE);
+
+        let res = handle_callback(review_callback(456, approve.clone()), &pool, &bot).await;
+        assert_eq!(res.0, StatusCode::OK);
+        let name = sqlx::query_scalar!("SELECT name FROM customers")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(name.as_deref(), Some("Cheap Backlinks"));
+        let edited = bot.edited.lock().unwrap().clone();
+        assert!(edited[0].2.ends_with("✅ Approved by Unknown"));
+
+        let res = handle_callback(review_callback(456, approve), &pool, &bot).await;
+        assert_eq!(res.0, StatusCode::OK);
+        let customers = sqlx::query_scalar!("SELECT COUNT(*) FROM customers")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(customers, 1);
+        let edited = bot.edited.lock().unwrap().clone();
+        assert!(edited[1].2.ends_with("Already reviewed by another manager"));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_failed_approval_can_be_retried(pool: MySqlPool) {
+        positioned_user(&pool, 1, 2, 456).await;
+        let quarantine_id = sqlx::query!(
+            "INSERT INTO quarantined_leads (company_id, payload, score, reasons) VALUES (1, 'not a lead', 90, 'link in message')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        let bot = MockTelegram::new();
+        let approve = format!("spam:approve:{quarantine_id}");
+
+        let res = handle_callback(review_callback(456, approve), &pool, &bot).await;
+
+        assert!(!res.0.is_success());
+        let status = sqlx::query_scalar!(
+            "SELECT status FROM quarantined_leads WHERE id = ?",
+            quarantine_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(status, "pending");
+        assert!(bot.edited.lock().unwrap().is_empty());
+        assert_eq!(bot.answered.lock().unwrap()[0].1, APPROVAL_FAILED);
+    }
+
+    fn group_assign_callback(from: u64, data: String) -> CallbackQuery {
+        let inner_m = generate_message(-100_500, "New lead");
+        CallbackQuery {
+            id: "a".into(),
+            from: telegram_user(from),
+            message: Some(MaybeInaccessibleMessage::Regular(Box::new(inner_m))),
+            inline_message_id: None,
+            chat_instance: "".into(),
+            data: Some(data),
+            game_short_name: None,
+        }
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_lead_group_gets_lead_once_and_only_managers_assign(pool: MySqlPool) {
+        let sales_id = positioned_user(&pool, 1, 1, 123).await;
+        // generate_message sends as Telegram user 1
+        positioned_user(&pool, 1, 2, 1).await;
+        let bot = MockTelegram::new();
+        let res = handle_message(
+            generate_message(-100_500, "/linkgroup@GraniteBot"),
+            &pool,
+            &bot,
+        )
+        .await;
+        assert_eq!(res.0, StatusCode::OK);
+        assert_eq!(bot.sent.lock().unwrap()[0].1, GROUP_LINKED);
+
+        let (_, bot) = send_lead(&pool).await;
+        let sent = bot.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, -100_500);
+        let option = match sent[0].2.clone().unwrap().inline_keyboard[0][0]
+            .clone()
+            .kind
+        {
+            InlineKeyboardButtonKind::CallbackData(data) => data,
+            _ => unreachable!(),
+        };
+
+        let res = handle_callback(group_assign_callback(999, option.clone()), &pool, &bot).await;
+        assert_eq!(res, FORBIDDEN_RESPONSE);
+        assert!(get_all_deals(&pool).await.is_empty());
+
+        let res = handle_callback(group_assign_callback(1, option), &pool, &bot).await;
+        assert_eq!(res.0, StatusCode::OK);
+        assert_eq!(get_all_deals(&pool).await[0].user_id, Some(sales_id));
+        let edited = bot.edited.lock().unwrap().clone();
+        assert_eq!(edited.len(), 1);
+        assert_eq!(edited[0].0, -100_500);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_group_link_needs_a_manager_and_ignores_chatter(pool: MySqlPool) {
+        positioned_user(&pool, 1, 1, 1).await;
+        let bot = MockTelegram::new();
+
+        let res = handle_message(generate_message(-100_500, "/linkgroup"), &pool, &bot).await;
+        assert_eq!(res, FORBIDDEN_RESPONSE);
+        let res = handle_message(generate_message(-100_500, "hello team"), &pool, &bot).await;
+        assert_eq!(res, OK_RESPONSE);
+
+        let sent = bot.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].1, GROUP_LINK_FORBIDDEN);
+        let groups = sqlx::query_scalar!("SELECT COUNT(*) FROM telegram_lead_groups")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(groups, 0);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_manager_of_several_companies_cannot_link_a_group(pool: MySqlPool) {
+        // generate_message sends as Telegram user 1
+        let manager = positioned_user(&pool, 1, 2, 1).await;
+        let other_company = sqlx::query!("INSERT INTO company (name) VALUES ('Other Co')")
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_id();
+        assigned_user_position(&pool, i32::try_from(other_company).unwrap(), 2, manager)
+            .await
+            .unwrap();
+        let bot = MockTelegram::new();
+
+        let res = handle_message(generate_message(-100_500, "/linkgroup"), &pool, &bot).await;
+
+        assert_eq!(res, FORBIDDEN_RESPONSE);
+        assert_eq!(bot.sent.lock().unwrap()[0].1, GROUP_LINK_AMBIGUOUS);
+        let groups = sqlx::query_scalar!("SELECT COUNT(*) FROM telegram_lead_groups")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(groups, 0);
+    }
 }
@@ -0,0 +1,376 @@
+use crate::axum_helpers::guards::Telegram;
+use crate::crud::rep_commands::{
+    CustomerMatch, DueActivity, OpenDeal, RepStats, TelegramRep, find_rep_customers, get_rep_stats,
+    get_telegram_rep, list_due_activities, list_open_deals,
+};
+use crate::libs::constants::{ERR_DB, OK_RESPONSE, internal_error};
+use crate::libs::types::BasicResponse;
+use crate::telegram::utils::{RepList, is_command, lead_url, rep_page_callback_data};
+use lambda_http::tracing;
+use sqlx::MySqlPool;
+use std::fmt::Write;
+use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, MaybeInaccessibleMessage};
+
+const PAGE_SIZE: u32 = 5;
+/// Leaves room for `rep:find:<page>:` within Telegram's 64-byte callback data.
+const MAX_QUERY_BYTES: usize = 40;
+
+const NOT_LINKED: &str = "Link your CRM account first: /email <email>";
+const FIND_USAGE: &str = "Send /find <name or phone>";
+
+fn truncate_query(query: &str) -> &str {
+    let query = query.trim();
+    if query.len() <= MAX_QUERY_BYTES {
+        return query;
+    }
+    let mut end = MAX_QUERY_BYTES;
+    while !query.is_char_boundary(end) {
+        end -= 1;
+    }
+    &query[..end]
+}
+
+fn format_deals(deals: &[OpenDeal], page: u32) -> String {
+    if deals.is_empty() && page == 0 {
+        return "You have no open deals.".to_string();
+    }
+    let mut text = format!("📂 Your open deals (page {})", page + 1);
+    let mut stage: Option<&str> = None;
+    for deal in deals {
+        if stage != Some(deal.stage.as_str()) {
+            let _ = write!(text, "\n\n{}", deal.stage);
+            stage = Some(deal.stage.as_str());
+        }
+        let customer = deal.customer_name.as_deref().unwrap_or("Customer");
+        let _ = match deal.title.as_deref().filter(|title| !title.is_empty()) {
+            Some(title) => write!(text, "\n• {customer}: {title}"),
+            None => write!(text, "\n• {customer}"),
+        };
+        let _ = write!(text, "\n  {}", lead_url(deal.id));
+    }
+    text
+}
+
+fn format_activities(activities: &[DueActivity], page: u32) -> String {
+    if activities.is_empty() && page == 0 {
+        return "Nothing due today.".to_string();
+    }
+    let mut text = format!("📋 Due today (page {})", page + 1);
+    for activity in activities {
+        let customer = activity.customer_name.as_deref().unwrap_or("Customer");
+        let _ = write!(
+            text,
+            "\n\n• {} ({customer})\n  Due {} UTC\n  {}",
+            activity.message,
+            activity.due_at.format("%b %-d, %H:%M"),
+            lead_url(activity.deal_id)
+        );
+    }
+    text
+}
+
+fn format_customers(customers: &[CustomerMatch], query: &str, page: u32) -> String {
+    if customers.is_empty() && page == 0 {
+        return format!("No customers match \"{query}\".");
+    }
+    let mut text = format!("🔎 Customers matching \"{query}\" (page {})", page + 1);
+    for customer in customers {
+        let details: Vec<&str> = [customer.phone.as_deref(), customer.email.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter(|value| !value.is_empty())
+            .collect();
+        let _ = write!(
+            text,
+            "\n\n• {} (#{})",
+            customer.name.as_deref().unwrap_or("No name"),
+            customer.id
+        );
+        if !details.is_empty() {
+            let _ = write!(text, "\n  {}", details.join(" · "));
+        }
+    }
+    text
+}
+
+fn format_stats(stats: &RepStats) -> String {
+    format!(
+        "📊 This month so far\n\nNew leads: {}\nWon deals: {}",
+        stats.mtd_leads, stats.mtd_won
+    )
+}
+
+fn page_keyboard(list: RepList, page: u32, has_next: bool, query: &str) -> InlineKeyboardMarkup {
+    let mut row = Vec::new();
+    if page > 0 {
+        row.push(InlineKeyboardButton::callback(
+            "‹ Prev",
+            rep_page_callback_data(list, page - 1, query),
+        ));
+    }
+    if has_next {
+        row.push(InlineKeyboardButton::callback(
+            "Next ›",
+            rep_page_callback_data(list, page + 1, query),
+        ));
+    }
+    if row.is_empty() {
+        InlineKeyboardMarkup::default()
+    } else {
+        InlineKeyboardMarkup::new(vec![row])
+    }
+}
+
+/// One extra row is fetched to know whether a next page exists.
+fn split_page<R>(mut rows: Vec<R>) -> (Vec<R>, bool) {
+    let has_next = rows.len() > PAGE_SIZE as usize;
+    rows.truncate(PAGE_SIZE as usize);
+    (rows, has_next)
+}
+
+async fn render_page(
+    pool: &MySqlPool,
+    rep: &TelegramRep,
+    list: RepList,
+    page: u32,
+    query: &str,
+) -> Result<(String, InlineKeyboardMarkup), sqlx::Error> {
+    let offset = page.saturating_mul(PAGE_SIZE);
+    let (text, has_next) = match list {
+        RepList::Leads => {
+            let (deals, has_next) =
+                split_page(list_open_deals(pool, rep, PAGE_SIZE + 1, offset).await?);
+            (format_deals(&deals, page), has_next)
+        }
+        RepList::Today => {
+            let (activities, has_next) =
+                split_page(list_due_activities(pool, rep, PAGE_SIZE + 1, offset).await?);
+            (format_activities(&activities, page), has_next)
+        }
+        RepList::Find => {
+            let digits: String = query.chars().filter(char::is_ascii_digit).collect();
+            let (customers, has_next) = split_page(
+                find_rep_customers(pool, rep, query, &digits, PAGE_SIZE + 1, offset).await?,
+            );
+            (format_customers(&customers, query, page), has_next)
+        }
+    };
+    Ok((text, page_keyboard(list, page, has_next, query)))
+}
+
+/// Read-only `/leads`, `/today`, `/find` and `/stats` for a rep's private chat. Returns
+/// `None` when `text` is none of them.
+pub async fn handle_rep_command<T: Telegram>(
+    pool: &MySqlPool,
+    bot: &T,
+    chat_id: ChatId,
+    text: &str,
+) -> Option<BasicResponse> {
+    let list = if is_command(text, "/leads") {
+        RepList::Leads
+    } else if is_command(text, "/today") {
+        RepList::Today
+    } else if is_command(text, "/find") {
+        RepList::Find
+    } else if is_command(text, "/stats") {
+        return Some(handle_stats(pool, bot, chat_id).await);
+    } else {
+        return None;
+    };
+    let rep = match get_telegram_rep(pool, chat_id.0).await {
+        Ok(Some(rep)) => rep,
+        Ok(None) => {
+            return Some(
+                bot.send_message(chat_id, NOT_LINKED)
+                    .await
+                    .map_or_else(|e| e, |_| OK_RESPONSE),
+            );
+        }
+        Err(e) => {
+            tracing::error!(?e, chat_id = chat_id.0, "Failed to load telegram rep");
+            return Some(internal_error(ERR_DB));
+        }
+    };
+    let query = truncate_query(
+        text.split_once(char::is_whitespace)
+            .map_or("", |(_, rest)| rest),
+    );
+    if list == RepList::Find && query.is_empty() {
+        return Some(
+            bot.send_message(chat_id, FIND_USAGE)
+                .await
+                .map_or_else(|e| e, |_| OK_RESPONSE),
+        );
+    }
+
+    let (page_text, keyboard) = match render_page(pool, &rep, list, 0, query).await {
+        Ok(page) => page,
+        Err(e) => {
+            tracing::error!(?e, user_id = rep.user_id, "Failed to load rep command page");
+            return Some(internal_error(ERR_DB));
+        }
+    };
+    if let Err(e) = bot
+        .send_repliable_message(chat_id, page_text, keyboard)
+        .await
+    {
+        tracing::error!(?e, user_id = rep.user_id, "Failed to send rep command page");
+    }
+    Some(OK_RESPONSE)
+}
+
+async fn handle_stats<T: Telegram>(pool: &MySqlPool, bot: &T, chat_id: ChatId) -> BasicResponse {
+    let rep = match get_telegram_rep(pool, chat_id.0).await {
+        Ok(Some(rep)) => rep,
+        Ok(None) => {
+            return bot
+                .send_message(chat_id, NOT_LINKED)
+                .await
+                .map_or_else(|e| e, |_| OK_RESPONSE);
+        }
+        Err(e) => {
+            tracing::error!(?e, chat_id = chat_id.0, "Failed to load telegram rep");
+            return internal_error(ERR_DB);
+        }
+    };
+    match get_rep_stats(pool, &rep).await {
+        Ok(stats) => bot
+            .send_message(chat_id, format_stats(&stats))
+            .await
+            .map_or_else(|e| e, |_| OK_RESPONSE),
+        Err(e) => {
+            tracing::error!(?e, user_id = rep.user_id, "Failed to load rep stats");
+            internal_error(ERR_DB)
+        }
+    }
+}
+
+/// Turns a list message to another page. The rep is looked up from whoever tapped, so a
+/// forwarded keyboard never shows someone else's data.
+pub async fn handle_rep_page<T: Telegram>(
+    pool: &MySqlPool,
+    bot: &T,
+    cb: &CallbackQuery,
+    list: RepList,
+    page: u32,
+    query: &str,
+) -> BasicResponse {
+    let Some(MaybeInaccessibleMessage::Regular(message)) = &cb.message else {
+        return OK_RESPONSE;
+    };
+    let telegram_id = i64::try_from(cb.from.id.0).unwrap_or_default();
+    let rep = match get_telegram_rep(pool, telegram_id).await {
+        Ok(Some(rep)) => rep,
+        Ok(None) => return OK_RESPONSE,
+        Err(e) => {
+            tracing::error!(?e, telegram_id, "Failed to load telegram rep");
+            return internal_error(ERR_DB);
+        }
+    };
+    let (page_text, keyboard) = match render_page(pool, &rep, list, page, query).await {
+        Ok(page) => page,
+        Err(e) => {
+            tracing::error!(?e, user_id = rep.user_id, "Failed to load rep command page");
+            return internal_error(ERR_DB);
+        }
+    };
+    bot.edit_repliable_message(message.chat.id.0, message.id.0, page_text, keyboard)
+        .await
+        .map_or_else(|e| e, |_| OK_RESPONSE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telegram::utils::parse_rep_page;
+    use crate::tests::telegram::MockTelegram;
+    use crate::tests::utils::insert_user;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn page_callback_data_round_trips_and_fits_telegram() {
+        let query = truncate_query(&"Ğranite ".repeat(10));
+        assert!(query.len() <= MAX_QUERY_BYTES);
+        let data = rep_page_callback_data(RepList::Find, 12, query);
+        assert!(data.len() <= 64);
+        assert_eq!(parse_rep_page(&data), Some((RepList::Find, 12, query)));
+        assert_eq!(
+            parse_rep_page(&rep_page_callback_data(RepList::Leads, 0, "")),
+            Some((RepList::Leads, 0, ""))
+        );
+        assert_eq!(parse_rep_page("assign:1:2"), None);
+    }
+
+    #[test]
+    fn deals_are_grouped_by_stage() {
+        let deal = |id, stage: &str| OpenDeal {
+            id,
+            stage: stage.to_string(),
+            customer_name: Some(format!("Customer {id}")),
+            title: None,
+        };
+        let text = format_deals(&[deal(1, "New"), deal(2, "New"), deal(3, "Quoted")], 0);
+        assert_eq!(text.matches("\n\nNew").count(), 1);
+        assert!(text.contains("\n\nQuoted\n• Customer 3"));
+        assert_eq!(format_deals(&[], 0), "You have no open deals.");
+    }
+
+    #[test]
+    fn activities_show_due_time() {
+        let text = format_activities(
+            &[DueActivity {
+                deal_id: 7,
+                message: "Call back".to_string(),
+                customer_name: Some("Jane".to_string()),
+                due_at: Utc.with_ymd_and_hms(2026, 10, 18, 15, 30, 0).unwrap(),
+            }],
+            0,
+        );
+        assert!(text.contains("• Call back (Jane)\n  Due Oct 18, 15:30 UTC"));
+    }
+
+    #[test]
+    fn keyboard_only_offers_existing_pages() {
+        assert!(
+            page_keyboard(RepList::Leads, 0, false, "")
+                .inline_keyboard
+                .is_empty()
+        );
+        let keyboard = page_keyboard(RepList::Leads, 1, true, "");
+        assert_eq!(keyboard.inline_keyboard[0].len(), 2);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_unlinked_chat_is_asked_to_link(pool: MySqlPool) {
+        let bot = MockTelegram::new();
+        let response = handle_rep_command(&pool, &bot, ChatId(456), "/leads").await;
+        assert_eq!(response, Some(OK_RESPONSE));
+        assert_eq!(bot.sent.lock().unwrap()[0].1, NOT_LINKED);
+        assert!(
+            handle_rep_command(&pool, &bot, ChatId(456), "hello")
+                .await
+                .is_none()
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_find_lists_rep_customers(pool: MySqlPool) {
+        let user_id = insert_user(&pool, "rep@example.com", Some(456))
+            .await
+            .unwrap();
+        sqlx::query!(
+            "INSERT INTO customers (name, company_id, phone, source, sales_rep) VALUES ('Jane Stone', 1, '3175550142', 'leads', ?)",
+            user_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let bot = MockTelegram::new();
+        handle_rep_command(&pool, &bot, ChatId(456), "/find jane").await;
+        let sent = bot.sent.lock().unwrap();
+        assert!(sent[0].1.contains("Jane Stone"));
+        assert!(sent[0].2.as_ref().unwrap().inline_keyboard.is_empty());
+    }
+}
@@ -0,0 +1,401 @@
+use crate::axum_helpers::guards::{NotificationsTelegramBot, Telegram};
+use crate::cloudtalk::api::send_cloudtalk_sms;
+use crate::cloudtalk::schemas::SendSmsPayload;
+use crate::crud::cloudtalk::insert_api_sent_sms;
+use crate::crud::email::{get_thread_reply_context, list_thread_message_ids};
+use crate::crud::telegram_replies::{ReplyTarget, get_reply_target};
+use crate::libs::constants::{ERR_DB, OK_RESPONSE, internal_error};
+use crate::libs::types::BasicResponse;
+use crate::telegram::actions::handle_crm_action;
+use axum::extract::State;
+use common::amazon::email::{assigned_sender_from, extract_email_address, send_reply_message};
+use common::crud::outbound_email::{OutboundEmail, record_outbound_email};
+use common::crud::suppressions::get_email_suppression_reason;
+use common::crud::template::fetch_template_variable_data;
+use common::utils::phone::PhoneNumber;
+use lambda_http::tracing;
+use reqwest::Client;
+use sqlx::MySqlPool;
+use teloxide::types::{CallbackQuery, Message, Update, UpdateKind};
+
+const REPLY_SENT: &str = "✅ Sent to the customer.";
+const REPLY_FAILED: &str = "Couldn't send your reply. Please answer from the CRM.";
+const REPLY_SUPPRESSED: &str = "The customer unsubscribed from emails, so the reply was not sent.";
+const REPLY_UNKNOWN: &str =
+    "To answer a customer, reply to their email or SMS notification with your message.";
+
+fn reply_subject(subject: Option<&str>) -> String {
+    let subject = subject
+        .map(str::trim)
+        .filter(|subject| !subject.is_empty())
+        .unwrap_or("Your message");
+    if subject
+        .get(..3)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("re:"))
+    {
+        subject.to_string()
+    } else {
+        format!("Re: {subject}")
+    }
+}
+
+/// Telegram text is plain; escape it so the customer sees exactly what the rep typed.
+fn plain_text_html(text: &str) -> String {
+    let escaped = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    format!("<p>{}</p>", escaped.replace('\n', "<br>"))
+}
+
+async fn send_email_reply(
+    pool: &MySqlPool,
+    target: &ReplyTarget,
+    text: &str,
+) -> Result<(), &'static str> {
+    let Some(thread_id) = target.thread_id.as_deref() else {
+        return Err(REPLY_FAILED);
+    };
+    let context = match get_thread_reply_context(pool, thread_id).await {
+        Ok(Some(context)) => context,
+        Ok(None) => {
+            tracing::warn!(thread_id, "No inbound email to reply to in thread");
+            return Err(REPLY_FAILED);
+        }
+        Err(e) => {
+            tracing::error!(?e, thread_id, "Failed to load thread reply context");
+            return Err(REPLY_FAILED);
+        }
+    };
+    let (Some(sender_email), Some(in_reply_to), Some(company_id)) = (
+        context.sender_email.as_deref(),
+        context.message_id.as_deref(),
+        context.company_id,
+    ) else {
+        tracing::warn!(
+            thread_id,
+            "Inbound email is missing its sender or message id"
+        );
+        return Err(REPLY_FAILED);
+    };
+    let address = extract_email_address(sender_email);
+    match get_email_suppression_reason(pool, company_id, &address).await {
+        Ok(None) => {}
+        Ok(Some(_)) => return Err(REPLY_SUPPRESSED),
+        Err(e) => {
+            tracing::error!(?e, company_id, "Failed to check suppressions");
+            return Err(REPLY_FAILED);
+        }
+    }
+
+    let deal_id = context.deal_id.and_then(|id| i32::try_from(id).ok());
+    let data = match fetch_template_variable_data(
+        pool,
+        target.user_id,
+        deal_id,
+        context.customer_id,
+        company_id,
+    )
+    .await
+    {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::error!(?e, user_id = target.user_id, "Failed to load reply sender");
+            return Err(REPLY_FAILED);
+        }
+    };
+    let from = assigned_sender_from(
+        data.company
+            .as_ref()
+            .and_then(|company| company.domain.as_deref()),
+        data.user.email.as_deref(),
+        data.user.email_name.as_deref(),
+    );
+    let references = match list_thread_message_ids(pool, thread_id).await {
+        Ok(ids) => ids,
+        Err(e) => {
+            tracing::error!(?e, thread_id, "Failed to load thread message ids");
+            Vec::new()
+        }
+    };
+    let subject = reply_subject(context.subject.as_deref());
+    let html_body = plain_text_html(text);
+
+    let message_id = match send_reply_message(
+        &[&address],
+        &subject,
+        &html_body,
+        &from,
+        in_reply_to,
+        &references,
+    )
+    .await
+    {
+        Ok(message_id) => message_id,
+        Err(e) => {
+            tracing::error!(?e, thread_id, "Failed to send email reply from Telegram");
+            return Err(REPLY_FAILED);
+        }
+    };
+    if let Err(e) = record_outbound_email(
+        pool,
+        &OutboundEmail {
+            user_id: target.user_id,
+            customer_id: context.customer_id,
+            company_id,
+            deal_id,
+            subject: &subject,
+            html_body: &html_body,
+            sender_from: &from,
+            recipient_email: &address,
+            message_id: &message_id,
+            thread_id: Some(thread_id),
+        },
+    )
+    .await
+    {
+        tracing::error!(?e, thread_id, "Failed to record email reply from Telegram");
+    }
+    Ok(())
+}
+
+async fn send_sms_reply(
+    pool: &MySqlPool,
+    target: &ReplyTarget,
+    text: &str,
+) -> Result<(), &'static str> {
+    let (Some(company_id), Some(customer_phone), Some(business_phone)) = (
+        target.company_id,
+        target.customer_phone,
+        target.business_phone,
+    ) else {
+        return Err(REPLY_FAILED);
+    };
+    let (Some(recipient), Some(sender), Ok(cloudtalk_company_id)) = (
+        PhoneNumber::from_match_key(customer_phone),
+        PhoneNumber::from_match_key(business_phone),
+        u64::try_from(company_id),
+    ) else {
+        tracing::warn!(company_id, "SMS reply target has an unusable phone number");
+        return Err(REPLY_FAILED);
+    };
+    let payload = SendSmsPayload {
+        sender: sender.e164(),
+        recipient: recipient.e164(),
+        message: text.to_string(),
+    };
+    let error = send_cloudtalk_sms(pool, &Client::new(), cloudtalk_company_id, &payload)
+        .await
+        .err()
+        .map(|e| e.to_string());
+    if let Some(error) = &error {
+        tracing::error!(%error, company_id, "Failed to send SMS reply from Telegram");
+    }
+    if let Err(e) = insert_api_sent_sms(
+        pool,
+        company_id,
+        target.user_id,
+        business_phone,
+        customer_phone,
+        text,
+        error.as_deref(),
+    )
+    .await
+    {
+        tracing::error!(?e, company_id, "Failed to record SMS reply from Telegram");
+    }
+    error.map_or(Ok(()), |_| Err(REPLY_FAILED))
+}
+
+/// Sends a reply to an inbound email or SMS notification back to the customer on the
+/// same channel and confirms the outcome in the chat.
+pub async fn handle_customer_reply<T: Telegram>(
+    pool: &MySqlPool,
+    bot: &T,
+    msg: &Message,
+) -> BasicResponse {
+    let Some(text) = msg.text().map(str::trim).filter(|text| !text.is_empty()) else {
+        return OK_RESPONSE;
+    };
+    let target = match msg.reply_to_message() {
+        Some(replied) => match get_reply_target(pool, msg.chat.id.0, replied.id.0).await {
+            Ok(target) => target,
+            Err(e) => {
+                tracing::error!(?e, chat_id = msg.chat.id.0, "Failed to load reply target");
+                return internal_error(ERR_DB);
+            }
+        },
+        None => None,
+    };
+    let outcome = match &target {
+        None => Err(REPLY_UNKNOWN),
+        Some(target) if target.channel == "email" => send_email_reply(pool, target, text).await,
+        Some(target) => send_sms_reply(pool, target, text).await,
+    };
+    let _ = bot
+        .send_message(msg.chat.id, outcome.map_or_else(|e| e, |()| REPLY_SENT))
+        .await;
+    OK_RESPONSE
+}
+
+/// The notifications bot only sends CRM buttons, so lead buttons forwarded to it do nothing.
+async fn handle_notifications_callback<T: Telegram>(
+    pool: &MySqlPool,
+    bot: &T,
+    cb: &CallbackQuery,
+) -> BasicResponse {
+    if cb
+        .data
+        .as_deref()
+        .is_some_and(|data| data.starts_with("crm:"))
+    {
+        return handle_crm_action(pool, bot, cb).await;
+    }
+    OK_RESPONSE
+}
+
+/// Webhook of the notifications bot: replies to customers and notification buttons.
+pub async fn notifications_webhook_handler(
+    State(pool): State<MySqlPool>,
+    bot: NotificationsTelegramBot,
+    axum::extract::Json(update): axum::extract::Json<Update>,
+) -> BasicResponse {
+    match update.kind {
+        UpdateKind::Message(msg) if msg.chat.id.is_user() => {
+            handle_customer_reply(&pool, &bot, &msg).await
+        }
+        UpdateKind::CallbackQuery(cb) => handle_notifications_callback(&pool, &bot, &cb).await,
+        _ => OK_RESPONSE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crud::telegram_replies::insert_email_reply_target;
+    use crate::libs::constants::SALES_MANAGER;
+    use crate::tests::telegram::{
+        MockTelegram, generate_message, generate_message_with_id, telegram_user,
+    };
+    use crate::tests::utils::{insert_user, positioned_user};
+    use common::crud::suppressions::{SuppressionReason, add_email_suppression};
+    use teloxide::types::{MaybeInaccessibleMessage, MessageKind};
+
+    fn reply_to(chat_id: i64, replied_id: i32, text: &str) -> Message {
+        let mut msg = generate_message(chat_id, text);
+        if let MessageKind::Common(common) = &mut msg.kind {
+            common.reply_to_message = Some(Box::new(generate_message_with_id(
+                chat_id,
+                replied_id,
+                "✉️ New email",
+            )));
+        }
+        msg
+    }
+
+    #[test]
+    fn reply_subject_adds_prefix_once() {
+        assert_eq!(reply_subject(Some("Quote")), "Re: Quote");
+        assert_eq!(reply_subject(Some("RE: Quote")), "RE: Quote");
+        assert_eq!(reply_subject(None), "Re: Your message");
+    }
+
+    #[test]
+    fn plain_text_html_escapes_markup_and_keeps_lines() {
+        assert_eq!(
+            plain_text_html("Price <$5k>\nThanks & bye"),
+            "<p>Price &lt;$5k&gt;<br>Thanks &amp; bye</p>"
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_lead_buttons_are_ignored_by_the_notifications_bot(pool: MySqlPool) {
+        positioned_user(&pool, 1, SALES_MANAGER, 456).await;
+        let quarantine_id = sqlx::query!(
+            "INSERT INTO quarantined_leads (company_id, payload, score, reasons) VALUES (1, '{}', 90, 'link in message')"
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        let cb = CallbackQuery {
+            id: "a".into(),
+            from: telegram_user(456),
+            message: Some(MaybeInaccessibleMessage::Regular(Box::new(
+                generate_message(456, "🛡 Held as possible spam"),
+            ))),
+            inline_message_id: None,
+            chat_instance: String::new(),
+            data: Some(format!("spam:approve:{quarantine_id}")),
+            game_short_name: None,
+        };
+        let bot = MockTelegram::new();
+
+        let res = handle_notifications_callback(&pool, &bot, &cb).await;
+
+        assert_eq!(res, OK_RESPONSE);
+        let status = sqlx::query_scalar!(
+            "SELECT status FROM quarantined_leads WHERE id = ?",
+            quarantine_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(status, "pending");
+        assert!(bot.edited.lock().unwrap().is_empty());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_reply_without_target_explains_usage(pool: MySqlPool) {
+        let bot = MockTelegram::new();
+        handle_customer_reply(&pool, &bot, &reply_to(456, 99, "Hello")).await;
+        let sent = bot.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].1, REPLY_UNKNOWN);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_reply_to_suppressed_customer_is_not_sent(pool: MySqlPool) {
+        let user_id = insert_user(&pool, "rep@example.com", Some(456))
+            .await
+            .unwrap();
+        sqlx::query!(
+            r#"
+            INSERT INTO emails (subject, body, thread_id, receiver_user_id, sender_email, message_id, company_id)
+            VALUES ('Quote', 'Hi', '6f9619ff-8b86-d011-b42d-00cf4fc964ff', ?, 'Jane <jane@example.com>', 'inbound-1@example.com', 1)
+            "#,
+            user_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        add_email_suppression(
+            &pool,
+            1,
+            "jane@example.com",
+            SuppressionReason::Unsubscribe,
+            "test",
+        )
+        .await
+        .unwrap();
+        insert_email_reply_target(
+            &pool,
+            456,
+            7,
+            user_id,
+            "6f9619ff-8b86-d011-b42d-00cf4fc964ff",
+        )
+        .await
+        .unwrap();
+
+        let bot = MockTelegram::new();
+        handle_customer_reply(&pool, &bot, &reply_to(456, 7, "Thanks Jane")).await;
+        assert_eq!(bot.sent.lock().unwrap()[0].1, REPLY_SUPPRESSED);
+        let outbound: i64 =
+            sqlx::query_scalar!("SELECT COUNT(*) FROM emails WHERE sender_user_id IS NOT NULL")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(outbound, 0);
+    }
+}
@@ -2,14 +2,21 @@ use crate::axum_helpers::guards::Telegram;
 use std::fmt::Display;
 use std::sync::Arc;
 use teloxide::prelude::*;
-use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode};
 use tokio::task::JoinSet;
 
-use crate::crud::telegram_messages::insert_telegram_lead_message;
+use crate::crud::telegram_groups::get_lead_group_chat_id;
+use crate::crud::telegram_messages::insert_telegram_lead_message_with_text;
 use crate::crud::users::{SalesUser, get_sales_users};
 use crate::libs::constants::{ERR_DB, OK_RESPONSE, SALES_MANAGER, SALES_WORKER, internal_error};
+use crate::libs::notification_routing::{Recipients, plan_lead_notification};
 use crate::libs::types::BasicResponse;
+use crate::telegram::utils::{QuarantineDecision, quarantine_callback_data};
 
+use chrono::NaiveDateTime;
+use common::crud::lead_notifications::queue_delayed_lead_notification;
+use common::telegram::html::{escape_html, split_html_message};
+use common::telegram::leads::{LEAD_PART_LIMIT, assign_button_label, assign_callback_data};
 use lambda_http::tracing;
 use sqlx::MySqlPool;
 
@@ -23,6 +30,42 @@ type Candidate = (
     i64,    /*mtd_lead_count*/
 );
 
+/// A sent lead message part and the HTML it was sent with.
+pub type SentPart = (Message, String);
+
+/// Sends an HTML lead message split to fit Telegram's limit, with `keyboard` on the last
+/// part.
+async fn send_lead_parts<V>(
+    bot: &V,
+    chat_id: i64,
+    text: &str,
+    mut keyboard: Option<InlineKeyboardMarkup>,
+) -> Result<Vec<SentPart>, teloxide::RequestError>
+where
+    V: Telegram + Send + Sync,
+{
+    let parts = split_html_message(text, LEAD_PART_LIMIT);
+    let last_index = parts.len().saturating_sub(1);
+    let mut sent = Vec::with_capacity(parts.len());
+    for (index, part) in parts.into_iter().enumerate() {
+        let part_keyboard = if index == last_index {
+            keyboard.take()
+        } else {
+            None
+        };
+        let message = bot
+            .send_formatted_message(
+                ChatId(chat_id),
+                part.clone(),
+                ParseMode::Html,
+                part_keyboard,
+            )
+            .await?;
+        sent.push((message, part));
+    }
+    Ok(sent)
+}
+
 fn kb_for_users(lead_id: u64, candidates: &[Candidate]) -> InlineKeyboardMarkup {
     let mut rows: Vec<Vec<InlineKeyboardButton>> = Vec::new();
 
@@ -30,8 +73,8 @@ fn kb_for_users(lead_id: u64, candidates: &[Candidate]) -> InlineKeyboardMarkup
         let mut row: Vec<InlineKeyboardButton> = Vec::new();
         for (name, user_id, mtd_lead_count) in chunk {
             row.push(InlineKeyboardButton::callback(
-                format!("{}: {}", name.clone(), mtd_lead_count),
-                format!("assign:{lead_id}:{user_id}"),
+                assign_button_label(name, *mtd_lead_count),
+                assign_callback_data(lead_id, *user_id),
             ));
         }
         rows.push(row);
@@ -40,6 +83,19 @@ fn kb_for_users(lead_id: u64, candidates: &[Candidate]) -> InlineKeyboardMarkup
     InlineKeyboardMarkup::new(rows)
 }
 
+fn manager_message_text<T: Display + ?Sized>(
+    message: &T,
+    include_assignment_prompt: bool,
+) -> String {
+    if include_assignment_prompt {
+        let body = message.to_string();
+        format!("{}\nChoose a salesperson.", body.trim_end())
+    } else {
+        message.to_string()
+    }
+}
+
+/// Sends the lead card, which is HTML, with the assign keyboard on its last part.
 pub async fn send_lead_manager_message_to_all<T, V>(
     message: &T,
     lead_id: u64,
@@ -47,17 +103,12 @@ pub async fn send_lead_manager_message_to_all<T, V>(
     candidates: &[Candidate],
     include_assignment_prompt: bool,
     raw_bot: Arc<V>,
-) -> Result<Vec<Message>, teloxide::RequestError>
+) -> Result<Vec<SentPart>, teloxide::RequestError>
 where
     T: Display + Sync + ?Sized,
     V: Telegram + Send + Sync + 'static + Clone,
 {
-    let full_message = if include_assignment_prompt {
-        let body = message.to_string();
-        format!("{}\nChoose a salesperson.", body.trim_end())
-    } else {
-        message.to_string()
-    };
+    let full_message = manager_message_text(message, include_assignment_prompt);
     let kb = kb_for_users(lead_id, candidates);
 
     let mut set = JoinSet::new();
@@ -67,13 +118,13 @@ where
         let msg = full_message.clone();
         let kb = kb.clone();
 
-        set.spawn(async move { bot.send_repliable_message(ChatId(user_id), msg, kb).await });
+        set.spawn(async move { send_lead_parts(bot.as_ref(), user_id, &msg, Some(kb)).await });
     }
 
     let mut out = Vec::new();
     while let Some(res) = set.join_next().await {
-        let msg = res.expect("task panicked or was cancelled")?;
-        out.push(msg);
+        let parts = res.expect("task panicked or was cancelled")?;
+        out.extend(parts);
     }
 
     Ok(out)
@@ -98,19 +149,37 @@ fn get_manager_telegram_ids(users: &[SalesUser]) -> Vec<i64> {
         .collect()
 }
 
+/// A linked lead group gets the lead once in place of every manager's private chat.
+async fn lead_group_or_managers(
+    pool: &MySqlPool,
+    company_id: i32,
+    users: &[SalesUser],
+) -> Vec<i64> {
+    match get_lead_group_chat_id(pool, company_id).await {
+        Ok(Some(chat_id)) => vec![chat_id],
+        Ok(None) => get_manager_telegram_ids(users),
+        Err(error) => {
+            tracing::error!(?error, company_id, "Failed to get lead group");
+            get_manager_telegram_ids(users)
+        }
+    }
+}
+
+/// Stores each part's text as sent, HTML included, so later edits can render it again.
 async fn persist_lead_messages(
     pool: &MySqlPool,
     customer_id: i32,
     company_id: i32,
-    messages: &[Message],
+    parts: &[SentPart],
 ) {
-    for message in messages {
-        if let Err(error) = insert_telegram_lead_message(
+    for (message, text) in parts {
+        if let Err(error) = insert_telegram_lead_message_with_text(
             pool,
             customer_id,
             company_id,
             message.chat.id.0,
             message.id.0,
+            Some(text),
         )
         .await
         {
@@ -131,8 +200,63 @@ pub async fn persist_lead_message(
     customer_id: i32,
     company_id: i32,
     message: &Message,
+    text: &str,
 ) {
-    persist_lead_messages(pool, customer_id, company_id, std::slice::from_ref(message)).await;
+    persist_lead_messages(
+        pool,
+        customer_id,
+        company_id,
+        &[(message.clone(), text.to_string())],
+    )
+    .await;
+}
+
+/// Chats a lead notification goes to under the company's routing rules, and when to send it
+/// if quiet hours hold it back.
+async fn lead_notification_chats(
+    pool: &MySqlPool,
+    company_id: i32,
+    customer_id: i32,
+    users: &[SalesUser],
+) -> (Vec<i64>, Option<NaiveDateTime>) {
+    let plan = plan_lead_notification(pool, company_id, customer_id).await;
+    let telegram_ids = match plan.recipients {
+        Recipients::AllManagers => lead_group_or_managers(pool, company_id, users).await,
+        Recipients::Chats(chat_ids) => chat_ids,
+    };
+    (telegram_ids, plan.send_after)
+}
+
+/// Holds the notification for the time-triggered lambda to send once quiet hours end.
+async fn queue_lead_messages(
+    pool: &MySqlPool,
+    company_id: i32,
+    customer_id: i32,
+    message: &str,
+    include_assignment_prompt: bool,
+    telegram_ids: &[i64],
+    send_after: NaiveDateTime,
+) {
+    for &chat_id in telegram_ids {
+        if let Err(error) = queue_delayed_lead_notification(
+            pool,
+            company_id,
+            customer_id,
+            chat_id,
+            message,
+            include_assignment_prompt,
+            send_after,
+        )
+        .await
+        {
+            tracing::error!(
+                ?error,
+                customer_id,
+                chat_id,
+                "Failed to queue lead notification for after quiet hours"
+            );
+        }
+    }
 }
 
 pub async fn send_telegram_manager_assign<T: Display, V>(
@@ -164,7 +288,16 @@ where
             )
         })
         .collect();
-    let telegram_ids = get_manager_telegram_ids(&all_users);
+    let customer_id_i32 = i32::try_from(customer_id).ok();
+    let (telegram_ids, send_after) = match customer_id_i32 {
+        Some(customer_id) => {
+            lead_notification_chats(pool, company_id, customer_id, &all_users).await
+        }
+        None => (
+            lead_group_or_managers(pool, company_id, &all_users).await,
+            None,
+        ),
+    };
 
     if telegram_ids.is_empty() {
         tracing::error!(
@@ -174,6 +307,20 @@ where
         );
         return Err(internal_error(ERR_DB));
     }
+    if let (Some(send_after), Some(customer_id)) = (send_after, customer_id_i32) {
+        let message = manager_message_text(&data.to_string(), include_assignment_prompt);
+        queue_lead_messages(
+            pool,
+            company_id,
+            customer_id,
+            &message,
+            include_assignment_prompt,
+            &telegram_ids,
+            send_after,
+        )
+        .await;
+        return Ok(());
+    }
     let new_bot = Arc::new(bot.clone());
     let send_message = send_lead_manager_message_to_all(
         &data.to_string(),
@@ -187,7 +334,7 @@ where
 
     match send_message {
         Ok(messages) => {
-            if let Ok(customer_id_i32) = i32::try_from(customer_id) {
+            if let Some(customer_id_i32) = customer_id_i32 {
                 persist_lead_messages(pool, customer_id_i32, company_id, &messages).await;
             } else {
                 tracing::error!(
@@ -213,11 +360,12 @@ where
     Ok(())
 }
 
+/// Sends an HTML lead notice, split like the lead card, without the assign keyboard.
 pub async fn send_lead_managers_dupliacate<V>(
     message: String,
     telegram_ids: Vec<i64>,
     raw_bot: Arc<V>,
-) -> Result<Vec<Message>, teloxide::RequestError>
+) -> Result<Vec<SentPart>, teloxide::RequestError>
 where
     V: Telegram + Send + Sync + 'static + Clone,
 {
@@ -227,7 +375,7 @@ where
         let bot = Arc::clone(&raw_bot);
         let msg = message.clone();
 
-        set.spawn(async move { send_plain_message_to_chat(user_id, &msg, bot.as_ref()).await });
+        set.spawn(async move { send_lead_parts(bot.as_ref(), user_id, &msg, None).await });
     }
 
     let mut out = Vec::new();
@@ -245,7 +393,7 @@ where
             }
         };
         match res_inner {
-            Ok(msg) => out.push(msg),
+            Ok(parts) => out.extend(parts),
             Err(error) => {
                 tracing::error!(
                     ?error,
@@ -283,7 +431,8 @@ where
         || "Unknown".to_string(),
         |u| u.name.clone().unwrap_or_else(|| "Unknown".to_string()),
     );
-    let telegram_ids = get_manager_telegram_ids(&all_users);
+    let (telegram_ids, send_after) =
+        lead_notification_chats(pool, company_id, customer_id, &all_users).await;
     if telegram_ids.is_empty() {
         tracing::error!(
             ?company_id,
@@ -292,7 +441,24 @@ where
         );
         return false;
     }
-    let message = format!("Repeat lead {lead_name} for sales rep {assigned_name}\n\n{lead_body}");
+    let message = format!(
+        "Repeat lead {} for sales rep {}\n\n{lead_body}",
+        escape_html(lead_name),
+        escape_html(&assigned_name)
+    );
+    if let Some(send_after) = send_after {
+        queue_lead_messages(
+            pool,
+            company_id,
+            customer_id,
+            &message,
+            false,
+            &telegram_ids,
+            send_after,
+        )
+        .await;
+        return false;
+    }
     let new_bot = Arc::new(bot.clone());
     match send_lead_managers_dupliacate(message, telegram_ids, new_bot).await {
         Ok(messages) => {
@@ -302,3 +468,106 @@ where
         Err(_) => true,
     }
 }
+
+/// Tells managers which rep the assignment policy picked, instead of the assign keyboard.
+/// Routed and held back like any other lead notification.
+pub async fn send_telegram_auto_assign_notification<T>(
+    pool: &MySqlPool,
+    company_id: i32,
+    customer_id: i32,
+    users: &[SalesUser],
+    assigned_name: &str,
+    lead_body: String,
+    bot: &T,
+) where
+    T: Telegram + Send + Sync + 'static + Clone,
+{
+    let (telegram_ids, send_after) =
+        lead_notification_chats(pool, company_id, customer_id, users).await;
+    if telegram_ids.is_empty() {
+        tracing::error!(
+            ?company_id,
+            position_id = SALES_MANAGER,
+            "No sales manager found"
+        );
+        return;
+    }
+    let message = format!(
+        "{}\n\nLead auto-assigned to {}",
+        lead_body.trim_end(),
+        escape_html(assigned_name)
+    );
+    if let Some(send_after) = send_after {
+        queue_lead_messages(
+            pool,
+            company_id,
+            customer_id,
+            &message,
+            false,
+            &telegram_ids,
+            send_after,
+        )
+        .await;
+        return;
+    }
+    let new_bot = Arc::new(bot.clone());
+    if let Ok(messages) = send_lead_managers_dupliacate(message, telegram_ids, new_bot).await {
+        persist_lead_messages(pool, customer_id, company_id, &messages).await;
+    }
+}
+
+fn kb_for_quarantine(quarantine_id: u64) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback(
+            "✅ Approve",
+            quarantine_callback_data(quarantine_id, QuarantineDecision::Approve),
+        ),
+        InlineKeyboardButton::callback(
+            "🚫 Reject",
+            quarantine_callback_data(quarantine_id, QuarantineDecision::Reject),
+        ),
+    ]])
+}
+
+/// Asks managers to approve or reject a lead held as possible spam. Sent without a
+/// notification sound, since most held leads are spam.
+pub async fn send_telegram_quarantine_review<T>(
+    pool: &MySqlPool,
+    company_id: i32,
+    quarantine_id: u64,
+    message: &str,
+    bot: &T,
+) where
+    T: Telegram + Send + Sync + 'static + Clone,
+{
+    let all_users = match get_sales_users(pool, company_id).await {
+        Ok(users) => users,
+        Err(e) => {
+            tracing::error!(?e, company_id = company_id, "Error fetching users");
+            return;
+        }
+    };
+    let telegram_ids = get_manager_telegram_ids(&all_users);
+    if telegram_ids.is_empty() {
+        tracing::error!(
+            ?company_id,
+            position_id = SALES_MANAGER,
+            "No sales manager found"
+        );
+        return;
+    }
+    let kb = kb_for_quarantine(quarantine_id);
+    for telegram_id in telegram_ids {
+        if let Err(error) = bot
+            .send_quiet_message(ChatId(telegram_id), message, kb.clone())
+            .await
+        {
+            tracing::error!(
+                ?error,
+                quarantine_id = quarantine_id,
+                telegram_id = telegram_id,
+                "Failed to send quarantined lead for review"
+            );
+        }
+    }
+}
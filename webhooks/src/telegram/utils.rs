@@ -11,6 +11,84 @@ pub fn parse_assign(data: &str) -> Option<(i32, i32)> {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuarantineDecision {
+    Approve,
+    Reject,
+}
+
+impl QuarantineDecision {
+    /// Value of `quarantined_leads.status` after the decision.
+    pub const fn status(self) -> &'static str {
+        match self {
+            Self::Approve => "approved",
+            Self::Reject => "rejected",
+        }
+    }
+}
+
+pub fn quarantine_callback_data(quarantine_id: u64, decision: QuarantineDecision) -> String {
+    let action = match decision {
+        QuarantineDecision::Approve => "approve",
+        QuarantineDecision::Reject => "reject",
+    };
+    format!("spam:{action}:{quarantine_id}")
+}
+
+pub fn parse_quarantine_review(data: &str) -> Option<(QuarantineDecision, i32)> {
+    let parts: Vec<&str> = data.split(':').collect();
+    if parts.len() != 3 || parts[0] != "spam" {
+        return None;
+    }
+    let decision = match parts[1] {
+        "approve" => QuarantineDecision::Approve,
+        "reject" => QuarantineDecision::Reject,
+        _ => return None,
+    };
+    Some((decision, parts[2].parse().ok()?))
+}
+
+/// Paginated lists a rep can open with a bot command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepList {
+    Leads,
+    Today,
+    Find,
+}
+
+impl RepList {
+    const fn key(self) -> &'static str {
+        match self {
+            Self::Leads => "leads",
+            Self::Today => "today",
+            Self::Find => "find",
+        }
+    }
+}
+
+/// `query` rides along for `/find`; callers keep it short enough for Telegram's 64-byte limit.
+pub fn rep_page_callback_data(list: RepList, page: u32, query: &str) -> String {
+    match list {
+        RepList::Find => format!("rep:{}:{page}:{query}", list.key()),
+        _ => format!("rep:{}:{page}", list.key()),
+    }
+}
+
+pub fn parse_rep_page(data: &str) -> Option<(RepList, u32, &str)> {
+    let mut parts = data.splitn(4, ':');
+    if parts.next()? != "rep" {
+        return None;
+    }
+    let list = match parts.next()? {
+        "leads" => RepList::Leads,
+        "today" => RepList::Today,
+        "find" => RepList::Find,
+        _ => return None,
+    };
+    let page = parts.next()?.parse().ok()?;
+    Some((list, page, parts.next().unwrap_or_default()))
+}
+
 pub fn lead_url(deal_id: u64) -> String {
     format!("https://granite-manager.com/employee/deals/edit/{deal_id}/project")
 }
@@ -37,6 +115,15 @@ pub fn parse_slash_email(text: &str) -> Option<String> {
     Some(email.to_string())
 }
 
+/// Whether `text` is `command`, also in the `/command@BotName` form used in groups.
+pub fn is_command(text: &str, command: &str) -> bool {
+    text.split_whitespace().next().is_some_and(|first| {
+        first
+            .strip_prefix(command)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('@'))
+    })
+}
+
 pub fn parse_code(text: &str) -> Option<i32> {
     let code = text.trim();
     if code.len() != 6 {
@@ -51,7 +138,7 @@ pub fn gen_code() -> i32 {
 
 pub fn extract_message(message: &MaybeInaccessibleMessage) -> Option<String> {
     if let MaybeInaccessibleMessage::Regular(msg) = message {
-        return msg.text().map(std::string::ToString::to_string);
+        return msg.html_text();
     }
     None
 }
@@ -8,6 +8,7 @@ use axum::{
 };
 use common::{
     crud::template::{TemplateVariableData, fetch_template_variable_data},
+    utils::click_tracking::{ClickTarget, track_links_from_env},
     utils::template::replace_template_variables,
 };
 use lambda_http::tracing;
@@ -78,7 +79,18 @@ pub async fn get_complete_template(
             return internal_error(ERR_DB).into_response();
         }
     };
-    let result = replace_template_variables(&payload.template, &data);
+    let mut result = replace_template_variables(&payload.template, &data);
+    // CRM-sent emails: links are tracked whenever the recipient is a known customer.
+    if let Some(customer_id) = data.customer.as_ref().and_then(|customer| customer.id) {
+        result = track_links_from_env(
+            &result,
+            &ClickTarget {
+                company_id,
+                customer_id,
+                scheduled_email_id: None,
+            },
+        );
+    }
     (StatusCode::OK, result).into_response()
 }
 
@@ -6,6 +6,9 @@ pub const INBOUND_SMS: &[u8] = b"{\"id\":null,\"sender\":\"+16468956758[sender]\
 /// Shape captured in production 2026-08-04: a photo-only MMS sends text as JSON null.
 pub const INBOUND_MMS_NULL_TEXT: &[u8] = b"{\"id\":51753924,\"sender\":\"+16468956758\",\"recipient\":\"+13173161456\",\"text\":null,\"agent\":null,\"media\":null,\"attachments\":null,\"media_urls\":null}";
 
+/// MMS with media in each shape we accept: a bare URL, objects, and a list repeating one URL.
+pub const INBOUND_MMS_WITH_MEDIA: &[u8] = b"{\"id\":51753925,\"sender\":\"+16468956758\",\"recipient\":\"+13173161456\",\"text\":\"Kitchen photos\",\"agent\":\"540273\",\"media\":\"https://media.example.com/a.jpg\",\"attachments\":[{\"url\":\"https://media.example.com/b.png\",\"type\":\"image/png\"}],\"media_urls\":[\"https://media.example.com/a.jpg\",\"https://media.example.com/c.gif\"]}";
+
 #[cfg(test)]
 mod flow_enrollment_tests {
     use super::INBOUND_SMS;
@@ -1,2 +1,3 @@
+pub mod ses_events_json;
 pub mod ses_open_json;
 pub mod ses_received;
@@ -0,0 +1,59 @@
+use crate::tests::data::ses_open_json::ses_open_event_json;
+use serde_json::{Value, json};
+
+/// The open fixture re-shaped into another SES event type; `payload` goes under `key`.
+fn ses_event_json(event_type: &str, key: &str, payload: Value) -> Value {
+    let mut event = ses_open_event_json();
+    event["id"] = json!(format!("{event_type}-df1515d9-b441-32ac-346a-8b4d5dd153c6"));
+    let detail = event["detail"].as_object_mut().unwrap();
+    detail.remove("open");
+    detail.insert("eventType".to_string(), json!(event_type));
+    detail.insert(key.to_string(), payload);
+    event
+}
+
+pub fn ses_bounce_event_json(bounce_type: &str) -> Value {
+    ses_event_json(
+        "Bounce",
+        "bounce",
+        json!({
+            "bounceType": bounce_type,
+            "bounceSubType": "General",
+            "bouncedRecipients": [{
+                "emailAddress": "Colin99Delahunty@gmail.com",
+                "action": "failed",
+                "status": "5.1.1",
+                "diagnosticCode": "smtp; 550 5.1.1 user unknown"
+            }],
+            "timestamp": "2025-11-19T00:12:34.926Z",
+            "feedbackId": "0100017a-bounce"
+        }),
+    )
+}
+
+pub fn ses_complaint_event_json() -> Value {
+    ses_event_json(
+        "Complaint",
+        "complaint",
+        json!({
+            "complainedRecipients": [{ "emailAddress": "colin99delahunty@gmail.com" }],
+            "complaintFeedbackType": "abuse",
+            "timestamp": "2025-11-19T00:12:34.926Z",
+            "feedbackId": "0100017a-complaint"
+        }),
+    )
+}
+
+pub fn ses_delivery_event_json() -> Value {
+    ses_event_json(
+        "Delivery",
+        "delivery",
+        json!({
+            "timestamp": "2025-11-19T00:12:34.926Z",
+            "processingTimeMillis": 546,
+            "recipients": ["colin99delahunty@gmail.com"],
+            "smtpResponse": "250 ok",
+            "reportingMTA": "a8-70.smtp-out.amazonses.com"
+        }),
+    )
+}
@@ -2,7 +2,7 @@ use crate::axum_helpers::guards::Telegram;
 use crate::libs::types::BasicResponse;
 use chrono::Utc;
 use teloxide::types::InlineKeyboardMarkup;
-use teloxide::types::{Message, Recipient};
+use teloxide::types::{CallbackQuery, Message, ParseMode, Recipient};
 
 use crate::libs::constants::ERR_SEND_TELEGRAM;
 use crate::libs::constants::internal_error;
@@ -89,12 +89,17 @@ pub fn generate_message_with_id(chat_id: i64, message_id: i32, text: &str) -> Me
 type MockTelegramSent = Arc<Mutex<Vec<(i64, String, Option<InlineKeyboardMarkup>)>>>;
 type MockTelegramDeleted = Arc<Mutex<Vec<(i64, i32)>>>;
 type MockTelegramEdited = Arc<Mutex<Vec<(i64, i32, String)>>>;
+type MockTelegramAnswered = Arc<Mutex<Vec<(u64, String)>>>;
+type MockTelegramParseModes = Arc<Mutex<Vec<(i64, ParseMode)>>>;
 
 #[derive(Clone)]
 pub struct MockTelegram {
     pub sent: MockTelegramSent,
     pub deleted: MockTelegramDeleted,
     pub edited: MockTelegramEdited,
+    pub answered: MockTelegramAnswered,
+    /// Chat and parse mode of every formatted send or edit.
+    pub parse_modes: MockTelegramParseModes,
     pub fail: bool,
     pub fail_delete: bool,
     pub fail_edit_chat_ids: Arc<Mutex<Vec<i64>>>,
@@ -107,6 +112,8 @@ impl Default for MockTelegram {
             sent: Arc::new(Mutex::new(Vec::new())),
             deleted: Arc::new(Mutex::new(Vec::new())),
             edited: Arc::new(Mutex::new(Vec::new())),
+            answered: Arc::new(Mutex::new(Vec::new())),
+            parse_modes: Arc::new(Mutex::new(Vec::new())),
             fail: false,
             fail_delete: false,
             fail_edit_chat_ids: Arc::new(Mutex::new(Vec::new())),
@@ -179,6 +186,20 @@ impl Telegram for MockTelegram {
         }
     }
 
+    async fn send_quiet_message<C, T>(
+        &self,
+        chat: C,
+        text: T,
+        repliable: InlineKeyboardMarkup,
+    ) -> Result<Message, teloxide::RequestError>
+    where
+        C: Into<Recipient> + Send,
+        T: Into<String> + Send,
+    {
+        self.send_formatted_message(chat, text, ParseMode::Html, Some(repliable))
+            .await
+    }
+
     async fn edit_message_text<T>(
         &self,
         chat_id: i64,
@@ -199,6 +220,19 @@ impl Telegram for MockTelegram {
         Ok(generate_message_with_id(chat_id, message_id, &text))
     }
 
+    async fn edit_repliable_message<T>(
+        &self,
+        chat_id: i64,
+        message_id: i32,
+        text: T,
+        _repliable: InlineKeyboardMarkup,
+    ) -> Result<Message, BasicResponse>
+    where
+        T: Into<String> + Send,
+    {
+        self.edit_message_text(chat_id, message_id, text).await
+    }
+
     async fn delete_message(&self, chat_id: i64, message_id: i32) -> Result<(), BasicResponse> {
         if self.fail_delete {
             return Err(internal_error(ERR_SEND_TELEGRAM));
@@ -206,4 +240,63 @@ impl Telegram for MockTelegram {
         self.deleted.lock().unwrap().push((chat_id, message_id));
         Ok(())
     }
+
+    async fn answer_callback_query<T>(
+        &self,
+        cb: &CallbackQuery,
+        text: T,
+    ) -> Result<(), BasicResponse>
+    where
+        T: Into<String> + Send,
+    {
+        self.answered
+            .lock()
+            .unwrap()
+            .push((cb.from.id.0, text.into()));
+        Ok(())
+    }
+
+    async fn send_formatted_message<C, T>(
+        &self,
+        chat: C,
+        text: T,
+        parse_mode: ParseMode,
+        repliable: Option<InlineKeyboardMarkup>,
+    ) -> Result<Message, teloxide::RequestError>
+    where
+        C: Into<Recipient> + Send,
+        T: Into<String> + Send,
+    {
+        let recipient = chat.into();
+        let text = text.into();
+
+        let chat_id = match recipient {
+            Recipient::Id(id) => id.0,
+            Recipient::ChannelUsername(_) => 0,
+        };
+        self.parse_modes.lock().unwrap().push((chat_id, parse_mode));
+        self.sent
+            .lock()
+            .unwrap()
+            .push((chat_id, text.clone(), repliable));
+        if self.fail {
+            Err(teloxide::RequestError::Api(teloxide::ApiError::BotBlocked))
+        } else {
+            Ok(self.dummy_message(chat_id, &text))
+        }
+    }
+
+    async fn edit_formatted_message<T>(
+        &self,
+        chat_id: i64,
+        message_id: i32,
+        text: T,
+        parse_mode: ParseMode,
+    ) -> Result<Message, BasicResponse>
+    where
+        T: Into<String> + Send,
+    {
+        self.parse_modes.lock().unwrap().push((chat_id, parse_mode));
+        self.edit_message_text(chat_id, message_id, text).await
+    }
 }
@@ -2,6 +2,10 @@ use crate::amazon::bucket::S3Bucket;
 #[cfg(test)]
 use crate::axum_helpers::axum_app::new_main_app;
 #[cfg(test)]
+use crate::meta::graph::{GraphError, MetaGraph};
+#[cfg(test)]
+use crate::meta::schemas::GraphLead;
+#[cfg(test)]
 use axum_test::TestServer;
 use bytes::Bytes;
 use sqlx::MySqlPool;
@@ -33,6 +37,38 @@ impl S3Bucket for MockClient {
     }
 }
 
+/// Returns the same lead for every id and records `(leadgen_id, access_token)`.
+#[cfg(test)]
+pub struct MockGraph {
+    pub lead: serde_json::Value,
+    pub requests: std::sync::Mutex<Vec<(String, String)>>,
+}
+
+#[cfg(test)]
+impl MockGraph {
+    pub fn new(lead: serde_json::Value) -> Self {
+        Self {
+            lead,
+            requests: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+impl MetaGraph for MockGraph {
+    async fn fetch_lead(
+        &self,
+        leadgen_id: &str,
+        access_token: &str,
+    ) -> Result<GraphLead, GraphError> {
+        self.requests
+            .lock()
+            .unwrap()
+            .push((leadgen_id.to_string(), access_token.to_string()));
+        serde_json::from_value(self.lead.clone()).map_err(|e| GraphError::Api(e.to_string()))
+    }
+}
+
 pub struct Email {
     pub id: i32,
     pub receiver_user_id: Option<i32>,
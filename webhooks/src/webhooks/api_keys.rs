@@ -0,0 +1,109 @@
+use crate::axum_helpers::guards::RemixBackend;
+use crate::crud::api_keys::{ApiKeySummary, create_api_key, list_api_keys, revoke_api_key};
+use crate::libs::constants::{
+    BAD_REQUEST, ERR_DB, NOT_FOUND_RESPONSE, OK_RESPONSE, internal_error,
+};
+use crate::libs::types::BasicResponse;
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use lambda_http::tracing;
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+
+#[derive(Debug, Deserialize)]
+pub struct NewApiKeyRequest {
+    pub label: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NewApiKeyResponse {
+    pub id: u64,
+    pub label: String,
+    pub key: String,
+}
+
+pub async fn list_company_api_keys(
+    _: RemixBackend,
+    State(pool): State<MySqlPool>,
+    Path(company_id): Path<i32>,
+) -> Result<Json<Vec<ApiKeySummary>>, BasicResponse> {
+    match list_api_keys(&pool, company_id).await {
+        Ok(keys) => Ok(Json(keys)),
+        Err(error) => {
+            tracing::error!(?error, company_id = company_id, "Failed to list API keys");
+            Err(internal_error(ERR_DB))
+        }
+    }
+}
+
+pub async fn create_company_api_key(
+    _: RemixBackend,
+    State(pool): State<MySqlPool>,
+    Path(company_id): Path<i32>,
+    Json(body): Json<NewApiKeyRequest>,
+) -> Result<(StatusCode, Json<NewApiKeyResponse>), BasicResponse> {
+    let label = body.label.trim();
+    if label.is_empty() {
+        return Err(BAD_REQUEST);
+    }
+    match create_api_key(&pool, company_id, label).await {
+        Ok((id, key)) => Ok((
+            StatusCode::CREATED,
+            Json(NewApiKeyResponse {
+                id,
+                label: label.to_string(),
+                key,
+            }),
+        )),
+        Err(error) => {
+            tracing::error!(?error, company_id = company_id, "Failed to create API key");
+            Err(internal_error(ERR_DB))
+        }
+    }
+}
+
+pub async fn revoke_company_api_key(
+    _: RemixBackend,
+    State(pool): State<MySqlPool>,
+    Path((company_id, key_id)): Path<(i32, i32)>,
+) -> BasicResponse {
+    match revoke_api_key(&pool, company_id, key_id).await {
+        Ok(result) if result.rows_affected() > 0 => OK_RESPONSE,
+        Ok(_) => NOT_FOUND_RESPONSE,
+        Err(error) => {
+            tracing::error!(
+                ?error,
+                company_id = company_id,
+                key_id = key_id,
+                "Failed to revoke API key"
+            );
+            internal_error(ERR_DB)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::utils::new_test_app;
+    use axum::http::StatusCode;
+    use serde_json::json;
+    use sqlx::MySqlPool;
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_api_key_routes_require_remix_backend(pool: MySqlPool) {
+        let app = new_test_app(pool);
+
+        let response = app
+            .post("/v1/api-keys/1")
+            .json(&json!({ "label": "Zapier" }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+        let response = app.get("/v1/api-keys/1").await;
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+        let response = app.delete("/v1/api-keys/1/1").await;
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+    }
+}
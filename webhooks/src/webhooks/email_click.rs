@@ -0,0 +1,319 @@
+use crate::axum_helpers::guards::NotificationsTelegramBot;
+use crate::crud::email::{
+    NewEmailClick, claim_first_email_click, create_email_click, get_email_click_notify_context,
+    get_scheduled_email_message_id,
+};
+use crate::libs::constants::{BAD_REQUEST, FORBIDDEN_RESPONSE, internal_error};
+use crate::telegram::crm::{EmailClickTelegramNotify, send_email_click_telegram_notification};
+use axum::extract::{Query, State};
+use axum::http::header::USER_AGENT;
+use axum::http::{HeaderMap, Uri};
+use axum::response::{IntoResponse, Redirect, Response};
+use common::utils::click_tracking::{ClickTarget, verify_click};
+use lambda_http::tracing;
+use serde::Deserialize;
+use sqlx::MySqlPool;
+use std::env::var;
+
+#[derive(Debug, Deserialize)]
+pub struct EmailClickQuery {
+    pub company_id: i32,
+    pub customer_id: i32,
+    pub scheduled_email_id: Option<i32>,
+    pub url: String,
+    pub sig: String,
+}
+
+impl EmailClickQuery {
+    const fn target(&self) -> ClickTarget {
+        ClickTarget {
+            company_id: self.company_id,
+            customer_id: self.customer_id,
+            scheduled_email_id: self.scheduled_email_id,
+        }
+    }
+}
+
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+/// API Gateway appends the caller to `X-Forwarded-For`; the first entry is the client.
+fn client_ip(headers: &HeaderMap) -> Option<&str> {
+    header_value(headers, "x-forwarded-for")
+        .and_then(|value| value.split(',').next())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+/// Stores the click and, on the first click of this link by this customer, returns the
+/// Telegram notification for their sales rep.
+async fn log_email_click(
+    pool: &MySqlPool,
+    query: &EmailClickQuery,
+    headers: &HeaderMap,
+) -> Result<Option<EmailClickTelegramNotify>, sqlx::Error> {
+    let message_id = match query.scheduled_email_id {
+        Some(id) => get_scheduled_email_message_id(pool, query.company_id, id).await?,
+        None => None,
+    };
+    create_email_click(
+        pool,
+        &NewEmailClick {
+            company_id: query.company_id,
+            customer_id: query.customer_id,
+            scheduled_email_id: query.scheduled_email_id,
+            message_id: message_id.as_deref(),
+            url: &query.url,
+            user_agent: header_value(headers, USER_AGENT.as_str()),
+            ip_address: client_ip(headers),
+        },
+    )
+    .await?;
+    if !claim_first_email_click(pool, query.company_id, query.customer_id, &query.url).await? {
+        return Ok(None);
+    }
+
+    let context = get_email_click_notify_context(pool, query.company_id, query.customer_id).await?;
+    Ok(context.and_then(|context| {
+        context
+            .sales_rep
+            .map(|receiver_user_id| EmailClickTelegramNotify {
+                receiver_user_id,
+                customer_name: context.customer_name,
+                url: query.url.clone(),
+            })
+    }))
+}
+
+/// Target of every tracked link in outbound emails. A failure to log never blocks the
+/// redirect, but a link whose signature does not match is refused so this is no open
+/// redirect.
+pub async fn email_click(
+    State(pool): State<MySqlPool>,
+    headers: HeaderMap,
+    Query(query): Query<EmailClickQuery>,
+) -> Response {
+    let secret = match var("CLICK_TRACKING_SECRET") {
+        Ok(secret) if !secret.is_empty() => secret,
+        _ => {
+            tracing::error!("CLICK_TRACKING_SECRET is not set");
+            return internal_error("failed to get click tracking secret").into_response();
+        }
+    };
+    if !verify_click(&secret, &query.target(), &query.url, &query.sig) {
+        return FORBIDDEN_RESPONSE.into_response();
+    }
+    // `Redirect::to` panics on a location that is not a valid header value.
+    if query.url.parse::<Uri>().is_err() {
+        tracing::warn!(
+            company_id = query.company_id,
+            "Signed click URL is not a valid redirect target"
+        );
+        return BAD_REQUEST.into_response();
+    }
+
+    match log_email_click(&pool, &query, &headers).await {
+        Ok(Some(payload)) => {
+            let bot = NotificationsTelegramBot::default();
+            if let Err(error) = send_email_click_telegram_notification(&pool, &bot, &payload).await
+            {
+                tracing::error!(
+                    ?error,
+                    user_id = payload.receiver_user_id,
+                    company_id = query.company_id,
+                    "Failed to send email click telegram notification"
+                );
+            }
+        }
+        Ok(None) => {}
+        Err(error) => tracing::error!(
+            ?error,
+            company_id = query.company_id,
+            customer_id = query.customer_id,
+            "Failed to log email click"
+        ),
+    }
+    Redirect::to(&query.url).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::telegram::MockTelegram;
+    use crate::tests::utils::new_test_app;
+    use axum::http::{HeaderValue, StatusCode};
+    use common::utils::click_tracking::tracked_link;
+    use teloxide::types::ParseMode;
+
+    const CLICK_SECRET: &str = "test-click-secret";
+    const STONES_URL: &str = "https://example.granite-manager.com/customer/1/stones";
+
+    /// Same fixed values on every call: safe under parallel test execution since no other
+    /// test in this crate reads/writes the `CLICK_TRACKING_SECRET` env var.
+    fn set_click_env() {
+        unsafe {
+            std::env::set_var("CLICK_TRACKING_SECRET", CLICK_SECRET);
+        }
+    }
+
+    async fn insert_customer(pool: &MySqlPool, sales_rep: Option<i32>) -> i32 {
+        let customer_id = sqlx::query!(
+            "INSERT INTO customers (name, company_id, source, sales_rep) VALUES ('Jordan Smith', 1, 'leads', ?)",
+            sales_rep
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        i32::try_from(customer_id).unwrap()
+    }
+
+    fn click_path(customer_id: i32, url: &str) -> String {
+        tracked_link(
+            "",
+            CLICK_SECRET,
+            &ClickTarget {
+                company_id: 1,
+                customer_id,
+                scheduled_email_id: None,
+            },
+            url,
+        )
+    }
+
+    fn query(customer_id: i32) -> EmailClickQuery {
+        EmailClickQuery {
+            company_id: 1,
+            customer_id,
+            scheduled_email_id: None,
+            url: STONES_URL.to_string(),
+            sig: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_client_ip_uses_first_forwarded_address() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(client_ip(&headers), None);
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("203.0.113.7, 10.0.0.1"),
+        );
+        assert_eq!(client_ip(&headers), Some("203.0.113.7"));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_signed_click_is_logged_and_redirected(pool: MySqlPool) {
+        set_click_env();
+        let app = new_test_app(pool.clone());
+        let customer_id = insert_customer(&pool, None).await;
+
+        let response = app
+            .get(&click_path(customer_id, STONES_URL))
+            .add_header("user-agent", "Mozilla/5.0")
+            .add_header("x-forwarded-for", "203.0.113.7")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::SEE_OTHER);
+        assert_eq!(response.header("location"), STONES_URL);
+
+        let click = sqlx::query!(
+            "SELECT customer_id, message_id, url, user_agent, ip_address FROM email_clicks"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(click.customer_id, customer_id);
+        assert_eq!(click.message_id, None);
+        assert_eq!(click.url, STONES_URL);
+        assert_eq!(click.user_agent.as_deref(), Some("Mozilla/5.0"));
+        assert_eq!(click.ip_address.as_deref(), Some("203.0.113.7"));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_tampered_url_is_not_redirected(pool: MySqlPool) {
+        set_click_env();
+        let app = new_test_app(pool.clone());
+        let customer_id = insert_customer(&pool, None).await;
+
+        let path = click_path(customer_id, STONES_URL).replace(
+            &urlencoding::encode(STONES_URL).into_owned(),
+            &urlencoding::encode("https://evil.example.com").into_owned(),
+        );
+        let response = app.get(&path).await;
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+        let count = sqlx::query_scalar!("SELECT COUNT(*) FROM email_clicks")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_signed_url_that_is_not_a_header_value_is_rejected(pool: MySqlPool) {
+        set_click_env();
+        let app = new_test_app(pool.clone());
+        let customer_id = insert_customer(&pool, None).await;
+
+        let response = app
+            .get(&click_path(
+                customer_id,
+                "https://acme.com/\r\nSet-Cookie: a=b",
+            ))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_concurrent_first_clicks_claim_once(pool: MySqlPool) {
+        let (first, second) = tokio::join!(
+            claim_first_email_click(&pool, 1, 7, STONES_URL),
+            claim_first_email_click(&pool, 1, 7, STONES_URL),
+        );
+        assert!(first.unwrap() ^ second.unwrap());
+        assert!(
+            claim_first_email_click(&pool, 1, 8, STONES_URL)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_first_click_notifies_sales_rep_once(pool: MySqlPool) {
+        let rep_id = sqlx::query!(
+            "INSERT INTO users (email, name, company_id, notifications_telegram_id, telegram_email_notifications) \
+             VALUES ('rep-click@test.com', 'Rep', 1, 777, 1)"
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        let customer_id = insert_customer(&pool, Some(i32::try_from(rep_id).unwrap())).await;
+        let headers = HeaderMap::new();
+
+        let payload = log_email_click(&pool, &query(customer_id), &headers)
+            .await
+            .unwrap()
+            .expect("first click notifies the rep");
+        let bot = MockTelegram::new();
+        send_email_click_telegram_notification(&pool, &bot, &payload)
+            .await
+            .unwrap();
+        let sent = bot.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, 777);
+        assert!(sent[0].1.contains("<b>Customer:</b> Jordan Smith"));
+        assert!(sent[0].1.contains(STONES_URL));
+        assert_eq!(bot.parse_modes.lock().unwrap()[0], (777, ParseMode::Html));
+
+        let repeat = log_email_click(&pool, &query(customer_id), &headers)
+            .await
+            .unwrap();
+        assert!(repeat.is_none());
+    }
+}
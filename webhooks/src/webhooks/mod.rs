@@ -1 +1,5 @@
+pub mod api_keys;
+pub mod email_click;
 pub mod receive;
+pub mod suppressions;
+pub mod unsubscribe;
@@ -1,53 +1,201 @@
 use crate::axum_helpers::guards::MarketingUser;
 use crate::axum_helpers::guards::{Telegram, TelegramBot};
+use crate::crud::lead_forms::get_lead_form_definition;
+use crate::crud::lead_submissions::{claim_lead_submission, release_lead_submission};
+use crate::libs::constants::{
+    CREATED_RESPONSE, ERR_DB, MALFORMED_RESPONSE, NOT_FOUND_RESPONSE, internal_error,
+};
+use crate::libs::idempotency::{SubmissionKey, submission_key};
 use crate::libs::leads::process_lead;
 use crate::libs::types::BasicResponse;
 use crate::schemas::add_customer::{
     FaceBookContactForm, LeadPayload, NewLeadForm, WordpressContactForm,
 };
-use axum::extract::Path;
-use axum::extract::{Json, State};
+use crate::schemas::form_lead::FormLead;
+use axum::extract::{Json, Path, State};
+use axum::http::HeaderMap;
+use lambda_http::tracing;
+use serde::Serialize;
+use serde_json::{Map, Value};
 use sqlx::MySqlPool;
 
 pub async fn wordpress_contact_form(
-    _: MarketingUser,
-    Path(company_id): Path<i32>,
+    MarketingUser { company_id }: MarketingUser,
     State(pool): State<MySqlPool>,
+    headers: HeaderMap,
     Json(contact_form): Json<WordpressContactForm>,
 ) -> BasicResponse {
     let tg_bot = TelegramBot::default();
-    new_lead_form_inner(company_id, pool, contact_form, &tg_bot).await
+    submit_lead_once(
+        company_id,
+        pool,
+        &headers,
+        "wordpress-contact-form",
+        contact_form,
+        &tg_bot,
+    )
+    .await
 }
 
 pub async fn facebook_contact_form(
-    _: MarketingUser,
-    Path(company_id): Path<i32>,
+    MarketingUser { company_id }: MarketingUser,
     State(pool): State<MySqlPool>,
+    headers: HeaderMap,
     Json(contact_form): Json<FaceBookContactForm>,
 ) -> BasicResponse {
     let tg_bot = TelegramBot::default();
-    new_lead_form_inner(company_id, pool, contact_form, &tg_bot).await
+    submit_lead_once(
+        company_id,
+        pool,
+        &headers,
+        "facebook-contact-form",
+        contact_form,
+        &tg_bot,
+    )
+    .await
 }
 
 #[utoipa::path(
     post,
     path = "/v1/webhooks/new-lead-form/{company_id}",
     description = "Create a marketing lead from Make, Zapier, or the website.\n\n\
+Authenticate with `Authorization: Bearer <api key>`; the key must belong to `company_id`.\n\n\
 **`referral_source`** is optional. When provided, prefer `website` or `facebook` so statistics group correctly.\n\n\
 **`form_name`** is optional. When provided, use the specific form id, for example `cabinet_quote`, \
-`facebook_form`, `facebook_cabinet_quote_form`, or `quick_quote`.",
-    params(("company_id" = i32, Path, description = "Company ID")),
+`facebook_form`, `facebook_cabinet_quote_form`, or `quick_quote`.\n\n\
+**UTM parameters** (`utm_source`, `utm_medium`, `utm_campaign`, `utm_term`, `utm_content`), click ids \
+(`gclid`, `fbclid`, `wbraid`), `landing_page` and `referrer` are optional. They are saved as the \
+customer's first-touch attribution on the first lead and as last-touch attribution on every lead.\n\n\
+**`Idempotency-Key`** is optional. Retries with the same key within 24 hours return `201` without \
+creating another lead. Without it, an identical payload sent again within 10 minutes is ignored.\n\n\
+When the company screens leads for spam, likely spam is held for a manager to approve in Telegram \
+and still returns `201`.",
+    params(
+        ("company_id" = i32, Path, description = "Company ID"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Unique id of this submission, reused on retries")
+    ),
     request_body = NewLeadForm,
-    responses((status = CREATED, body = str), (status = INTERNAL_SERVER_ERROR, body = str))
+    responses(
+        (status = CREATED, body = str),
+        (status = FORBIDDEN, body = str),
+        (status = INTERNAL_SERVER_ERROR, body = str)
+    )
 )]
 pub async fn new_lead_form(
-    _: MarketingUser,
-    Path(company_id): Path<i32>,
+    MarketingUser { company_id }: MarketingUser,
     State(pool): State<MySqlPool>,
+    headers: HeaderMap,
     Json(contact_form): Json<NewLeadForm>,
 ) -> BasicResponse {
     let tg_bot = TelegramBot::default();
-    new_lead_form_inner(company_id, pool, contact_form, &tg_bot).await
+    submit_lead_once(
+        company_id,
+        pool,
+        &headers,
+        "new-lead-form",
+        contact_form,
+        &tg_bot,
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/webhooks/forms/{company_id}/{form_name}",
+    description = "Create a lead from a form configured for the company in Granite Manager.\n\n\
+Send the form's fields as a flat JSON object. Keys are mapped to customer fields by the form \
+definition; any other keys are stored with the customer and shown in the lead notification. \
+A `name` must be present after mapping.\n\n\
+Authentication and `Idempotency-Key` work the same as for `/v1/webhooks/new-lead-form/{company_id}`.",
+    params(
+        ("company_id" = i32, Path, description = "Company ID"),
+        ("form_name" = String, Path, description = "Form name configured for the company"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Unique id of this submission, reused on retries")
+    ),
+    request_body(content = Object, description = "Submitted form fields"),
+    responses(
+        (status = CREATED, body = str),
+        (status = FORBIDDEN, body = str),
+        (status = NOT_FOUND, body = str),
+        (status = UNPROCESSABLE_ENTITY, body = str),
+        (status = INTERNAL_SERVER_ERROR, body = str)
+    )
+)]
+pub async fn custom_lead_form(
+    MarketingUser { company_id }: MarketingUser,
+    State(pool): State<MySqlPool>,
+    Path((_, form_name)): Path<(i32, String)>,
+    headers: HeaderMap,
+    Json(payload): Json<Map<String, Value>>,
+) -> BasicResponse {
+    let definition = match get_lead_form_definition(&pool, company_id, &form_name).await {
+        Ok(Some(definition)) => definition,
+        Ok(None) => return NOT_FOUND_RESPONSE,
+        Err(e) => {
+            tracing::error!(?e, company_id, form_name = %form_name, "Failed to load lead form");
+            return internal_error(ERR_DB);
+        }
+    };
+    let lead = match FormLead::from_payload(&definition, payload) {
+        Ok(lead) => lead,
+        Err(e) => {
+            tracing::error!(?e, company_id, form_name = %form_name, "Malformed form lead");
+            return MALFORMED_RESPONSE;
+        }
+    };
+    let tg_bot = TelegramBot::default();
+    let route = format!("forms/{form_name}");
+    submit_lead_once(company_id, pool, &headers, &route, lead, &tg_bot).await
+}
+
+/// Replays of an already processed submission return `201 created` without creating the
+/// lead again, the same way `insert_inbound_sms` ignores repeated `CloudTalk` deliveries.
+/// A failed submission releases its key so the sender's retry goes through.
+async fn submit_lead_once<T, V: LeadPayload + Serialize>(
+    company_id: i32,
+    pool: MySqlPool,
+    headers: &HeaderMap,
+    route: &str,
+    lead_form: V,
+    bot: &T,
+) -> BasicResponse
+where
+    T: Telegram + Send + Sync + 'static + Clone,
+{
+    let key = submission_key(headers, route, &lead_form);
+    submit_lead_with_key(company_id, pool, &key, lead_form, bot).await
+}
+
+/// [`submit_lead_once`] for senders that carry their own id for the submission, such as
+/// Meta's `leadgen_id`.
+pub async fn submit_lead_with_key<T, V: LeadPayload>(
+    company_id: i32,
+    pool: MySqlPool,
+    key: &SubmissionKey,
+    lead_form: V,
+    bot: &T,
+) -> BasicResponse
+where
+    T: Telegram + Send + Sync + 'static + Clone,
+{
+    match claim_lead_submission(&pool, company_id, &key.value, key.ttl_minutes).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::info!(company_id, key = %key.value, "Ignoring replayed lead submission");
+            return CREATED_RESPONSE;
+        }
+        Err(e) => {
+            tracing::error!(?e, company_id, "Failed to claim lead submission");
+        }
+    }
+
+    let response = new_lead_form_inner(company_id, pool.clone(), lead_form, bot).await;
+    if !response.0.is_success()
+        && let Err(e) = release_lead_submission(&pool, company_id, &key.value).await
+    {
+        tracing::error!(?e, company_id, "Failed to release lead submission");
+    }
+    response
 }
 
 pub async fn new_lead_form_inner<T, V: LeadPayload>(
@@ -65,6 +213,8 @@ where
 #[cfg(test)]
 mod local_tests {
     use super::*;
+    use crate::axum_helpers::guards::CORRECT_ID;
+    use crate::crud::api_keys::{create_api_key, revoke_api_key};
     use crate::crud::leads::create_deal;
     use crate::tests::telegram::MockTelegram;
     use crate::tests::utils::{assigned_user_position, insert_user, new_test_app, positioned_user};
@@ -117,9 +267,11 @@ mod local_tests {
             .await
             .unwrap();
         assigned_user_position(&pool, 1, 2, admin_id).await.unwrap();
+        let (_, key) = create_api_key(&pool, 1, "Facebook").await.unwrap();
 
         let response = app
             .post("/facebook-contact-form/1")
+            .authorization_bearer(key)
             .json(&lead_payload_json())
             .await;
 
@@ -136,8 +288,13 @@ mod local_tests {
             .await
             .unwrap();
         assigned_user_position(&pool, 1, 2, admin_id).await.unwrap();
+        let (_, key) = create_api_key(&pool, 1, "Website").await.unwrap();
 
-        let response = app.post("/wordpress-contact-form/1").json(&lead).await;
+        let response = app
+            .post("/wordpress-contact-form/1")
+            .authorization_bearer(key)
+            .json(&lead)
+            .await;
 
         assert_eq!(response.status_code(), StatusCode::CREATED);
     }
@@ -152,8 +309,265 @@ mod local_tests {
             .await
             .unwrap();
         assigned_user_position(&pool, 1, 2, admin_id).await.unwrap();
+        let (_, key) = create_api_key(&pool, 1, "Zapier").await.unwrap();
+
+        let response = app
+            .post("/v1/webhooks/new-lead-form/1")
+            .authorization_bearer(key)
+            .json(&lead)
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+
+        let last_used = sqlx::query_scalar!(
+            r#"SELECT last_used_at FROM company_api_keys WHERE company_id = 1"#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(last_used.is_some());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_new_lead_form_replayed_idempotency_key_is_ignored(pool: MySqlPool) {
+        let app = new_test_app(pool.clone());
+        let (_, key) = create_api_key(&pool, 1, "Zapier").await.unwrap();
+
+        for name in ["Zap Lead", "Zap Lead Edited"] {
+            let response = app
+                .post("/v1/webhooks/new-lead-form/1")
+                .authorization_bearer(key.clone())
+                .add_header("Idempotency-Key", "zap-run-42")
+                .json(&json!({ "name": name, "phone": "+13179995973" }))
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CREATED);
+        }
+
+        let customers = get_customers(&pool).await.unwrap();
+        assert_eq!(customers.len(), 1);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_wordpress_double_submit_creates_one_lead(pool: MySqlPool) {
+        let app = new_test_app(pool.clone());
+        let (_, key) = create_api_key(&pool, 1, "Website").await.unwrap();
+
+        for phone in ["+13179995973", " +13179995973"] {
+            let response = app
+                .post("/wordpress-contact-form/1")
+                .authorization_bearer(key.clone())
+                .json(&json!({ "name": "Test", "Phone": phone }))
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CREATED);
+        }
+
+        let submissions = sqlx::query_scalar!(r#"SELECT COUNT(*) as count FROM lead_submissions"#)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(submissions, 1);
+        let customers = get_customers(&pool).await.unwrap();
+        assert_eq!(customers.len(), 1);
+    }
+
+    async fn insert_kitchen_quiz(pool: &MySqlPool) {
+        sqlx::query!(
+            r#"INSERT INTO lead_form_definitions (company_id, form_name, field_map, referral_source)
+               VALUES (1, 'kitchen_quiz', '{"Full Name": "name", "Cell": "phone"}', 'website')"#
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_custom_form_maps_fields_and_stores_extras(pool: MySqlPool) {
+        let app = new_test_app(pool.clone());
+        insert_kitchen_quiz(&pool).await;
+        let (_, key) = create_api_key(&pool, 1, "Website").await.unwrap();
+
+        let response = app
+            .post("/v1/webhooks/forms/1/kitchen_quiz")
+            .authorization_bearer(key)
+            .json(&json!({
+                "Full Name": "Quiz Lead",
+                "Cell": "+13179995973",
+                "Cabinet color": "White",
+                "Budget": 15000
+            }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+
+        let row = sqlx::query!(
+            r#"SELECT name, referral_source, form_name, CAST(extra_fields AS CHAR) AS "extra_fields: String"
+               FROM customers WHERE phone = '317-999-5973'"#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(row.name.as_deref(), Some("Quiz Lead"));
+        assert_eq!(row.referral_source.as_deref(), Some("website"));
+        assert_eq!(row.form_name.as_deref(), Some("kitchen_quiz"));
+        let extra_fields: Value = serde_json::from_str(&row.extra_fields.unwrap()).unwrap();
+        assert_eq!(
+            extra_fields,
+            json!({ "Budget": 15000, "Cabinet color": "White" })
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_custom_form_longest_name_and_key_are_deduplicated(pool: MySqlPool) {
+        let app = new_test_app(pool.clone());
+        let form_name = "q".repeat(100);
+        sqlx::query!(
+            r#"INSERT INTO lead_form_definitions (company_id, form_name, field_map)
+               VALUES (1, ?, '{"Full Name": "name", "Cell": "phone"}')"#,
+            form_name
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let (_, key) = create_api_key(&pool, 1, "Zapier").await.unwrap();
+        let idempotency_key = "z".repeat(200);
+
+        for name in ["Long Key Lead", "Long Key Lead Edited"] {
+            let response = app
+                .post(&format!("/v1/webhooks/forms/1/{form_name}"))
+                .authorization_bearer(key.clone())
+                .add_header("Idempotency-Key", idempotency_key.clone())
+                .json(&json!({ "Full Name": name, "Cell": "+13179995973" }))
+                .await;
+            assert_eq!(response.status_code(), StatusCode::CREATED);
+        }
+
+        let submissions = sqlx::query_scalar!(r#"SELECT COUNT(*) as count FROM lead_submissions"#)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(submissions, 1);
+        let customers = get_customers(&pool).await.unwrap();
+        assert_eq!(customers.len(), 1);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_custom_form_unknown_form_is_not_found(pool: MySqlPool) {
+        let app = new_test_app(pool.clone());
+        let (_, key) = create_api_key(&pool, 1, "Website").await.unwrap();
+
+        let response = app
+            .post("/v1/webhooks/forms/1/kitchen_quiz")
+            .authorization_bearer(key)
+            .json(&json!({ "name": "Test", "phone": "+13179995973" }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+        let customers = get_customers(&pool).await.unwrap();
+        assert!(customers.is_empty());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_custom_form_extras_in_telegram_message(pool: MySqlPool) {
+        insert_kitchen_quiz(&pool).await;
+        let definition = get_lead_form_definition(&pool, 1, "kitchen_quiz")
+            .await
+            .unwrap()
+            .unwrap();
+        let payload = json!({ "Full Name": "Quiz Lead", "Cabinet color": "White" });
+        let Value::Object(payload) = payload else {
+            unreachable!()
+        };
+        let lead = FormLead::from_payload(&definition, payload).unwrap();
+        let bot = MockTelegram::new();
+        positioned_user(&pool, 1, 2, 456).await;
+
+        let response = new_lead_form_inner(1, pool.clone(), lead, &bot).await;
+        assert_eq!(response.0, StatusCode::CREATED);
+
+        let sent = bot.sent.lock().unwrap();
+        assert!(
+            sent.iter()
+                .any(|message| message.1.contains("<b>Cabinet color:</b> White"))
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_new_lead_form_rejects_missing_key(pool: MySqlPool) {
+        let app = new_test_app(pool.clone());
+        let lead = json!({ "name": "Test", "phone": "+13179995973" });
 
         let response = app.post("/v1/webhooks/new-lead-form/1").json(&lead).await;
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .post("/v1/webhooks/new-lead-form/1")
+            .authorization_bearer(CORRECT_ID.to_string())
+            .json(&lead)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+        let customers = get_customers(&pool).await.unwrap();
+        assert!(customers.is_empty());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_new_lead_form_rejects_other_company_key(pool: MySqlPool) {
+        let app = new_test_app(pool.clone());
+        let other_company = sqlx::query!(r#"INSERT INTO company (name) VALUES ('Other Co')"#)
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_id();
+        let other_company = i32::try_from(other_company).unwrap();
+        let (_, key) = create_api_key(&pool, other_company, "Zapier")
+            .await
+            .unwrap();
+        sqlx::query!("UPDATE company SET lead_auth_lenient = 1 WHERE id = 1")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let response = app
+            .post("/v1/webhooks/new-lead-form/1")
+            .authorization_bearer(key)
+            .json(&json!({ "name": "Test", "phone": "+13179995973" }))
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_new_lead_form_rejects_revoked_key(pool: MySqlPool) {
+        let app = new_test_app(pool.clone());
+        let (id, key) = create_api_key(&pool, 1, "Zapier").await.unwrap();
+        revoke_api_key(&pool, 1, i32::try_from(id).unwrap())
+            .await
+            .unwrap();
+
+        let response = app
+            .post("/v1/webhooks/new-lead-form/1")
+            .authorization_bearer(key)
+            .json(&json!({ "name": "Test", "phone": "+13179995973" }))
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_new_lead_form_lenient_company_accepts_missing_key(pool: MySqlPool) {
+        let app = new_test_app(pool.clone());
+        let admin_id = insert_user(&pool, "admin@example.com", Some(456))
+            .await
+            .unwrap();
+        assigned_user_position(&pool, 1, 2, admin_id).await.unwrap();
+        sqlx::query!("UPDATE company SET lead_auth_lenient = 1 WHERE id = 1")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let response = app
+            .post("/v1/webhooks/new-lead-form/1")
+            .json(&json!({ "name": "Test", "phone": "+13179995973" }))
+            .await;
 
         assert_eq!(response.status_code(), StatusCode::CREATED);
     }
@@ -189,7 +603,7 @@ mod local_tests {
         let sent = bot.sent.lock().unwrap();
         assert!(
             sent.iter()
-                .any(|message| message.1.contains("Form Name: cabinet_quote"))
+                .any(|message| message.1.contains("<b>Form Name:</b> cabinet_quote"))
         );
     }
 
@@ -0,0 +1,143 @@
+use crate::axum_helpers::guards::RemixBackend;
+use crate::libs::constants::{
+    BAD_REQUEST, CREATED_RESPONSE, ERR_DB, NOT_FOUND_RESPONSE, OK_RESPONSE, internal_error,
+};
+use crate::libs::types::BasicResponse;
+use axum::Json;
+use axum::extract::{Path, State};
+use common::crud::suppressions::{
+    EmailSuppression, SuppressionReason, add_email_suppression, list_email_suppressions,
+    remove_email_suppression,
+};
+use lambda_http::tracing;
+use serde::Deserialize;
+use sqlx::MySqlPool;
+
+const CRM_SOURCE: &str = "crm";
+
+#[derive(Debug, Deserialize)]
+pub struct NewSuppressionRequest {
+    pub email: String,
+    #[serde(default = "default_reason")]
+    pub reason: SuppressionReason,
+    pub source: Option<String>,
+}
+
+const fn default_reason() -> SuppressionReason {
+    SuppressionReason::Manual
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RemoveSuppressionRequest {
+    pub email: String,
+}
+
+fn is_plausible_email(email: &str) -> bool {
+    let email = email.trim();
+    !email.is_empty() && email.len() <= 255 && email.contains('@')
+}
+
+pub async fn list_company_suppressions(
+    _: RemixBackend,
+    State(pool): State<MySqlPool>,
+    Path(company_id): Path<i32>,
+) -> Result<Json<Vec<EmailSuppression>>, BasicResponse> {
+    match list_email_suppressions(&pool, company_id).await {
+        Ok(entries) => Ok(Json(entries)),
+        Err(error) => {
+            tracing::error!(
+                ?error,
+                company_id = company_id,
+                "Failed to list suppressions"
+            );
+            Err(internal_error(ERR_DB))
+        }
+    }
+}
+
+pub async fn add_company_suppression(
+    _: RemixBackend,
+    State(pool): State<MySqlPool>,
+    Path(company_id): Path<i32>,
+    Json(body): Json<NewSuppressionRequest>,
+) -> BasicResponse {
+    if !is_plausible_email(&body.email) {
+        return BAD_REQUEST;
+    }
+    let source = body
+        .source
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .unwrap_or(CRM_SOURCE);
+    match add_email_suppression(&pool, company_id, &body.email, body.reason, source).await {
+        Ok(result) if result.rows_affected() > 0 => CREATED_RESPONSE,
+        Ok(_) => OK_RESPONSE,
+        Err(error) => {
+            tracing::error!(?error, company_id = company_id, "Failed to add suppression");
+            internal_error(ERR_DB)
+        }
+    }
+}
+
+pub async fn remove_company_suppression(
+    _: RemixBackend,
+    State(pool): State<MySqlPool>,
+    Path(company_id): Path<i32>,
+    Json(body): Json<RemoveSuppressionRequest>,
+) -> BasicResponse {
+    match remove_email_suppression(&pool, company_id, &body.email).await {
+        Ok(result) if result.rows_affected() > 0 => OK_RESPONSE,
+        Ok(_) => NOT_FOUND_RESPONSE,
+        Err(error) => {
+            tracing::error!(
+                ?error,
+                company_id = company_id,
+                "Failed to remove suppression"
+            );
+            internal_error(ERR_DB)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::utils::new_test_app;
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    #[test]
+    fn test_plausible_email() {
+        assert!(is_plausible_email(" lead@example.com "));
+        assert!(!is_plausible_email("   "));
+        assert!(!is_plausible_email("not-an-email"));
+    }
+
+    #[test]
+    fn test_reason_defaults_to_manual() {
+        let body: NewSuppressionRequest =
+            serde_json::from_value(json!({ "email": "lead@example.com" })).unwrap();
+        assert_eq!(body.reason, SuppressionReason::Manual);
+
+        let body: NewSuppressionRequest =
+            serde_json::from_value(json!({ "email": "lead@example.com", "reason": "unsubscribe" }))
+                .unwrap();
+        assert_eq!(body.reason, SuppressionReason::Unsubscribe);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_suppression_routes_require_remix_backend(pool: MySqlPool) {
+        let app = new_test_app(pool);
+        let body = json!({ "email": "lead@example.com" });
+
+        let response = app.get("/v1/email-suppressions/1").await;
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+        let response = app.post("/v1/email-suppressions/1").json(&body).await;
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+        let response = app.delete("/v1/email-suppressions/1").json(&body).await;
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::crud::unsubscribe::{cancel_flow_enrollments_on_unsubscribe, get_customer_emails};
+use crate::libs::constants::{ERR_DB, FORBIDDEN_RESPONSE, internal_error};
+use crate::libs::types::BasicResponse;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::Html;
+use common::crud::scheduled_emails::cancel_pending_scheduled_emails_for_customer;
+use common::crud::suppressions::{SuppressionReason, add_email_suppression};
+use common::utils::unsubscribe::verify_unsubscribe;
+use lambda_http::tracing;
+use sqlx::MySqlPool;
+use std::env::var;
+
+const UNSUBSCRIBED_RESPONSE: BasicResponse = (
+    StatusCode::OK,
+    "You have been unsubscribed and will not receive further emails.",
+);
+const UNSUBSCRIBE_SOURCE: &str = "unsubscribe_link";
+/// Posts back to the same URL, so the signature travels with the form.
+const CONFIRM_PAGE: &str = r#"<!doctype html>
+<html>
+<head><meta charset="utf-8"><meta name="viewport" content="width=device-width, initial-scale=1"><title>Unsubscribe</title></head>
+<body>
+<p>Do you want to stop receiving emails from us?</p>
+<form method="post"><button type="submit">Unsubscribe</button></form>
+</body>
+</html>
+"#;
+
+async fn record_unsubscribe(
+    pool: &MySqlPool,
+    company_id: i32,
+    customer_id: i32,
+) -> Result<(), sqlx::Error> {
+    for email in get_customer_emails(pool, company_id, customer_id).await? {
+        add_email_suppression(
+            pool,
+            company_id,
+            &email,
+            SuppressionReason::Unsubscribe,
+            UNSUBSCRIBE_SOURCE,
+        )
+        .await?;
+    }
+    let emails = cancel_pending_scheduled_emails_for_customer(
+        pool,
+        company_id,
+        customer_id,
+        "Customer unsubscribed",
+    )
+    .await?;
+    let flows = cancel_flow_enrollments_on_unsubscribe(pool, company_id, customer_id).await?;
+    tracing::info!(
+        company_id,
+        customer_id,
+        cancelled_emails = emails.rows_affected(),
+        cancelled_flows = flows,
+        "Customer unsubscribed"
+    );
+    Ok(())
+}
+
+fn unsubscribe_secret() -> Result<String, BasicResponse> {
+    match var("UNSUBSCRIBE_SECRET") {
+        Ok(secret) if !secret.is_empty() => Ok(secret),
+        _ => {
+            tracing::error!("UNSUBSCRIBE_SECRET is not set");
+            Err(internal_error("failed to get unsubscribe secret"))
+        }
+    }
+}
+
+/// GET target of `{{unsubscribe_link}}`. Only shows a confirmation form: link scanners and
+/// inbox prefetchers fetch links, so a GET must never opt the customer out.
+pub async fn confirm_unsubscribe(
+    Path((company_id, customer_id, signature)): Path<(i32, i32, String)>,
+) -> Result<Html<&'static str>, BasicResponse> {
+    let secret = unsubscribe_secret()?;
+    if !verify_unsubscribe(&secret, company_id, customer_id, &signature) {
+        return Err(FORBIDDEN_RESPONSE);
+    }
+    Ok(Html(CONFIRM_PAGE))
+}
+
+/// POST from the confirmation form or the RFC 8058 one-click request from the mailbox
+/// (`List-Unsubscribe-Post`).
+pub async fn unsubscribe_customer(
+    State(pool): State<MySqlPool>,
+    Path((company_id, customer_id, signature)): Path<(i32, i32, String)>,
+) -> BasicResponse {
+    let secret = match unsubscribe_secret() {
+        Ok(secret) => secret,
+        Err(response) => return response,
+    };
+    if !verify_unsubscribe(&secret, company_id, customer_id, &signature) {
+        return FORBIDDEN_RESPONSE;
+    }
+    match record_unsubscribe(&pool, company_id, customer_id).await {
+        Ok(()) => UNSUBSCRIBED_RESPONSE,
+        Err(error) => {
+            tracing::error!(
+                ?error,
+                company_id,
+                customer_id,
+                "Failed to record unsubscribe"
+            );
+            internal_error(ERR_DB)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::utils::new_test_app;
+    use common::utils::unsubscribe::sign_unsubscribe;
+
+    const UNSUBSCRIBE_SECRET: &str = "test-unsubscribe-secret";
+
+    /// Same fixed values on every call: safe under parallel test execution since no other
+    /// test in this crate reads/writes the `UNSUBSCRIBE_SECRET` env var.
+    fn set_unsubscribe_env() {
+        unsafe {
+            std::env::set_var("UNSUBSCRIBE_SECRET", UNSUBSCRIBE_SECRET);
+        }
+    }
+
+    async fn insert_customer_with_drip_and_flow(pool: &MySqlPool) -> i32 {
+        let customer_id = sqlx::query!(
+            "INSERT INTO customers (name, company_id, source) VALUES ('Unsub Lead', 1, 'leads')"
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        let email_id = sqlx::query!(
+            "INSERT INTO customers_emails (customer_id, email) VALUES (?, 'Unsub@Example.com')",
+            customer_id
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        sqlx::query!(
+            "UPDATE customers SET email_id = ? WHERE id = ?",
+            email_id,
+            customer_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        let template_id = sqlx::query!(
+            "INSERT INTO email_templates (template_name, template_body, company_id, hour_delay, show_template) VALUES ('drip', 'Hi', 1, 24, 1)"
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_id();
+        sqlx::query!(
+            "INSERT INTO scheduled_emails (template_id, deal_id, customer_id, user_id, company_id, send_at) \
+             VALUES (?, 1, ?, 1, 1, UTC_TIMESTAMP() + INTERVAL 1 DAY)",
+            template_id,
+            customer_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO sms_flow_enrollments (flow_id, company_id, customer_phone_digits, customer_id, user_id, status, anchor_at) \
+             VALUES (1, 1, 5550000000, ?, 1, 'active', UTC_TIMESTAMP())",
+            customer_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        i32::try_from(customer_id).unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_one_click_unsubscribe_cancels_drips_and_flows(pool: MySqlPool) {
+        set_unsubscribe_env();
+        let app = new_test_app(pool.clone());
+        let customer_id = insert_customer_with_drip_and_flow(&pool).await;
+        let signature = sign_unsubscribe(UNSUBSCRIBE_SECRET, 1, customer_id);
+
+        let response = app
+            .post(&format!("/v1/unsubscribe/1/{customer_id}/{signature}"))
+            .text("List-Unsubscribe=One-Click")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let suppression = sqlx::query!("SELECT email, reason, source FROM email_suppressions")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(suppression.email, "unsub@example.com");
+        assert_eq!(suppression.reason, "unsubscribe");
+        assert_eq!(suppression.source, UNSUBSCRIBE_SOURCE);
+
+        let drip = sqlx::query!("SELECT status, error_message FROM scheduled_emails")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(drip.status, "cancelled");
+        assert_eq!(drip.error_message.as_deref(), Some("Customer unsubscribed"));
+
+        let flow = sqlx::query_scalar!("SELECT status FROM sms_flow_enrollments")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(flow, "cancelled");
+
+        // Clicking the link from the email body again is harmless.
+        let response = app
+            .get(&format!("/v1/unsubscribe/1/{customer_id}/{signature}"))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_get_only_asks_for_confirmation(pool: MySqlPool) {
+        set_unsubscribe_env();
+        let app = new_test_app(pool.clone());
+        let customer_id = insert_customer_with_drip_and_flow(&pool).await;
+        let signature = sign_unsubscribe(UNSUBSCRIBE_SECRET, 1, customer_id);
+
+        let response = app
+            .get(&format!("/v1/unsubscribe/1/{customer_id}/{signature}"))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert!(response.text().contains(r#"<form method="post">"#));
+
+        let suppressions = sqlx::query_scalar!("SELECT COUNT(*) FROM email_suppressions")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(suppressions, 0);
+        let drip = sqlx::query_scalar!("SELECT status FROM scheduled_emails")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(drip, "pending");
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_unsubscribe_rejects_signature_for_another_customer(pool: MySqlPool) {
+        set_unsubscribe_env();
+        let app = new_test_app(pool.clone());
+        let customer_id = insert_customer_with_drip_and_flow(&pool).await;
+        let signature = sign_unsubscribe(UNSUBSCRIBE_SECRET, 1, customer_id + 1);
+
+        let response = app
+            .get(&format!("/v1/unsubscribe/1/{customer_id}/{signature}"))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+        let drip = sqlx::query_scalar!("SELECT status FROM scheduled_emails")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(drip, "pending");
+    }
+}